@@ -0,0 +1,14 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    // The gRPC code is generated from the proto file with a vendored protoc,
+    // so building it needs no system install
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/superdev.proto");
+        let mut config = prost_build::Config::new();
+        config.protoc_executable(protoc_bin_vendored::protoc_bin_path().expect("vendored protoc is available"));
+        tonic_build::configure()
+            .compile_protos_with_config(config, &["proto/superdev.proto"], &["proto"])
+            .expect("proto/superdev.proto compiles");
+    }
+}
@@ -0,0 +1,494 @@
+use axum::{
+    body::Body,
+    extract::State,
+    http::{HeaderMap, HeaderName, Request, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+use std::{
+    fmt, fs,
+    future::Future,
+    io::Write,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use crate::config::AppConfig;
+use crate::utils::{i18n::Locale, ApiErrorCode, ErrorResponse, SuccessResponse};
+
+// When API keys are configured, every API request must present one in this
+// header, or gRPC metadata entry. Each key is limited to the scopes it was
+// configured with, so a front-end key can be allowed to build instructions
+// but never to sign. Without any keys configured the API is open.
+pub const HEADER: HeaderName = HeaderName::from_static("x-api-key");
+
+// Length of the keys issued by rotation
+const ISSUED_KEY_LEN: usize = 32;
+
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    // Lookups, decoding and verification
+    Read,
+    // Instructions and unsigned transactions
+    Build,
+    // Anything using keys held by the server, or signing with keys sent in
+    Sign,
+    // Broadcasting to the cluster
+    Submit,
+    // Server administration, such as reading the audit log
+    Admin,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 5] = [Scope::Read, Scope::Build, Scope::Sign, Scope::Submit, Scope::Admin];
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "read" => Some(Scope::Read),
+            "build" => Some(Scope::Build),
+            "sign" => Some(Scope::Sign),
+            "submit" => Some(Scope::Submit),
+            "admin" => Some(Scope::Admin),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Scope::Read => "read",
+            Scope::Build => "build",
+            Scope::Sign => "sign",
+            Scope::Submit => "submit",
+            Scope::Admin => "admin",
+        })
+    }
+}
+
+// Only a digest of the key is kept, so the comparison against a presented
+// key doesn't leak how much of it matched
+#[derive(Clone)]
+pub struct ApiKey {
+    digest: [u8; 32],
+    scopes: Vec<Scope>,
+}
+
+impl fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ApiKey").field("scopes", &self.scopes).finish_non_exhaustive()
+    }
+}
+
+// Names a key in logs without revealing it
+fn key_id(digest: &[u8; 32]) -> String {
+    hex::encode(&digest[..4])
+}
+
+fn digest(key: &str) -> [u8; 32] {
+    Sha256::digest(key.as_bytes()).into()
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0)
+}
+
+// Keys are written `key` for every scope or `key:scope+scope`, such as
+// `frontend-key:read+build`
+pub fn parse_api_keys(value: &str) -> Result<Vec<ApiKey>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (key, scopes) = match entry.split_once(':') {
+                Some((key, scopes)) => {
+                    let scopes = scopes
+                        .split('+')
+                        .map(|scope| Scope::parse(scope.trim()).ok_or_else(|| format!("unknown API key scope '{}'", scope)))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    (key, scopes)
+                }
+                None => (entry, Scope::ALL.to_vec()),
+            };
+            if key.is_empty() {
+                return Err("API keys cannot be empty".to_string());
+            }
+            Ok(ApiKey { digest: digest(key), scopes })
+        })
+        .collect()
+}
+
+// A key as the key store tracks it. Keys from API_KEYS are listed again on
+// every start; keys issued by rotation exist only here.
+#[derive(Serialize, Deserialize, Clone)]
+struct KeyRecord {
+    id: String,
+    // Hex SHA-256 of the key
+    digest: String,
+    scopes: Vec<Scope>,
+    #[serde(default)]
+    issued: bool,
+    // Unix seconds; the end of the overlap once the key has been rotated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    revoked_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    replaced_by: Option<String>,
+    // Usage since the server started
+    #[serde(skip)]
+    requests: u64,
+    #[serde(skip)]
+    last_used_at: Option<u64>,
+}
+
+impl KeyRecord {
+    fn new(digest: &[u8; 32], scopes: Vec<Scope>, issued: bool) -> Self {
+        KeyRecord {
+            id: key_id(digest),
+            digest: hex::encode(digest),
+            scopes,
+            issued,
+            expires_at: None,
+            revoked_at: None,
+            replaced_by: None,
+            requests: 0,
+            last_used_at: None,
+        }
+    }
+
+    fn valid(&self, now: u64) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+
+    fn info(&self) -> ApiKeyInfo {
+        ApiKeyInfo {
+            key_id: self.id.clone(),
+            scopes: self.scopes.clone(),
+            valid: self.valid(now()),
+            expires_at: self.expires_at,
+            revoked_at: self.revoked_at,
+            replaced_by: self.replaced_by.clone(),
+            requests: self.requests,
+            last_used_at: self.last_used_at,
+        }
+    }
+}
+
+struct KeyStore {
+    // Where rotations and revocations are saved; kept in memory when unset
+    path: Option<String>,
+    records: Vec<KeyRecord>,
+}
+
+impl KeyStore {
+    fn find(&mut self, key_id: &str) -> Result<&mut KeyRecord, ErrorResponse> {
+        self.records
+            .iter_mut()
+            .find(|record| record.id == key_id)
+            .ok_or_else(|| ErrorResponse::new(format!("Unknown API key {}", key_id)))
+    }
+
+    // Written to a temporary file and renamed into place, as the key vault is
+    fn save(&self) -> Result<(), ErrorResponse> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let temporary = format!("{}.tmp", path);
+        let contents = serde_json::to_vec_pretty(&self.records).unwrap();
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(&temporary)
+            .and_then(|mut file| file.write_all(&contents).and_then(|_| file.sync_all()))
+            .and_then(|_| fs::rename(&temporary, path))
+            .map_err(|err| ErrorResponse::new(format!("Failed to write the API key store: {}", err)))
+    }
+
+    // Applies the change and saves it, leaving the store as it was if the
+    // save fails
+    fn update<T>(&mut self, change: impl FnOnce(&mut Self) -> Result<T, ErrorResponse>) -> Result<T, ErrorResponse> {
+        let previous = self.records.clone();
+        let result = change(self).and_then(|value| self.save().map(|_| value));
+        if result.is_err() {
+            self.records = previous;
+        }
+        result
+    }
+}
+
+// The API keys in force: those configured, the replacements rotation has
+// issued, and which keys are revoked or in their overlap window. Clones of
+// the config share one store.
+#[derive(Clone)]
+pub struct ApiKeys(Arc<Mutex<KeyStore>>);
+
+impl fmt::Debug for ApiKeys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let store = self.0.lock().unwrap();
+        f.debug_list().entries(store.records.iter().map(|record| &record.id)).finish()
+    }
+}
+
+impl Default for ApiKeys {
+    fn default() -> Self {
+        ApiKeys::load(Vec::new(), None)
+    }
+}
+
+impl ApiKeys {
+    // The configured keys, with the rotations and revocations saved at the
+    // path. Keys since removed from the configuration are dropped, and the
+    // configuration decides each configured key's scopes.
+    pub fn load(configured: Vec<ApiKey>, path: Option<&str>) -> Self {
+        let saved: Vec<KeyRecord> = match path.filter(|_| !configured.is_empty()).map(fs::read) {
+            Some(Ok(contents)) => serde_json::from_slice(&contents)
+                .unwrap_or_else(|err| panic!("API key store {} is corrupt: {}", path.unwrap_or_default(), err)),
+            Some(Err(err)) if err.kind() != std::io::ErrorKind::NotFound => {
+                panic!("Failed to read API key store {}: {}", path.unwrap_or_default(), err)
+            }
+            _ => Vec::new(),
+        };
+
+        let mut records: Vec<KeyRecord> = configured
+            .iter()
+            .map(|key| {
+                let digest = hex::encode(key.digest);
+                let mut record = saved
+                    .iter()
+                    .find(|record| record.digest == digest)
+                    .cloned()
+                    .unwrap_or_else(|| KeyRecord::new(&key.digest, key.scopes.clone(), false));
+                record.scopes = key.scopes.clone();
+                record.issued = false;
+                record
+            })
+            .collect();
+        records.extend(saved.into_iter().filter(|record| record.issued));
+
+        ApiKeys(Arc::new(Mutex::new(KeyStore { path: path.map(str::to_string), records })))
+    }
+
+    // Without keys the API is open
+    pub fn is_empty(&self) -> bool {
+        self.0.lock().unwrap().records.is_empty()
+    }
+
+    // Finds the valid key with the digest and counts the request against it
+    fn record_use(&self, digest: &[u8; 32]) -> Option<Grant> {
+        let digest = hex::encode(digest);
+        let now = now();
+        let mut store = self.0.lock().unwrap();
+        let record = store.records.iter_mut().find(|record| record.digest == digest && record.valid(now))?;
+        record.requests += 1;
+        record.last_used_at = Some(now);
+        Some(Grant { key_id: Some(record.id.clone()), scopes: record.scopes.clone() })
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ApiKeyInfo {
+    pub key_id: String,
+    pub scopes: Vec<Scope>,
+    // False once revoked or past its overlap window
+    pub valid: bool,
+    // Unix seconds; set when the key has been rotated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<u64>,
+    // The key issued to replace this one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replaced_by: Option<String>,
+    // Requests made with the key since the server started, for confirming
+    // clients have moved to a replacement before revoking the old key
+    pub requests: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ApiKeysResponse {
+    pub keys: Vec<ApiKeyInfo>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RotateKeyRequest {
+    // How long the old key stays valid alongside the new one; defaults to
+    // API_KEY_OVERLAP_SECS
+    #[serde(rename = "overlapSeconds")]
+    pub overlap_seconds: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RotateKeyResponse {
+    // The new key, shown only here
+    pub key: String,
+    pub key_id: String,
+    pub scopes: Vec<Scope>,
+    pub replaces: String,
+    // Unix seconds; when the old key stops working
+    pub old_key_expires_at: u64,
+}
+
+pub fn list_keys(keys: &ApiKeys) -> Result<SuccessResponse<ApiKeysResponse>, ErrorResponse> {
+    let store = keys.0.lock().unwrap();
+    Ok(SuccessResponse::new(ApiKeysResponse { keys: store.records.iter().map(KeyRecord::info).collect() }))
+}
+
+// Issues a key with the same scopes as the old one, which keeps working
+// until the overlap ends
+pub fn rotate_key(
+    keys: &ApiKeys,
+    key_id: String,
+    request: RotateKeyRequest,
+    default_overlap: u64,
+) -> Result<SuccessResponse<RotateKeyResponse>, ErrorResponse> {
+    let key: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(ISSUED_KEY_LEN)
+        .map(char::from)
+        .collect();
+    let now = now();
+
+    let mut store = keys.0.lock().unwrap();
+    store.update(|store| {
+        let old = store.find(&key_id)?;
+        if !old.valid(now) {
+            return Err(ErrorResponse::new(format!("API key {} is no longer valid", key_id)));
+        }
+        if let Some(replaced_by) = &old.replaced_by {
+            return Err(ErrorResponse::new(format!("API key {} was already rotated to {}", key_id, replaced_by)));
+        }
+        let replacement = KeyRecord::new(&digest(&key), old.scopes.clone(), true);
+        let expires_at = now.saturating_add(request.overlap_seconds.unwrap_or(default_overlap));
+        old.expires_at = Some(expires_at);
+        old.replaced_by = Some(replacement.id.clone());
+
+        let response = RotateKeyResponse {
+            key,
+            key_id: replacement.id.clone(),
+            scopes: replacement.scopes.clone(),
+            replaces: key_id.clone(),
+            old_key_expires_at: expires_at,
+        };
+        store.records.push(replacement);
+        Ok(SuccessResponse::new(response))
+    })
+}
+
+// Stops the key working at once. Its record stays, so it shows in the key
+// list and can't come back from API_KEYS.
+pub fn revoke_key(keys: &ApiKeys, key_id: String) -> Result<SuccessResponse<ApiKeyInfo>, ErrorResponse> {
+    let mut store = keys.0.lock().unwrap();
+    store.update(|store| {
+        let record = store.find(&key_id)?;
+        record.revoked_at.get_or_insert_with(now);
+        Ok(SuccessResponse::new(record.info()))
+    })
+}
+
+// What a request was authenticated for; the key ID is unset when the API is
+// open or the work is in-process
+#[derive(Clone)]
+pub struct Grant {
+    key_id: Option<String>,
+    scopes: Vec<Scope>,
+}
+
+impl Grant {
+    fn all() -> Self {
+        Grant { key_id: None, scopes: Scope::ALL.to_vec() }
+    }
+
+    pub fn permits(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+tokio::task_local! {
+    static GRANTED: Grant;
+}
+
+// Finds the configured key presented in the headers, or grants everything
+// when no keys are configured
+pub fn grant(config: &AppConfig, headers: &HeaderMap) -> Result<Grant, ErrorResponse> {
+    if config.api_keys.is_empty() {
+        return Ok(Grant::all());
+    }
+
+    let presented = headers.get(&HEADER).map(|value| digest(value.to_str().unwrap_or_default()));
+    presented
+        .and_then(|presented| config.api_keys.record_use(&presented))
+        .ok_or_else(|| ErrorResponse::with_code(ApiErrorCode::InvalidApiKey, "A valid X-API-Key header is required"))
+}
+
+// Runs the work with the grant, for permits() and current_key_id() to read
+pub async fn with_grant<F: Future>(grant: Grant, work: F) -> F::Output {
+    GRANTED.scope(grant, work).await
+}
+
+// Runs the work with the grant when there is one, for carrying the current
+// request's grant over to a task it spawns
+pub async fn scope<F: Future>(grant: Option<Grant>, work: F) -> F::Output {
+    match grant {
+        Some(grant) => with_grant(grant, work).await,
+        None => work.await,
+    }
+}
+
+pub fn current_grant() -> Option<Grant> {
+    GRANTED.try_with(Grant::clone).ok()
+}
+
+// For work the server starts itself rather than on a caller's behalf, which
+// may use every scope
+pub async fn in_process<F: Future>(work: F) -> F::Output {
+    with_grant(Grant::all(), work).await
+}
+
+// Whether the current request may use the scope. Nothing is permitted
+// outside an authenticated request or in_process().
+pub fn permits(scope: Scope) -> bool {
+    GRANTED.try_with(|granted| granted.permits(scope)).unwrap_or(false)
+}
+
+// The ID of the API key the current request presented
+pub fn current_key_id() -> Option<String> {
+    GRANTED.try_with(|granted| granted.key_id.clone()).ok().flatten()
+}
+
+pub fn missing_scope(scope: Scope) -> ErrorResponse {
+    ErrorResponse::with_code(ApiErrorCode::NotAuthorized, format!("This API key does not have the {} scope", scope))
+}
+
+fn reject(status: StatusCode, locale: Locale, err: ErrorResponse) -> Response {
+    (status, Json(err.translated(locale))).into_response()
+}
+
+pub async fn authenticate(State(config): State<AppConfig>, request: Request<Body>, next: Next) -> Response {
+    match grant(&config, request.headers()) {
+        Ok(grant) => with_grant(grant, next.run(request)).await,
+        Err(err) => reject(StatusCode::UNAUTHORIZED, Locale::from_headers(request.headers()), err),
+    }
+}
+
+async fn require(State(scope): State<Scope>, request: Request<Body>, next: Next) -> Response {
+    if !permits(scope) {
+        return reject(StatusCode::FORBIDDEN, Locale::from_headers(request.headers()), missing_scope(scope));
+    }
+    next.run(request).await
+}
+
+// Limits every route already on the router to keys with the scope
+pub fn restrict<S: Clone + Send + Sync + 'static>(router: Router<S>, scope: Scope) -> Router<S> {
+    router.route_layer(middleware::from_fn_with_state(scope, require))
+}
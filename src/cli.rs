@@ -0,0 +1,73 @@
+use clap::{Arg, Command};
+use crate::config::{self, AppConfig};
+use crate::utils::registry::{registry, Operation};
+
+pub enum Invocation {
+    // No subcommand was given, so the server should start
+    Serve(Box<AppConfig>),
+    // A subcommand ran, or the arguments were invalid; exit with this code
+    Exit(i32),
+}
+
+fn command() -> Command<'static> {
+    let command = Command::new(env!("CARGO_PKG_NAME"))
+        .about("Start the HTTP server, or run one of the subcommands")
+        .subcommand(Command::new("operations").about("List the registered instruction builders"))
+        .subcommand(
+            Command::new("build")
+                .about("Print the instructions for one operation")
+                .arg(Arg::new("operation").required(true))
+                .arg(Arg::new("json").required(true).help("The operation's parameters")),
+        );
+    config::args(command)
+}
+
+pub fn run(args: Vec<String>) -> Invocation {
+    let matches = match command().try_get_matches_from(args) {
+        Ok(matches) => matches,
+        Err(err) => {
+            // --help and --version are reported as errors, but on stdout
+            let _ = err.print();
+            return Invocation::Exit(if err.use_stderr() { 2 } else { 0 });
+        }
+    };
+
+    match matches.subcommand() {
+        Some(("operations", _)) => {
+            for builder in registry().builders() {
+                println!("{:<24}{}", builder.name(), builder.description());
+            }
+            Invocation::Exit(0)
+        }
+        Some(("build", build_matches)) => Invocation::Exit(build(
+            build_matches.get_one::<String>("operation").unwrap(),
+            build_matches.get_one::<String>("json").unwrap(),
+        )),
+        _ => Invocation::Serve(Box::new(AppConfig::from_matches(&matches))),
+    }
+}
+
+fn build(operation: &str, params: &str) -> i32 {
+    let params: serde_json::Value = match serde_json::from_str(params) {
+        Ok(params) => params,
+        Err(err) => {
+            eprintln!("Invalid JSON parameters: {}", err);
+            return 2;
+        }
+    };
+
+    let operation = Operation {
+        operation_type: operation.to_string(),
+        params,
+    };
+    match operation.build() {
+        Ok(instructions) => {
+            println!("{}", serde_json::to_string_pretty(&instructions).unwrap());
+            0
+        }
+        Err(err) => {
+            eprintln!("{}", err.error);
+            1
+        }
+    }
+}
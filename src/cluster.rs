@@ -0,0 +1,145 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::State,
+    http::{header, HeaderMap, HeaderName, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use std::{future::Future, time::Duration};
+use crate::config::{self, AppConfig, Cluster};
+use crate::limits;
+use crate::utils::{
+    i18n::Locale,
+    rpc,
+    ErrorResponse,
+};
+
+// Requests pick the cluster their RPC calls go to with this header, or with
+// a top-level `cluster` field in a JSON body, naming mainnet-beta, devnet,
+// testnet or localnet, or giving one of the RPC URLs in CLUSTER_RPC_URLS.
+// Requests that pick nothing use the configured cluster.
+pub const HEADER: HeaderName = HeaderName::from_static("x-solana-cluster");
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Target {
+    Named(Cluster),
+    Url(String),
+}
+
+impl Target {
+    pub fn parse(value: &str) -> Result<Self, ErrorResponse> {
+        let value = value.trim();
+        if let Some(cluster) = Cluster::parse(value) {
+            return Ok(Target::Named(cluster));
+        }
+        if config::get().cluster_rpc_urls.iter().any(|url| url == value) {
+            return Ok(Target::Url(value.to_string()));
+        }
+        Err(ErrorResponse::new("cluster must be mainnet-beta, devnet, testnet, localnet or an allowed RPC URL"))
+    }
+
+    pub fn rpc_url(&self) -> &str {
+        match self {
+            Target::Named(cluster) => cluster.default_rpc_url(),
+            Target::Url(url) => url,
+        }
+    }
+
+    // Safe to log, since RPC URLs often carry an API key
+    pub fn label(&self) -> String {
+        match self {
+            Target::Named(cluster) => cluster.name().to_string(),
+            Target::Url(url) => rpc::display_url(url),
+        }
+    }
+}
+
+tokio::task_local! {
+    static CLUSTER: Target;
+}
+
+// The cluster picked by the current request, unless it is the one the
+// server is configured for, whose providers and caches are used as normal
+pub fn current() -> Option<Target> {
+    CLUSTER
+        .try_with(Target::clone)
+        .ok()
+        .filter(|target| *target != Target::Named(config::get().cluster))
+}
+
+// The cluster the current work is for, for logs and traces
+pub fn label() -> String {
+    current().map_or_else(|| config::get().cluster.name().to_string(), |target| target.label())
+}
+
+// Run work for a cluster picked earlier, such as a job taken off the queue
+// or a watch spawned from a request, which otherwise would fall back to the
+// configured cluster
+pub async fn scope<F: Future>(target: Option<Target>, work: F) -> F::Output {
+    match target {
+        Some(target) => CLUSTER.scope(target, work).await,
+        None => work.await,
+    }
+}
+
+pub fn from_headers(headers: &HeaderMap) -> Result<Option<Target>, ErrorResponse> {
+    headers
+        .get(&HEADER)
+        .map(|value| Target::parse(value.to_str().unwrap_or_default()))
+        .transpose()
+}
+
+#[derive(Deserialize)]
+struct ClusterField {
+    cluster: Option<String>,
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"))
+}
+
+fn reject(locale: Locale, err: ErrorResponse) -> Response {
+    (StatusCode::BAD_REQUEST, Json(err.translated(locale))).into_response()
+}
+
+// The header wins over the body field. Bodies are read here, ahead of the
+// routes' own limits, so they are held to the largest limit any route has
+// and to the default timeout.
+pub async fn select(State(config): State<AppConfig>, request: Request<Body>, next: Next) -> Response {
+    let locale = Locale::from_headers(request.headers());
+    let target = match from_headers(request.headers()) {
+        Ok(target) => target,
+        Err(err) => return reject(locale, err),
+    };
+    if target.is_some() || request.method() != Method::POST || !is_json(request.headers()) {
+        record_span(&target);
+        return scope(target, next.run(request)).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let read = to_bytes(body, limits::BATCH_BODY_LIMIT.max(config.body_limit_bytes));
+    // Given the usual error envelope by limits::json_errors
+    let bytes = match tokio::time::timeout(Duration::from_secs(config.request_timeout_secs), read).await {
+        Ok(Ok(bytes)) => bytes,
+        Ok(Err(_)) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+        Err(_) => return StatusCode::REQUEST_TIMEOUT.into_response(),
+    };
+    // Bodies that aren't a JSON object are left for the handler to reject
+    let field = serde_json::from_slice::<ClusterField>(&bytes).ok().and_then(|field| field.cluster);
+    let target = match field.as_deref().map(Target::parse).transpose() {
+        Ok(target) => target,
+        Err(err) => return reject(locale, err),
+    };
+    record_span(&target);
+    scope(target, next.run(Request::from_parts(parts, Body::from(bytes)))).await
+}
+
+// Fills in the request span's cluster
+fn record_span(target: &Option<Target>) {
+    let label = target.as_ref().map_or_else(|| config::get().cluster.name().to_string(), Target::label);
+    tracing::Span::current().record("cluster", label);
+}
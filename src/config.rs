@@ -0,0 +1,612 @@
+use clap::{value_parser, Arg, ArgMatches, Command};
+use std::sync::OnceLock;
+use crate::auth::{self, ApiKey, ApiKeys};
+
+static CONFIG: OnceLock<AppConfig> = OnceLock::new();
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Cluster {
+    MainnetBeta,
+    Devnet,
+    Testnet,
+    Localnet,
+}
+
+impl Cluster {
+    pub const NAMES: [&'static str; 4] = ["mainnet-beta", "devnet", "testnet", "localnet"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "mainnet-beta" => Some(Cluster::MainnetBeta),
+            "devnet" => Some(Cluster::Devnet),
+            "testnet" => Some(Cluster::Testnet),
+            "localnet" => Some(Cluster::Localnet),
+            _ => None,
+        }
+    }
+
+    fn from_name(name: &str) -> Self {
+        Self::parse(name).unwrap_or(Cluster::Devnet)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Cluster::MainnetBeta => "mainnet-beta",
+            Cluster::Devnet => "devnet",
+            Cluster::Testnet => "testnet",
+            Cluster::Localnet => "localnet",
+        }
+    }
+
+    // Public endpoint used when no RPC URL is configured
+    pub fn default_rpc_url(&self) -> &'static str {
+        match self {
+            Cluster::MainnetBeta => "https://api.mainnet-beta.solana.com",
+            Cluster::Devnet => "https://api.devnet.solana.com",
+            Cluster::Testnet => "https://api.testnet.solana.com",
+            Cluster::Localnet => "http://127.0.0.1:8899",
+        }
+    }
+}
+
+// Server settings, each taken from a command-line flag or else its
+// environment variable. Handlers receive it as axum state; code outside a
+// request reads it through config::get().
+#[derive(Clone, Debug)]
+pub struct AppConfig {
+    pub host: String,
+    // Each port gets its own listener; none when serve_tcp is off
+    pub ports: Vec<u16>,
+    // Also serve plain HTTP on this Unix domain socket, for services on the
+    // same host
+    pub unix_socket_path: Option<String>,
+    // Also serve the gRPC API on this port; needs the grpc feature
+    pub grpc_port: Option<u16>,
+    // PEM certificate chain and private key; HTTPS is served when both are set
+    pub tls_cert_path: Option<String>,
+    pub tls_key_path: Option<String>,
+    // How long to wait for in-flight requests after a shutdown signal
+    pub shutdown_timeout_secs: u64,
+    // Limits for routes without their own; see limits.rs for the overrides
+    pub request_timeout_secs: u64,
+    pub body_limit_bytes: usize,
+    // Public URL of the deployment, advertised in the OpenAPI spec
+    pub base_url: String,
+    pub cluster: Cluster,
+    // Override the cluster's public endpoint, in order of preference; later
+    // ones are failed over to when earlier ones error or rate-limit
+    pub rpc_urls: Vec<String>,
+    // Further attempts for a read that failed with a transient error
+    pub rpc_max_retries: u32,
+    pub ws_url: Option<String>,
+    // Endpoints requests may pick with X-Solana-Cluster besides the named
+    // clusters; any other URL is refused so callers can't aim the server at
+    // arbitrary hosts
+    pub cluster_rpc_urls: Vec<String>,
+    // Empty allows any origin
+    pub cors_origins: Vec<String>,
+    pub ip_rate_limit: u32,
+    pub wallet_rate_limit: u32,
+    // Take the client address from Fly-Client-IP or X-Forwarded-For rather
+    // than the socket peer; only safe behind a proxy that sets them
+    pub trust_proxy_headers: bool,
+    // Keys the HTTP API requires, each with its scopes, along with those
+    // issued by rotation; open when empty
+    pub api_keys: ApiKeys,
+    // How long a rotated API key keeps working alongside its replacement
+    pub api_key_overlap_secs: u64,
+    // Shares Idempotency-Key records between instances; kept in memory when unset
+    pub idempotency_redis_url: Option<String>,
+    // Encrypts keys stored with /vault/keys; the vault is off when unset
+    pub vault_master_key: Option<String>,
+    pub vault_path: String,
+    // Append-only log of signing and key generation; kept in memory when unset
+    pub audit_log_path: Option<String>,
+    // Remote signers selected with signerBackend
+    pub transit_addr: Option<String>,
+    pub transit_token: Option<String>,
+    pub transit_mount: String,
+    // The only keys requests may name for each backend; a backend with
+    // none listed can't be used
+    pub transit_key_ids: Vec<String>,
+    pub kms_region: Option<String>,
+    pub kms_key_ids: Vec<String>,
+    // Override the regional KMS endpoint, such as for a VPC endpoint
+    pub kms_endpoint: Option<String>,
+    pub signer_sidecar_url: Option<String>,
+    // Tasks signing and broadcasting /jobs/submit transactions
+    pub job_workers: usize,
+    // Signs webhook deliveries; submissions can only ask for callbacks when set
+    pub webhook_secret: Option<String>,
+    // OTLP collector spans are exported to; needs the otel feature
+    pub otlp_endpoint: Option<String>,
+    // Solana Pay transaction requests at /pay/:reference pay pay_amount
+    // lamports, or base units of pay_spl_token, to pay_recipient; off
+    // unless both are set
+    pub pay_recipient: Option<String>,
+    pub pay_amount: Option<u64>,
+    pub pay_spl_token: Option<String>,
+    // Shown by wallets before the payer approves
+    pub pay_label: String,
+    pub pay_icon: Option<String>,
+    pub pay_message: Option<String>,
+    // Fee payer for /transaction/sponsor, which is off unless set
+    pub sponsor_secret_key: Option<String>,
+    // Feature flags
+    pub preload_on_startup: bool,
+    pub readiness_rpc_check: bool,
+    // Answer RPC calls from an in-memory mock instead of a cluster, for
+    // integration tests; /health reports it
+    pub mock_rpc: bool,
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// Accepts the values the environment variables have always taken
+fn parse_switch(value: &str) -> Result<bool, String> {
+    match value {
+        "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        _ => Err(format!("expected true or false, got '{}'", value)),
+    }
+}
+
+pub fn args(command: Command<'static>) -> Command<'static> {
+    command
+        .arg(Arg::new("host").long("host").env("HOST").default_value("0.0.0.0").help("Address to listen on"))
+        .arg(
+            Arg::new("port")
+                .long("port")
+                .env("PORT")
+                .default_value("3000")
+                .value_delimiter(',')
+                .value_parser(value_parser!(u16))
+                .help("Ports to listen on, comma-separated"),
+        )
+        .arg(
+            Arg::new("unix-socket")
+                .long("unix-socket")
+                .env("UNIX_SOCKET_PATH")
+                .takes_value(true)
+                .help("Also serve plain HTTP on a Unix domain socket at this path, readable by the owner and group"),
+        )
+        .arg(
+            Arg::new("grpc-port")
+                .long("grpc-port")
+                .env("GRPC_PORT")
+                .takes_value(true)
+                .value_parser(value_parser!(u16))
+                .help("Port to serve the gRPC API on; requires the grpc feature"),
+        )
+        .arg(
+            Arg::new("tls-cert")
+                .long("tls-cert")
+                .env("TLS_CERT_PATH")
+                .takes_value(true)
+                .requires("tls-key")
+                .help("PEM certificate chain; serves HTTPS instead of HTTP"),
+        )
+        .arg(
+            Arg::new("tls-key")
+                .long("tls-key")
+                .env("TLS_KEY_PATH")
+                .takes_value(true)
+                .requires("tls-cert")
+                .help("PEM private key for the certificate"),
+        )
+        .arg(
+            Arg::new("shutdown-timeout")
+                .long("shutdown-timeout")
+                .env("SHUTDOWN_TIMEOUT_SECS")
+                .default_value("25")
+                .value_parser(value_parser!(u64))
+                .help("Seconds to let in-flight requests finish after SIGTERM or SIGINT"),
+        )
+        .arg(
+            Arg::new("request-timeout")
+                .long("request-timeout")
+                .env("REQUEST_TIMEOUT_SECS")
+                .default_value("30")
+                .value_parser(value_parser!(u64))
+                .help("Seconds a request may take before it fails with a timeout error"),
+        )
+        .arg(
+            Arg::new("body-limit")
+                .long("body-limit")
+                .env("REQUEST_BODY_LIMIT_BYTES")
+                .default_value("262144")
+                .value_parser(value_parser!(usize))
+                .help("Largest request body accepted, in bytes"),
+        )
+        .arg(
+            Arg::new("base-url")
+                .long("base-url")
+                .env("BASE_URL")
+                .default_value("https://rust-server-superdev.fly.dev")
+                .help("Public URL advertised in the OpenAPI spec"),
+        )
+        .arg(
+            Arg::new("cluster")
+                .long("cluster")
+                .env("SOLANA_CLUSTER")
+                .default_value("devnet")
+                .value_parser(Cluster::NAMES)
+                .help("Cluster whose public RPC endpoint is used when no RPC URL is set"),
+        )
+        .arg(
+            Arg::new("rpc-url")
+                .long("rpc-url")
+                .env("SOLANA_RPC_URL")
+                .takes_value(true)
+                .help("Comma-separated JSON-RPC endpoints, in order of preference"),
+        )
+        .arg(
+            Arg::new("rpc-max-retries")
+                .long("rpc-max-retries")
+                .env("RPC_MAX_RETRIES")
+                .default_value("2")
+                .value_parser(value_parser!(u32))
+                .help("Retries for reads that fail with a transient error or rate limit"),
+        )
+        .arg(
+            Arg::new("ws-url")
+                .long("ws-url")
+                .env("SOLANA_WS_URL")
+                .takes_value(true)
+                .help("Pubsub endpoint; defaults to the RPC URL with a ws scheme"),
+        )
+        .arg(
+            Arg::new("cluster-rpc-urls")
+                .long("cluster-rpc-urls")
+                .env("CLUSTER_RPC_URLS")
+                .takes_value(true)
+                .help("Comma-separated RPC URLs requests may select with X-Solana-Cluster"),
+        )
+        .arg(
+            Arg::new("cors-origins")
+                .long("cors-origins")
+                .env("CORS_ORIGINS")
+                .takes_value(true)
+                .help("Comma-separated origins allowed by CORS; any origin when unset"),
+        )
+        .arg(
+            Arg::new("rate-limit")
+                .long("rate-limit")
+                .env("RATE_LIMIT_PER_MINUTE")
+                .default_value("60")
+                .value_parser(value_parser!(u32))
+                .help("Requests per minute for each client IP"),
+        )
+        .arg(
+            Arg::new("wallet-rate-limit")
+                .long("wallet-rate-limit")
+                .env("WALLET_RATE_LIMIT_PER_MINUTE")
+                .default_value("120")
+                .value_parser(value_parser!(u32))
+                .help("Requests per minute for each signed-in wallet"),
+        )
+        .arg(
+            Arg::new("trust-proxy-headers")
+                .long("trust-proxy-headers")
+                .env("TRUST_PROXY_HEADERS")
+                .default_value("false")
+                .value_parser(parse_switch)
+                .help("Rate limit by the client address in Fly-Client-IP or X-Forwarded-For; only enable behind a proxy that sets them"),
+        )
+        .arg(
+            Arg::new("api-keys")
+                .long("api-keys")
+                .env("API_KEYS")
+                .takes_value(true)
+                .hide_env_values(true)
+                .value_parser(auth::parse_api_keys)
+                .help("Comma-separated API keys, each `key` or `key:scope+scope` with scopes read, build, sign, submit and admin"),
+        )
+        .arg(
+            Arg::new("api-key-store-path")
+                .long("api-key-store-path")
+                .env("API_KEY_STORE_PATH")
+                .default_value("api_keys.json")
+                .help("File keeping API keys issued by rotation, and which keys are rotated or revoked"),
+        )
+        .arg(
+            Arg::new("api-key-overlap-secs")
+                .long("api-key-overlap-secs")
+                .env("API_KEY_OVERLAP_SECS")
+                .default_value("604800")
+                .value_parser(value_parser!(u64))
+                .help("Seconds a rotated API key stays valid alongside its replacement, unless the rotation sets its own"),
+        )
+        .arg(
+            Arg::new("idempotency-redis-url")
+                .long("idempotency-redis-url")
+                .env("IDEMPOTENCY_REDIS_URL")
+                .takes_value(true)
+                .help("Redis URL for Idempotency-Key records; requires the redis feature"),
+        )
+        .arg(
+            Arg::new("vault-master-key")
+                .long("vault-master-key")
+                .env("VAULT_MASTER_KEY")
+                .takes_value(true)
+                .hide_env_values(true)
+                .help("32-byte key, as hex or base64, encrypting the key vault; enables /vault/keys"),
+        )
+        .arg(
+            Arg::new("vault-path")
+                .long("vault-path")
+                .env("VAULT_PATH")
+                .default_value("vault.json")
+                .help("File the key vault is kept in"),
+        )
+        .arg(
+            Arg::new("audit-log-path")
+                .long("audit-log-path")
+                .env("AUDIT_LOG_PATH")
+                .takes_value(true)
+                .help("File signing and key generation are recorded in, as JSON lines"),
+        )
+        .arg(
+            Arg::new("transit-addr")
+                .long("transit-addr")
+                .env("HASHICORP_VAULT_ADDR")
+                .takes_value(true)
+                .help("HashiCorp Vault address for the vault-transit signer backend"),
+        )
+        .arg(
+            Arg::new("transit-token")
+                .long("transit-token")
+                .env("HASHICORP_VAULT_TOKEN")
+                .takes_value(true)
+                .hide_env_values(true)
+                .help("HashiCorp Vault token allowed to read and sign with transit keys"),
+        )
+        .arg(
+            Arg::new("transit-mount")
+                .long("transit-mount")
+                .env("HASHICORP_VAULT_TRANSIT_MOUNT")
+                .default_value("transit")
+                .help("Path the transit secrets engine is mounted at"),
+        )
+        .arg(
+            Arg::new("transit-key-ids")
+                .long("transit-key-ids")
+                .env("HASHICORP_VAULT_TRANSIT_KEY_IDS")
+                .takes_value(true)
+                .help("Comma-separated transit keys requests may sign with"),
+        )
+        .arg(
+            Arg::new("kms-region")
+                .long("kms-region")
+                .env("AWS_REGION")
+                .takes_value(true)
+                .help("AWS region for the aws-kms signer backend; credentials come from the AWS_* variables"),
+        )
+        .arg(
+            Arg::new("kms-endpoint")
+                .long("kms-endpoint")
+                .env("KMS_ENDPOINT")
+                .takes_value(true)
+                .help("KMS endpoint to use instead of the region's public one"),
+        )
+        .arg(
+            Arg::new("kms-key-ids")
+                .long("kms-key-ids")
+                .env("KMS_KEY_IDS")
+                .takes_value(true)
+                .help("Comma-separated KMS key ids, ARNs or aliases requests may sign with, as requests name them"),
+        )
+        .arg(
+            Arg::new("signer-sidecar-url")
+                .long("signer-sidecar-url")
+                .env("SIGNER_SIDECAR_URL")
+                .takes_value(true)
+                .help("Base URL of a signing sidecar for the sidecar signer backend"),
+        )
+        .arg(
+            Arg::new("job-workers")
+                .long("job-workers")
+                .env("JOB_WORKERS")
+                .default_value("4")
+                .value_parser(value_parser!(usize))
+                .help("Workers signing and broadcasting queued transaction jobs"),
+        )
+        .arg(
+            Arg::new("webhook-secret")
+                .long("webhook-secret")
+                .env("WEBHOOK_SECRET")
+                .takes_value(true)
+                .hide_env_values(true)
+                .help("Secret for the HMAC signature on transaction webhooks; enables callbackUrl"),
+        )
+        .arg(
+            Arg::new("otlp-endpoint")
+                .long("otlp-endpoint")
+                .env("OTEL_EXPORTER_OTLP_ENDPOINT")
+                .takes_value(true)
+                .help("OTLP gRPC collector to export request and RPC spans to; requires the otel feature"),
+        )
+        .arg(
+            Arg::new("pay-recipient")
+                .long("pay-recipient")
+                .env("SOLANA_PAY_RECIPIENT")
+                .takes_value(true)
+                .help("Wallet Solana Pay transaction requests pay; enables /pay with --pay-amount"),
+        )
+        .arg(
+            Arg::new("pay-amount")
+                .long("pay-amount")
+                .env("SOLANA_PAY_AMOUNT")
+                .takes_value(true)
+                .value_parser(value_parser!(u64))
+                .help("Amount Solana Pay transaction requests pay, in lamports or the token's base units"),
+        )
+        .arg(
+            Arg::new("pay-spl-token")
+                .long("pay-spl-token")
+                .env("SOLANA_PAY_SPL_TOKEN")
+                .takes_value(true)
+                .help("Mint Solana Pay transaction requests pay in, instead of SOL"),
+        )
+        .arg(
+            Arg::new("pay-label")
+                .long("pay-label")
+                .env("SOLANA_PAY_LABEL")
+                .default_value(env!("CARGO_PKG_NAME"))
+                .help("Merchant name wallets show for Solana Pay transaction requests"),
+        )
+        .arg(
+            Arg::new("pay-icon")
+                .long("pay-icon")
+                .env("SOLANA_PAY_ICON")
+                .takes_value(true)
+                .help("Absolute URL of an SVG, PNG or WebP icon wallets show for Solana Pay transaction requests"),
+        )
+        .arg(
+            Arg::new("pay-message")
+                .long("pay-message")
+                .env("SOLANA_PAY_MESSAGE")
+                .takes_value(true)
+                .help("Message wallets show with the transaction Solana Pay transaction requests return"),
+        )
+        .arg(
+            Arg::new("sponsor-secret-key")
+                .long("sponsor-secret-key")
+                .env("SPONSOR_SECRET_KEY")
+                .takes_value(true)
+                .hide_env_values(true)
+                .help("Base58 secret key paying fees for sponsored transactions; enables /transaction/sponsor"),
+        )
+        .arg(
+            Arg::new("preload-on-startup")
+                .long("preload-on-startup")
+                .env("PRELOAD_ON_STARTUP")
+                .default_value("false")
+                .value_parser(parse_switch)
+                .help("Warm caches before /ready passes"),
+        )
+        .arg(
+            Arg::new("readiness-rpc-check")
+                .long("readiness-rpc-check")
+                .env("READINESS_RPC_CHECK")
+                .default_value("true")
+                .value_parser(parse_switch)
+                .help("Have /ready make an RPC round trip"),
+        )
+        .arg(
+            Arg::new("serve-tcp")
+                .long("serve-tcp")
+                .env("SERVE_TCP")
+                .default_value("true")
+                .value_parser(parse_switch)
+                .help("Listen on the TCP ports; turn off to serve only on the Unix socket"),
+        )
+        .arg(
+            Arg::new("mock-rpc")
+                .long("mock-rpc")
+                .env("MOCK_RPC")
+                .default_value("false")
+                .value_parser(parse_switch)
+                .help("Answer RPC calls from a deterministic in-memory mock instead of a cluster, for integration tests"),
+        )
+}
+
+impl AppConfig {
+    pub fn from_matches(matches: &ArgMatches) -> Self {
+        let string = |name: &str| matches.get_one::<String>(name).cloned();
+        AppConfig {
+            host: string("host").unwrap_or_default(),
+            ports: if *matches.get_one("serve-tcp").unwrap() {
+                matches.get_many::<u16>("port").unwrap().copied().collect()
+            } else {
+                Vec::new()
+            },
+            unix_socket_path: string("unix-socket"),
+            grpc_port: matches.get_one("grpc-port").copied(),
+            tls_cert_path: string("tls-cert"),
+            tls_key_path: string("tls-key"),
+            shutdown_timeout_secs: *matches.get_one("shutdown-timeout").unwrap(),
+            request_timeout_secs: *matches.get_one("request-timeout").unwrap(),
+            body_limit_bytes: *matches.get_one("body-limit").unwrap(),
+            base_url: string("base-url").unwrap_or_default(),
+            cluster: Cluster::from_name(&string("cluster").unwrap_or_default()),
+            rpc_urls: string("rpc-url").map(|urls| split_list(&urls)).unwrap_or_default(),
+            rpc_max_retries: *matches.get_one("rpc-max-retries").unwrap(),
+            ws_url: string("ws-url"),
+            cluster_rpc_urls: string("cluster-rpc-urls").map(|urls| split_list(&urls)).unwrap_or_default(),
+            cors_origins: string("cors-origins")
+                .map(|origins| split_list(&origins).into_iter().filter(|origin| origin != "*").collect())
+                .unwrap_or_default(),
+            ip_rate_limit: *matches.get_one("rate-limit").unwrap(),
+            wallet_rate_limit: *matches.get_one("wallet-rate-limit").unwrap(),
+            trust_proxy_headers: *matches.get_one("trust-proxy-headers").unwrap(),
+            api_keys: ApiKeys::load(
+                matches.get_one::<Vec<ApiKey>>("api-keys").cloned().unwrap_or_default(),
+                string("api-key-store-path").as_deref(),
+            ),
+            api_key_overlap_secs: *matches.get_one("api-key-overlap-secs").unwrap(),
+            idempotency_redis_url: string("idempotency-redis-url"),
+            vault_master_key: string("vault-master-key"),
+            vault_path: string("vault-path").unwrap_or_default(),
+            audit_log_path: string("audit-log-path"),
+            transit_addr: string("transit-addr"),
+            transit_token: string("transit-token"),
+            transit_mount: string("transit-mount").unwrap_or_default(),
+            transit_key_ids: string("transit-key-ids").map(|ids| split_list(&ids)).unwrap_or_default(),
+            kms_region: string("kms-region"),
+            kms_key_ids: string("kms-key-ids").map(|ids| split_list(&ids)).unwrap_or_default(),
+            kms_endpoint: string("kms-endpoint"),
+            signer_sidecar_url: string("signer-sidecar-url"),
+            job_workers: *matches.get_one("job-workers").unwrap(),
+            webhook_secret: string("webhook-secret"),
+            otlp_endpoint: string("otlp-endpoint"),
+            pay_recipient: string("pay-recipient"),
+            pay_amount: matches.get_one("pay-amount").copied(),
+            pay_spl_token: string("pay-spl-token"),
+            pay_label: string("pay-label").unwrap_or_default(),
+            pay_icon: string("pay-icon"),
+            pay_message: string("pay-message"),
+            sponsor_secret_key: string("sponsor-secret-key"),
+            preload_on_startup: *matches.get_one("preload-on-startup").unwrap(),
+            readiness_rpc_check: *matches.get_one("readiness-rpc-check").unwrap(),
+            mock_rpc: *matches.get_one("mock-rpc").unwrap(),
+        }
+    }
+
+    // Settings from the environment alone, for code that runs without the
+    // server, such as CLI subcommands, tests and services embedding the router
+    pub fn from_env() -> Self {
+        let matches = args(Command::new(env!("CARGO_PKG_NAME"))).get_matches_from([env!("CARGO_PKG_NAME")]);
+        Self::from_matches(&matches)
+    }
+
+    // The configured endpoints, or the cluster's public one
+    pub fn rpc_urls(&self) -> Vec<String> {
+        if self.rpc_urls.is_empty() {
+            vec![self.cluster.default_rpc_url().to_string()]
+        } else {
+            self.rpc_urls.clone()
+        }
+    }
+
+    // The preferred endpoint
+    pub fn rpc_url(&self) -> String {
+        self.rpc_urls().swap_remove(0)
+    }
+}
+
+// Must be called before anything reads the config; later calls are ignored
+pub fn init(config: AppConfig) {
+    let _ = CONFIG.set(config);
+}
+
+pub fn get() -> &'static AppConfig {
+    CONFIG.get_or_init(AppConfig::from_env)
+}
@@ -0,0 +1,503 @@
+use axum::http;
+use futures::future::BoxFuture;
+use std::task::{Context, Poll};
+use tokio_util::sync::CancellationToken;
+use tonic::{
+    body::BoxBody, metadata::{MetadataKey, MetadataMap, MetadataValue}, transport::Server, Code, Request, Response, Status,
+};
+use tower::{layer::layer_fn, Service};
+use crate::auth::{self, Scope};
+use crate::cluster;
+use crate::config;
+use crate::idempotency;
+use crate::logging;
+use crate::shutdown;
+use crate::utils::{
+    self, i18n::Locale, ApiErrorCode, BatchInstruction, ErrorResponse, InstructionResponse, SuccessResponse,
+};
+
+// Generated by build.rs from proto/superdev.proto
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("superdev.v1");
+}
+
+use proto::{
+    keypair_service_server::{KeypairService, KeypairServiceServer},
+    message_service_server::{MessageService, MessageServiceServer},
+    token_service_server::{TokenService, TokenServiceServer},
+    transaction_service_server::{TransactionService, TransactionServiceServer},
+    transfer_service_server::{TransferService, TransferServiceServer},
+};
+
+// Accept-Language is read from the call's metadata, as from HTTP headers
+fn locale(metadata: &MetadataMap) -> Locale {
+    Locale::from_headers(&metadata.clone().into_headers())
+}
+
+// Errors are logged and translated as respond() does for HTTP, and the
+// error body goes in the status details so clients get its code and fields
+fn status(locale: Locale, err: ErrorResponse) -> Status {
+    tracing::warn!(error = %logging::redact_secrets(&err.error), "gRPC request failed");
+    let err = err.translated(locale);
+
+    let code = match err.code {
+        ApiErrorCode::RpcUnavailable | ApiErrorCode::SignerUnavailable => Code::Unavailable,
+        ApiErrorCode::RateLimited | ApiErrorCode::BodyTooLarge => Code::ResourceExhausted,
+        ApiErrorCode::Timeout => Code::DeadlineExceeded,
+        ApiErrorCode::NotAuthorized => Code::PermissionDenied,
+        ApiErrorCode::InvalidApiKey => Code::Unauthenticated,
+        ApiErrorCode::AccountNotEmpty => Code::FailedPrecondition,
+        ApiErrorCode::InstructionFailed => Code::Internal,
+        _ => Code::InvalidArgument,
+    };
+    let details = serde_json::to_vec(&err).unwrap();
+    Status::with_details(code, err.error, details.into())
+}
+
+// Status is the error type tonic requires, however large
+#[allow(clippy::result_large_err)]
+fn reply<T, U: From<T>>(locale: Locale, result: Result<SuccessResponse<T>, ErrorResponse>) -> Result<Response<U>, Status> {
+    result
+        .map(|response| Response::new(response.data.into()))
+        .map_err(|err| status(locale, err))
+}
+
+// The API key scope a method needs, matching its HTTP route
+fn scope(path: &str) -> Scope {
+    match path.strip_prefix("/superdev.v1.").unwrap_or(path) {
+        "MessageService/Sign" | "TransactionService/Sign" => Scope::Sign,
+        "MessageService/Verify" => Scope::Read,
+        "TransactionService/Submit" => Scope::Submit,
+        _ => Scope::Build,
+    }
+}
+
+// Checks x-api-key metadata against the same keys as the HTTP API, and runs
+// the call with the key's grant so signing is audited under it
+#[derive(Clone)]
+struct Authenticate<S>(S);
+
+impl<S, B> Service<http::Request<B>> for Authenticate<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let scope = scope(request.uri().path());
+        let grant = auth::grant(config::get(), request.headers()).and_then(|grant| {
+            if grant.permits(scope) { Ok(grant) } else { Err(auth::missing_scope(scope)) }
+        });
+        match grant {
+            Ok(grant) => {
+                // The service that was polled ready takes the call
+                let ready = self.0.clone();
+                let mut inner = std::mem::replace(&mut self.0, ready);
+                Box::pin(auth::with_grant(grant, inner.call(request)))
+            }
+            Err(err) => {
+                let response = status(Locale::from_headers(request.headers()), err).into_http();
+                Box::pin(async move { Ok(response) })
+            }
+        }
+    }
+}
+
+// Out-of-range values become ones validation rejects with its usual error
+fn decimals(decimals: u32) -> u8 {
+    u8::try_from(decimals).unwrap_or(u8::MAX)
+}
+
+impl From<BatchInstruction> for proto::Instruction {
+    fn from(instruction: BatchInstruction) -> Self {
+        proto::Instruction {
+            program_id: instruction.program_id,
+            accounts: instruction.accounts.into_iter().map(|account| proto::AccountMeta {
+                pubkey: account.pubkey,
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            }).collect(),
+            instruction_data: instruction.instruction_data,
+        }
+    }
+}
+
+impl From<InstructionResponse> for proto::Instruction {
+    fn from(response: InstructionResponse) -> Self {
+        BatchInstruction::from(response).into()
+    }
+}
+
+impl From<utils::CreateTokenResponse> for proto::Instruction {
+    fn from(response: utils::CreateTokenResponse) -> Self {
+        response.instruction.into()
+    }
+}
+
+impl From<proto::PriorityFee> for utils::compute_budget::PriorityFee {
+    fn from(fee: proto::PriorityFee) -> Self {
+        utils::compute_budget::PriorityFee {
+            micro_lamports_per_unit: fee.micro_lamports_per_unit,
+            unit_limit: fee.unit_limit,
+        }
+    }
+}
+
+impl From<utils::KeypairResponse> for proto::KeypairResponse {
+    fn from(response: utils::KeypairResponse) -> Self {
+        proto::KeypairResponse {
+            pubkey: response.pubkey,
+            secret: response.secret,
+            mnemonic: response.mnemonic,
+            derivation_path: response.derivation_path,
+        }
+    }
+}
+
+impl From<utils::MintTokenResponse> for proto::MintTokenResponse {
+    fn from(response: utils::MintTokenResponse) -> Self {
+        proto::MintTokenResponse {
+            instruction: Some(response.instruction.into()),
+            deprecation: response.deprecation,
+        }
+    }
+}
+
+macro_rules! impl_into_transfer_response {
+    ($($response:ty),*) => {
+        $(
+            impl From<$response> for proto::TransferResponse {
+                fn from(response: $response) -> Self {
+                    proto::TransferResponse {
+                        instruction: Some(response.instruction.into()),
+                        compute_budget_instructions: response.compute_budget_instructions
+                            .into_iter()
+                            .map(|instruction| BatchInstruction::from(instruction).into())
+                            .collect(),
+                        memo_instruction: response.memo_instruction.map(|instruction| BatchInstruction::from(instruction).into()),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_into_transfer_response!(utils::SendSolResponse, utils::SendTokenResponse);
+
+impl From<proto::SigningFormat> for utils::SigningFormat {
+    fn from(format: proto::SigningFormat) -> Self {
+        match format {
+            proto::SigningFormat::Raw => utils::SigningFormat::Raw,
+            proto::SigningFormat::Offchain => utils::SigningFormat::Offchain,
+        }
+    }
+}
+
+impl From<proto::MessageEncoding> for utils::MessageEncoding {
+    fn from(encoding: proto::MessageEncoding) -> Self {
+        match encoding {
+            proto::MessageEncoding::Utf8 => utils::MessageEncoding::Utf8,
+            proto::MessageEncoding::Base64 => utils::MessageEncoding::Base64,
+            proto::MessageEncoding::Hex => utils::MessageEncoding::Hex,
+        }
+    }
+}
+
+impl From<proto::SignatureEncoding> for Option<utils::SignatureEncoding> {
+    fn from(encoding: proto::SignatureEncoding) -> Self {
+        match encoding {
+            proto::SignatureEncoding::Unspecified => None,
+            proto::SignatureEncoding::Base64 => Some(utils::SignatureEncoding::Base64),
+            proto::SignatureEncoding::Base58 => Some(utils::SignatureEncoding::Base58),
+        }
+    }
+}
+
+impl From<proto::Commitment> for utils::confirmation::Commitment {
+    fn from(commitment: proto::Commitment) -> Self {
+        match commitment {
+            proto::Commitment::Finalized => utils::confirmation::Commitment::Finalized,
+            proto::Commitment::Confirmed => utils::confirmation::Commitment::Confirmed,
+            proto::Commitment::Processed => utils::confirmation::Commitment::Processed,
+        }
+    }
+}
+
+impl From<proto::SignerBackend> for utils::signer::SignerBackend {
+    fn from(backend: proto::SignerBackend) -> Self {
+        match backend {
+            proto::SignerBackend::Local => utils::signer::SignerBackend::Local,
+            proto::SignerBackend::VaultTransit => utils::signer::SignerBackend::VaultTransit,
+            proto::SignerBackend::AwsKms => utils::signer::SignerBackend::AwsKms,
+            proto::SignerBackend::Sidecar => utils::signer::SignerBackend::Sidecar,
+        }
+    }
+}
+
+impl From<utils::SignMessageResponse> for proto::SignMessageResponse {
+    fn from(response: utils::SignMessageResponse) -> Self {
+        proto::SignMessageResponse {
+            signature: response.signature,
+            public_key: response.public_key,
+            message: response.message,
+        }
+    }
+}
+
+impl From<utils::VerifyMessageResponse> for proto::VerifyMessageResponse {
+    fn from(response: utils::VerifyMessageResponse) -> Self {
+        proto::VerifyMessageResponse {
+            valid: response.valid,
+            message: response.message,
+            pubkey: response.pubkey,
+        }
+    }
+}
+
+impl From<utils::SignTransactionResponse> for proto::SignTransactionResponse {
+    fn from(response: utils::SignTransactionResponse) -> Self {
+        proto::SignTransactionResponse {
+            transaction: response.transaction,
+            signed_by: response.signed_by,
+            missing_signers: response.missing_signers,
+            complete: response.complete,
+        }
+    }
+}
+
+impl From<utils::SubmitTransactionResponse> for proto::SubmitTransactionResponse {
+    fn from(response: utils::SubmitTransactionResponse) -> Self {
+        proto::SubmitTransactionResponse { signature: response.signature }
+    }
+}
+
+// Every service is answered by the same handlers as the HTTP routes
+struct Api;
+
+#[tonic::async_trait]
+impl KeypairService for Api {
+    async fn generate(&self, request: Request<proto::GenerateKeypairRequest>) -> Result<Response<proto::KeypairResponse>, Status> {
+        let locale = locale(request.metadata());
+        let request = request.into_inner();
+        reply(locale, utils::generate_keypair(utils::KeypairQuery {
+            mnemonic_words: request.mnemonic_words.map(|words| words as usize),
+            count: None,
+        }))
+    }
+
+    async fn from_mnemonic(&self, request: Request<proto::KeypairFromMnemonicRequest>) -> Result<Response<proto::KeypairResponse>, Status> {
+        let locale = locale(request.metadata());
+        let request = request.into_inner();
+        reply(locale, utils::keypair_from_mnemonic(utils::KeypairFromMnemonicRequest {
+            mnemonic: request.mnemonic,
+            passphrase: request.passphrase,
+            account_index: request.account_index,
+        }))
+    }
+}
+
+#[tonic::async_trait]
+impl TokenService for Api {
+    async fn create(&self, request: Request<proto::CreateTokenRequest>) -> Result<Response<proto::Instruction>, Status> {
+        let locale = locale(request.metadata());
+        let request = request.into_inner();
+        reply(locale, utils::create_token(utils::CreateTokenRequest {
+            mint_authority: request.mint_authority,
+            mint: request.mint,
+            decimals: decimals(request.decimals),
+            freeze_authority: None,
+            extensions: None,
+            initial_supply: None,
+            initial_holder: None,
+            payer: None,
+        }))
+    }
+
+    async fn mint(&self, request: Request<proto::MintTokenRequest>) -> Result<Response<proto::MintTokenResponse>, Status> {
+        let locale = locale(request.metadata());
+        let request = request.into_inner();
+        reply(locale, utils::mint_token(utils::MintTokenRequest {
+            mint_authority: request.mint_authority,
+            mint: request.mint,
+            destination: request.destination,
+            amount: request.amount,
+            decimals: request.decimals.map(decimals),
+        }))
+    }
+
+    async fn close_account(&self, request: Request<proto::CloseTokenAccountRequest>) -> Result<Response<proto::Instruction>, Status> {
+        let locale = locale(request.metadata());
+        let request = request.into_inner();
+        reply(locale, utils::close_token_account_checked(utils::CloseTokenAccountRequest {
+            account: request.account,
+            mint: request.mint,
+            owner: request.owner,
+            destination: request.destination,
+        }).await)
+    }
+
+    async fn approve(&self, request: Request<proto::ApproveTokenRequest>) -> Result<Response<proto::Instruction>, Status> {
+        let locale = locale(request.metadata());
+        let request = request.into_inner();
+        reply(locale, utils::approve_token(utils::ApproveTokenRequest {
+            mint: request.mint,
+            owner: request.owner,
+            delegate: request.delegate,
+            amount: request.amount,
+            account: request.account,
+            decimals: request.decimals.map(decimals),
+        }))
+    }
+
+    async fn revoke(&self, request: Request<proto::RevokeTokenRequest>) -> Result<Response<proto::Instruction>, Status> {
+        let locale = locale(request.metadata());
+        let request = request.into_inner();
+        reply(locale, utils::revoke_token(utils::RevokeTokenRequest {
+            account: request.account,
+            mint: request.mint,
+            owner: request.owner,
+        }))
+    }
+}
+
+#[tonic::async_trait]
+impl MessageService for Api {
+    async fn sign(&self, request: Request<proto::SignMessageRequest>) -> Result<Response<proto::SignMessageResponse>, Status> {
+        let locale = locale(request.metadata());
+        let request = request.into_inner();
+        let (format, encoding, signer_backend) = (request.format().into(), request.encoding().into(), request.signer_backend().into());
+        let signature_encoding = request.signature_encoding().into();
+        reply(locale, utils::sign_message(utils::SignMessageRequest {
+            message: request.message,
+            secret: request.secret,
+            key_id: request.key_id,
+            signer_backend,
+            format,
+            encoding,
+            signature_encoding,
+        }).await)
+    }
+
+    async fn verify(&self, request: Request<proto::VerifyMessageRequest>) -> Result<Response<proto::VerifyMessageResponse>, Status> {
+        let locale = locale(request.metadata());
+        let request = request.into_inner();
+        let (format, encoding) = (request.format().into(), request.encoding().into());
+        let signature_encoding = request.signature_encoding().into();
+        reply(locale, utils::verify_message(utils::VerifyMessageRequest {
+            message: request.message,
+            signature: request.signature,
+            pubkey: request.pubkey,
+            format,
+            encoding,
+            signature_encoding,
+        }))
+    }
+}
+
+#[tonic::async_trait]
+impl TransferService for Api {
+    async fn send_sol(&self, request: Request<proto::SendSolRequest>) -> Result<Response<proto::TransferResponse>, Status> {
+        let locale = locale(request.metadata());
+        let request = request.into_inner();
+        reply(locale, utils::send_sol_with_name_lookup(utils::SendSolRequest {
+            from: request.from,
+            to: request.to,
+            lamports: request.lamports,
+            priority_fee: request.priority_fee.map(Into::into),
+            memo: request.memo,
+        }).await)
+    }
+
+    async fn send_token(&self, request: Request<proto::SendTokenRequest>) -> Result<Response<proto::TransferResponse>, Status> {
+        let locale = locale(request.metadata());
+        let request = request.into_inner();
+        reply(locale, utils::send_token_with_mint_lookup(utils::SendTokenRequest {
+            destination: request.destination,
+            mint: request.mint,
+            owner: request.owner,
+            amount: request.amount,
+            decimals: request.decimals.map(decimals),
+            priority_fee: request.priority_fee.map(Into::into),
+            memo: request.memo,
+        }).await)
+    }
+}
+
+#[tonic::async_trait]
+impl TransactionService for Api {
+    async fn sign(&self, request: Request<proto::SignTransactionRequest>) -> Result<Response<proto::SignTransactionResponse>, Status> {
+        let locale = locale(request.metadata());
+        let request = request.into_inner();
+        let signer_backend = request.signer_backend().into();
+        reply(locale, utils::sign_transaction(utils::SignTransactionRequest {
+            transaction: request.transaction,
+            secret_keys: request.secret_keys,
+            key_ids: request.key_ids,
+            signer_backend,
+        }).await)
+    }
+
+    // Shares Idempotency-Key records with POST /transaction/submit, so a
+    // retry is recognized whichever API it comes through
+    async fn submit(&self, request: Request<proto::SubmitTransactionRequest>) -> Result<Response<proto::SubmitTransactionResponse>, Status> {
+        let locale = locale(request.metadata());
+        let headers = request.metadata().clone().into_headers();
+        // Picked with x-solana-cluster metadata, as with the HTTP header
+        let target = cluster::from_headers(&headers).map_err(|err| status(locale, err))?;
+        let request = request.into_inner();
+        let commitment = request.commitment().into();
+        let request = utils::SubmitTransactionRequest {
+            transaction: request.transaction,
+            skip_preflight: request.skip_preflight,
+            commitment,
+            callback_url: request.callback_url,
+        };
+
+        let fingerprint = idempotency::fingerprint(&request);
+        let submission = async move {
+            idempotency::run(&headers, "transaction/submit", fingerprint, utils::submit_transaction(request)).await
+        };
+        let outcome = cluster::scope(target, shutdown::detached(submission)).await;
+        let result = outcome.result.map(|response| {
+            SuccessResponse::new(serde_json::from_value::<utils::SubmitTransactionResponse>(response.data).unwrap())
+        });
+        let mut response = reply(locale, result)?;
+        if outcome.replayed {
+            let key = MetadataKey::from_bytes(idempotency::REPLAYED_HEADER.as_str().as_bytes()).unwrap();
+            response.metadata_mut().insert(key, MetadataValue::from_static("true"));
+        }
+        Ok(response)
+    }
+}
+
+// Serves the gRPC API on its own port until shutdown is cancelled. Calls
+// need an API key as HTTP requests do, but unlike the HTTP routes they are
+// not rate limited, being meant for trusted backends.
+pub async fn serve(addr: String, shutdown: CancellationToken) {
+    let Some(socket_addr) = tokio::net::lookup_host(&addr).await.ok().and_then(|mut addrs| addrs.next()) else {
+        tracing::error!(%addr, "cannot resolve the gRPC listen address");
+        return;
+    };
+
+    tracing::info!(addr = %socket_addr, "gRPC server is running");
+    let result = Server::builder()
+        .layer(layer_fn(Authenticate))
+        .add_service(KeypairServiceServer::new(Api))
+        .add_service(TokenServiceServer::new(Api))
+        .add_service(MessageServiceServer::new(Api))
+        .add_service(TransferServiceServer::new(Api))
+        .add_service(TransactionServiceServer::new(Api))
+        .serve_with_shutdown(socket_addr, shutdown.cancelled_owned())
+        .await;
+    if let Err(err) = result {
+        tracing::error!(error = %err, "gRPC server stopped");
+    }
+}
@@ -0,0 +1,242 @@
+use axum::http::{HeaderMap, HeaderName};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+use crate::config;
+use crate::utils::{ErrorResponse, SuccessResponse};
+
+pub const HEADER: HeaderName = HeaderName::from_static("idempotency-key");
+// Set to "true" on responses replayed from an earlier request
+pub const REPLAYED_HEADER: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+// How long a completed response is replayed for its key
+const TTL: Duration = Duration::from_secs(24 * 60 * 60);
+// How long a key stays claimed by a request that never finishes, e.g. when
+// the instance handling it dies
+const PENDING_TTL: Duration = Duration::from_secs(120);
+const MAX_KEY_LEN: usize = 255;
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Record {
+    // Hash of the request body the key was first used with
+    fingerprint: String,
+    // None while the first request is still running
+    response: Option<serde_json::Value>,
+}
+
+enum Claim {
+    Acquired,
+    Existing(Record),
+}
+
+// Connected on first use, so startup doesn't wait on Redis
+#[cfg(feature = "redis")]
+struct RedisStore {
+    client: redis::Client,
+    connection: tokio::sync::OnceCell<redis::aio::ConnectionManager>,
+}
+
+enum Store {
+    Memory(Mutex<HashMap<String, (Instant, Record)>>),
+    #[cfg(feature = "redis")]
+    Redis(Box<RedisStore>),
+}
+
+static STORE: OnceLock<Store> = OnceLock::new();
+
+// IDEMPOTENCY_REDIS_URL selects Redis, so retries are recognized whichever
+// instance they reach; otherwise records live in this process
+pub fn store() {
+    STORE.get_or_init(|| match &config::get().idempotency_redis_url {
+        None => Store::Memory(Mutex::new(HashMap::new())),
+        #[cfg(feature = "redis")]
+        Some(url) => {
+            let client = redis::Client::open(url.as_str())
+                .unwrap_or_else(|err| panic!("IDEMPOTENCY_REDIS_URL is not a valid Redis URL: {}", err));
+            Store::Redis(Box::new(RedisStore {
+                client,
+                connection: tokio::sync::OnceCell::new(),
+            }))
+        }
+        #[cfg(not(feature = "redis"))]
+        Some(_) => panic!("IDEMPOTENCY_REDIS_URL is set but this build does not include the redis feature"),
+    });
+}
+
+#[cfg(feature = "redis")]
+fn store_error(err: impl std::fmt::Display) -> ErrorResponse {
+    ErrorResponse::new(format!("Idempotency store unavailable: {}", err))
+}
+
+impl Store {
+    async fn claim(&self, key: &str, fingerprint: &str) -> Result<Claim, ErrorResponse> {
+        let pending = Record {
+            fingerprint: fingerprint.to_string(),
+            response: None,
+        };
+        match self {
+            Store::Memory(entries) => {
+                let now = Instant::now();
+                let mut entries = entries.lock().unwrap();
+                if let Some((expires_at, record)) = entries.get(key)
+                    && *expires_at > now
+                {
+                    return Ok(Claim::Existing(record.clone()));
+                }
+                if entries.len() > 100_000 {
+                    entries.retain(|_, (expires_at, _)| *expires_at > now);
+                }
+                entries.insert(key.to_string(), (now + PENDING_TTL, pending));
+                Ok(Claim::Acquired)
+            }
+            #[cfg(feature = "redis")]
+            Store::Redis(redis_store) => {
+                let mut connection = redis_store.connection().await?;
+                let value = serde_json::to_string(&pending).unwrap();
+                // The record can expire between SET NX and GET, so try again once
+                for _ in 0..2 {
+                    let claimed: Option<String> = redis::cmd("SET")
+                        .arg(key)
+                        .arg(&value)
+                        .arg("NX")
+                        .arg("EX")
+                        .arg(PENDING_TTL.as_secs())
+                        .query_async(&mut connection)
+                        .await
+                        .map_err(store_error)?;
+                    if claimed.is_some() {
+                        return Ok(Claim::Acquired);
+                    }
+                    let existing: Option<String> =
+                        redis::cmd("GET").arg(key).query_async(&mut connection).await.map_err(store_error)?;
+                    if let Some(existing) = existing {
+                        return serde_json::from_str(&existing).map(Claim::Existing).map_err(store_error);
+                    }
+                }
+                Err(store_error("record expired while it was being read"))
+            }
+        }
+    }
+
+    async fn complete(&self, key: &str, record: Record) -> Result<(), ErrorResponse> {
+        match self {
+            Store::Memory(entries) => {
+                entries.lock().unwrap().insert(key.to_string(), (Instant::now() + TTL, record));
+                Ok(())
+            }
+            #[cfg(feature = "redis")]
+            Store::Redis(redis_store) => {
+                let mut connection = redis_store.connection().await?;
+                redis::cmd("SET")
+                    .arg(key)
+                    .arg(serde_json::to_string(&record).unwrap())
+                    .arg("EX")
+                    .arg(TTL.as_secs())
+                    .query_async::<_, ()>(&mut connection)
+                    .await
+                    .map_err(store_error)
+            }
+        }
+    }
+
+    async fn release(&self, key: &str) -> Result<(), ErrorResponse> {
+        match self {
+            Store::Memory(entries) => {
+                entries.lock().unwrap().remove(key);
+                Ok(())
+            }
+            #[cfg(feature = "redis")]
+            Store::Redis(redis_store) => {
+                let mut connection = redis_store.connection().await?;
+                redis::cmd("DEL").arg(key).query_async::<_, ()>(&mut connection).await.map_err(store_error)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "redis")]
+impl RedisStore {
+    async fn connection(&self) -> Result<redis::aio::ConnectionManager, ErrorResponse> {
+        self.connection
+            .get_or_try_init(|| redis::aio::ConnectionManager::new(self.client.clone()))
+            .await
+            .cloned()
+            .map_err(store_error)
+    }
+}
+
+// Hash of a request body, so a key reused for a different request is caught
+pub fn fingerprint(request: &impl Serialize) -> String {
+    hex::encode(Sha256::digest(serde_json::to_vec(request).unwrap()))
+}
+
+pub struct Outcome {
+    pub result: Result<SuccessResponse<serde_json::Value>, ErrorResponse>,
+    pub replayed: bool,
+}
+
+fn fresh<T: Serialize>(result: Result<SuccessResponse<T>, ErrorResponse>) -> Result<SuccessResponse<serde_json::Value>, ErrorResponse> {
+    result.map(|response| SuccessResponse::new(serde_json::to_value(response.data).unwrap()))
+}
+
+// Runs a submission at most once per Idempotency-Key. A retry with the same
+// key and body gets the first response back instead of submitting again.
+// Failed submissions release the key so the client can retry them; requests
+// without the header are submitted as usual.
+pub async fn run<T, Fut>(headers: &HeaderMap, scope: &str, fingerprint: String, submit: Fut) -> Outcome
+where
+    T: Serialize,
+    Fut: Future<Output = Result<SuccessResponse<T>, ErrorResponse>>,
+{
+    let Some(key) = headers.get(&HEADER) else {
+        return Outcome { result: fresh(submit.await), replayed: false };
+    };
+    let key = match key.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LEN => format!("idempotency:{}:{}", scope, key),
+        _ => {
+            return Outcome {
+                result: Err(ErrorResponse::new(format!("Idempotency-Key must be 1 to {} visible ASCII characters", MAX_KEY_LEN))),
+                replayed: false,
+            };
+        }
+    };
+
+    let store = STORE.get().expect("idempotency::store() is called at startup");
+    let existing = match store.claim(&key, &fingerprint).await {
+        Ok(Claim::Acquired) => None,
+        Ok(Claim::Existing(record)) => Some(record),
+        Err(err) => return Outcome { result: Err(err), replayed: false },
+    };
+    if let Some(record) = existing {
+        let result = if record.fingerprint != fingerprint {
+            Err(ErrorResponse::new("Idempotency-Key was already used for a different request"))
+        } else if let Some(response) = record.response {
+            return Outcome { result: Ok(SuccessResponse::new(response)), replayed: true };
+        } else {
+            Err(ErrorResponse::new("A request with this Idempotency-Key is still in progress"))
+        };
+        return Outcome { result, replayed: false };
+    }
+
+    let result = fresh(submit.await);
+    let saved = match &result {
+        Ok(response) => {
+            let record = Record {
+                fingerprint,
+                response: Some(response.data.clone()),
+            };
+            store.complete(&key, record).await
+        }
+        Err(_) => store.release(&key).await,
+    };
+    // The submission already happened, so its result still goes back
+    if let Err(err) = saved {
+        tracing::warn!(error = %err.error, "failed to record idempotent response");
+    }
+    Outcome { result, replayed: false }
+}
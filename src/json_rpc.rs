@@ -0,0 +1,229 @@
+use axum::{
+    body::Bytes,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::future::join_all;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use crate::auth::{self, Scope};
+use crate::logging;
+use crate::utils::{self, i18n::Locale, ErrorResponse, SuccessResponse};
+
+// Calls in one batch request, which run concurrently
+const MAX_BATCH_CALLS: usize = 32;
+
+// Codes reserved by the JSON-RPC 2.0 specification
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+// Operations that fail report this, with the usual error body as data
+const OPERATION_FAILED: i64 = -32000;
+
+#[derive(Serialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError { code, message: message.into(), data: None }
+    }
+}
+
+#[derive(Serialize)]
+struct RpcResponse {
+    jsonrpc: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+    id: Value,
+}
+
+impl RpcResponse {
+    fn new(id: Value, outcome: Result<Value, RpcError>) -> Self {
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        RpcResponse { jsonrpc: "2.0", result, error, id }
+    }
+}
+
+fn params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|err| RpcError::new(INVALID_PARAMS, format!("Invalid params: {}", err)))
+}
+
+fn result<T: Serialize>(locale: Locale, result: Result<SuccessResponse<T>, ErrorResponse>) -> Result<Value, RpcError> {
+    result
+        .map(|response| serde_json::to_value(response.data).unwrap())
+        .map_err(|err| {
+            tracing::warn!(error = %logging::redact_secrets(&err.error), "JSON-RPC call failed");
+            let err = err.translated(locale);
+            RpcError {
+                code: OPERATION_FAILED,
+                message: err.error.clone(),
+                data: Some(serde_json::to_value(err).unwrap()),
+            }
+        })
+}
+
+// The API key scope a method needs, matching its HTTP route
+fn scope(method: &str) -> Scope {
+    match method {
+        "signMessage" | "signMessageSecp256k1" | "signTransaction" | "sponsorTransaction" => Scope::Sign,
+        "submitJob" => Scope::Submit,
+        "verifyMessage" | "verifyMessageBatch" | "explainTransaction" | "decodeInstruction" | "decodeTransaction"
+        | "estimateTransactionFee" | "decodeBorsh" | "deriveAddressWithSeed" | "verifyMessageSecp256k1" => Scope::Read,
+        _ => Scope::Build,
+    }
+}
+
+// Methods are named after the operation IDs in the OpenAPI spec and take
+// the HTTP request body as params. Operations needing headers, such as
+// sign-in, tenants and Idempotency-Key, are only served over HTTP.
+async fn call(locale: Locale, method: &str, p: Value) -> Result<Value, RpcError> {
+    if !auth::permits(scope(method)) {
+        return result::<()>(locale, Err(auth::missing_scope(scope(method))));
+    }
+    match method {
+        "generateKeypair" => {
+            let query: utils::KeypairQuery = params(p)?;
+            match query.count {
+                Some(_) => result(locale, utils::generate_keypairs(query).await),
+                None => result(locale, utils::generate_keypair(query)),
+            }
+        }
+        "keypairFromMnemonic" => result(locale, utils::keypair_from_mnemonic(params(p)?)),
+        "convertKeypair" => result(locale, utils::convert_keypair(params(p)?)),
+        "createToken" => result(locale, utils::create_token(params(p)?)),
+        "mintToken" => result(locale, utils::mint_token(params(p)?)),
+        "closeTokenAccount" => result(locale, utils::close_token_account_checked(params(p)?).await),
+        "approveToken" => result(locale, utils::approve_token(params(p)?)),
+        "revokeToken" => result(locale, utils::revoke_token(params(p)?)),
+        "initializeTransferFeeMint" => result(locale, utils::initialize_transfer_fee_mint(params(p)?)),
+        "harvestTransferFees" => result(locale, utils::harvest_transfer_fees(params(p)?)),
+        "transferWithFee" => result(locale, utils::transfer_with_fee_from_mint(params(p)?).await),
+        "signMessage" => result(locale, utils::sign_message(params(p)?).await),
+        "verifyMessage" => result(locale, utils::verify_message(params(p)?)),
+        "signMessageSecp256k1" => result(locale, utils::sign_message_secp256k1(params(p)?)),
+        "verifyMessageSecp256k1" => result(locale, utils::verify_message_secp256k1(params(p)?)),
+        "verifyMessageBatch" => result(locale, utils::verify_message_batch(params(p)?, locale).await),
+        "sendSol" => result(locale, utils::send_sol_with_name_lookup(params(p)?).await),
+        "transferWithSeed" => result(locale, utils::transfer_with_seed(params(p)?)),
+        "sendToken" => result(locale, utils::send_token_with_mint_lookup(params(p)?).await),
+        "wrapSol" => result(locale, utils::wrap_sol(params(p)?)),
+        "unwrapSol" => result(locale, utils::unwrap_sol(params(p)?)),
+        "createVoteAccount" => result(locale, utils::create_vote_account(params(p)?)),
+        "authorizeVote" => result(locale, utils::authorize_vote(params(p)?)),
+        "updateCommission" => result(locale, utils::update_commission(params(p)?)),
+        "withdrawFromVoteAccount" => result(locale, utils::withdraw_from_vote_account(params(p)?)),
+        "createStakeAccount" => result(locale, utils::create_stake_account(params(p)?)),
+        "delegateStake" => result(locale, utils::delegate_stake(params(p)?)),
+        "deactivateStake" => result(locale, utils::deactivate_stake(params(p)?)),
+        "withdrawStake" => result(locale, utils::withdraw_stake(params(p)?)),
+        "splitStake" => result(locale, utils::split_stake(params(p)?)),
+        "stakePoolDepositSol" => result(locale, utils::stake_pool_deposit_sol(params(p)?).await),
+        "stakePoolWithdrawSol" => result(locale, utils::stake_pool_withdraw_sol(params(p)?).await),
+        "stakePoolDepositStake" => result(locale, utils::stake_pool_deposit_stake(params(p)?).await),
+        "stakePoolWithdrawStake" => result(locale, utils::stake_pool_withdraw_stake(params(p)?).await),
+        "createAccount" => result(locale, utils::create_account_with_rent(params(p)?).await),
+        "createAccountWithSeed" => result(locale, utils::create_account_with_seed(params(p)?)),
+        "deriveAddressWithSeed" => result(locale, utils::derive_address_with_seed(params(p)?)),
+        "createNonceAccount" => result(locale, utils::create_nonce_account(params(p)?)),
+        "advanceNonce" => result(locale, utils::advance_nonce(params(p)?)),
+        "withdrawNonce" => result(locale, utils::withdraw_nonce(params(p)?)),
+        "createLookupTable" => result(locale, utils::create_lookup_table_with_slot(params(p)?).await),
+        "extendLookupTable" => result(locale, utils::extend_lookup_table(params(p)?)),
+        "deactivateLookupTable" => result(locale, utils::deactivate_lookup_table(params(p)?)),
+        "closeLookupTable" => result(locale, utils::close_lookup_table(params(p)?)),
+        "createCompressedNftTree" => result(locale, utils::create_tree(params(p)?)),
+        "mintCompressedNft" => result(locale, utils::mint_compressed_nft(params(p)?)),
+        "mintNft" => result(locale, utils::mint_nft(params(p)?)),
+        "explainTransaction" => result(locale, utils::explain_transaction(params(p)?, locale).await),
+        "decodeInstruction" => result(locale, utils::decode_instruction(params(p)?, locale)),
+        "decodeTransaction" => result(locale, utils::decode_transaction_details(params(p)?, locale)),
+        "signTransaction" => result(locale, utils::sign_transaction(params(p)?).await),
+        "submitJob" => result(locale, utils::submit_job(params(p)?).await),
+        "sponsorTransaction" => result(locale, utils::sponsor_transaction(params(p)?).await),
+        "createPendingTransaction" => result(locale, utils::create_pending_transaction(params(p)?)),
+        "buildEd25519Verify" => result(locale, utils::build_ed25519_verify(params(p)?)),
+        "buildSecp256k1Verify" => result(locale, utils::build_secp256k1_verify(params(p)?)),
+        "buildMemo" => result(locale, utils::build_memo(params(p)?)),
+        "cloneTransaction" => result(locale, utils::clone_transaction(params(p)?).await),
+        "estimateTransactionFee" => result(locale, utils::estimate_transaction_fee(params(p)?).await),
+        "encodeBorsh" => result(locale, utils::encode_borsh(params(p)?)),
+        "decodeBorsh" => result(locale, utils::decode_borsh(params(p)?)),
+        _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+    }
+}
+
+// None for notifications, which are run but get no response
+async fn handle_call(locale: Locale, call_value: Value) -> Option<RpcResponse> {
+    let Value::Object(mut object) = call_value else {
+        return Some(RpcResponse::new(Value::Null, Err(RpcError::new(INVALID_REQUEST, "Invalid Request"))));
+    };
+
+    let id = object.remove("id");
+    if id.as_ref().is_some_and(|id| !(id.is_string() || id.is_number() || id.is_null())) {
+        return Some(RpcResponse::new(Value::Null, Err(RpcError::new(INVALID_REQUEST, "id must be a string, number or null"))));
+    }
+    let method = match (object.remove("jsonrpc"), object.remove("method")) {
+        (Some(Value::String(version)), Some(Value::String(method))) if version == "2.0" => method,
+        _ => {
+            let error = RpcError::new(INVALID_REQUEST, "Invalid Request: jsonrpc must be \"2.0\" and method a string");
+            return Some(RpcResponse::new(id.unwrap_or(Value::Null), Err(error)));
+        }
+    };
+    let outcome = match object.remove("params") {
+        None => call(locale, &method, Value::Object(Map::new())).await,
+        Some(p @ Value::Object(_)) => call(locale, &method, p).await,
+        Some(_) => Err(RpcError::new(INVALID_PARAMS, "params must be an object")),
+    };
+
+    id.map(|id| RpcResponse::new(id, outcome))
+}
+
+#[utoipa::path(
+    post,
+    path = "/rpc",
+    operation_id = "jsonRpc",
+    tag = "rpc",
+    request_body(
+        content = Object,
+        description = "A JSON-RPC 2.0 request, or an array of up to 32 for a batch. Methods are the operation \
+            IDs of the JSON endpoints, such as sendSol, with the endpoint's request body as params",
+    ),
+    responses(
+        (status = 200, description = "The JSON-RPC response, or an array of them for a batch", body = Object),
+        (status = 204, description = "Every call was a notification, so there is nothing to return"),
+    )
+)]
+pub async fn handler(locale: Locale, body: Bytes) -> Response {
+    let Ok(request) = serde_json::from_slice::<Value>(&body) else {
+        return Json(RpcResponse::new(Value::Null, Err(RpcError::new(PARSE_ERROR, "Parse error")))).into_response();
+    };
+
+    let responses = match request {
+        Value::Array(calls) if calls.is_empty() || calls.len() > MAX_BATCH_CALLS => {
+            let message = format!("Invalid Request: a batch must have 1 to {} calls", MAX_BATCH_CALLS);
+            return Json(RpcResponse::new(Value::Null, Err(RpcError::new(INVALID_REQUEST, message)))).into_response();
+        }
+        Value::Array(calls) => {
+            let responses: Vec<_> = join_all(calls.into_iter().map(|call| handle_call(locale, call)))
+                .await
+                .into_iter()
+                .flatten()
+                .collect();
+            (!responses.is_empty()).then(|| Json(responses).into_response())
+        }
+        call => handle_call(locale, call).await.map(|response| Json(response).into_response()),
+    };
+    responses.unwrap_or_else(|| StatusCode::NO_CONTENT.into_response())
+}
@@ -0,0 +1,2410 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, HeaderValue, Method, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Router,
+    Json,
+};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use serde::Serialize;
+use std::time::Duration;
+use auth::Scope;
+use config::AppConfig;
+
+pub mod auth;
+pub mod cli;
+mod cluster;
+pub mod config;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+mod idempotency;
+mod json_rpc;
+mod limits;
+pub mod logging;
+mod openapi;
+mod program_logs;
+mod rate_limit;
+mod request_id;
+pub mod shutdown;
+pub mod tls;
+#[cfg(unix)]
+pub mod unix_socket;
+pub mod utils;
+mod versioning;
+mod ws;
+use utils::{
+    ErrorResponse, SuccessResponse, generate_keypair, generate_keypairs, keypair_from_mnemonic, KeypairQuery, KeypairFromMnemonicRequest,
+    convert_keypair, ConvertKeypairRequest, generate_vanity_keypair, get_vanity_job, VanityKeypairRequest, create_token, mint_token, close_token_account_checked, CloseTokenAccountRequest, approve_token, ApproveTokenRequest, revoke_token, RevokeTokenRequest,
+    initialize_transfer_fee_mint, InitializeTransferFeeMintRequest, harvest_transfer_fees, HarvestTransferFeesRequest,
+    transfer_with_fee_from_mint, TransferWithFeeRequest, sign_message, verify_message, verify_message_batch, VerifyMessageBatchRequest, send_sol_with_name_lookup, send_token_with_mint_lookup, get_mint_info, get_token_metadata, resolve_name,
+    CreateTokenRequest, MintTokenRequest, SignMessageRequest, VerifyMessageRequest, SendSolRequest,
+    SendTokenRequest, wrap_sol, WrapSolRequest, unwrap_sol, UnwrapSolRequest, create_vote_account, authorize_vote, update_commission, withdraw_from_vote_account,
+    CreateVoteAccountRequest, AuthorizeVoteRequest, UpdateCommissionRequest, WithdrawVoteRequest,
+    create_stake_account, delegate_stake, deactivate_stake, withdraw_stake, split_stake,
+    CreateStakeAccountRequest, DelegateStakeRequest, DeactivateStakeRequest, WithdrawStakeRequest, SplitStakeRequest,
+    stake_pool_deposit_sol, stake_pool_withdraw_sol, stake_pool_deposit_stake, stake_pool_withdraw_stake,
+    StakePoolDepositSolRequest, StakePoolWithdrawSolRequest, StakePoolDepositStakeRequest, StakePoolWithdrawStakeRequest,
+    get_stake_rewards, StakeRewardsQuery, health, ready, rpc, siws_challenge, siws_verify,
+    SiwsChallengeRequest, SiwsVerifyRequest, explain_transaction, ExplainTransactionRequest, decode_instruction, DecodeInstructionRequest, decode_transaction_details, DecodeTransactionRequest,
+    sign_transaction, SignTransactionRequest, submit_transaction, SubmitTransactionRequest, get_signature_status, submit_job, get_job, SubmitJobRequest, get_sponsor, sponsor_transaction, SponsorTransactionRequest,
+    pay_metadata, pay_transaction, PayTransactionRequest, create_pending_transaction, CreatePendingTransactionRequest, add_signatures, AddSignaturesRequest, get_pending_transaction, store_key, get_key, update_key_state, delete_key, StoreKeyRequest, UpdateKeyStateRequest, build_batch, BatchInstructionsRequest, create_nonce_account, advance_nonce, withdraw_nonce,
+    CreateNonceAccountRequest, AdvanceNonceRequest, WithdrawNonceRequest,
+    create_tree, CreateTreeRequest, mint_compressed_nft, MintCompressedNftRequest, mint_nft, MintNftRequest,
+    create_lookup_table_with_slot, extend_lookup_table, deactivate_lookup_table, close_lookup_table, get_lookup_table,
+    CreateLookupTableRequest, ExtendLookupTableRequest, DeactivateLookupTableRequest, CloseLookupTableRequest, create_account_with_rent, CreateAccountRequest, create_account_with_seed, CreateAccountWithSeedRequest, transfer_with_seed, TransferWithSeedRequest, get_account_info, get_rent_minimum, RentMinimumQuery, compose_transaction, ComposeTransactionRequest, compile_message, CompileMessageRequest,
+    build_ed25519_verify, Ed25519VerifyRequest, sign_message_secp256k1, Secp256k1SignRequest, verify_message_secp256k1, Secp256k1VerifyRequest, build_secp256k1_verify, Secp256k1InstructionRequest, build_memo, MemoRequest, build_raw_instruction_for, RawInstructionRequest, register_idl, RegisterIdlRequest, get_idl, build_idl_instruction_for, IdlInstructionRequest, encode_borsh, EncodeBorshRequest, decode_borsh, DecodeBorshRequest, estimate_transaction_fee, TransactionFeeRequest, recommend_priority_fees, PriorityFeeQuery, clone_transaction, CloneTransactionRequest, get_sol_balance, get_token_balance, get_token_holdings, TokenHoldingsQuery, get_footprint,
+    request_airdrop, AirdropRequest, get_blockhash, get_audit_log, AuditQuery, validate_address, AddressValidateQuery, derive_address_with_seed, AddressWithSeedRequest, derive_associated_token_address, AssociatedTokenAddressQuery,
+    heuristics, i18n::Locale, program_allowlist::Tenant, timing, IntoInstructions,
+    FormatQuery, ResponseFormat, to_wallet_adapter, encode_instruction_data, ComposeFormatQuery, ComposeFormat, to_multisig_proposal,
+};
+
+
+// The whole API, ready to serve or to embed in another service. The config
+// is also installed for code that reads it outside a request; background
+// work such as blockhash refreshing is started separately, by
+// spawn_background_tasks.
+pub fn build_router(config: AppConfig) -> Router {
+    config::init(config.clone());
+    // Reject an unknown SIGNATURE_BACKEND up front rather than on the first request
+    utils::signing::backend();
+    idempotency::store();
+    utils::vault::store();
+    utils::audit::sink();
+
+    let origins: Vec<_> = config.cors_origins.iter().filter_map(|origin| origin.parse().ok()).collect();
+    let cors = CorsLayer::new()
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_headers(Any)
+        .expose_headers([request_id::HEADER, idempotency::REPLAYED_HEADER, versioning::DEPRECATION_HEADER])
+        .allow_origin(if origins.is_empty() { AllowOrigin::any() } else { AllowOrigin::list(origins) });
+
+    // Health probes are exempt from rate limiting
+    let probes = Router::new()
+        .route("/health", get(handle_health))
+        .route("/ready", get(handle_ready));
+
+    // The landing page and API docs sit outside the versioned API
+    let meta = Router::new()
+        .route("/", get(|| async { "Hello, World!" }))
+        .route("/openapi.json", get(openapi::openapi_json))
+        .route("/docs", get(openapi::docs));
+
+    // Routes are grouped by the API key scope they need
+    let read = Router::new()
+        .route("/auth/siws/challenge", post(handle_siws_challenge))
+        .route("/auth/siws/verify", post(handle_siws_verify))
+        .route("/keypair/vanity/:job_id", get(handle_get_vanity_job))
+        .route("/keypair/:pubkey/footprint", get(handle_get_footprint))
+        .route("/token/mint/:mint", get(handle_get_mint_info))
+        .route("/token/:mint/metadata", get(handle_get_token_metadata))
+        .route("/resolve/:name", get(handle_resolve_name))
+        .route("/message/verify", post(handle_verify_message))
+        .route("/message/verify/secp256k1", post(handle_verify_message_secp256k1))
+        .route("/account/:pubkey", get(handle_get_account_info))
+        .route("/rent/minimum", get(handle_get_rent_minimum))
+        .route("/alt/:address", get(handle_get_lookup_table))
+        .route("/address/:stake_account/rewards", get(handle_get_stake_rewards))
+        .route("/address/:owner/tokens", get(handle_get_token_holdings))
+        .route("/address/validate/:pubkey", get(handle_validate_address))
+        .route("/address/with-seed", post(handle_derive_address_with_seed))
+        .route("/ata/:owner/:mint", get(handle_derive_associated_token_address))
+        .route("/balance/sol/:pubkey", get(handle_get_sol_balance))
+        .route("/balance/token/:owner/:mint", get(handle_get_token_balance))
+        .route("/transaction/explain", post(handle_explain_transaction))
+        .route("/instruction/decode", post(handle_decode_instruction))
+        .route("/transaction/decode", post(handle_decode_transaction))
+        .route("/transaction/:signature/status", get(handle_get_signature_status))
+        .route("/transaction/sponsor", get(handle_get_sponsor))
+        .route("/jobs/:job_id", get(handle_get_job))
+        .route("/transaction/:id/signatures", get(handle_get_pending_transaction))
+        .route("/transaction/fee", post(handle_estimate_transaction_fee))
+        .route("/fees/priority", get(handle_recommend_priority_fees))
+        .route("/blockhash", get(handle_get_blockhash))
+        .route("/idl/:program", get(handle_get_idl))
+        .route("/decode/borsh", post(handle_decode_borsh))
+        .route("/ws", get(ws::handler))
+        .route("/program/:program_id/logs/stream", get(handle_program_logs));
+
+    // Golden instruction fixtures are a debugging aid, left out of release builds
+    #[cfg(debug_assertions)]
+    let read = read.route("/fixtures/:operation", get(handle_get_fixture));
+
+    let build = Router::new()
+        .route("/keypair/from-mnemonic", post(handle_keypair_from_mnemonic))
+        .route("/keypair/convert", post(handle_convert_keypair))
+        .route("/keypair/vanity", post(handle_generate_vanity_keypair))
+        .route("/token/create", post(handle_create_token))
+        .route("/token/mint", post(handle_mint_token))
+        .route("/token/account/close", post(handle_close_token_account))
+        .route("/token/approve", post(handle_approve_token))
+        .route("/token/revoke", post(handle_revoke_token))
+        .route("/token-2022/transfer-fee/mint", post(handle_initialize_transfer_fee_mint))
+        .route("/token-2022/transfer-fee/harvest", post(handle_harvest_transfer_fees))
+        .route("/token-2022/transfer-fee/transfer", post(handle_transfer_with_fee))
+        .route("/send/sol", post(handle_send_sol))
+        .route("/send/sol/with-seed", post(handle_transfer_with_seed))
+        .route("/send/token", post(handle_send_token))
+        .route("/sol/wrap", post(handle_wrap_sol))
+        .route("/sol/unwrap", post(handle_unwrap_sol))
+        .route("/vote/create", post(handle_create_vote_account))
+        .route("/vote/authorize", post(handle_authorize_vote))
+        .route("/vote/commission", post(handle_update_commission))
+        .route("/vote/withdraw", post(handle_withdraw_from_vote_account))
+        .route("/stake/create", post(handle_create_stake_account))
+        .route("/stake/delegate", post(handle_delegate_stake))
+        .route("/stake/deactivate", post(handle_deactivate_stake))
+        .route("/stake/withdraw", post(handle_withdraw_stake))
+        .route("/stake/split", post(handle_split_stake))
+        .route("/stake-pool/deposit-sol", post(handle_stake_pool_deposit_sol))
+        .route("/stake-pool/withdraw-sol", post(handle_stake_pool_withdraw_sol))
+        .route("/stake-pool/deposit-stake", post(handle_stake_pool_deposit_stake))
+        .route("/stake-pool/withdraw-stake", post(handle_stake_pool_withdraw_stake))
+        .route("/account/create", post(handle_create_account))
+        .route("/account/create-with-seed", post(handle_create_account_with_seed))
+        .route("/nonce/create", post(handle_create_nonce_account))
+        .route("/nonce/advance", post(handle_advance_nonce))
+        .route("/nonce/withdraw", post(handle_withdraw_nonce))
+        .route("/alt/create", post(handle_create_lookup_table))
+        .route("/alt/extend", post(handle_extend_lookup_table))
+        .route("/alt/deactivate", post(handle_deactivate_lookup_table))
+        .route("/alt/close", post(handle_close_lookup_table))
+        .route("/cnft/tree", post(handle_create_compressed_nft_tree))
+        .route("/cnft/mint", post(handle_mint_compressed_nft))
+        .route("/nft/mint", post(handle_mint_nft))
+        .route("/transaction/clone", post(handle_clone_transaction))
+        .route("/transaction/pending", post(handle_create_pending_transaction))
+        .route("/transaction/:id/signatures", post(handle_add_signatures))
+        .route("/instruction/ed25519-verify", post(handle_build_ed25519_verify))
+        .route("/instruction/secp256k1-verify", post(handle_build_secp256k1_verify))
+        .route("/instruction/memo", post(handle_build_memo))
+        .route("/instruction/raw", post(handle_build_raw_instruction))
+        .route("/idl/:program/instruction", post(handle_build_idl_instruction))
+        .route("/encode/borsh", post(handle_encode_borsh))
+        .route("/transaction/message", post(handle_compile_message));
+
+    let sign = Router::new()
+        .route("/vault/keys", post(handle_store_key))
+        .route("/vault/keys/:key_id", get(handle_get_key).delete(handle_delete_key))
+        .route("/vault/keys/:key_id/state", put(handle_update_key_state))
+        .route("/message/sign", post(handle_sign_message))
+        .route("/message/sign/secp256k1", post(handle_sign_message_secp256k1))
+        .route("/transaction/sign", post(handle_sign_transaction));
+
+    let submit = Router::new()
+        .route("/jobs/submit", post(handle_submit_job));
+
+    let admin = Router::new()
+        .route("/audit", get(handle_get_audit_log))
+        .route("/api-keys", get(handle_list_api_keys))
+        .route("/api-keys/:key_id/rotate", post(handle_rotate_api_key))
+        .route("/api-keys/:key_id", delete(handle_revoke_api_key))
+        .route("/idl", post(handle_register_idl));
+
+    let api = auth::restrict(read, Scope::Read)
+        .merge(auth::restrict(build, Scope::Build))
+        .merge(auth::restrict(sign, Scope::Sign))
+        .merge(auth::restrict(submit, Scope::Submit))
+        .merge(auth::restrict(admin, Scope::Admin));
+
+    let request_timeout = Duration::from_secs(config.request_timeout_secs);
+    let api = limits::apply(api, request_timeout, config.body_limit_bytes)
+        .merge(limits::apply(
+            auth::restrict(Router::new().route("/keypair", get(handle_generate_keypair)), Scope::Build),
+            limits::KEYPAIR_TIMEOUT,
+            config.body_limit_bytes,
+        ))
+        .merge(limits::apply(
+            auth::restrict(Router::new().route("/transaction/submit", post(handle_submit_transaction)), Scope::Submit),
+            limits::SUBMIT_TIMEOUT,
+            config.body_limit_bytes,
+        ))
+        // Broadcasts when asked to, which also needs the submit scope
+        .merge(limits::apply(
+            auth::restrict(Router::new().route("/transaction/sponsor", post(handle_sponsor_transaction)), Scope::Sign),
+            limits::SUBMIT_TIMEOUT,
+            config.body_limit_bytes,
+        ))
+        .merge(limits::apply(
+            auth::restrict(Router::new().route("/airdrop", post(handle_request_airdrop)), Scope::Submit),
+            limits::AIRDROP_TIMEOUT,
+            config.body_limit_bytes,
+        ))
+        .merge(limits::apply(
+            auth::restrict(Router::new().route("/message/verify/batch", post(handle_verify_message_batch)), Scope::Read)
+                .merge(auth::restrict(
+                    Router::new()
+                        .route("/transaction/compose", post(handle_compose_transaction))
+                        .route("/instructions/batch", post(handle_build_batch)),
+                    Scope::Build,
+                ))
+                // Checks each call's scope itself
+                .route("/rpc", post(json_rpc::handler)),
+            request_timeout,
+            limits::BATCH_BODY_LIMIT.max(config.body_limit_bytes),
+        ))
+        .layer(middleware::from_fn_with_state(config.clone(), auth::authenticate))
+        // Wallets scanning a Solana Pay QR code have no API key to present
+        .merge(limits::apply(
+            Router::new().route("/pay/:reference", get(handle_pay_metadata).post(handle_pay_transaction)),
+            request_timeout,
+            config.body_limit_bytes,
+        ));
+
+    let app = Router::new()
+        .nest(versioning::PREFIX, api.clone())
+        .merge(api.layer(middleware::from_fn(versioning::deprecate_unversioned)))
+        .merge(meta);
+
+    app
+        .layer(middleware::from_fn_with_state(config.clone(), cluster::select))
+        .layer(middleware::from_fn(timing::collect))
+        .layer(middleware::from_fn_with_state(config.clone(), rate_limit::enforce))
+        .merge(probes)
+        .layer(middleware::from_fn(limits::json_errors))
+        .layer(cors)
+        .layer(logging::trace_layer())
+        .layer(middleware::from_fn(request_id::assign))
+        .with_state(config)
+}
+
+// Must run inside a Tokio runtime, after build_router
+pub fn spawn_background_tasks() {
+    rpc::spawn_blockhash_refresher();
+    rpc::spawn_provider_health_checks();
+    utils::preload::spawn_preloader();
+}
+
+// Errors are logged in English and returned in the caller's language
+fn respond<T: Serialize>(locale: Locale, result: Result<SuccessResponse<T>, ErrorResponse>) -> Json<serde_json::Value> {
+    let mut body = timing::measure("serialization", || match result {
+        Ok(response) => serde_json::to_value(response).unwrap(),
+        Err(mut err) => {
+            tracing::warn!(error = %logging::redact_secrets(&err.error), "request failed");
+            // Errors built off the request's task, e.g. in spawned work, miss the ID
+            err.request_id = err.request_id.or_else(request_id::current);
+            serde_json::to_value(err.translated(locale)).unwrap()
+        }
+    });
+
+    if let Some(timings) = timing::snapshot() {
+        body["timings"] = serde_json::to_value(timings).unwrap();
+    }
+    Json(body)
+}
+
+// Solana Pay wallets read the spec's bodies as they are, so successes are
+// sent without the envelope and failures carry an HTTP status
+fn respond_pay<T: Serialize>(locale: Locale, result: Result<SuccessResponse<T>, ErrorResponse>) -> Response {
+    match result {
+        Ok(response) => Json(response.data).into_response(),
+        Err(err) => {
+            let status = if utils::pay::is_enabled() { StatusCode::BAD_REQUEST } else { StatusCode::NOT_FOUND };
+            (status, respond::<()>(locale, Err(err))).into_response()
+        }
+    }
+}
+
+// Builder endpoints can return their instructions in web3.js's shape
+// instead, and their instruction data in base58
+fn respond_instructions<T: Serialize + IntoInstructions>(
+    locale: Locale,
+    format: FormatQuery,
+    result: Result<SuccessResponse<T>, ErrorResponse>,
+) -> Json<serde_json::Value> {
+    match format.format {
+        Some(ResponseFormat::WalletAdapter) => respond(locale, encode_instruction_data(to_wallet_adapter(result), format.encoding)),
+        None => respond(locale, encode_instruction_data(result, format.encoding)),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/health",
+    operation_id = "health",
+    tag = "health",
+    responses((status = 200, description = "Liveness probe, always returns 200; rpc_mode is mock when RPC calls are answered by the in-memory mock", body = SuccessResponse<utils::HealthResponse>))
+)]
+async fn handle_health() -> Json<serde_json::Value> {
+    Json(serde_json::to_value(health()).unwrap())
+}
+
+#[utoipa::path(
+    get,
+    path = "/ready",
+    operation_id = "ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Readiness probe checking RPC connectivity and the blockhash cache", body = SuccessResponse<utils::ReadinessResponse>),
+        (status = 503, description = "Not ready", body = SuccessResponse<utils::ReadinessResponse>),
+    )
+)]
+async fn handle_ready(State(config): State<AppConfig>) -> (StatusCode, Json<serde_json::Value>) {
+    let response = ready(&config).await;
+    let status = if response.data.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(serde_json::to_value(response).unwrap()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/keypair",
+    operation_id = "generateKeypair",
+    tag = "keypair",
+    params(utils::KeypairQuery),
+    responses(
+        (status = 200, description = "Generate a new ed25519 keypair, optionally with a recovery phrase, or an array of \
+            count of them", body = SuccessResponse<utils::KeypairResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_generate_keypair(
+    locale: Locale,
+    Query(query): Query<KeypairQuery>,
+) -> Json<serde_json::Value> {
+    match query.count {
+        Some(_) => respond(locale, generate_keypairs(query).await),
+        None => respond(locale, generate_keypair(query)),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/keypair/from-mnemonic",
+    operation_id = "keypairFromMnemonic",
+    tag = "keypair",
+    request_body = utils::KeypairFromMnemonicRequest,
+    responses(
+        (status = 200, description = "Recover the keypair a BIP39 recovery phrase derives at the standard Solana path", body = SuccessResponse<utils::KeypairResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_keypair_from_mnemonic(
+    locale: Locale,
+    Json(request): Json<KeypairFromMnemonicRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, keypair_from_mnemonic(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/keypair/convert",
+    operation_id = "convertKeypair",
+    tag = "keypair",
+    request_body = utils::ConvertKeypairRequest,
+    responses(
+        (status = 200, description = "Convert a secret key between base58, Solana CLI JSON and hex", body = SuccessResponse<utils::ConvertKeypairResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_convert_keypair(
+    locale: Locale,
+    Json(request): Json<ConvertKeypairRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, convert_keypair(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/keypair/vanity",
+    operation_id = "generateVanityKeypair",
+    tag = "keypair",
+    request_body = utils::VanityKeypairRequest,
+    responses(
+        (status = 200, description = "Search for a keypair whose address has the given prefix and/or suffix; \
+            long searches return a running job to poll", body = SuccessResponse<utils::VanityKeypairResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_generate_vanity_keypair(
+    locale: Locale,
+    Json(request): Json<VanityKeypairRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, generate_vanity_keypair(request).await)
+}
+
+#[utoipa::path(
+    get,
+    path = "/keypair/vanity/{job_id}",
+    operation_id = "getVanityJob",
+    tag = "keypair",
+    params(("job_id" = String, Path, description = "Job id returned by /keypair/vanity")),
+    responses(
+        (status = 200, description = "Status of a vanity search; a found keypair is returned once and then forgotten", body = SuccessResponse<utils::VanityKeypairResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_vanity_job(
+    locale: Locale,
+    Path(job_id): Path<String>,
+) -> Json<serde_json::Value> {
+    respond(locale, get_vanity_job(job_id))
+}
+
+#[utoipa::path(
+    post,
+    path = "/vault/keys",
+    operation_id = "storeVaultKey",
+    tag = "keypair",
+    request_body = utils::StoreKeyRequest,
+    responses(
+        (status = 200, description = "Store a keypair, or a newly generated one, encrypted in the key vault; signing \
+            endpoints take the returned key id in place of the secret", body = SuccessResponse<utils::StoreKeyResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_store_key(
+    locale: Locale,
+    Json(request): Json<StoreKeyRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, store_key(request))
+}
+
+#[utoipa::path(
+    get,
+    path = "/vault/keys/{key_id}",
+    operation_id = "getVaultKey",
+    tag = "keypair",
+    params(("key_id" = String, Path, description = "Key id returned by /vault/keys")),
+    responses(
+        (status = 200, description = "A vault key's public key and lifecycle state, including revoked keys",
+            body = SuccessResponse<utils::VaultKeyResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_key(
+    locale: Locale,
+    Path(key_id): Path<String>,
+) -> Json<serde_json::Value> {
+    respond(locale, get_key(key_id))
+}
+
+#[utoipa::path(
+    put,
+    path = "/vault/keys/{key_id}/state",
+    operation_id = "updateVaultKeyState",
+    tag = "keypair",
+    params(("key_id" = String, Path, description = "Key id returned by /vault/keys")),
+    request_body = utils::UpdateKeyStateRequest,
+    responses(
+        (status = 200, description = "Pause, resume or revoke a vault key. Paused and revoked keys are refused by the \
+            signing endpoints at once; revoking is permanent and destroys the secret", body = SuccessResponse<utils::VaultKeyResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_update_key_state(
+    locale: Locale,
+    Path(key_id): Path<String>,
+    Json(request): Json<UpdateKeyStateRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, update_key_state(key_id, request))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/vault/keys/{key_id}",
+    operation_id = "deleteVaultKey",
+    tag = "keypair",
+    params(("key_id" = String, Path, description = "Key id returned by /vault/keys")),
+    responses(
+        (status = 200, description = "Revoke a vault key, destroying its secret but keeping its metadata for audit",
+            body = SuccessResponse<utils::DeleteKeyResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_delete_key(
+    locale: Locale,
+    Path(key_id): Path<String>,
+) -> Json<serde_json::Value> {
+    respond(locale, delete_key(key_id))
+}
+
+#[utoipa::path(
+    get,
+    path = "/keypair/{pubkey}/footprint",
+    operation_id = "getFootprint",
+    tag = "keypair",
+    params(("pubkey" = String, Path, description = "Base58 account address")),
+    responses(
+        (status = 200, description = "Balances and accounts owned or controlled by a public key: associated token accounts for known mints, stake and nonce accounts, and its metadata PDA", body = SuccessResponse<utils::FootprintResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_footprint(
+    locale: Locale,
+    Path(pubkey): Path<String>,
+) -> Json<serde_json::Value> {
+    respond(locale, get_footprint(pubkey).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/token/create",
+    operation_id = "createToken",
+    tag = "token",
+    request_body = utils::CreateTokenRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build the mint setup as one ordered `instructions` list: with a payer, create the \
+            mint account, initialize its Token-2022 extensions and the mint, create the holder's token account and mint \
+            the initial supply. The top-level instruction fields are a deprecated alias for the initialize-mint \
+            instruction", body = SuccessResponse<utils::CreateTokenResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_create_token(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<CreateTokenRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, create_token(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/token/mint",
+    operation_id = "mintToken",
+    tag = "token",
+    request_body = utils::MintTokenRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build an SPL Token mint-to-checked instruction for the destination's associated token account", body = SuccessResponse<utils::MintTokenResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_mint_token(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<MintTokenRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, mint_token(request))
+}
+
+#[utoipa::path(
+    get,
+    path = "/token/{mint}/metadata",
+    operation_id = "getTokenMetadata",
+    tag = "token",
+    params(("mint" = String, Path, description = "Base58 mint address")),
+    responses(
+        (status = 200, description = "Name, symbol, URI and creators of a token, from its Metaplex metadata account or Token-2022 metadata extension", body = SuccessResponse<utils::TokenMetadataResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_token_metadata(
+    locale: Locale,
+    Path(mint): Path<String>,
+) -> Json<serde_json::Value> {
+    respond(locale, get_token_metadata(mint).await)
+}
+
+#[utoipa::path(
+    get,
+    path = "/resolve/{name}",
+    operation_id = "resolveName",
+    tag = "address",
+    params(("name" = String, Path, description = "A .sol domain, or a base58 address to find the domains of")),
+    responses(
+        (status = 200, description = "Resolve a Solana Name Service domain to the wallet that owns it, or an address to the \
+            .sol domains it owns", body = SuccessResponse<utils::ResolveNameResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_resolve_name(
+    locale: Locale,
+    Path(name): Path<String>,
+) -> Json<serde_json::Value> {
+    respond(locale, resolve_name(name).await)
+}
+
+#[utoipa::path(
+    get,
+    path = "/token/mint/{mint}",
+    operation_id = "getMintInfo",
+    tag = "token",
+    params(("mint" = String, Path, description = "Base58 mint address")),
+    responses(
+        (status = 200, description = "A mint's supply, decimals and authorities, with its Token-2022 extensions", body = SuccessResponse<utils::MintInfoResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_mint_info(
+    locale: Locale,
+    Path(mint): Path<String>,
+) -> Json<serde_json::Value> {
+    respond(locale, get_mint_info(mint).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/token/account/close",
+    operation_id = "closeTokenAccount",
+    tag = "token",
+    request_body = utils::CloseTokenAccountRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build an SPL Token close-account instruction after checking that the account's balance is zero", body = SuccessResponse<utils::InstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_close_token_account(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<CloseTokenAccountRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, close_token_account_checked(request).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/token/approve",
+    operation_id = "approveToken",
+    tag = "token",
+    request_body = utils::ApproveTokenRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build an SPL Token approve-checked instruction letting a delegate transfer up to an allowance", body = SuccessResponse<utils::InstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_approve_token(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<ApproveTokenRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, approve_token(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/token/revoke",
+    operation_id = "revokeToken",
+    tag = "token",
+    request_body = utils::RevokeTokenRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build an SPL Token revoke instruction removing a token account's delegate", body = SuccessResponse<utils::InstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_revoke_token(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<RevokeTokenRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, revoke_token(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/token-2022/transfer-fee/mint",
+    operation_id = "initializeTransferFeeMint",
+    tag = "token",
+    request_body = utils::InitializeTransferFeeMintRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build the ordered instructions that create a Token-2022 mint with the transfer fee extension", body = SuccessResponse<utils::TransferFeeMintResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_initialize_transfer_fee_mint(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<InitializeTransferFeeMintRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, initialize_transfer_fee_mint(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/token-2022/transfer-fee/harvest",
+    operation_id = "harvestTransferFees",
+    tag = "token",
+    request_body = utils::HarvestTransferFeesRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build instructions that move withheld transfer fees to the mint, and optionally withdraw them", body = SuccessResponse<utils::HarvestTransferFeesResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_harvest_transfer_fees(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<HarvestTransferFeesRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, harvest_transfer_fees(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/token-2022/transfer-fee/transfer",
+    operation_id = "transferWithFee",
+    tag = "token",
+    request_body = utils::TransferWithFeeRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build a Token-2022 transfer-checked-with-fee instruction, reading decimals and the current fee from the mint when omitted", body = SuccessResponse<utils::TransferWithFeeResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_transfer_with_fee(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<TransferWithFeeRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, transfer_with_fee_from_mint(request).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/message/sign",
+    operation_id = "signMessage",
+    tag = "message",
+    request_body = utils::SignMessageRequest,
+    responses(
+        (status = 200, description = "Sign a message with a base58 secret key", body = SuccessResponse<utils::SignMessageResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_sign_message(
+    locale: Locale,
+    Json(request): Json<SignMessageRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, sign_message(request).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/message/verify",
+    operation_id = "verifyMessage",
+    tag = "message",
+    request_body = utils::VerifyMessageRequest,
+    responses(
+        (status = 200, description = "Verify a message signature", body = SuccessResponse<utils::VerifyMessageResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_verify_message(
+    locale: Locale,
+    Json(request): Json<VerifyMessageRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, verify_message(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/message/sign/secp256k1",
+    operation_id = "signMessageSecp256k1",
+    tag = "message",
+    request_body = utils::Secp256k1SignRequest,
+    responses(
+        (status = 200, description = "Sign the keccak256 hash of a message with a hex secp256k1 secret key, as Ethereum does", body = SuccessResponse<utils::Secp256k1SignResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_sign_message_secp256k1(
+    locale: Locale,
+    Json(request): Json<Secp256k1SignRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, sign_message_secp256k1(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/message/verify/secp256k1",
+    operation_id = "verifyMessageSecp256k1",
+    tag = "message",
+    request_body = utils::Secp256k1VerifyRequest,
+    responses(
+        (status = 200, description = "Verify a secp256k1 message signature against an Ethereum address", body = SuccessResponse<utils::Secp256k1VerifyResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_verify_message_secp256k1(
+    locale: Locale,
+    Json(request): Json<Secp256k1VerifyRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, verify_message_secp256k1(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/message/verify/batch",
+    operation_id = "verifyMessageBatch",
+    tag = "message",
+    request_body = utils::VerifyMessageBatchRequest,
+    responses(
+        (status = 200, description = "Verify many message signatures at once, concurrently off the request thread, with a result per message and whether all were valid", body = SuccessResponse<utils::VerifyMessageBatchResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_verify_message_batch(
+    locale: Locale,
+    Json(request): Json<VerifyMessageBatchRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, verify_message_batch(request, locale).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/send/sol",
+    operation_id = "sendSol",
+    tag = "transfer",
+    request_body = utils::SendSolRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build a system transfer instruction, resolving a .sol recipient", body = SuccessResponse<utils::SendSolResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_send_sol(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<SendSolRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, send_sol_with_name_lookup(request).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/send/sol/with-seed",
+    operation_id = "transferWithSeed",
+    tag = "transfer",
+    request_body = utils::TransferWithSeedRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build a system transfer-with-seed instruction, moving SOL out of an address derived \
+            from a base key", body = SuccessResponse<utils::TransferWithSeedResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_transfer_with_seed(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<TransferWithSeedRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, transfer_with_seed(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/send/token",
+    operation_id = "sendToken",
+    tag = "transfer",
+    request_body = utils::SendTokenRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build an SPL Token transfer instruction between associated token accounts, reading the mint's decimals when they are not given or known and resolving a .sol destination", body = SuccessResponse<utils::SendTokenResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_send_token(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<SendTokenRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, send_token_with_mint_lookup(request).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/sol/wrap",
+    operation_id = "wrapSol",
+    tag = "transfer",
+    request_body = utils::WrapSolRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build the ordered instructions that move SOL into the owner's wrapped SOL token account", body = SuccessResponse<utils::WrappedSolResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_wrap_sol(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<WrapSolRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, wrap_sol(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/sol/unwrap",
+    operation_id = "unwrapSol",
+    tag = "transfer",
+    request_body = utils::UnwrapSolRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build the instruction that closes the owner's wrapped SOL token account, returning its balance as SOL", body = SuccessResponse<utils::WrappedSolResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_unwrap_sol(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<UnwrapSolRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, unwrap_sol(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/vote/create",
+    operation_id = "createVoteAccount",
+    tag = "vote",
+    request_body = utils::CreateVoteAccountRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build the instructions to create and initialize a vote account", body = SuccessResponse<utils::CreateVoteAccountResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_create_vote_account(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<CreateVoteAccountRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, create_vote_account(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/vote/authorize",
+    operation_id = "authorizeVote",
+    tag = "vote",
+    request_body = utils::AuthorizeVoteRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build a vote authorize instruction for a new voter or withdrawer", body = SuccessResponse<utils::InstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_authorize_vote(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<AuthorizeVoteRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, authorize_vote(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/vote/commission",
+    operation_id = "updateCommission",
+    tag = "vote",
+    request_body = utils::UpdateCommissionRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build a vote update-commission instruction", body = SuccessResponse<utils::InstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_update_commission(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<UpdateCommissionRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, update_commission(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/vote/withdraw",
+    operation_id = "withdrawFromVoteAccount",
+    tag = "vote",
+    request_body = utils::WithdrawVoteRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build a vote account withdraw instruction", body = SuccessResponse<utils::InstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_withdraw_from_vote_account(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<WithdrawVoteRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, withdraw_from_vote_account(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/stake/create",
+    operation_id = "createStakeAccount",
+    tag = "stake",
+    request_body = utils::CreateStakeAccountRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build the instructions to create and initialize a stake account", body = SuccessResponse<utils::CreateStakeAccountResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_create_stake_account(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<CreateStakeAccountRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, create_stake_account(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/stake/delegate",
+    operation_id = "delegateStake",
+    tag = "stake",
+    request_body = utils::DelegateStakeRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build a stake delegation instruction", body = SuccessResponse<utils::InstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_delegate_stake(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<DelegateStakeRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, delegate_stake(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/stake/deactivate",
+    operation_id = "deactivateStake",
+    tag = "stake",
+    request_body = utils::DeactivateStakeRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build a stake deactivation instruction", body = SuccessResponse<utils::InstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_deactivate_stake(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<DeactivateStakeRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, deactivate_stake(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/stake/withdraw",
+    operation_id = "withdrawStake",
+    tag = "stake",
+    request_body = utils::WithdrawStakeRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build a stake account withdraw instruction", body = SuccessResponse<utils::InstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_withdraw_stake(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<WithdrawStakeRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, withdraw_stake(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/stake/split",
+    operation_id = "splitStake",
+    tag = "stake",
+    request_body = utils::SplitStakeRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build the instructions to split a stake account", body = SuccessResponse<utils::SplitStakeResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_split_stake(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<SplitStakeRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, split_stake(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/stake-pool/deposit-sol",
+    operation_id = "stakePoolDepositSol",
+    tag = "stake",
+    request_body = utils::StakePoolDepositSolRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build the instructions to deposit SOL into an SPL stake pool for pool tokens, reading the pool's accounts", body = SuccessResponse<utils::StakePoolResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_stake_pool_deposit_sol(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<StakePoolDepositSolRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, stake_pool_deposit_sol(request).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/stake-pool/withdraw-sol",
+    operation_id = "stakePoolWithdrawSol",
+    tag = "stake",
+    request_body = utils::StakePoolWithdrawSolRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build the instruction to burn pool tokens for SOL from an SPL stake pool's reserve", body = SuccessResponse<utils::StakePoolResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_stake_pool_withdraw_sol(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<StakePoolWithdrawSolRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, stake_pool_withdraw_sol(request).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/stake-pool/deposit-stake",
+    operation_id = "stakePoolDepositStake",
+    tag = "stake",
+    request_body = utils::StakePoolDepositStakeRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build the instructions to deposit a delegated stake account into an SPL stake pool for pool tokens", body = SuccessResponse<utils::StakePoolResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_stake_pool_deposit_stake(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<StakePoolDepositStakeRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, stake_pool_deposit_stake(request).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/stake-pool/withdraw-stake",
+    operation_id = "stakePoolWithdrawStake",
+    tag = "stake",
+    request_body = utils::StakePoolWithdrawStakeRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build the instructions to burn pool tokens for a new stake account split off an SPL stake pool's validator or reserve", body = SuccessResponse<utils::StakePoolResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_stake_pool_withdraw_stake(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<StakePoolWithdrawStakeRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, stake_pool_withdraw_stake(request).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/account/create",
+    operation_id = "createAccount",
+    tag = "account",
+    request_body = utils::CreateAccountRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build a system create-account instruction, funded for rent exemption unless lamports are given", body = SuccessResponse<utils::CreateAccountResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_create_account(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<CreateAccountRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, create_account_with_rent(request).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/account/create-with-seed",
+    operation_id = "createAccountWithSeed",
+    tag = "account",
+    request_body = utils::CreateAccountWithSeedRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build a system create-account-with-seed instruction for an address derived from a base \
+            key, funded for rent exemption unless lamports are given", body = SuccessResponse<utils::CreateAccountWithSeedResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_create_account_with_seed(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<CreateAccountWithSeedRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, create_account_with_seed(request))
+}
+
+#[utoipa::path(
+    get,
+    path = "/rent/minimum",
+    operation_id = "getRentMinimum",
+    tag = "account",
+    params(utils::RentMinimumQuery),
+    responses(
+        (status = 200, description = "Lamports an account with this much data needs to be rent exempt", body = SuccessResponse<utils::RentMinimumResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_rent_minimum(
+    locale: Locale,
+    Query(query): Query<RentMinimumQuery>,
+) -> Json<serde_json::Value> {
+    respond(locale, get_rent_minimum(query).await)
+}
+
+#[utoipa::path(
+    get,
+    path = "/account/{pubkey}",
+    operation_id = "getAccountInfo",
+    tag = "account",
+    params(("pubkey" = String, Path, description = "Base58 account address")),
+    responses(
+        (status = 200, description = "An account's lamports, owner and data length, with its token account or mint state when the SPL Token program owns it", body = SuccessResponse<utils::AccountInfoResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_account_info(
+    locale: Locale,
+    Path(pubkey): Path<String>,
+) -> Json<serde_json::Value> {
+    respond(locale, get_account_info(pubkey).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/nonce/create",
+    operation_id = "createNonceAccount",
+    tag = "nonce",
+    request_body = utils::CreateNonceAccountRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build the instructions to create and initialize a durable nonce account", body = SuccessResponse<utils::CreateNonceAccountResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_create_nonce_account(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<CreateNonceAccountRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, create_nonce_account(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/nonce/advance",
+    operation_id = "advanceNonce",
+    tag = "nonce",
+    request_body = utils::AdvanceNonceRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build an advance-nonce instruction", body = SuccessResponse<utils::InstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_advance_nonce(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<AdvanceNonceRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, advance_nonce(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/nonce/withdraw",
+    operation_id = "withdrawNonce",
+    tag = "nonce",
+    request_body = utils::WithdrawNonceRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build a nonce account withdraw instruction", body = SuccessResponse<utils::InstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_withdraw_nonce(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<WithdrawNonceRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, withdraw_nonce(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/alt/create",
+    operation_id = "createLookupTable",
+    tag = "alt",
+    request_body = utils::CreateLookupTableRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build an address lookup table create instruction, deriving the table address from the latest finalized slot unless recentSlot is given", body = SuccessResponse<utils::CreateLookupTableResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_create_lookup_table(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<CreateLookupTableRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, create_lookup_table_with_slot(request).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/alt/extend",
+    operation_id = "extendLookupTable",
+    tag = "alt",
+    request_body = utils::ExtendLookupTableRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build an instruction adding addresses to a lookup table", body = SuccessResponse<utils::InstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_extend_lookup_table(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<ExtendLookupTableRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, extend_lookup_table(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/alt/deactivate",
+    operation_id = "deactivateLookupTable",
+    tag = "alt",
+    request_body = utils::DeactivateLookupTableRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build a lookup table deactivate instruction", body = SuccessResponse<utils::InstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_deactivate_lookup_table(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<DeactivateLookupTableRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, deactivate_lookup_table(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/alt/close",
+    operation_id = "closeLookupTable",
+    tag = "alt",
+    request_body = utils::CloseLookupTableRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build a lookup table close instruction, for tables deactivated long enough ago", body = SuccessResponse<utils::InstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_close_lookup_table(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<CloseLookupTableRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, close_lookup_table(request))
+}
+
+#[utoipa::path(
+    get,
+    path = "/alt/{address}",
+    operation_id = "getLookupTable",
+    tag = "alt",
+    params(("address" = String, Path, description = "Base58 lookup table address")),
+    responses(
+        (status = 200, description = "A lookup table's authority, status and stored addresses", body = SuccessResponse<utils::LookupTableResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_lookup_table(
+    locale: Locale,
+    Path(address): Path<String>,
+) -> Json<serde_json::Value> {
+    respond(locale, get_lookup_table(address).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/cnft/tree",
+    operation_id = "createCompressedNftTree",
+    tag = "cnft",
+    request_body = utils::CreateTreeRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build the instructions that allocate a merkle tree account and initialize it as a Bubblegum tree", body = SuccessResponse<utils::CreateTreeResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_create_compressed_nft_tree(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<CreateTreeRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, create_tree(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/cnft/mint",
+    operation_id = "mintCompressedNft",
+    tag = "cnft",
+    request_body = utils::MintCompressedNftRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build a Bubblegum mint instruction for a compressed NFT, minting into the collection when one is given", body = SuccessResponse<utils::MintCompressedNftResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_mint_compressed_nft(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<MintCompressedNftRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, mint_compressed_nft(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/nft/mint",
+    operation_id = "mintNft",
+    tag = "nft",
+    request_body = utils::MintNftRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build every instruction for minting an NFT in order, compiled into an unsigned transaction when recentBlockhash is given", body = SuccessResponse<utils::MintNftResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_mint_nft(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<MintNftRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, mint_nft(request))
+}
+
+#[utoipa::path(
+    get,
+    path = "/address/{stake_account}/rewards",
+    operation_id = "getStakeRewards",
+    tag = "stake",
+    params(("stake_account" = String, Path, description = "Base58 stake account address"), utils::StakeRewardsQuery),
+    responses(
+        (status = 200, description = "Inflation rewards and realized APY for a stake account", body = SuccessResponse<utils::StakeRewardsResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_stake_rewards(
+    locale: Locale,
+    Path(stake_account): Path<String>,
+    Query(query): Query<StakeRewardsQuery>,
+) -> Json<serde_json::Value> {
+    respond(locale, get_stake_rewards(stake_account, query).await)
+}
+
+#[utoipa::path(
+    get,
+    path = "/program/{program_id}/logs/stream",
+    operation_id = "streamProgramLogs",
+    tag = "program",
+    params(("program_id" = String, Path, description = "Base58 program address")),
+    responses(
+        (status = 200, description = "Server-sent events: a `logs` event per transaction mentioning the program, \
+            with its slot, signature, error and parsed log lines, and an `error` event if the subscription fails",
+            content_type = "text/event-stream", body = program_logs::ProgramLogsEvent),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_program_logs(
+    locale: Locale,
+    Path(program_id): Path<String>,
+) -> Response {
+    match program_logs::stream(program_id) {
+        Ok(events) => events.into_response(),
+        Err(err) => respond(locale, Err::<SuccessResponse<()>, _>(err)).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/address/with-seed",
+    operation_id = "deriveAddressWithSeed",
+    tag = "address",
+    request_body = utils::AddressWithSeedRequest,
+    responses(
+        (status = 200, description = "Derive the address Pubkey::create_with_seed gives for a base key, seed and owner program", body = SuccessResponse<utils::AddressWithSeedResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_derive_address_with_seed(
+    locale: Locale,
+    Json(request): Json<AddressWithSeedRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, derive_address_with_seed(request))
+}
+
+#[utoipa::path(
+    get,
+    path = "/address/validate/{pubkey}",
+    operation_id = "validateAddress",
+    tag = "address",
+    params(("pubkey" = String, Path, description = "Address to check"), utils::AddressValidateQuery),
+    responses(
+        (status = 200, description = "Whether a string is a valid address, whether it is on the ed25519 curve, \
+            and optionally whether it is an associated token account", body = SuccessResponse<utils::AddressValidateResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_validate_address(
+    locale: Locale,
+    Path(pubkey): Path<String>,
+    Query(query): Query<AddressValidateQuery>,
+) -> Json<serde_json::Value> {
+    respond(locale, validate_address(pubkey, query).await)
+}
+
+#[utoipa::path(
+    get,
+    path = "/ata/{owner}/{mint}",
+    operation_id = "deriveAssociatedTokenAddress",
+    tag = "address",
+    params(
+        ("owner" = String, Path, description = "Base58 wallet address"),
+        ("mint" = String, Path, description = "Base58 mint address"),
+        utils::AssociatedTokenAddressQuery,
+    ),
+    responses(
+        (status = 200, description = "The owner's associated token account for a mint, and whether it exists yet", body = SuccessResponse<utils::AssociatedTokenAddressResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_derive_associated_token_address(
+    locale: Locale,
+    Path((owner, mint)): Path<(String, String)>,
+    Query(query): Query<AssociatedTokenAddressQuery>,
+) -> Json<serde_json::Value> {
+    respond(locale, derive_associated_token_address(owner, mint, query).await)
+}
+
+#[utoipa::path(
+    get,
+    path = "/balance/sol/{pubkey}",
+    operation_id = "getSolBalance",
+    tag = "balance",
+    params(("pubkey" = String, Path, description = "Base58 account address")),
+    responses(
+        (status = 200, description = "Lamport balance of an account", body = SuccessResponse<utils::SolBalanceResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_sol_balance(
+    locale: Locale,
+    Path(pubkey): Path<String>,
+) -> Json<serde_json::Value> {
+    respond(locale, get_sol_balance(pubkey).await)
+}
+
+#[utoipa::path(
+    get,
+    path = "/balance/token/{owner}/{mint}",
+    operation_id = "getTokenBalance",
+    tag = "balance",
+    params(
+        ("owner" = String, Path, description = "Base58 wallet address"),
+        ("mint" = String, Path, description = "Base58 mint address"),
+    ),
+    responses(
+        (status = 200, description = "Balance of the owner's associated token account for the mint", body = SuccessResponse<utils::TokenBalanceResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_token_balance(
+    locale: Locale,
+    Path((owner, mint)): Path<(String, String)>,
+) -> Json<serde_json::Value> {
+    respond(locale, get_token_balance(owner, mint).await)
+}
+
+#[utoipa::path(
+    get,
+    path = "/address/{owner}/tokens",
+    operation_id = "getTokenHoldings",
+    tag = "balance",
+    params(
+        ("owner" = String, Path, description = "Base58 wallet address"),
+        utils::TokenHoldingsQuery,
+    ),
+    responses(
+        (status = 200, description = "Every SPL Token and Token-2022 account the owner holds, with raw and UI amounts", body = SuccessResponse<utils::TokenHoldingsResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_token_holdings(
+    locale: Locale,
+    Path(owner): Path<String>,
+    Query(query): Query<TokenHoldingsQuery>,
+) -> Json<serde_json::Value> {
+    respond(locale, get_token_holdings(owner, query).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/siws/challenge",
+    operation_id = "siwsChallenge",
+    tag = "auth",
+    request_body = utils::SiwsChallengeRequest,
+    responses(
+        (status = 200, description = "Issue a Sign-In With Solana challenge message", body = SuccessResponse<utils::SiwsChallengeResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_siws_challenge(
+    locale: Locale,
+    Json(request): Json<SiwsChallengeRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, siws_challenge(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/siws/verify",
+    operation_id = "siwsVerify",
+    tag = "auth",
+    request_body = utils::SiwsVerifyRequest,
+    responses(
+        (status = 200, description = "Exchange a signed challenge for a session token", body = SuccessResponse<utils::SiwsSessionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_siws_verify(
+    locale: Locale,
+    Json(request): Json<SiwsVerifyRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, siws_verify(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/transaction/explain",
+    operation_id = "explainTransaction",
+    tag = "transaction",
+    request_body = utils::ExplainTransactionRequest,
+    responses(
+        (status = 200, description = "Human-readable summary of a serialized transaction, with the balance changes a simulation shows when simulate is set", body = SuccessResponse<utils::ExplainTransactionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_explain_transaction(
+    locale: Locale,
+    Json(request): Json<ExplainTransactionRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, explain_transaction(request, locale).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/instruction/decode",
+    operation_id = "decodeInstruction",
+    tag = "transaction",
+    request_body = utils::DecodeInstructionRequest,
+    responses(
+        (status = 200, description = "Name and describe a single instruction, in the shape the instruction endpoints return", body = SuccessResponse<utils::DecodeInstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_decode_instruction(
+    locale: Locale,
+    Json(request): Json<DecodeInstructionRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, decode_instruction(request, locale))
+}
+
+#[utoipa::path(
+    post,
+    path = "/transaction/decode",
+    operation_id = "decodeTransaction",
+    tag = "transaction",
+    request_body = utils::DecodeTransactionRequest,
+    responses(
+        (status = 200, description = "Fee payer, blockhash, signature status and decoded instructions of a serialized transaction", body = SuccessResponse<utils::DecodeTransactionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_decode_transaction(
+    locale: Locale,
+    Json(request): Json<DecodeTransactionRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, decode_transaction_details(request, locale))
+}
+
+#[utoipa::path(
+    post,
+    path = "/transaction/sign",
+    operation_id = "signTransaction",
+    tag = "transaction",
+    request_body = utils::SignTransactionRequest,
+    responses(
+        (status = 200, description = "Add signatures to a serialized transaction and report the signers still missing", body = SuccessResponse<utils::SignTransactionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_sign_transaction(
+    locale: Locale,
+    Json(request): Json<SignTransactionRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, sign_transaction(request).await)
+}
+
+#[utoipa::path(
+    get,
+    path = "/audit",
+    operation_id = "getAuditLog",
+    tag = "admin",
+    params(utils::AuditQuery),
+    responses(
+        (status = 200, description = "Recorded key generation and signing calls, newest first; never includes secrets", body = SuccessResponse<utils::AuditResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_audit_log(
+    locale: Locale,
+    Query(query): Query<AuditQuery>,
+) -> Json<serde_json::Value> {
+    respond(locale, get_audit_log(query))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api-keys",
+    operation_id = "listApiKeys",
+    tag = "admin",
+    responses(
+        (status = 200, description = "The API keys in force, with their rotation state and how often each was used since \
+            the server started; never includes the keys themselves", body = SuccessResponse<auth::ApiKeysResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_list_api_keys(
+    locale: Locale,
+    State(config): State<AppConfig>,
+) -> Json<serde_json::Value> {
+    respond(locale, auth::list_keys(&config.api_keys))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api-keys/{key_id}/rotate",
+    operation_id = "rotateApiKey",
+    tag = "admin",
+    params(("key_id" = String, Path, description = "Key id from /api-keys")),
+    request_body = auth::RotateKeyRequest,
+    responses(
+        (status = 200, description = "Issue a replacement key with the same scopes. The old key keeps working until the \
+            overlap ends, or until it is revoked", body = SuccessResponse<auth::RotateKeyResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_rotate_api_key(
+    locale: Locale,
+    State(config): State<AppConfig>,
+    Path(key_id): Path<String>,
+    Json(request): Json<auth::RotateKeyRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, auth::rotate_key(&config.api_keys, key_id, request, config.api_key_overlap_secs))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api-keys/{key_id}",
+    operation_id = "revokeApiKey",
+    tag = "admin",
+    params(("key_id" = String, Path, description = "Key id from /api-keys")),
+    responses(
+        (status = 200, description = "Revoke an API key at once", body = SuccessResponse<auth::ApiKeyInfo>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_revoke_api_key(
+    locale: Locale,
+    State(config): State<AppConfig>,
+    Path(key_id): Path<String>,
+) -> Json<serde_json::Value> {
+    respond(locale, auth::revoke_key(&config.api_keys, key_id))
+}
+
+#[utoipa::path(
+    get,
+    path = "/blockhash",
+    operation_id = "getBlockhash",
+    tag = "transaction",
+    responses(
+        (status = 200, description = "The latest blockhash and the block height it is valid until, from a cache refreshed in the background", body = SuccessResponse<utils::BlockhashResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_blockhash(locale: Locale) -> Json<serde_json::Value> {
+    respond(locale, get_blockhash().await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/airdrop",
+    operation_id = "requestAirdrop",
+    tag = "airdrop",
+    request_body = utils::AirdropRequest,
+    responses(
+        (status = 200, description = "Request lamports from the cluster's faucet, rejected on mainnet-beta, optionally waiting until the airdrop is confirmed", body = SuccessResponse<utils::AirdropResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_request_airdrop(
+    locale: Locale,
+    Json(request): Json<AirdropRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, request_airdrop(request).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/transaction/submit",
+    operation_id = "submitTransaction",
+    tag = "transaction",
+    request_body = utils::SubmitTransactionRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key and body return the first response instead of broadcasting again"),
+    ),
+    responses(
+        (status = 200, description = "Broadcast a fully signed transaction and return its signature; it is rebroadcast until confirmed and watched in the background", body = SuccessResponse<utils::SubmitTransactionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_submit_transaction(
+    locale: Locale,
+    headers: HeaderMap,
+    Json(request): Json<SubmitTransactionRequest>,
+) -> Response {
+    let fingerprint = idempotency::fingerprint(&request);
+    // Detached too, so the Idempotency-Key is settled if the client disconnects
+    let outcome = shutdown::detached(async move {
+        idempotency::run(&headers, "transaction/submit", fingerprint, submit_transaction(request)).await
+    })
+        .await;
+    let mut response = respond(locale, outcome.result).into_response();
+    if outcome.replayed {
+        response.headers_mut().insert(idempotency::REPLAYED_HEADER, HeaderValue::from_static("true"));
+    }
+    response
+}
+
+#[utoipa::path(
+    get,
+    path = "/transaction/{signature}/status",
+    operation_id = "getSignatureStatus",
+    tag = "transaction",
+    params(("signature" = String, Path, description = "Base58 transaction signature")),
+    responses(
+        (status = 200, description = "Confirmation status, slot, error and confirmation count of a transaction; notFound until the cluster has seen it", body = SuccessResponse<utils::SignatureStatusResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_signature_status(
+    locale: Locale,
+    Path(signature): Path<String>,
+) -> Json<serde_json::Value> {
+    respond(locale, get_signature_status(signature).await)
+}
+
+#[utoipa::path(
+    get,
+    path = "/transaction/sponsor",
+    operation_id = "getSponsor",
+    tag = "transaction",
+    responses(
+        (status = 200, description = "Fee payer of sponsored transactions and the policy they are held to", body = SuccessResponse<utils::SponsorInfoResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_sponsor(locale: Locale) -> Json<serde_json::Value> {
+    respond(locale, get_sponsor())
+}
+
+#[utoipa::path(
+    post,
+    path = "/transaction/sponsor",
+    operation_id = "sponsorTransaction",
+    tag = "transaction",
+    request_body = utils::SponsorTransactionRequest,
+    responses(
+        (status = 200, description = "Check a transaction against the sponsorship policy, make the server's key its fee payer and sign \
+            for it, then return it or broadcast it", body = SuccessResponse<utils::SponsorTransactionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_sponsor_transaction(
+    locale: Locale,
+    Json(request): Json<SponsorTransactionRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, sponsor_transaction(request).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/jobs/submit",
+    operation_id = "submitJob",
+    tag = "transaction",
+    request_body = utils::SubmitJobRequest,
+    responses(
+        (status = 200, description = "Queue a transaction to be signed, broadcast and watched until it reaches the commitment asked for, \
+            returning a job id to poll", body = SuccessResponse<utils::JobResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_submit_job(
+    locale: Locale,
+    Json(request): Json<SubmitJobRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, submit_job(request).await)
+}
+
+#[utoipa::path(
+    get,
+    path = "/jobs/{job_id}",
+    operation_id = "getJob",
+    tag = "transaction",
+    params(("job_id" = String, Path, description = "Job id returned by /jobs/submit")),
+    responses(
+        (status = 200, description = "Status of a transaction job; finished jobs are kept for an hour", body = SuccessResponse<utils::JobResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_job(
+    locale: Locale,
+    Path(job_id): Path<String>,
+) -> Json<serde_json::Value> {
+    respond(locale, get_job(job_id))
+}
+
+#[utoipa::path(
+    get,
+    path = "/pay/{reference}",
+    operation_id = "getPayMetadata",
+    tag = "pay",
+    params(("reference" = String, Path, description = "Public key attached to the payment to find it on chain")),
+    responses(
+        (status = 200, description = "Label and icon a Solana Pay wallet shows for a transaction request, \
+            without the usual envelope", body = utils::PayMetadataResponse),
+        (status = 404, description = "Solana Pay is not enabled on this server", body = ErrorResponse),
+    )
+)]
+async fn handle_pay_metadata(locale: Locale) -> Response {
+    respond_pay(locale, pay_metadata())
+}
+
+#[utoipa::path(
+    post,
+    path = "/pay/{reference}",
+    operation_id = "createPayTransaction",
+    tag = "pay",
+    params(("reference" = String, Path, description = "Public key attached to the payment to find it on chain")),
+    request_body = utils::PayTransactionRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction paying the configured amount from the account, for a \
+            Solana Pay wallet to sign and send, without the usual envelope", body = utils::PayTransactionResponse),
+        (status = 400, description = "The account or reference is invalid", body = ErrorResponse),
+        (status = 404, description = "Solana Pay is not enabled on this server", body = ErrorResponse),
+    )
+)]
+async fn handle_pay_transaction(
+    locale: Locale,
+    Path(reference): Path<String>,
+    Json(request): Json<PayTransactionRequest>,
+) -> Response {
+    respond_pay(locale, pay_transaction(reference, request).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/transaction/pending",
+    operation_id = "createPendingTransaction",
+    tag = "transaction",
+    request_body = utils::CreatePendingTransactionRequest,
+    responses(
+        (status = 200, description = "Hold a transaction while its signers add their signatures, returning an id \
+            to share with them; kept for an hour", body = SuccessResponse<utils::PendingTransactionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_create_pending_transaction(
+    locale: Locale,
+    Json(request): Json<CreatePendingTransactionRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, create_pending_transaction(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/transaction/{id}/signatures",
+    operation_id = "addSignatures",
+    tag = "transaction",
+    params(("id" = String, Path, description = "Id returned by /transaction/pending")),
+    request_body = utils::AddSignaturesRequest,
+    responses(
+        (status = 200, description = "Add signatures to a pending transaction, returning the fully signed \
+            transaction once none are missing", body = SuccessResponse<utils::PendingTransactionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_add_signatures(
+    locale: Locale,
+    Path(id): Path<String>,
+    Json(request): Json<AddSignaturesRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, add_signatures(id, request))
+}
+
+#[utoipa::path(
+    get,
+    path = "/transaction/{id}/signatures",
+    operation_id = "getPendingTransaction",
+    tag = "transaction",
+    params(("id" = String, Path, description = "Id returned by /transaction/pending")),
+    responses(
+        (status = 200, description = "Signatures collected for a pending transaction and the signers still missing", body = SuccessResponse<utils::PendingTransactionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_pending_transaction(
+    locale: Locale,
+    Path(id): Path<String>,
+) -> Json<serde_json::Value> {
+    respond(locale, get_pending_transaction(id))
+}
+
+#[utoipa::path(
+    post,
+    path = "/instruction/ed25519-verify",
+    operation_id = "buildEd25519Verify",
+    tag = "message",
+    request_body = utils::Ed25519VerifyRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build an Ed25519 program instruction that verifies a signature on chain", body = SuccessResponse<utils::Ed25519VerifyResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_build_ed25519_verify(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<Ed25519VerifyRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, build_ed25519_verify(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/instruction/secp256k1-verify",
+    operation_id = "buildSecp256k1Verify",
+    tag = "message",
+    request_body = utils::Secp256k1InstructionRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build a Secp256k1 program instruction that verifies an Ethereum signature on chain", body = SuccessResponse<utils::Secp256k1InstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_build_secp256k1_verify(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<Secp256k1InstructionRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, build_secp256k1_verify(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/instruction/memo",
+    operation_id = "buildMemo",
+    tag = "transfer",
+    request_body = utils::MemoRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build an SPL Memo instruction", body = SuccessResponse<utils::InstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_build_memo(
+    locale: Locale,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<MemoRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, build_memo(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/instruction/raw",
+    operation_id = "buildRawInstruction",
+    tag = "transaction",
+    request_body = utils::RawInstructionRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Build an instruction for any program from its accounts and data, for programs \
+            without an endpoint of their own", body = SuccessResponse<utils::InstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_build_raw_instruction(
+    locale: Locale,
+    tenant: Tenant,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<RawInstructionRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, build_raw_instruction_for(request, &tenant))
+}
+
+#[utoipa::path(
+    post,
+    path = "/idl",
+    operation_id = "registerIdl",
+    tag = "admin",
+    request_body = utils::RegisterIdlRequest,
+    responses(
+        (status = 200, description = "Register a program's Anchor IDL so its instructions can be built by name, \
+            replacing any IDL registered before", body = SuccessResponse<utils::IdlResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_register_idl(
+    locale: Locale,
+    Json(request): Json<RegisterIdlRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, register_idl(request))
+}
+
+#[utoipa::path(
+    get,
+    path = "/idl/{program}",
+    operation_id = "getIdl",
+    tag = "transaction",
+    params(("program" = String, Path, description = "Program id the IDL was registered for")),
+    responses(
+        (status = 200, description = "The Anchor IDL registered for a program", body = SuccessResponse<utils::IdlResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_get_idl(
+    locale: Locale,
+    Path(program): Path<String>,
+) -> Json<serde_json::Value> {
+    respond(locale, get_idl(program))
+}
+
+#[utoipa::path(
+    post,
+    path = "/idl/{program}/instruction",
+    operation_id = "buildIdlInstruction",
+    tag = "transaction",
+    params(("program" = String, Path, description = "Program id the IDL was registered for"), utils::FormatQuery),
+    request_body = utils::IdlInstructionRequest,
+    responses(
+        (status = 200, description = "Build an instruction by name from a registered Anchor IDL, with its \
+            discriminator, borsh-encoded arguments and resolved accounts", body = SuccessResponse<utils::InstructionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_build_idl_instruction(
+    locale: Locale,
+    tenant: Tenant,
+    Path(program): Path<String>,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<IdlInstructionRequest>,
+) -> Json<serde_json::Value> {
+    respond_instructions(locale, format, build_idl_instruction_for(program, request, &tenant))
+}
+
+#[utoipa::path(
+    post,
+    path = "/encode/borsh",
+    operation_id = "encodeBorsh",
+    tag = "transaction",
+    request_body = utils::EncodeBorshRequest,
+    responses(
+        (status = 200, description = "Borsh-serialize a JSON value by a schema written as in an Anchor IDL, such as \
+            instruction data for /instruction/raw", body = SuccessResponse<utils::EncodeBorshResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_encode_borsh(
+    locale: Locale,
+    Json(request): Json<EncodeBorshRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, encode_borsh(request))
+}
+
+#[utoipa::path(
+    post,
+    path = "/decode/borsh",
+    operation_id = "decodeBorsh",
+    tag = "transaction",
+    request_body = utils::DecodeBorshRequest,
+    responses(
+        (status = 200, description = "Read Borsh-serialized bytes back into JSON by the same schema /encode/borsh takes", body = SuccessResponse<utils::DecodeBorshResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_decode_borsh(
+    locale: Locale,
+    Json(request): Json<DecodeBorshRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, decode_borsh(request))
+}
+
+#[cfg(debug_assertions)]
+async fn handle_get_fixture(locale: Locale, Path(operation): Path<String>) -> Json<serde_json::Value> {
+    respond(locale, utils::fixtures::get_fixture(operation))
+}
+
+#[utoipa::path(
+    post,
+    path = "/instructions/batch",
+    operation_id = "buildBatch",
+    tag = "transaction",
+    request_body = utils::BatchInstructionsRequest,
+    params(utils::FormatQuery),
+    responses(
+        (status = 200, description = "Instructions for every operation, in request order", body = SuccessResponse<utils::BatchInstructionsResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_build_batch(
+    locale: Locale,
+    tenant: Tenant,
+    Query(format): Query<FormatQuery>,
+    Json(request): Json<BatchInstructionsRequest>,
+) -> Json<serde_json::Value> {
+    let result = build_batch(request, &tenant).map(|mut response| {
+        heuristics::localize(&mut response.data.warnings, locale);
+        response
+    });
+    respond_instructions(locale, format, result)
+}
+
+#[utoipa::path(
+    post,
+    path = "/transaction/message",
+    operation_id = "compileMessage",
+    tag = "transaction",
+    request_body = utils::CompileMessageRequest,
+    responses(
+        (status = 200, description = "Compile instructions into a serialized message, without a transaction around it, \
+            for signers that sign message bytes such as hardware wallets", body = SuccessResponse<utils::CompileMessageResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_compile_message(
+    locale: Locale,
+    tenant: Tenant,
+    Json(request): Json<CompileMessageRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, compile_message(request, &tenant))
+}
+
+#[utoipa::path(
+    post,
+    path = "/transaction/compose",
+    operation_id = "composeTransaction",
+    tag = "transaction",
+    request_body = utils::ComposeTransactionRequest,
+    params(utils::ComposeFormatQuery),
+    responses(
+        (status = 200, description = "Unsigned transaction containing every operation's instructions, in request order", body = SuccessResponse<utils::ComposeTransactionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_compose_transaction(
+    locale: Locale,
+    tenant: Tenant,
+    Query(format): Query<ComposeFormatQuery>,
+    Json(request): Json<ComposeTransactionRequest>,
+) -> Json<serde_json::Value> {
+    let result = compose_transaction(request, &tenant).await.map(|mut response| {
+        heuristics::localize(&mut response.data.warnings, locale);
+        response
+    });
+    match format.format {
+        Some(ComposeFormat::Multisig) => respond(locale, to_multisig_proposal(result)),
+        None => respond(locale, result),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/transaction/clone",
+    operation_id = "cloneTransaction",
+    tag = "transaction",
+    request_body = utils::CloneTransactionRequest,
+    responses(
+        (status = 200, description = "A compose request that repeats an on-chain transaction's operations with a fresh blockhash", body = SuccessResponse<utils::CloneTransactionResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_clone_transaction(
+    locale: Locale,
+    Json(request): Json<CloneTransactionRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, clone_transaction(request).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/transaction/fee",
+    operation_id = "estimateTransactionFee",
+    tag = "transaction",
+    request_body = utils::TransactionFeeRequest,
+    responses(
+        (status = 200, description = "Exact fee for a transaction or message, with a recommended priority fee", body = SuccessResponse<utils::TransactionFeeResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_estimate_transaction_fee(
+    locale: Locale,
+    Json(request): Json<TransactionFeeRequest>,
+) -> Json<serde_json::Value> {
+    respond(locale, estimate_transaction_fee(request).await)
+}
+
+#[utoipa::path(
+    get,
+    path = "/fees/priority",
+    operation_id = "recommendPriorityFees",
+    tag = "transaction",
+    params(utils::PriorityFeeQuery),
+    responses(
+        (status = 200, description = "Low, medium and high compute unit prices from recent prioritization fees, ready to use as a builder's priorityFee", body = SuccessResponse<utils::PriorityFeeResponse>),
+        (status = "default", description = "Error envelope, returned with status 200", body = ErrorResponse),
+    )
+)]
+async fn handle_recommend_priority_fees(
+    locale: Locale,
+    Query(query): Query<PriorityFeeQuery>,
+) -> Json<serde_json::Value> {
+    respond(locale, recommend_priority_fees(query).await)
+}
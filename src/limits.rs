@@ -0,0 +1,54 @@
+use axum::{
+    body::Body,
+    extract::DefaultBodyLimit,
+    http::{header, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use std::time::Duration;
+use tower_http::{limit::RequestBodyLimitLayer, timeout::TimeoutLayer};
+use crate::utils::{
+    i18n::{self, Locale},
+    ApiErrorCode, ErrorResponse,
+};
+
+// Keypair generation is pure computation, so anything slower is stuck
+pub const KEYPAIR_TIMEOUT: Duration = Duration::from_secs(5);
+// Submission waits on preflight simulation, which can be slow under load
+pub const SUBMIT_TIMEOUT: Duration = Duration::from_secs(60);
+// Airdrops can wait for confirmation, which takes a few slots
+pub const AIRDROP_TIMEOUT: Duration = Duration::from_secs(45);
+// Batch endpoints take many items per request; this was axum's default limit
+pub const BATCH_BODY_LIMIT: usize = 2 * 1024 * 1024;
+
+// Bound how long the router's routes may take to respond and how large a
+// body they accept. Layers only wrap routes already added, so routes that
+// need other limits go in their own router, limited separately and merged.
+pub fn apply<S: Clone + Send + Sync + 'static>(router: Router<S>, timeout: Duration, body_limit: usize) -> Router<S> {
+    router
+        .layer(TimeoutLayer::new(timeout))
+        .layer(RequestBodyLimitLayer::new(body_limit))
+        // The extractors' own 2 MiB limit would otherwise still apply
+        .layer(DefaultBodyLimit::disable())
+}
+
+// The timeout and body limit layers answer with an empty body, or plain
+// text, so their responses are given the usual error envelope
+pub async fn json_errors(request: Request<Body>, next: Next) -> Response {
+    let locale = Locale::from_headers(request.headers());
+    let response = next.run(request).await;
+
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+    let (code, message) = match response.status() {
+        _ if is_json => return response,
+        StatusCode::REQUEST_TIMEOUT => (ApiErrorCode::Timeout, "Request timed out"),
+        StatusCode::PAYLOAD_TOO_LARGE => (ApiErrorCode::BodyTooLarge, "Request body is too large"),
+        _ => return response,
+    };
+
+    (response.status(), Json(ErrorResponse::with_code(code, i18n::translate(locale, message)))).into_response()
+}
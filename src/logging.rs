@@ -0,0 +1,109 @@
+use axum::{body::Body, http::Request};
+use tower_http::{
+    classify::{ServerErrorsAsFailures, SharedClassifier},
+    trace::{DefaultOnResponse, TraceLayer},
+    LatencyUnit,
+};
+use tracing::{field, Level, Span};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use crate::request_id;
+
+// A 64-byte secret key is at least 80 chars in base58, while pubkeys are at
+// most 44, so shorter words never need to be decoded
+const MIN_SECRET_LEN: usize = 80;
+
+type HttpTraceLayer = TraceLayer<SharedClassifier<ServerErrorsAsFailures>, fn(&Request<Body>) -> Span>;
+
+// Spans are also exported over OTLP when a collector endpoint is given.
+// Must run inside a Tokio runtime, which the exporter sends batches from.
+pub fn init(otlp_endpoint: Option<&str>) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new("info,tower_http=info"));
+    let json = tracing_subscriber::fmt::layer()
+        .json()
+        .with_current_span(true)
+        .with_span_list(false);
+
+    let registry = tracing_subscriber::registry().with(filter).with(json);
+    #[cfg(feature = "otel")]
+    let registry = registry.with(otlp_endpoint.map(otel::layer));
+    #[cfg(not(feature = "otel"))]
+    let _ = otlp_endpoint;
+    registry.init();
+}
+
+// Sends spans still waiting in the export batch
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use opentelemetry::{trace::TracerProvider as _, KeyValue};
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+    use tracing_opentelemetry::OpenTelemetryLayer;
+    use tracing_subscriber::registry::LookupSpan;
+
+    pub fn layer<S>(endpoint: &str) -> OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>
+    where
+        S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+    {
+        let exporter = SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .build()
+            .unwrap_or_else(|err| panic!("Failed to set up the OTLP exporter for {}: {}", endpoint, err));
+        let provider = TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_resource(Resource::new([KeyValue::new("service.name", env!("CARGO_PKG_NAME"))]))
+            .build();
+        let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+        opentelemetry::global::set_tracer_provider(provider);
+        tracing_opentelemetry::layer().with_tracer(tracer)
+    }
+}
+
+pub fn trace_layer() -> HttpTraceLayer {
+    TraceLayer::new_for_http()
+        .make_span_with(make_span as fn(&Request<Body>) -> Span)
+        .on_response(
+            DefaultOnResponse::new()
+                .level(Level::INFO)
+                .latency_unit(LatencyUnit::Millis),
+        )
+}
+
+fn make_span(request: &Request<Body>) -> Span {
+    let request_id = request
+        .headers()
+        .get(&request_id::HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    // The cluster is filled in once cluster::select has picked it
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        path = %request.uri().path(),
+        request_id,
+        cluster = field::Empty,
+    )
+}
+
+// Replace anything that looks like a base58 secret key with a placeholder so
+// error messages can be logged safely
+pub fn redact_secrets(message: &str) -> String {
+    message
+        .split(' ')
+        .map(|word| {
+            let is_secret = word.len() >= MIN_SECRET_LEN
+                && bs58::decode(word.trim_matches(|c: char| !c.is_ascii_alphanumeric()))
+                    .into_vec()
+                    .map(|bytes| bytes.len() == 64)
+                    .unwrap_or(false);
+            if is_secret { "[REDACTED]" } else { word }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
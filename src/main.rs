@@ -1,111 +1,101 @@
-use axum::{
-    http::Method,
-    routing::{get, post},
-    Router,
-    Json,
-};
+use futures::future::{join_all, BoxFuture};
 use tokio::net::TcpListener;
-use tower_http::cors::{Any, CorsLayer};
-use std::env;
-https
-// Base URL for the deployed API
-pub const BASE_URL: &str = "https://rust-server-superdev.fly.dev";
-
-mod utils;
-use utils::{
-    generate_keypair, create_token, mint_token, sign_message, verify_message, send_sol, send_token,
-    CreateTokenRequest, MintTokenRequest, SignMessageRequest, VerifyMessageRequest, SendSolRequest,
-    SendTokenRequest
+use std::{env, net::SocketAddr, time::Duration};
+use rust_server_superdev::{
+    build_router,
+    cli::{self, Invocation},
+    logging, shutdown, spawn_background_tasks, tls,
 };
 
 #[tokio::main]
 async fn main() {
-    let cors = CorsLayer::new()
-        .allow_methods([Method::GET, Method::POST])
-        .allow_headers(Any)
-        .allow_origin(Any);
-
-    let app = Router::new()
-        .route("/", get(|| async { "Hello, World!" }))
-        .route("/keypair", get(handle_generate_keypair))
-        .route("/token/create", post(handle_create_token))
-        .route("/token/mint", post(handle_mint_token))
-        .route("/message/sign", post(handle_sign_message))
-        .route("/message/verify", post(handle_verify_message))
-        .route("/send/sol", post(handle_send_sol))
-        .route("/send/token", post(handle_send_token))
-        .layer(cors);
+    let config = match cli::run(env::args().collect()) {
+        Invocation::Serve(config) => *config,
+        Invocation::Exit(code) => std::process::exit(code),
+    };
 
-    // Get port from environment variable or use 3000 as default
-    let port = env::var("PORT").unwrap_or_else(|_| "3000".to_string());
-    let addr = format!("0.0.0.0:{}", port);
-    
-    let listener = TcpListener::bind(&addr)
-        .await
-        .unwrap();
+    logging::init(config.otlp_endpoint.as_deref());
+    #[cfg(not(feature = "otel"))]
+    if config.otlp_endpoint.is_some() {
+        tracing::error!("OTEL_EXPORTER_OTLP_ENDPOINT is set but this build does not include the otel feature");
+        std::process::exit(1);
+    }
+    let app = build_router(config.clone());
+    spawn_background_tasks();
 
-    println!("Server is running on http://{}", addr);
+    let shutdown = shutdown::listen();
+    let shutdown_timeout = Duration::from_secs(config.shutdown_timeout_secs);
 
-    axum::serve(listener, app).await.unwrap();
-}
-
-async fn handle_generate_keypair() -> Json<serde_json::Value> {
-    match generate_keypair() {
-        Ok(response) => Json(serde_json::to_value(response).unwrap()),
-        Err(err) => Json(serde_json::to_value(err).unwrap()),
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_port) = config.grpc_port {
+        tokio::spawn(rust_server_superdev::grpc::serve(format!("{}:{}", config.host, grpc_port), shutdown.clone()));
     }
-}
-
-async fn handle_create_token(
-    Json(request): Json<CreateTokenRequest>,
-) -> Json<serde_json::Value> {
-    match create_token(request) {
-        Ok(response) => Json(serde_json::to_value(response).unwrap()),
-        Err(err) => Json(serde_json::to_value(err).unwrap()),
+    #[cfg(not(feature = "grpc"))]
+    if config.grpc_port.is_some() {
+        tracing::error!("GRPC_PORT is set but this build does not include the grpc feature");
+        std::process::exit(1);
     }
-}
 
-async fn handle_mint_token(
-    Json(request): Json<MintTokenRequest>,
-) -> Json<serde_json::Value> {
-    match mint_token(request) {
-        Ok(response) => Json(serde_json::to_value(response).unwrap()),
-        Err(err) => Json(serde_json::to_value(err).unwrap()),
+    if config.mock_rpc {
+        tracing::warn!("MOCK_RPC is set: RPC calls are answered by an in-memory mock and nothing reaches a cluster");
     }
-}
 
-async fn handle_sign_message(
-    Json(request): Json<SignMessageRequest>,
-) -> Json<serde_json::Value> {
-    match sign_message(request) {
-        Ok(response) => Json(serde_json::to_value(response).unwrap()),
-        Err(err) => Json(serde_json::to_value(err).unwrap()),
-    }
-}
+    // Without a certificate, plain HTTP is served and TLS is left to the
+    // proxy in front, as on fly.io
+    let tls_config = match (&config.tls_cert_path, &config.tls_key_path) {
+        (Some(cert_path), Some(key_path)) => Some(tls::load(cert_path, key_path).unwrap_or_else(|err| {
+            tracing::error!(error = %err, "failed to load TLS certificate");
+            std::process::exit(1);
+        })),
+        _ => None,
+    };
+
+    // Every listener serves the same router and drains on the same signal
+    let mut servers: Vec<BoxFuture<'static, ()>> = Vec::new();
+    for port in &config.ports {
+        let addr = format!("{}:{}", config.host, port);
+        let listener = TcpListener::bind(&addr)
+            .await
+            .unwrap();
 
-async fn handle_verify_message(
-    Json(request): Json<VerifyMessageRequest>,
-) -> Json<serde_json::Value> {
-    match verify_message(request) {
-        Ok(response) => Json(serde_json::to_value(response).unwrap()),
-        Err(err) => Json(serde_json::to_value(err).unwrap()),
+        match &tls_config {
+            Some(tls_config) => {
+                tracing::info!(%addr, cluster = config.cluster.name(), "server is running with TLS");
+                servers.push(Box::pin(tls::serve(listener, app.clone(), tls_config.clone(), shutdown.clone())));
+            }
+            None => {
+                tracing::info!(%addr, cluster = config.cluster.name(), "server is running");
+                let server = axum::serve(listener, app.clone().into_make_service_with_connect_info::<SocketAddr>())
+                    .with_graceful_shutdown(shutdown.clone().cancelled_owned());
+                servers.push(Box::pin(async { server.await.unwrap() }));
+            }
+        }
     }
-}
 
-async fn handle_send_sol(
-    Json(request): Json<SendSolRequest>,
-) -> Json<serde_json::Value> {
-    match send_sol(request) {
-        Ok(response) => Json(serde_json::to_value(response).unwrap()),
-        Err(err) => Json(serde_json::to_value(err).unwrap()),
+    #[cfg(unix)]
+    if let Some(path) = &config.unix_socket_path {
+        let listener = rust_server_superdev::unix_socket::bind(path).unwrap_or_else(|err| {
+            tracing::error!(%path, error = %err, "failed to bind the Unix socket");
+            std::process::exit(1);
+        });
+        tracing::info!(%path, cluster = config.cluster.name(), "server is running on a Unix socket");
+        servers.push(Box::pin(rust_server_superdev::unix_socket::serve(listener, path.clone(), app.clone(), shutdown.clone())));
+    }
+    #[cfg(not(unix))]
+    if config.unix_socket_path.is_some() {
+        tracing::error!("UNIX_SOCKET_PATH is set but Unix sockets are not supported on this platform");
+        std::process::exit(1);
     }
-}
 
-async fn handle_send_token(
-    Json(request): Json<SendTokenRequest>,
-) -> Json<serde_json::Value> {
-    match send_token(request) {
-        Ok(response) => Json(serde_json::to_value(response).unwrap()),
-        Err(err) => Json(serde_json::to_value(err).unwrap()),
+    if servers.is_empty() {
+        tracing::error!("SERVE_TCP is off and UNIX_SOCKET_PATH is not set, so there is nothing to listen on");
+        std::process::exit(1);
     }
+
+    let server = async {
+        join_all(servers).await;
+        shutdown::finish_detached().await;
+    };
+    shutdown::drain(server, &shutdown, shutdown_timeout).await;
+    logging::shutdown();
 }
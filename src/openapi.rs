@@ -0,0 +1,170 @@
+use axum::{extract::State, response::Html, Json};
+use utoipa::{openapi::{OpenApi as OpenApiSpec, Server}, OpenApi};
+use crate::utils::{ErrorResponse, MultisigProposalResponse, SuccessResponse, WalletAdapterResponse};
+use crate::config::AppConfig;
+use crate::versioning;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "rust-server-superdev",
+        description = "Solana keypair, message signing, and instruction building API",
+    ),
+    paths(
+        crate::handle_health,
+        crate::handle_ready,
+        crate::handle_siws_challenge,
+        crate::handle_siws_verify,
+        crate::handle_generate_keypair,
+        crate::handle_keypair_from_mnemonic,
+        crate::handle_convert_keypair,
+        crate::handle_generate_vanity_keypair,
+        crate::handle_get_vanity_job,
+        crate::handle_get_footprint,
+        crate::handle_store_key,
+        crate::handle_get_key,
+        crate::handle_update_key_state,
+        crate::handle_delete_key,
+        crate::handle_create_token,
+        crate::handle_mint_token,
+        crate::handle_get_mint_info,
+        crate::handle_get_token_metadata,
+        crate::handle_resolve_name,
+        crate::handle_close_token_account,
+        crate::handle_approve_token,
+        crate::handle_revoke_token,
+        crate::handle_initialize_transfer_fee_mint,
+        crate::handle_harvest_transfer_fees,
+        crate::handle_transfer_with_fee,
+        crate::handle_sign_message,
+        crate::handle_verify_message,
+        crate::handle_sign_message_secp256k1,
+        crate::handle_verify_message_secp256k1,
+        crate::handle_verify_message_batch,
+        crate::handle_send_sol,
+        crate::handle_transfer_with_seed,
+        crate::handle_send_token,
+        crate::handle_wrap_sol,
+        crate::handle_unwrap_sol,
+        crate::handle_create_vote_account,
+        crate::handle_authorize_vote,
+        crate::handle_update_commission,
+        crate::handle_withdraw_from_vote_account,
+        crate::handle_create_stake_account,
+        crate::handle_delegate_stake,
+        crate::handle_deactivate_stake,
+        crate::handle_withdraw_stake,
+        crate::handle_split_stake,
+        crate::handle_stake_pool_deposit_sol,
+        crate::handle_stake_pool_withdraw_sol,
+        crate::handle_stake_pool_deposit_stake,
+        crate::handle_stake_pool_withdraw_stake,
+        crate::handle_create_account,
+        crate::handle_create_account_with_seed,
+        crate::handle_get_account_info,
+        crate::handle_get_rent_minimum,
+        crate::handle_create_nonce_account,
+        crate::handle_advance_nonce,
+        crate::handle_withdraw_nonce,
+        crate::handle_create_lookup_table,
+        crate::handle_extend_lookup_table,
+        crate::handle_deactivate_lookup_table,
+        crate::handle_close_lookup_table,
+        crate::handle_get_lookup_table,
+        crate::handle_create_compressed_nft_tree,
+        crate::handle_mint_compressed_nft,
+        crate::handle_mint_nft,
+        crate::handle_get_stake_rewards,
+        crate::handle_validate_address,
+        crate::handle_derive_address_with_seed,
+        crate::handle_derive_associated_token_address,
+        crate::handle_get_sol_balance,
+        crate::handle_get_token_balance,
+        crate::handle_get_token_holdings,
+        crate::handle_explain_transaction,
+        crate::handle_decode_instruction,
+        crate::handle_decode_transaction,
+        crate::handle_sign_transaction,
+        crate::handle_submit_transaction,
+        crate::handle_request_airdrop,
+        crate::handle_get_blockhash,
+        crate::handle_get_audit_log,
+        crate::handle_list_api_keys,
+        crate::handle_rotate_api_key,
+        crate::handle_revoke_api_key,
+        crate::handle_submit_job,
+        crate::handle_get_signature_status,
+        crate::handle_get_sponsor,
+        crate::handle_sponsor_transaction,
+        crate::handle_get_job,
+        crate::handle_create_pending_transaction,
+        crate::handle_add_signatures,
+        crate::handle_get_pending_transaction,
+        crate::handle_pay_metadata,
+        crate::handle_pay_transaction,
+        crate::handle_compose_transaction,
+        crate::handle_compile_message,
+        crate::handle_clone_transaction,
+        crate::handle_estimate_transaction_fee,
+        crate::handle_recommend_priority_fees,
+        crate::handle_build_ed25519_verify,
+        crate::handle_build_secp256k1_verify,
+        crate::handle_build_memo,
+        crate::handle_build_raw_instruction,
+        crate::handle_register_idl,
+        crate::handle_get_idl,
+        crate::handle_build_idl_instruction,
+        crate::handle_encode_borsh,
+        crate::handle_decode_borsh,
+        crate::handle_build_batch,
+        crate::ws::handler,
+        crate::json_rpc::handler,
+        crate::handle_program_logs,
+    ),
+    components(schemas(ErrorResponse, SuccessResponse<WalletAdapterResponse>, SuccessResponse<MultisigProposalResponse>)),
+)]
+struct ApiDoc;
+
+// Swagger UI is loaded from a CDN so the build doesn't need to download and
+// embed its assets
+const DOCS_HTML: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <title>rust-server-superdev API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js" crossorigin></script>
+  <script>
+    window.onload = () => {
+      window.ui = SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });
+    };
+  </script>
+</body>
+</html>
+"##;
+
+// Only the versioned routes are documented; the health probes are the one
+// part of the API left unprefixed
+pub fn spec(base_url: &str) -> OpenApiSpec {
+    let mut spec = ApiDoc::openapi();
+    spec.servers = Some(vec![Server::new(base_url)]);
+    spec.paths.paths = std::mem::take(&mut spec.paths.paths)
+        .into_iter()
+        .map(|(path, item)| match path.as_str() {
+            "/health" | "/ready" => (path, item),
+            _ => (format!("{}{}", versioning::PREFIX, path), item),
+        })
+        .collect();
+    spec
+}
+
+pub async fn openapi_json(State(config): State<AppConfig>) -> Json<OpenApiSpec> {
+    Json(spec(&config.base_url))
+}
+
+pub async fn docs() -> Html<&'static str> {
+    Html(DOCS_HTML)
+}
@@ -0,0 +1,155 @@
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures::{stream, Stream, StreamExt};
+use serde::Serialize;
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+};
+use solana_sdk::pubkey::Pubkey;
+use std::{convert::Infallible, str::FromStr};
+use tokio::sync::mpsc;
+use utoipa::ToSchema;
+use crate::cluster;
+use crate::utils::{rpc, ErrorResponse};
+
+// Notifications waiting to be written to a slow client before newer ones
+// are dropped
+const STREAM_BUFFER: usize = 256;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LogKind {
+    Invoke,
+    Log,
+    Data,
+    Return,
+    Consumed,
+    Success,
+    Failed,
+    Other,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ParsedLogLine {
+    pub kind: LogKind,
+    // Program that was executing when the line was written
+    pub program_id: Option<String>,
+    pub depth: usize,
+    pub message: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ProgramLogsEvent {
+    pub slot: u64,
+    pub signature: String,
+    pub err: Option<String>,
+    pub logs: Vec<ParsedLogLine>,
+}
+
+// Attribute each line to the program on top of the invocation stack, which
+// the runtime's "invoke [n]" and "success"/"failed" lines push and pop
+fn parse_logs(logs: Vec<String>) -> Vec<ParsedLogLine> {
+    let mut stack: Vec<String> = Vec::new();
+
+    logs.into_iter()
+        .map(|line| {
+            let kind = if line.starts_with("Program log: ") {
+                LogKind::Log
+            } else if line.starts_with("Program data: ") {
+                LogKind::Data
+            } else if line.starts_with("Program return: ") {
+                LogKind::Return
+            } else if let Some(rest) = line.strip_prefix("Program ") {
+                let (program_id, event) = rest.split_once(' ').unwrap_or((rest, ""));
+                if event.starts_with("invoke [") {
+                    stack.push(program_id.to_string());
+                    LogKind::Invoke
+                } else if event == "success" {
+                    LogKind::Success
+                } else if event.starts_with("failed") {
+                    LogKind::Failed
+                } else if event.starts_with("consumed ") {
+                    LogKind::Consumed
+                } else {
+                    LogKind::Other
+                }
+            } else {
+                LogKind::Other
+            };
+
+            let program_id = stack.last().cloned();
+            let depth = stack.len();
+            if matches!(kind, LogKind::Success | LogKind::Failed) {
+                stack.pop();
+            }
+
+            let message = match kind {
+                LogKind::Log | LogKind::Data | LogKind::Return => line.splitn(3, ' ').nth(2).unwrap_or_default().to_string(),
+                _ => line,
+            };
+            ParsedLogLine { kind, program_id, depth, message }
+        })
+        .collect()
+}
+
+fn error_event(message: String) -> Event {
+    Event::default().event("error").data(message)
+}
+
+// Forward logsSubscribe notifications for the program until the client goes away
+async fn forward_logs(program_id: Pubkey, events: mpsc::Sender<Event>) {
+    let pubsub = match PubsubClient::new(&rpc::ws_url()).await {
+        Ok(pubsub) => pubsub,
+        Err(err) => {
+            let _ = events.send(error_event(format!("Failed to connect to Solana pubsub: {}", err))).await;
+            return;
+        }
+    };
+
+    let filter = RpcTransactionLogsFilter::Mentions(vec![program_id.to_string()]);
+    let config = RpcTransactionLogsConfig { commitment: Some(rpc::client().commitment()) };
+    let (mut notifications, unsubscribe) = match pubsub.logs_subscribe(filter, config).await {
+        Ok(subscription) => subscription,
+        Err(err) => {
+            let _ = events.send(error_event(format!("Failed to subscribe to program logs: {}", err))).await;
+            return;
+        }
+    };
+
+    loop {
+        tokio::select! {
+            notification = notifications.next() => {
+                let Some(notification) = notification else {
+                    let _ = events.send(error_event("Log subscription closed by the RPC node".to_string())).await;
+                    break;
+                };
+                let event = ProgramLogsEvent {
+                    slot: notification.context.slot,
+                    signature: notification.value.signature,
+                    err: notification.value.err.map(|err| err.to_string()),
+                    logs: parse_logs(notification.value.logs),
+                };
+                let event = Event::default().event("logs").json_data(event).unwrap();
+                if events.try_send(event).is_err() && events.is_closed() {
+                    break;
+                }
+            }
+            _ = events.closed() => break,
+        }
+    }
+
+    unsubscribe().await;
+}
+
+pub fn stream(program_id: String) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ErrorResponse> {
+    let program_id = Pubkey::from_str(&program_id)
+        .map_err(|_| ErrorResponse::new("Invalid public key format for program id"))?;
+
+    let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+    tokio::spawn(cluster::scope(cluster::current(), forward_logs(program_id, sender)));
+
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|event| (Ok(event), receiver))
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
@@ -0,0 +1,127 @@
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, State},
+    http::{header, HeaderMap, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+use crate::config::AppConfig;
+use crate::utils::{
+    i18n::{self, Locale},
+    verify_session_token, ApiErrorCode, ErrorResponse,
+};
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+// Fixed-window request counters, keyed by "ip:<addr>" for every request and
+// also by "wallet:<pubkey>" for clients that present a SIWS session token
+static WINDOWS: OnceLock<Mutex<HashMap<String, (Instant, u32)>>> = OnceLock::new();
+
+fn client_ip(headers: &HeaderMap, peer: Option<SocketAddr>, trust_proxy_headers: bool) -> String {
+    // fly.io's proxy reports the real client address in Fly-Client-IP; other
+    // proxies append it to X-Forwarded-For, so the last entry is theirs and
+    // anything before it came from the client
+    let forwarded = trust_proxy_headers
+        .then(|| {
+            headers
+                .get("fly-client-ip")
+                .and_then(|value| value.to_str().ok())
+                .or_else(|| {
+                    headers
+                        .get("x-forwarded-for")
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(|value| value.rsplit(',').next())
+                })
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+        })
+        .flatten();
+
+    forwarded
+        .map(str::to_string)
+        .or_else(|| peer.map(|addr| addr.ip().to_string()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn wallet_from_headers(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(verify_session_token)
+        .map(|pubkey| pubkey.to_string())
+}
+
+// Count a request against each of its keys, returning the seconds until the
+// longest window resets when any limit has been exceeded. Nothing is counted
+// for a rejected request.
+fn check(keys: &[(String, u32)]) -> Result<(), u64> {
+    let now = Instant::now();
+    let mut windows = WINDOWS.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap();
+
+    if windows.len() > 100_000 {
+        windows.retain(|_, (started, _)| now.duration_since(*started) < WINDOW);
+    }
+
+    let mut retry_after = None;
+    for (key, limit) in keys {
+        let (started, count) = windows.entry(key.clone()).or_insert((now, 0));
+        if now.duration_since(*started) >= WINDOW {
+            *started = now;
+            *count = 0;
+        }
+        if *count >= *limit {
+            let wait = WINDOW.saturating_sub(now.duration_since(*started)).as_secs().max(1);
+            retry_after = retry_after.max(Some(wait));
+        }
+    }
+    if let Some(retry_after) = retry_after {
+        return Err(retry_after);
+    }
+
+    for (key, _) in keys {
+        if let Some((_, count)) = windows.get_mut(key) {
+            *count += 1;
+        }
+    }
+    Ok(())
+}
+
+pub async fn enforce(State(config): State<AppConfig>, request: Request<Body>, next: Next) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr);
+
+    // Signed-in requests still count against their address, capped at the
+    // wallet limit, so signing in with more wallets doesn't buy more requests
+    let ip = format!("ip:{}", client_ip(request.headers(), peer, config.trust_proxy_headers));
+    let keys = match wallet_from_headers(request.headers()) {
+        Some(wallet) => vec![
+            (format!("wallet:{}", wallet), config.wallet_rate_limit),
+            (ip, config.wallet_rate_limit.max(config.ip_rate_limit)),
+        ],
+        None => vec![(ip, config.ip_rate_limit)],
+    };
+
+    if let Err(retry_after) = check(&keys) {
+        let mut response = (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(ErrorResponse::with_code(
+                ApiErrorCode::RateLimited,
+                i18n::translate(Locale::from_headers(request.headers()), "Rate limit exceeded, try again later"),
+            )),
+        ).into_response();
+        response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        return response;
+    }
+
+    next.run(request).await
+}
@@ -0,0 +1,60 @@
+use axum::{
+    body::Body,
+    http::{HeaderName, HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use std::future::Future;
+
+pub const HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+// Longer client-supplied IDs are replaced rather than logged
+const MAX_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+// IDs from clients or proxies are kept so a request can be followed across
+// systems, as long as they are short and safe to log
+fn is_acceptable(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_LEN
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+fn generate() -> String {
+    hex::encode(rand::random::<[u8; 16]>())
+}
+
+// The current request's ID, for errors built while handling it
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+// Runs the work under the ID when there is one, for carrying it over to a
+// task the request spawns
+pub async fn scope<F: Future>(id: Option<String>, work: F) -> F::Output {
+    match id {
+        Some(id) => REQUEST_ID.scope(id, work).await,
+        None => work.await,
+    }
+}
+
+// Runs outside the trace layer, so the ID is on the request by the time its
+// span is created, and is echoed back on every response
+pub async fn assign(mut request: Request<Body>, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(&HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_acceptable(id))
+        .map(str::to_string)
+        .unwrap_or_else(generate);
+    let value = HeaderValue::from_str(&id).unwrap();
+    request.headers_mut().insert(HEADER, value.clone());
+
+    let mut response = REQUEST_ID.scope(id, next.run(request)).await;
+    response.headers_mut().insert(HEADER, value);
+    response
+}
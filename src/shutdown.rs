@@ -0,0 +1,107 @@
+use std::{future::Future, sync::OnceLock, time::Duration};
+use tokio::task::JoinHandle;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+use tracing::{Instrument, Span};
+use crate::{auth, cluster, request_id};
+
+// Resolves with the name of the first shutdown signal received. fly.io
+// sends SIGINT by default and SIGTERM when kill_signal says so.
+async fn signal() -> &'static str {
+    let interrupt = async {
+        let _ = tokio::signal::ctrl_c().await;
+        "SIGINT"
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut stream) => {
+                stream.recv().await;
+            }
+            Err(err) => {
+                tracing::warn!(error = %err, "cannot listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+        "SIGTERM"
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<&'static str>();
+
+    tokio::select! {
+        reason = interrupt => reason,
+        reason = terminate => reason,
+    }
+}
+
+// A token cancelled once a shutdown signal arrives, at which point the
+// server stops accepting connections and lets in-flight requests finish
+pub fn listen() -> CancellationToken {
+    let token = CancellationToken::new();
+    let cancel = token.clone();
+    tokio::spawn(async move {
+        let reason = signal().await;
+        tracing::info!(reason, "shutdown requested, draining in-flight requests");
+        cancel.cancel();
+    });
+    token
+}
+
+// Work that must run to completion once started, such as a broadcast whose
+// client has stopped waiting for it
+fn detached_tasks() -> &'static TaskTracker {
+    static TASKS: OnceLock<TaskTracker> = OnceLock::new();
+    TASKS.get_or_init(TaskTracker::new)
+}
+
+// Starts the work on a tracked task of its own, which shutdown waits for.
+// The task keeps the request's cluster, API key and request ID.
+pub fn spawn_detached<F>(work: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let target = cluster::current();
+    let grant = auth::current_grant();
+    let id = request_id::current();
+    let work = async move {
+        let work = cluster::scope(target, work);
+        let work = auth::scope(grant, work);
+        request_id::scope(id, work).await
+    };
+    detached_tasks().spawn(work.instrument(Span::current()))
+}
+
+// Runs the work with spawn_detached and waits for it, so dropping the
+// caller, as when a client disconnects mid-request, can't cancel it halfway
+pub async fn detached<F>(work: F) -> F::Output
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match spawn_detached(work).await {
+        Ok(output) => output,
+        Err(err) => std::panic::resume_unwind(err.into_panic()),
+    }
+}
+
+// Resolves once every detached task has finished, for after the listeners
+// have drained so no more can start
+pub async fn finish_detached() {
+    detached_tasks().close();
+    detached_tasks().wait().await;
+}
+
+// Run the server until it has drained after shutdown, or until the drain
+// has taken longer than the timeout, whichever comes first
+pub async fn drain(server: impl Future<Output = ()>, token: &CancellationToken, timeout: Duration) {
+    let deadline = async {
+        token.cancelled().await;
+        tokio::time::sleep(timeout).await;
+    };
+
+    tokio::select! {
+        _ = server => tracing::info!("all connections drained, shutting down"),
+        _ = deadline => tracing::warn!(timeout_secs = timeout.as_secs(), "shutdown timed out with requests still in flight"),
+    }
+}
@@ -0,0 +1,95 @@
+use axum::{extract::ConnectInfo, Extension, Router};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::{conn::auto::Builder, graceful::GracefulShutdown},
+    service::TowerToHyperService,
+};
+use std::{fs::File, io::BufReader, net::SocketAddr, sync::Arc};
+use tokio::net::TcpListener;
+use tokio_rustls::{
+    rustls::{Certificate, PrivateKey, ServerConfig},
+    TlsAcceptor,
+};
+use tokio_util::sync::CancellationToken;
+
+fn read_pem(path: &str) -> Result<Vec<rustls_pemfile::Item>, String> {
+    let file = File::open(path).map_err(|err| format!("cannot open {}: {}", path, err))?;
+    rustls_pemfile::read_all(&mut BufReader::new(file)).map_err(|err| format!("cannot read {}: {}", path, err))
+}
+
+// Certificate chain and key from PEM files; the key may be PKCS#8, PKCS#1
+// (RSA) or SEC1 (EC)
+pub fn load(cert_path: &str, key_path: &str) -> Result<ServerConfig, String> {
+    let certs: Vec<Certificate> = read_pem(cert_path)?
+        .into_iter()
+        .filter_map(|item| match item {
+            rustls_pemfile::Item::X509Certificate(der) => Some(Certificate(der)),
+            _ => None,
+        })
+        .collect();
+    if certs.is_empty() {
+        return Err(format!("no certificates found in {}", cert_path));
+    }
+
+    let key = read_pem(key_path)?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(der) | rustls_pemfile::Item::RSAKey(der) | rustls_pemfile::Item::ECKey(der) => {
+                Some(PrivateKey(der))
+            }
+            _ => None,
+        })
+        .ok_or_else(|| format!("no private key found in {}", key_path))?;
+
+    let mut config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|err| format!("invalid certificate or key: {}", err))?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+// axum::serve only speaks plain HTTP, so HTTPS connections are accepted
+// here and handed to hyper directly. Each connection's router gets the peer
+// address the same way into_make_service_with_connect_info would add it.
+// Once shutdown is cancelled, no new connections are accepted and open ones
+// are closed as soon as their in-flight requests finish.
+pub async fn serve(listener: TcpListener, app: Router, config: ServerConfig, shutdown: CancellationToken) {
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+    let graceful = GracefulShutdown::new();
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(connection) => connection,
+                Err(err) => {
+                    tracing::warn!(error = %err, "failed to accept connection");
+                    continue;
+                }
+            },
+            _ = shutdown.cancelled() => break,
+        };
+        let acceptor = acceptor.clone();
+        let app = app.clone().layer(Extension(ConnectInfo::<SocketAddr>(peer)));
+        let watcher = graceful.watcher();
+
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    tracing::debug!(%peer, error = %err, "TLS handshake failed");
+                    return;
+                }
+            };
+            // Upgrades keep /ws and the log streams working over HTTPS
+            let builder = Builder::new(TokioExecutor::new());
+            let connection = builder.serve_connection_with_upgrades(TokioIo::new(stream), TowerToHyperService::new(app));
+            if let Err(err) = watcher.watch(connection.into_owned()).await {
+                tracing::debug!(%peer, error = %err, "connection closed with an error");
+            }
+        });
+    }
+
+    graceful.shutdown().await;
+}
@@ -0,0 +1,66 @@
+use axum::Router;
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::{conn::auto::Builder, graceful::GracefulShutdown},
+    service::TowerToHyperService,
+};
+use std::{
+    fs::{self, Permissions},
+    io,
+    os::unix::fs::{FileTypeExt, PermissionsExt},
+};
+use tokio::net::UnixListener;
+use tokio_util::sync::CancellationToken;
+
+// Only the owner and group may connect, since anyone who can reach the
+// socket can use the server's signers
+const SOCKET_MODE: u32 = 0o660;
+
+// A socket file left behind by a run that didn't shut down cleanly is
+// replaced; any other file at the path is an error
+pub fn bind(path: &str) -> io::Result<UnixListener> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => fs::remove_file(path)?,
+        Ok(_) => return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} exists and is not a socket", path))),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+    let listener = UnixListener::bind(path)?;
+    fs::set_permissions(path, Permissions::from_mode(SOCKET_MODE))?;
+    Ok(listener)
+}
+
+// Like tls::serve, but over plain HTTP. Unix socket peers have no address,
+// so there is no ConnectInfo and they all share one address's rate limit
+// bucket. The socket file is removed once drained.
+pub async fn serve(listener: UnixListener, path: String, app: Router, shutdown: CancellationToken) {
+    let graceful = GracefulShutdown::new();
+
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    tracing::warn!(error = %err, "failed to accept connection");
+                    continue;
+                }
+            },
+            _ = shutdown.cancelled() => break,
+        };
+        let app = app.clone();
+        let watcher = graceful.watcher();
+
+        tokio::spawn(async move {
+            let builder = Builder::new(TokioExecutor::new());
+            let connection = builder.serve_connection_with_upgrades(TokioIo::new(stream), TowerToHyperService::new(app));
+            if let Err(err) = watcher.watch(connection.into_owned()).await {
+                tracing::debug!(error = %err, "connection closed with an error");
+            }
+        });
+    }
+
+    graceful.shutdown().await;
+    if let Err(err) = fs::remove_file(&path) {
+        tracing::warn!(%path, error = %err, "failed to remove the Unix socket");
+    }
+}
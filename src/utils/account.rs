@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use solana_sdk::system_instruction::{self, MAX_PERMITTED_DATA_LENGTH};
+use super::address::derive_with_seed;
+use super::instruction::{validate_pubkey, InstructionResponse};
+use super::preload;
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+use super::rpc::{self, rpc_error};
+use super::token::{parse_token_state, MintView, TokenAccountView};
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateAccountRequest {
+    pub payer: String,
+    #[serde(rename = "newAccount")]
+    pub new_account: String,
+    // Bytes of account data to allocate
+    pub space: u64,
+    // Program that will own the new account, e.g. the SPL Token program for a mint
+    pub owner: String,
+    // Defaults to the rent-exempt minimum for `space` bytes
+    pub lamports: Option<u64>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RentMinimumQuery {
+    // Bytes of account data
+    pub space: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RentMinimumResponse {
+    pub space: u64,
+    pub lamports: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AccountInfoResponse {
+    pub address: String,
+    pub lamports: u64,
+    // Program that owns the account
+    pub owner: String,
+    pub executable: bool,
+    pub data_length: usize,
+    // Set for accounts owned by the SPL Token program
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_account: Option<TokenAccountView>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mint: Option<MintView>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreateAccountResponse {
+    #[serde(flatten)]
+    pub instruction: InstructionResponse,
+    pub lamports: u64,
+    pub space: u64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateAccountWithSeedRequest {
+    pub payer: String,
+    // Signs in place of the new account, whose address is derived from it
+    pub base: String,
+    // Up to 32 bytes
+    pub seed: String,
+    pub space: u64,
+    pub owner: String,
+    // Defaults to the rent-exempt minimum for `space` bytes
+    pub lamports: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreateAccountWithSeedResponse {
+    #[serde(flatten)]
+    pub instruction: InstructionResponse,
+    // The derived address of the new account
+    pub address: String,
+    pub lamports: u64,
+    pub space: u64,
+}
+
+fn validate_space(space: u64) -> Result<(), ErrorResponse> {
+    if space > MAX_PERMITTED_DATA_LENGTH {
+        return Err(ErrorResponse::new(format!(
+            "Accounts can hold at most {} bytes of data",
+            MAX_PERMITTED_DATA_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+fn build_create_account(request: CreateAccountRequest, minimum: u64) -> Result<SuccessResponse<CreateAccountResponse>, ErrorResponse> {
+    let payer = validate_pubkey(&request.payer, "payer")?;
+    let new_account = validate_pubkey(&request.new_account, "new account")?;
+    let owner = validate_pubkey(&request.owner, "owner program")?;
+
+    if payer == new_account {
+        return Err(ErrorResponse::new("Payer and new account cannot be the same"));
+    }
+
+    let lamports = request.lamports.unwrap_or(minimum);
+    if lamports < minimum {
+        return Err(ErrorResponse::new(format!(
+            "An account with {} bytes of data needs at least {} lamports to be rent exempt",
+            request.space, minimum
+        )));
+    }
+
+    let instruction = system_instruction::create_account(&payer, &new_account, lamports, request.space, &owner);
+
+    Ok(SuccessResponse::new(CreateAccountResponse {
+        instruction: InstructionResponse::from(&instruction),
+        lamports,
+        space: request.space,
+    }))
+}
+
+// Uses the rent parameters loaded at startup, so it can run inside batches
+// and composed transactions without an RPC call
+pub fn create_account(request: CreateAccountRequest) -> Result<SuccessResponse<CreateAccountResponse>, ErrorResponse> {
+    // Validate inputs
+    validate_space(request.space)?;
+    let minimum = preload::rent().minimum_balance(request.space as usize);
+
+    build_create_account(request, minimum)
+}
+
+// The new account's address comes from Pubkey::create_with_seed, so the base
+// key signs instead of a freshly generated keypair. Rent is taken from the
+// parameters loaded at startup, as for create_account.
+pub fn create_account_with_seed(request: CreateAccountWithSeedRequest) -> Result<SuccessResponse<CreateAccountWithSeedResponse>, ErrorResponse> {
+    // Validate inputs
+    validate_space(request.space)?;
+    let payer = validate_pubkey(&request.payer, "payer")?;
+    let base = validate_pubkey(&request.base, "base")?;
+    let owner = validate_pubkey(&request.owner, "owner program")?;
+    let address = derive_with_seed(&base, &request.seed, &owner)?;
+
+    let minimum = preload::rent().minimum_balance(request.space as usize);
+    let lamports = request.lamports.unwrap_or(minimum);
+    if lamports < minimum {
+        return Err(ErrorResponse::new(format!(
+            "An account with {} bytes of data needs at least {} lamports to be rent exempt",
+            request.space, minimum
+        )));
+    }
+
+    let instruction = system_instruction::create_account_with_seed(&payer, &address, &base, &request.seed, lamports, request.space, &owner);
+
+    Ok(SuccessResponse::new(CreateAccountWithSeedResponse {
+        instruction: InstructionResponse::from(&instruction),
+        address: address.to_string(),
+        lamports,
+        space: request.space,
+    }))
+}
+
+// When lamports are omitted, asks the cluster for the rent-exempt minimum,
+// which stays correct if its rent parameters differ from the ones loaded at
+// startup
+pub async fn create_account_with_rent(request: CreateAccountRequest) -> Result<SuccessResponse<CreateAccountResponse>, ErrorResponse> {
+    if request.lamports.is_some() {
+        return create_account(request);
+    }
+
+    // Validate inputs
+    validate_space(request.space)?;
+    let minimum = rent_exempt_minimum(request.space).await?;
+
+    build_create_account(request, minimum)
+}
+
+// Rent parameters only change with a feature activation, so answers are
+// cached by size and refreshed hourly
+async fn rent_exempt_minimum(space: u64) -> Result<u64, ErrorResponse> {
+    let space = space as usize;
+    rpc::rent_minimums()
+        .get_or_fetch(space, rpc::read("getMinimumBalanceForRentExemption", move |client| async move {
+            client.get_minimum_balance_for_rent_exemption(space).await
+        }))
+        .await
+        .map_err(rpc_error)
+}
+
+pub async fn get_rent_minimum(query: RentMinimumQuery) -> Result<SuccessResponse<RentMinimumResponse>, ErrorResponse> {
+    // Validate inputs
+    let space = query
+        .space
+        .ok_or_else(|| ErrorResponse::with_code(ApiErrorCode::MissingFields, "space is required"))?;
+    validate_space(space)?;
+
+    let lamports = rent_exempt_minimum(space).await?;
+
+    Ok(SuccessResponse::new(RentMinimumResponse { space, lamports }))
+}
+
+// Not cached, since balances change with every transaction
+pub async fn get_account_info(address: String) -> Result<SuccessResponse<AccountInfoResponse>, ErrorResponse> {
+    // Validate inputs
+    let address = validate_pubkey(&address, "account")?;
+
+    let account = rpc::read("getAccountInfo", move |client| async move {
+        client.get_account_with_commitment(&address, client.commitment()).await.map(|response| response.value)
+    })
+        .await
+        .map_err(rpc_error)?
+        .ok_or_else(|| ErrorResponse::with_code(ApiErrorCode::InvalidAccount, format!("No account exists at {}", address)))?;
+
+    let (token_account, mint) = if account.owner == spl_token::id() {
+        parse_token_state(&account.data)
+    } else {
+        (None, None)
+    };
+
+    Ok(SuccessResponse::new(AccountInfoResponse {
+        address: address.to_string(),
+        lamports: account.lamports,
+        owner: account.owner.to_string(),
+        executable: account.executable,
+        data_length: account.data.len(),
+        token_account,
+        mint,
+    }))
+}
@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use solana_sdk::{
+    program_pack::Pack,
+    pubkey::{Pubkey, PubkeyError},
+    system_program,
+};
+use spl_token::state::Account as TokenAccount;
+use super::instruction::validate_pubkey;
+use super::response_types::{SuccessResponse, ErrorResponse};
+use super::rpc::{self, rpc_error};
+use super::token::associated_token_address;
+use bs58;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AddressValidateQuery {
+    // Check whether the address is an associated token account for this mint
+    pub mint: Option<String>,
+    // Owner to check the associated token account against; looked up on
+    // chain when omitted
+    pub owner: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AssociatedTokenAddressQuery {
+    // SPL Token by default; pass the Token-2022 program id for its mints
+    #[serde(rename = "tokenProgram")]
+    pub token_program: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AssociatedTokenAddressResponse {
+    pub owner: String,
+    pub mint: String,
+    pub token_program: String,
+    pub address: String,
+    // Whether an account exists at the address yet; if not, it has to be
+    // created before it can receive tokens
+    pub exists: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AssociatedTokenAccountCheck {
+    pub mint: String,
+    pub owner: Option<String>,
+    pub is_associated_token_account: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AddressValidateResponse {
+    pub address: String,
+    pub valid_base58: bool,
+    pub valid_pubkey: bool,
+    // Keys on the ed25519 curve can sign, so they can be wallets; program
+    // derived addresses are always off the curve
+    pub on_curve: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub associated_token_account: Option<AssociatedTokenAccountCheck>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AddressWithSeedRequest {
+    pub base: String,
+    // Up to 32 bytes
+    pub seed: String,
+    // Program that will own the account; the System program by default
+    pub owner: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AddressWithSeedResponse {
+    pub address: String,
+    pub base: String,
+    pub seed: String,
+    pub owner: String,
+}
+
+// Pubkey::create_with_seed, with its errors as responses
+pub fn derive_with_seed(base: &Pubkey, seed: &str, owner: &Pubkey) -> Result<Pubkey, ErrorResponse> {
+    Pubkey::create_with_seed(base, seed, owner).map_err(|err| match err {
+        PubkeyError::MaxSeedLengthExceeded => ErrorResponse::new("Seeds can be at most 32 bytes"),
+        PubkeyError::IllegalOwner => ErrorResponse::new("The owner cannot be a program derived address marker"),
+        PubkeyError::InvalidSeeds => ErrorResponse::new("Invalid seed"),
+    })
+}
+
+pub fn derive_address_with_seed(request: AddressWithSeedRequest) -> Result<SuccessResponse<AddressWithSeedResponse>, ErrorResponse> {
+    // Validate inputs
+    let base = validate_pubkey(&request.base, "base")?;
+    let owner = match request.owner.as_deref() {
+        Some(owner) => validate_pubkey(owner, "owner")?,
+        None => system_program::id(),
+    };
+
+    let address = derive_with_seed(&base, &request.seed, &owner)?;
+
+    Ok(SuccessResponse::new(AddressWithSeedResponse {
+        address: address.to_string(),
+        base: base.to_string(),
+        seed: request.seed,
+        owner: owner.to_string(),
+    }))
+}
+
+// Owner of the token account at the address, if it is one
+async fn fetch_token_account_owner(address: Pubkey) -> Result<Option<Pubkey>, ErrorResponse> {
+    let account = rpc::accounts()
+        .get_or_fetch(address, rpc::read("getAccountInfo", move |client| async move {
+            client.get_account_with_commitment(&address, client.commitment()).await.map(|response| response.value)
+        }))
+        .await
+        .map_err(rpc_error)?;
+
+    Ok(account
+        .filter(|account| account.owner == spl_token::id())
+        .and_then(|account| TokenAccount::unpack(&account.data).ok())
+        .map(|token_account| token_account.owner))
+}
+
+pub async fn validate_address(address: String, query: AddressValidateQuery) -> Result<SuccessResponse<AddressValidateResponse>, ErrorResponse> {
+    // The address itself is what's being checked, so a bad one is reported
+    // in the response rather than as an error
+    let bytes = bs58::decode(&address).into_vec().ok();
+    let pubkey = bytes.as_deref().and_then(|bytes| Pubkey::try_from(bytes).ok());
+
+    let associated_token_account = match (&query.mint, pubkey) {
+        (Some(mint), Some(pubkey)) => {
+            let mint = validate_pubkey(mint, "mint")?;
+            let owner = match &query.owner {
+                Some(owner) => Some(validate_pubkey(owner, "owner")?),
+                None => fetch_token_account_owner(pubkey).await?,
+            };
+            let is_associated_token_account = owner.is_some_and(|owner| {
+                spl_associated_token_account::get_associated_token_address(&owner, &mint) == pubkey
+            });
+            Some(AssociatedTokenAccountCheck {
+                mint: mint.to_string(),
+                owner: owner.map(|owner| owner.to_string()),
+                is_associated_token_account,
+            })
+        }
+        _ => None,
+    };
+
+    Ok(SuccessResponse::new(AddressValidateResponse {
+        valid_base58: bytes.is_some(),
+        valid_pubkey: pubkey.is_some(),
+        on_curve: pubkey.map(|pubkey| pubkey.is_on_curve()),
+        associated_token_account,
+        address,
+    }))
+}
+
+pub async fn derive_associated_token_address(
+    owner: String,
+    mint: String,
+    query: AssociatedTokenAddressQuery,
+) -> Result<SuccessResponse<AssociatedTokenAddressResponse>, ErrorResponse> {
+    // Validate inputs
+    let owner = validate_pubkey(&owner, "owner")?;
+    let mint = validate_pubkey(&mint, "mint")?;
+    let token_program = match query.token_program.as_deref() {
+        Some(program) => validate_pubkey(program, "token program")?,
+        None => spl_token::id(),
+    };
+    if token_program != spl_token::id() && token_program != spl_token_2022::id() {
+        return Err(ErrorResponse::new("tokenProgram must be the SPL Token or Token-2022 program"));
+    }
+
+    let address = associated_token_address(&owner, &mint, &token_program);
+    let account = rpc::accounts()
+        .get_or_fetch(address, rpc::read("getAccountInfo", move |client| async move {
+            client.get_account_with_commitment(&address, client.commitment()).await.map(|response| response.value)
+        }))
+        .await
+        .map_err(rpc_error)?;
+
+    Ok(SuccessResponse::new(AssociatedTokenAddressResponse {
+        owner: owner.to_string(),
+        mint: mint.to_string(),
+        token_program: token_program.to_string(),
+        address: address.to_string(),
+        exists: account.is_some(),
+    }))
+}
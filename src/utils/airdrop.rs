@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::time::Duration;
+use crate::cluster::{self, Target};
+use crate::config::{self, Cluster};
+use crate::limits::AIRDROP_TIMEOUT;
+use super::confirmation;
+use super::instruction::{validate_lamports, validate_pubkey};
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+use super::rpc::{self, rpc_error};
+
+// Left for the rest of the request when waiting for confirmation, so the
+// answer arrives before the route's timeout
+const CONFIRMATION_MARGIN: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize, ToSchema)]
+pub struct AirdropRequest {
+    pub pubkey: String,
+    pub lamports: u64,
+    // Wait until the airdrop is confirmed before answering
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AirdropResponse {
+    pub pubkey: String,
+    pub lamports: u64,
+    pub signature: String,
+    // Set when confirm was requested; false if it wasn't confirmed in time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u64>,
+}
+
+// Allowed URLs can't be told apart by cluster, so their faucets are left to
+// refuse for themselves
+fn airdrops_available() -> bool {
+    match cluster::current() {
+        Some(Target::Named(cluster)) => cluster != Cluster::MainnetBeta,
+        Some(Target::Url(_)) => true,
+        None => config::get().cluster != Cluster::MainnetBeta,
+    }
+}
+
+pub async fn request_airdrop(request: AirdropRequest) -> Result<SuccessResponse<AirdropResponse>, ErrorResponse> {
+    // Validate inputs
+    let pubkey = validate_pubkey(&request.pubkey, "pubkey")?;
+    validate_lamports(request.lamports)?;
+    if !airdrops_available() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::InvalidRequest, "Airdrops are only available on devnet, testnet and localnet"));
+    }
+
+    // Not retried on another provider, since each call spends faucet funds
+    let client = rpc::client();
+    let signature = rpc::write("requestAirdrop", client, client.request_airdrop(&pubkey, request.lamports))
+        .await
+        .map_err(rpc_error)?;
+
+    let (confirmed, slot) = if request.confirm {
+        match confirmation::wait_confirmed(signature, AIRDROP_TIMEOUT - CONFIRMATION_MARGIN).await {
+            Ok(slot) => (Some(slot.is_some()), slot),
+            Err(error) => return Err(ErrorResponse::new(format!("Airdrop {} failed: {}", signature, error))),
+        }
+    } else {
+        (None, None)
+    };
+
+    Ok(SuccessResponse::new(AirdropResponse {
+        pubkey: pubkey.to_string(),
+        lamports: request.lamports,
+        signature: signature.to_string(),
+        confirmed,
+        slot,
+    }))
+}
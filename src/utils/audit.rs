@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use std::{
+    collections::VecDeque,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    sync::{Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use crate::auth;
+use crate::config;
+use crate::request_id;
+use super::response_types::{SuccessResponse, ErrorResponse};
+
+// Kept when there is no audit log file, oldest dropped first
+const MAX_MEMORY_ENTRIES: usize = 10_000;
+const DEFAULT_QUERY_LIMIT: usize = 100;
+const MAX_QUERY_LIMIT: usize = 1000;
+
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditOperation {
+    GenerateKeypair,
+    GenerateVanityKeypair,
+    KeypairFromMnemonic,
+    SignMessage,
+    SignTransaction,
+    SponsorTransaction,
+    StoreVaultKey,
+    UpdateVaultKeyState,
+}
+
+// Never holds secrets, only the public keys involved
+#[derive(Serialize, Deserialize, ToSchema, Clone)]
+pub struct AuditEntry {
+    // Unix seconds
+    pub timestamp: u64,
+    pub operation: AuditOperation,
+    pub success: bool,
+    #[serde(default)]
+    pub pubkeys: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    // ID of the API key used, when the server requires them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    pub operation: Option<AuditOperation>,
+    pub pubkey: Option<String>,
+    // Unix seconds; only entries at or after it
+    pub since: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AuditResponse {
+    // Newest first
+    pub entries: Vec<AuditEntry>,
+}
+
+// AUDIT_LOG_PATH selects an append-only file of JSON lines, which outlives
+// restarts; otherwise recent entries are kept in this process
+enum Sink {
+    Memory(Mutex<VecDeque<AuditEntry>>),
+    File { path: String, file: Mutex<File> },
+}
+
+static SINK: OnceLock<Sink> = OnceLock::new();
+
+// Opens the audit log up front, so a bad path fails at startup rather than
+// on the first signature
+pub fn sink() {
+    open();
+}
+
+fn open() -> &'static Sink {
+    SINK.get_or_init(|| match &config::get().audit_log_path {
+        None => Sink::Memory(Mutex::new(VecDeque::new())),
+        Some(path) => {
+            let mut options = OpenOptions::new();
+            options.append(true).create(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+            let file = options
+                .open(path)
+                .unwrap_or_else(|err| panic!("Failed to open audit log {}: {}", path, err));
+            Sink::File { path: path.clone(), file: Mutex::new(file) }
+        }
+    })
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn append(entry: AuditEntry) {
+    match open() {
+        Sink::Memory(entries) => {
+            let mut entries = entries.lock().unwrap();
+            if entries.len() == MAX_MEMORY_ENTRIES {
+                entries.pop_front();
+            }
+            entries.push_back(entry);
+        }
+        Sink::File { path, file } => {
+            let mut line = serde_json::to_vec(&entry).unwrap();
+            line.push(b'\n');
+            // A lost entry must not fail the request it describes
+            if let Err(err) = file.lock().unwrap().write_all(&line) {
+                tracing::error!(%path, error = %err, "Failed to write audit log entry");
+            }
+        }
+    }
+}
+
+// Records the operation with the public keys its response names, and
+// passes the result through
+pub fn recorded<T>(
+    operation: AuditOperation,
+    result: Result<SuccessResponse<T>, ErrorResponse>,
+    pubkeys: impl FnOnce(&T) -> Vec<String>,
+) -> Result<SuccessResponse<T>, ErrorResponse> {
+    append(AuditEntry {
+        timestamp: now(),
+        operation,
+        success: result.is_ok(),
+        pubkeys: result.as_ref().map(|response| pubkeys(&response.data)).unwrap_or_default(),
+        request_id: request_id::current(),
+        api_key: auth::current_key_id(),
+    });
+    result
+}
+
+pub fn get_audit_log(query: AuditQuery) -> Result<SuccessResponse<AuditResponse>, ErrorResponse> {
+    let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT).clamp(1, MAX_QUERY_LIMIT);
+    let matches = |entry: &AuditEntry| {
+        query.operation.is_none_or(|operation| entry.operation == operation)
+            && query.pubkey.as_ref().is_none_or(|pubkey| entry.pubkeys.contains(pubkey))
+            && query.since.is_none_or(|since| entry.timestamp >= since)
+    };
+
+    let mut entries: Vec<AuditEntry> = match open() {
+        Sink::Memory(entries) => entries.lock().unwrap().iter().filter(|entry| matches(entry)).cloned().collect(),
+        Sink::File { path, .. } => {
+            let file = File::open(path).map_err(|err| ErrorResponse::new(format!("Failed to read the audit log: {}", err)))?;
+            BufReader::new(file)
+                .lines()
+                .map_while(Result::ok)
+                .filter_map(|line| serde_json::from_str::<AuditEntry>(&line).ok())
+                .filter(|entry| matches(entry))
+                .collect()
+        }
+    };
+    entries.reverse();
+    entries.truncate(limit);
+
+    Ok(SuccessResponse::new(AuditResponse { entries }))
+}
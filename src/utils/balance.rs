@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use solana_account_decoder::UiAccountData;
+use solana_client::{rpc_request::TokenAccountsFilter, rpc_response::RpcKeyedAccount};
+use solana_sdk::{native_token::lamports_to_sol, pubkey::Pubkey};
+use std::str::FromStr;
+use super::instruction::validate_pubkey;
+use super::mint_config;
+use super::response_types::{SuccessResponse, ErrorResponse};
+use super::rpc::{self, rpc_error};
+use super::token::associated_token_address;
+
+#[derive(Serialize, ToSchema)]
+pub struct SolBalanceResponse {
+    pub pubkey: String,
+    pub lamports: u64,
+    pub sol: f64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TokenBalanceResponse {
+    pub owner: String,
+    pub mint: String,
+    pub token_account: String,
+    // Display symbol configured for the mint
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    // Raw base units, as a string since it can exceed what JSON numbers hold exactly
+    pub amount: String,
+    pub decimals: u8,
+    pub ui_amount: Option<f64>,
+    pub ui_amount_string: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TokenHoldingsQuery {
+    // Leave out token accounts with a zero balance
+    #[serde(rename = "hideZero", default)]
+    pub hide_zero: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TokenHolding {
+    pub mint: String,
+    pub token_account: String,
+    // Whether the token account is the owner's associated token account for the mint
+    pub associated: bool,
+    pub token_program: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    // Raw base units, as a string since it can exceed what JSON numbers hold exactly
+    pub amount: String,
+    pub decimals: u8,
+    pub ui_amount: Option<f64>,
+    pub ui_amount_string: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TokenHoldingsResponse {
+    pub owner: String,
+    pub holdings: Vec<TokenHolding>,
+}
+
+// The parts of a jsonParsed token account this endpoint reports
+#[derive(Deserialize)]
+struct ParsedTokenAccount {
+    info: ParsedTokenAccountInfo,
+}
+
+#[derive(Deserialize)]
+struct ParsedTokenAccountInfo {
+    mint: String,
+    #[serde(rename = "tokenAmount")]
+    token_amount: ParsedTokenAmount,
+}
+
+#[derive(Deserialize)]
+struct ParsedTokenAmount {
+    amount: String,
+    decimals: u8,
+    #[serde(rename = "uiAmount")]
+    ui_amount: Option<f64>,
+    #[serde(rename = "uiAmountString")]
+    ui_amount_string: String,
+}
+
+pub async fn get_sol_balance(pubkey: String) -> Result<SuccessResponse<SolBalanceResponse>, ErrorResponse> {
+    // Validate inputs
+    let pubkey = validate_pubkey(&pubkey, "address")?;
+
+    let lamports = rpc::read("getBalance", move |client| async move {
+        client.get_balance(&pubkey).await
+    })
+        .await
+        .map_err(rpc_error)?;
+
+    Ok(SuccessResponse::new(SolBalanceResponse {
+        pubkey: pubkey.to_string(),
+        lamports,
+        sol: lamports_to_sol(lamports),
+    }))
+}
+
+// Balance of the owner's associated token account for the mint
+pub async fn get_token_balance(owner: String, mint: String) -> Result<SuccessResponse<TokenBalanceResponse>, ErrorResponse> {
+    // Validate inputs
+    let owner = validate_pubkey(&owner, "owner")?;
+    let mint = validate_pubkey(&mint, "mint")?;
+
+    let token_account = spl_associated_token_account::get_associated_token_address(&owner, &mint);
+    let balance = rpc::read("getTokenAccountBalance", move |client| async move {
+        client.get_token_account_balance(&token_account).await
+    })
+        .await
+        .map_err(rpc_error)?;
+
+    Ok(SuccessResponse::new(TokenBalanceResponse {
+        owner: owner.to_string(),
+        mint: mint.to_string(),
+        token_account: token_account.to_string(),
+        symbol: mint_config::symbol(&mint).map(str::to_string),
+        amount: balance.amount,
+        decimals: balance.decimals,
+        ui_amount: balance.ui_amount,
+        ui_amount_string: balance.ui_amount_string,
+    }))
+}
+
+async fn token_accounts_by_owner(owner: Pubkey, token_program: Pubkey) -> Result<Vec<RpcKeyedAccount>, ErrorResponse> {
+    rpc::read("getTokenAccountsByOwner", move |client| async move {
+        client.get_token_accounts_by_owner(&owner, TokenAccountsFilter::ProgramId(token_program)).await
+    })
+        .await
+        .map_err(rpc_error)
+}
+
+fn to_holding(owner: &Pubkey, token_program: &Pubkey, keyed: RpcKeyedAccount) -> Option<TokenHolding> {
+    let UiAccountData::Json(data) = keyed.account.data else {
+        return None;
+    };
+    let parsed: ParsedTokenAccount = serde_json::from_value(data.parsed).ok()?;
+    let mint = Pubkey::from_str(&parsed.info.mint).ok()?;
+    let amount = parsed.info.token_amount;
+
+    Some(TokenHolding {
+        associated: associated_token_address(owner, &mint, token_program).to_string() == keyed.pubkey,
+        mint: mint.to_string(),
+        token_account: keyed.pubkey,
+        token_program: token_program.to_string(),
+        symbol: mint_config::symbol(&mint).map(str::to_string),
+        amount: amount.amount,
+        decimals: amount.decimals,
+        ui_amount: amount.ui_amount,
+        ui_amount_string: amount.ui_amount_string,
+    })
+}
+
+// Every SPL Token and Token-2022 account the owner holds, not only
+// associated token accounts
+pub async fn get_token_holdings(owner: String, query: TokenHoldingsQuery) -> Result<SuccessResponse<TokenHoldingsResponse>, ErrorResponse> {
+    // Validate inputs
+    let owner = validate_pubkey(&owner, "owner")?;
+
+    let (token_accounts, token_2022_accounts) = tokio::join!(
+        token_accounts_by_owner(owner, spl_token::id()),
+        token_accounts_by_owner(owner, spl_token_2022::id()),
+    );
+    let holdings = token_accounts?
+        .into_iter()
+        .filter_map(|keyed| to_holding(&owner, &spl_token::id(), keyed))
+        .chain(token_2022_accounts?.into_iter().filter_map(|keyed| to_holding(&owner, &spl_token_2022::id(), keyed)))
+        .filter(|holding| !query.hide_zero || holding.amount != "0")
+        .collect();
+
+    Ok(SuccessResponse::new(TokenHoldingsResponse {
+        owner: owner.to_string(),
+        holdings,
+    }))
+}
@@ -0,0 +1,293 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use super::account::{CreateAccountResponse, CreateAccountWithSeedResponse};
+use super::cnft::{CreateTreeResponse, MintCompressedNftResponse};
+use super::ed25519::Ed25519VerifyResponse;
+use super::secp256k1::Secp256k1InstructionResponse;
+use super::compose::to_instruction;
+use super::compute_budget::{
+    format_compute_budget_instructions, is_compute_budget_program, priority_fee_instructions,
+    ComputeBudgetInstructionResponse, PriorityFee,
+};
+use super::heuristics::{self, TransactionWarning};
+use super::lookup_table::CreateLookupTableResponse;
+use super::instruction::{AccountInfo, InstructionResponse};
+use super::program_allowlist::{check_programs, Tenant};
+use super::response_types::{SuccessResponse, ErrorResponse};
+use super::nft::MintNftResponse;
+use super::nonce::CreateNonceAccountResponse;
+use super::registry::Operation;
+use super::token::{CreateTokenResponse, MintTokenResponse, SendTokenResponse};
+use super::stake::{CreateStakeAccountResponse, SplitStakeResponse};
+use super::stake_pool::StakePoolResponse;
+use super::token_2022::{HarvestTransferFeesResponse, TransferFeeMintResponse, TransferWithFeeResponse};
+use super::transfer::{SendSolResponse, TransferWithSeedResponse};
+use super::vote::CreateVoteAccountResponse;
+use super::wrapped_sol::WrappedSolResponse;
+
+const MAX_BATCH_OPERATIONS: usize = 32;
+
+#[derive(Deserialize, ToSchema)]
+pub struct BatchInstructionsRequest {
+    pub operations: Vec<Operation>,
+    // Applies to the whole batch, so operations may not set their own
+    #[serde(rename = "priorityFee")]
+    pub priority_fee: Option<PriorityFee>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BatchInstruction {
+    // Absent for instructions added by the batch itself, such as its priority fee
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation_index: Option<usize>,
+    pub program_id: String,
+    pub accounts: Vec<AccountInfo>,
+    pub instruction_data: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BatchInstructionsResponse {
+    pub instructions: Vec<BatchInstruction>,
+    // Suspicious patterns among the instructions; see heuristics::audit
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<TransactionWarning>,
+}
+
+// Converts a builder's response into the instructions it describes
+pub trait IntoInstructions {
+    fn into_instructions(self) -> Vec<BatchInstruction>;
+}
+
+impl From<InstructionResponse> for BatchInstruction {
+    fn from(response: InstructionResponse) -> Self {
+        BatchInstruction {
+            operation_index: None,
+            program_id: response.program_id,
+            accounts: response.accounts,
+            instruction_data: response.instruction_data,
+        }
+    }
+}
+
+macro_rules! impl_into_batch_instruction_via_instruction {
+    ($($response:ty),*) => {
+        $(
+            impl From<$response> for BatchInstruction {
+                fn from(response: $response) -> Self {
+                    response.instruction.into()
+                }
+            }
+        )*
+    };
+}
+
+impl_into_batch_instruction_via_instruction!(
+    SendSolResponse,
+    SendTokenResponse,
+    MintTokenResponse,
+    CreateTokenResponse,
+    CreateLookupTableResponse,
+    MintCompressedNftResponse,
+    CreateAccountResponse,
+    CreateAccountWithSeedResponse,
+    TransferWithSeedResponse,
+    TransferWithFeeResponse
+);
+
+impl From<ComputeBudgetInstructionResponse> for BatchInstruction {
+    fn from(response: ComputeBudgetInstructionResponse) -> Self {
+        BatchInstruction {
+            operation_index: None,
+            program_id: response.program_id,
+            accounts: Vec::new(),
+            instruction_data: response.instruction_data,
+        }
+    }
+}
+
+macro_rules! impl_into_instructions {
+    ($($response:ty),*) => {
+        $(
+            impl IntoInstructions for $response {
+                fn into_instructions(self) -> Vec<BatchInstruction> {
+                    vec![self.into()]
+                }
+            }
+        )*
+    };
+}
+
+impl_into_instructions!(
+    InstructionResponse,
+    MintTokenResponse,
+    CreateLookupTableResponse,
+    MintCompressedNftResponse,
+    CreateAccountResponse,
+    TransferWithFeeResponse,
+    CreateAccountWithSeedResponse,
+    TransferWithSeedResponse
+);
+
+// Transfers carry their own priority fee instructions, which go first, and
+// an optional memo, which goes last
+macro_rules! impl_into_instructions_with_compute_budget {
+    ($($response:ty),*) => {
+        $(
+            impl IntoInstructions for $response {
+                fn into_instructions(mut self) -> Vec<BatchInstruction> {
+                    let mut instructions: Vec<BatchInstruction> = std::mem::take(&mut self.compute_budget_instructions)
+                        .into_iter()
+                        .map(BatchInstruction::from)
+                        .collect();
+                    let memo = self.memo_instruction.take();
+                    instructions.push(self.into());
+                    instructions.extend(memo.map(BatchInstruction::from));
+                    instructions
+                }
+            }
+        )*
+    };
+}
+
+impl_into_instructions_with_compute_budget!(SendSolResponse, SendTokenResponse);
+
+impl IntoInstructions for Ed25519VerifyResponse {
+    fn into_instructions(self) -> Vec<BatchInstruction> {
+        vec![BatchInstruction {
+            operation_index: None,
+            program_id: self.program_id,
+            accounts: Vec::new(),
+            instruction_data: self.instruction_data,
+        }]
+    }
+}
+
+impl IntoInstructions for Secp256k1InstructionResponse {
+    fn into_instructions(self) -> Vec<BatchInstruction> {
+        vec![BatchInstruction {
+            operation_index: None,
+            program_id: self.program_id,
+            accounts: Vec::new(),
+            instruction_data: self.instruction_data,
+        }]
+    }
+}
+
+impl IntoInstructions for BatchInstructionsResponse {
+    fn into_instructions(self) -> Vec<BatchInstruction> {
+        self.instructions
+    }
+}
+
+impl IntoInstructions for CreateVoteAccountResponse {
+    fn into_instructions(self) -> Vec<BatchInstruction> {
+        self.instructions.into_iter().map(BatchInstruction::from).collect()
+    }
+}
+
+impl IntoInstructions for CreateNonceAccountResponse {
+    fn into_instructions(self) -> Vec<BatchInstruction> {
+        self.instructions.into_iter().map(BatchInstruction::from).collect()
+    }
+}
+
+impl IntoInstructions for CreateStakeAccountResponse {
+    fn into_instructions(self) -> Vec<BatchInstruction> {
+        self.instructions.into_iter().map(BatchInstruction::from).collect()
+    }
+}
+
+impl IntoInstructions for SplitStakeResponse {
+    fn into_instructions(self) -> Vec<BatchInstruction> {
+        self.instructions.into_iter().map(BatchInstruction::from).collect()
+    }
+}
+
+impl IntoInstructions for CreateTokenResponse {
+    fn into_instructions(self) -> Vec<BatchInstruction> {
+        self.instructions.into_iter().map(BatchInstruction::from).collect()
+    }
+}
+
+impl IntoInstructions for StakePoolResponse {
+    fn into_instructions(self) -> Vec<BatchInstruction> {
+        self.instructions.into_iter().map(BatchInstruction::from).collect()
+    }
+}
+
+impl IntoInstructions for TransferFeeMintResponse {
+    fn into_instructions(self) -> Vec<BatchInstruction> {
+        self.instructions.into_iter().map(BatchInstruction::from).collect()
+    }
+}
+
+impl IntoInstructions for HarvestTransferFeesResponse {
+    fn into_instructions(self) -> Vec<BatchInstruction> {
+        self.instructions.into_iter().map(BatchInstruction::from).collect()
+    }
+}
+
+impl IntoInstructions for WrappedSolResponse {
+    fn into_instructions(self) -> Vec<BatchInstruction> {
+        self.instructions.into_iter().map(BatchInstruction::from).collect()
+    }
+}
+
+impl IntoInstructions for CreateTreeResponse {
+    fn into_instructions(self) -> Vec<BatchInstruction> {
+        self.instructions.into_iter().map(BatchInstruction::from).collect()
+    }
+}
+
+impl IntoInstructions for MintNftResponse {
+    fn into_instructions(self) -> Vec<BatchInstruction> {
+        self.instructions.into_iter().map(BatchInstruction::from).collect()
+    }
+}
+
+pub fn build_batch(request: BatchInstructionsRequest, tenant: &Tenant) -> Result<SuccessResponse<BatchInstructionsResponse>, ErrorResponse> {
+    // Validate inputs
+    if request.operations.is_empty() {
+        return Err(ErrorResponse::new("At least one operation is required"));
+    }
+    if request.operations.len() > MAX_BATCH_OPERATIONS {
+        return Err(ErrorResponse::new(format!("At most {} operations are allowed per batch", MAX_BATCH_OPERATIONS)));
+    }
+
+    let compute_budget = priority_fee_instructions(request.priority_fee.as_ref())?;
+    let mut priority_fee_sources = usize::from(!compute_budget.is_empty());
+
+    let mut instructions: Vec<BatchInstruction> = format_compute_budget_instructions(&compute_budget)
+        .into_iter()
+        .map(BatchInstruction::from)
+        .collect();
+    for (index, operation) in request.operations.into_iter().enumerate() {
+        let name = operation.operation_type.clone();
+        let built = operation
+            .build()
+            .map_err(|err| ErrorResponse { error: format!("Operation {} ({}): {}", index, name, err.error), ..err })?;
+
+        // A transaction with two compute unit price instructions is rejected
+        if built.iter().any(|instruction| is_compute_budget_program(&instruction.program_id)) {
+            priority_fee_sources += 1;
+        }
+        if priority_fee_sources > 1 {
+            return Err(ErrorResponse::new("Only one priorityFee may be set per batch"));
+        }
+
+        instructions.extend(built.into_iter().map(|instruction| BatchInstruction {
+            operation_index: Some(index),
+            ..instruction
+        }));
+    }
+
+    check_programs(tenant, instructions.iter().map(|instruction| instruction.program_id.as_str()))?;
+
+    let compiled = instructions
+        .iter()
+        .map(to_instruction)
+        .collect::<Result<Vec<_>, ErrorResponse>>()?;
+    let warnings = heuristics::audit(&compiled, None);
+
+    Ok(SuccessResponse::new(BatchInstructionsResponse { instructions, warnings }))
+}
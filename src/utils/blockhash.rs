@@ -0,0 +1,25 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+use super::response_types::{SuccessResponse, ErrorResponse};
+use super::rpc;
+
+#[derive(Serialize, ToSchema)]
+pub struct BlockhashResponse {
+    pub blockhash: String,
+    // Transactions using the blockhash must land by this block height
+    pub last_valid_block_height: u64,
+    // How long ago the blockhash was fetched
+    pub age_ms: u64,
+}
+
+// Served from the cache the blockhash refresher keeps warm, so clients
+// filling in the instruction templates need no RPC access of their own
+pub async fn get_blockhash() -> Result<SuccessResponse<BlockhashResponse>, ErrorResponse> {
+    let cached = rpc::latest_cached_blockhash().await?;
+
+    Ok(SuccessResponse::new(BlockhashResponse {
+        blockhash: cached.blockhash.to_string(),
+        last_valid_block_height: cached.last_valid_block_height,
+        age_ms: cached.fetched_at.elapsed().as_millis() as u64,
+    }))
+}
@@ -0,0 +1,543 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use super::instruction::validate_pubkey;
+use super::raw_instruction::{RawData, RawDataEncoding};
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+// Borsh schemas, written the way Anchor IDLs write types, so registered
+// IDLs and the /encode/borsh and /decode/borsh endpoints share one encoder.
+
+// Deeper type nesting than this is taken to be a recursive definition
+const MAX_TYPE_DEPTH: usize = 32;
+const MAX_BORSH_BYTES: usize = 1024 * 1024;
+// Values one decode may produce. Empty structs take no bytes, so the data's
+// length alone doesn't bound how many of them a schema can ask for.
+const MAX_DECODED_VALUES: usize = 2 * MAX_BORSH_BYTES;
+// Variant indexes are a single byte
+const MAX_ENUM_VARIANTS: usize = 256;
+
+#[derive(Deserialize, ToSchema)]
+pub struct EncodeBorshRequest {
+    // An array of {name, type} fields for a struct, or a single type such as
+    // "u64", {"vec": "pubkey"} or {"defined": "Name"}
+    #[schema(value_type = Object)]
+    pub schema: Value,
+    // Named types the schema refers to, as in an Anchor IDL's types
+    #[serde(default)]
+    #[schema(value_type = Vec<Object>)]
+    pub types: Vec<Value>,
+    // Integers wider than 53 bits may be given as strings, bytes as base64 or
+    // an array, and public keys in base58
+    #[schema(value_type = Object)]
+    pub value: Value,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EncodeBorshResponse {
+    // Base64
+    pub data: String,
+    pub length: usize,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DecodeBorshRequest {
+    #[schema(value_type = Object)]
+    pub schema: Value,
+    #[serde(default)]
+    #[schema(value_type = Vec<Object>)]
+    pub types: Vec<Value>,
+    pub data: RawData,
+    // How `data` is encoded when it is a string
+    #[serde(rename = "dataEncoding", default)]
+    pub data_encoding: RawDataEncoding,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DecodeBorshResponse {
+    // In the shape /encode/borsh takes, with 64- and 128-bit integers as
+    // strings
+    #[schema(value_type = Object)]
+    pub value: Value,
+}
+
+#[derive(Clone)]
+pub(super) enum BorshType {
+    Bool,
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    U128,
+    I128,
+    F32,
+    F64,
+    String,
+    Bytes,
+    Pubkey,
+    Vec(Box<BorshType>),
+    Option(Box<BorshType>),
+    Array(Box<BorshType>, usize),
+    Defined(String),
+    // A schema given as a bare field list
+    Struct(Fields),
+}
+
+#[derive(Clone)]
+pub(super) enum Fields {
+    Named(Vec<(String, BorshType)>),
+    Tuple(Vec<BorshType>),
+}
+
+pub(super) enum TypeDef {
+    Struct(Fields),
+    Enum(Vec<(String, Option<Fields>)>),
+}
+
+fn invalid(message: impl Into<String>) -> ErrorResponse {
+    ErrorResponse::new(format!("Invalid schema: {}", message.into()))
+}
+
+// IDL names are camelCase in older IDLs and snake_case since 0.30, and
+// callers may use either
+pub(super) fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (index, ch) in name.chars().enumerate() {
+        if ch.is_ascii_uppercase() {
+            if index > 0 {
+                snake.push('_');
+            }
+            snake.push(ch.to_ascii_lowercase());
+        } else {
+            snake.push(ch);
+        }
+    }
+    snake
+}
+
+pub(super) fn same_name(a: &str, b: &str) -> bool {
+    a == b || snake_case(a) == snake_case(b)
+}
+
+pub(super) fn lookup<'a, V>(entries: impl IntoIterator<Item = (&'a String, &'a V)>, name: &str) -> Option<&'a V> {
+    entries.into_iter().find(|(key, _)| same_name(key, name)).map(|(_, value)| value)
+}
+
+pub(super) fn array_field<'a>(value: &'a Value, field: &str) -> &'a [Value] {
+    value[field].as_array().map(Vec::as_slice).unwrap_or_default()
+}
+
+fn str_field<'a>(value: &'a Value, field: &str) -> Result<&'a str, ErrorResponse> {
+    value[field].as_str().ok_or_else(|| invalid(format!("missing {}", field)))
+}
+
+pub(super) fn parse_type(value: &Value) -> Result<BorshType, ErrorResponse> {
+    if let Some(name) = value.as_str() {
+        return Ok(match name {
+            "bool" => BorshType::Bool,
+            "u8" => BorshType::U8,
+            "i8" => BorshType::I8,
+            "u16" => BorshType::U16,
+            "i16" => BorshType::I16,
+            "u32" => BorshType::U32,
+            "i32" => BorshType::I32,
+            "u64" => BorshType::U64,
+            "i64" => BorshType::I64,
+            "u128" => BorshType::U128,
+            "i128" => BorshType::I128,
+            "f32" => BorshType::F32,
+            "f64" => BorshType::F64,
+            "string" => BorshType::String,
+            "bytes" => BorshType::Bytes,
+            "pubkey" | "publicKey" => BorshType::Pubkey,
+            other => return Err(invalid(format!("unsupported type '{}'", other))),
+        });
+    }
+    if let Some(inner) = value.get("vec") {
+        return Ok(BorshType::Vec(Box::new(parse_type(inner)?)));
+    }
+    if let Some(inner) = value.get("option") {
+        return Ok(BorshType::Option(Box::new(parse_type(inner)?)));
+    }
+    if let Some([inner, len]) = value.get("array").and_then(Value::as_array).map(Vec::as_slice) {
+        let len = len.as_u64().ok_or_else(|| invalid("array lengths must be numbers"))?;
+        return Ok(BorshType::Array(Box::new(parse_type(inner)?), len as usize));
+    }
+    match value.get("defined") {
+        Some(Value::String(name)) => Ok(BorshType::Defined(name.clone())),
+        Some(defined) => Ok(BorshType::Defined(str_field(defined, "name")?.to_string())),
+        None => Err(invalid(format!("unsupported type {}", value))),
+    }
+}
+
+fn parse_fields(fields: &[Value]) -> Result<Fields, ErrorResponse> {
+    if fields.iter().all(|field| field.get("name").is_some()) {
+        let named = fields
+            .iter()
+            .map(|field| Ok((str_field(field, "name")?.to_string(), parse_type(&field["type"])?)))
+            .collect::<Result<_, ErrorResponse>>()?;
+        Ok(Fields::Named(named))
+    } else {
+        Ok(Fields::Tuple(fields.iter().map(parse_type).collect::<Result<_, _>>()?))
+    }
+}
+
+fn parse_type_def(value: &Value) -> Result<TypeDef, ErrorResponse> {
+    let definition = &value["type"];
+    match str_field(definition, "kind")? {
+        "struct" => parse_fields(array_field(definition, "fields")).map(TypeDef::Struct),
+        "enum" if array_field(definition, "variants").len() > MAX_ENUM_VARIANTS => {
+            Err(invalid(format!("enums can have at most {} variants", MAX_ENUM_VARIANTS)))
+        }
+        "enum" => array_field(definition, "variants")
+            .iter()
+            .map(|variant| {
+                let fields = variant.get("fields").and_then(Value::as_array).map(|fields| parse_fields(fields)).transpose()?;
+                Ok((str_field(variant, "name")?.to_string(), fields))
+            })
+            .collect::<Result<_, _>>()
+            .map(TypeDef::Enum),
+        other => Err(invalid(format!("unsupported type kind '{}'", other))),
+    }
+}
+
+pub(super) fn parse_type_defs(types: &[Value]) -> Result<HashMap<String, TypeDef>, ErrorResponse> {
+    types
+        .iter()
+        .map(|definition| Ok((str_field(definition, "name")?.to_string(), parse_type_def(definition)?)))
+        .collect()
+}
+
+fn value_error(path: &str, expected: &str) -> ErrorResponse {
+    ErrorResponse::new(format!("{} must be {}", path, expected))
+}
+
+fn too_deep(path: &str) -> ErrorResponse {
+    ErrorResponse::new(format!("{} is nested too deeply", path))
+}
+
+fn undefined(name: &str) -> ErrorResponse {
+    ErrorResponse::new(format!("Type {} is not defined", name))
+}
+
+// Integers as JSON numbers, or as strings when they don't fit in a double
+fn integer<T: std::str::FromStr + TryFrom<i128>>(value: &Value, path: &str, kind: &str) -> Result<T, ErrorResponse> {
+    let parsed = match value {
+        Value::Number(number) => number.as_i64().map(i128::from).or_else(|| number.as_u64().map(i128::from)).and_then(|number| T::try_from(number).ok()),
+        Value::String(text) => text.parse().ok(),
+        _ => None,
+    };
+    parsed.ok_or_else(|| value_error(path, &format!("a {}", kind)))
+}
+
+pub(super) struct Encoder<'a> {
+    pub types: &'a HashMap<String, TypeDef>,
+    pub data: Vec<u8>,
+}
+
+impl Encoder<'_> {
+    fn len(&mut self, len: usize) {
+        self.data.extend((len as u32).to_le_bytes());
+    }
+
+    pub fn encode(&mut self, ty: &BorshType, value: &Value, path: &str, depth: usize) -> Result<(), ErrorResponse> {
+        if depth > MAX_TYPE_DEPTH {
+            return Err(too_deep(path));
+        }
+        match ty {
+            BorshType::Bool => self.data.push(value.as_bool().ok_or_else(|| value_error(path, "true or false"))? as u8),
+            BorshType::U8 => self.data.extend(integer::<u8>(value, path, "u8")?.to_le_bytes()),
+            BorshType::I8 => self.data.extend(integer::<i8>(value, path, "i8")?.to_le_bytes()),
+            BorshType::U16 => self.data.extend(integer::<u16>(value, path, "u16")?.to_le_bytes()),
+            BorshType::I16 => self.data.extend(integer::<i16>(value, path, "i16")?.to_le_bytes()),
+            BorshType::U32 => self.data.extend(integer::<u32>(value, path, "u32")?.to_le_bytes()),
+            BorshType::I32 => self.data.extend(integer::<i32>(value, path, "i32")?.to_le_bytes()),
+            BorshType::U64 => self.data.extend(integer::<u64>(value, path, "u64")?.to_le_bytes()),
+            BorshType::I64 => self.data.extend(integer::<i64>(value, path, "i64")?.to_le_bytes()),
+            BorshType::U128 => self.data.extend(integer::<u128>(value, path, "u128")?.to_le_bytes()),
+            BorshType::I128 => self.data.extend(integer::<i128>(value, path, "i128")?.to_le_bytes()),
+            BorshType::F32 => self.data.extend((value.as_f64().ok_or_else(|| value_error(path, "a number"))? as f32).to_le_bytes()),
+            BorshType::F64 => self.data.extend(value.as_f64().ok_or_else(|| value_error(path, "a number"))?.to_le_bytes()),
+            BorshType::String => {
+                let text = value.as_str().ok_or_else(|| value_error(path, "a string"))?;
+                self.len(text.len());
+                self.data.extend(text.as_bytes());
+            }
+            BorshType::Bytes => {
+                let bytes = match value {
+                    Value::String(encoded) => BASE64.decode(encoded).map_err(|_| value_error(path, "base64 or an array of bytes"))?,
+                    _ => value
+                        .as_array()
+                        .and_then(|bytes| bytes.iter().map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok())).collect())
+                        .ok_or_else(|| value_error(path, "base64 or an array of bytes"))?,
+                };
+                self.len(bytes.len());
+                self.data.extend(bytes);
+            }
+            BorshType::Pubkey => {
+                let pubkey = value.as_str().ok_or_else(|| value_error(path, "a base58 public key"))?;
+                self.data.extend(validate_pubkey(pubkey, path)?.to_bytes());
+            }
+            BorshType::Vec(inner) => {
+                let items = value.as_array().ok_or_else(|| value_error(path, "an array"))?;
+                self.len(items.len());
+                for (index, item) in items.iter().enumerate() {
+                    self.encode(inner, item, &format!("{}[{}]", path, index), depth + 1)?;
+                }
+            }
+            BorshType::Array(inner, len) => {
+                let items = value.as_array().filter(|items| items.len() == *len).ok_or_else(|| value_error(path, &format!("an array of {}", len)))?;
+                for (index, item) in items.iter().enumerate() {
+                    self.encode(inner, item, &format!("{}[{}]", path, index), depth + 1)?;
+                }
+            }
+            BorshType::Option(inner) => match value {
+                Value::Null => self.data.push(0),
+                value => {
+                    self.data.push(1);
+                    self.encode(inner, value, path, depth + 1)?;
+                }
+            },
+            BorshType::Struct(fields) => self.encode_fields(fields, value, path, depth)?,
+            BorshType::Defined(name) => {
+                let types = self.types;
+                match types.get(name).ok_or_else(|| undefined(name))? {
+                    TypeDef::Struct(fields) => self.encode_fields(fields, value, path, depth)?,
+                    TypeDef::Enum(variants) => {
+                        // Unit variants by name, others as {"Variant": fields}
+                        let (variant, fields) = match value {
+                            Value::String(variant) => (variant.as_str(), &Value::Null),
+                            Value::Object(object) if object.len() == 1 => {
+                                let (variant, fields) = object.iter().next().unwrap();
+                                (variant.as_str(), fields)
+                            }
+                            _ => return Err(value_error(path, &format!("a variant of {}", name))),
+                        };
+                        let (index, (_, variant_fields)) = variants
+                            .iter()
+                            .enumerate()
+                            .find(|(_, (candidate, _))| same_name(candidate, variant))
+                            .ok_or_else(|| value_error(path, &format!("a variant of {}", name)))?;
+                        // parse_type_def keeps enums within a byte's range
+                        self.data.push(u8::try_from(index).expect("at most 256 variants"));
+                        if let Some(variant_fields) = variant_fields {
+                            self.encode_fields(variant_fields, fields, &format!("{}.{}", path, variant), depth)?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn encode_fields(&mut self, fields: &Fields, value: &Value, path: &str, depth: usize) -> Result<(), ErrorResponse> {
+        match fields {
+            Fields::Named(fields) => {
+                let object = value.as_object().ok_or_else(|| value_error(path, "an object"))?;
+                for (name, ty) in fields {
+                    let field_path = format!("{}.{}", path, name);
+                    let field = lookup(object, name).unwrap_or(&Value::Null);
+                    self.encode(ty, field, &field_path, depth + 1)?;
+                }
+            }
+            Fields::Tuple(types) => {
+                let items = value.as_array().filter(|items| items.len() == types.len()).ok_or_else(|| value_error(path, &format!("an array of {}", types.len())))?;
+                for (index, (ty, item)) in types.iter().zip(items).enumerate() {
+                    self.encode(ty, item, &format!("{}[{}]", path, index), depth + 1)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// The inverse of Encoder, reading values back into the JSON it accepts
+struct Decoder<'a> {
+    types: &'a HashMap<String, TypeDef>,
+    data: &'a [u8],
+    // Values decoded so far, against MAX_DECODED_VALUES
+    values: usize,
+}
+
+impl Decoder<'_> {
+    fn take<const N: usize>(&mut self, path: &str) -> Result<[u8; N], ErrorResponse> {
+        Ok(self.bytes(N, path)?.try_into().unwrap())
+    }
+
+    fn bytes(&mut self, len: usize, path: &str) -> Result<&[u8], ErrorResponse> {
+        if self.data.len() < len {
+            return Err(ErrorResponse::new(format!("Data ends before {}", path)));
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    // Every element takes at least a byte, which caps a length prefix before
+    // anything is allocated for it
+    fn len(&mut self, path: &str) -> Result<usize, ErrorResponse> {
+        let len = u32::from_le_bytes(self.take(path)?) as usize;
+        if len > self.data.len() {
+            return Err(ErrorResponse::new(format!("Data ends before {}", path)));
+        }
+        Ok(len)
+    }
+
+    fn decode(&mut self, ty: &BorshType, path: &str, depth: usize) -> Result<Value, ErrorResponse> {
+        if depth > MAX_TYPE_DEPTH {
+            return Err(too_deep(path));
+        }
+        self.values += 1;
+        if self.values > MAX_DECODED_VALUES {
+            return Err(ErrorResponse::new(format!("Data decodes to more than {} values", MAX_DECODED_VALUES)));
+        }
+        Ok(match ty {
+            BorshType::Bool => match self.take::<1>(path)? {
+                [0] => Value::Bool(false),
+                [1] => Value::Bool(true),
+                _ => return Err(value_error(path, "0 or 1")),
+            },
+            BorshType::U8 => u8::from_le_bytes(self.take(path)?).into(),
+            BorshType::I8 => i8::from_le_bytes(self.take(path)?).into(),
+            BorshType::U16 => u16::from_le_bytes(self.take(path)?).into(),
+            BorshType::I16 => i16::from_le_bytes(self.take(path)?).into(),
+            BorshType::U32 => u32::from_le_bytes(self.take(path)?).into(),
+            BorshType::I32 => i32::from_le_bytes(self.take(path)?).into(),
+            // Strings, since JavaScript can't hold every value as a number
+            BorshType::U64 => u64::from_le_bytes(self.take(path)?).to_string().into(),
+            BorshType::I64 => i64::from_le_bytes(self.take(path)?).to_string().into(),
+            BorshType::U128 => u128::from_le_bytes(self.take(path)?).to_string().into(),
+            BorshType::I128 => i128::from_le_bytes(self.take(path)?).to_string().into(),
+            BorshType::F32 => f32::from_le_bytes(self.take(path)?).into(),
+            BorshType::F64 => f64::from_le_bytes(self.take(path)?).into(),
+            BorshType::String => {
+                let len = self.len(path)?;
+                let text = std::str::from_utf8(self.bytes(len, path)?).map_err(|_| value_error(path, "UTF-8"))?;
+                text.into()
+            }
+            BorshType::Bytes => {
+                let len = self.len(path)?;
+                BASE64.encode(self.bytes(len, path)?).into()
+            }
+            BorshType::Pubkey => Pubkey::new_from_array(self.take(path)?).to_string().into(),
+            BorshType::Vec(inner) => {
+                let len = self.len(path)?;
+                (0..len)
+                    .map(|index| self.decode(inner, &format!("{}[{}]", path, index), depth + 1))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .into()
+            }
+            BorshType::Array(inner, len) => {
+                if *len > self.data.len() {
+                    return Err(ErrorResponse::new(format!("Data ends before {}", path)));
+                }
+                (0..*len)
+                    .map(|index| self.decode(inner, &format!("{}[{}]", path, index), depth + 1))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .into()
+            }
+            BorshType::Option(inner) => match self.take::<1>(path)? {
+                [0] => Value::Null,
+                [1] => self.decode(inner, path, depth + 1)?,
+                _ => return Err(value_error(path, "an option tag of 0 or 1")),
+            },
+            BorshType::Struct(fields) => self.decode_fields(fields, path, depth)?,
+            BorshType::Defined(name) => {
+                let types = self.types;
+                match types.get(name).ok_or_else(|| undefined(name))? {
+                    TypeDef::Struct(fields) => self.decode_fields(fields, path, depth)?,
+                    TypeDef::Enum(variants) => {
+                        let [index] = self.take::<1>(path)?;
+                        let (variant, fields) = variants
+                            .get(index as usize)
+                            .ok_or_else(|| value_error(path, &format!("a variant of {}", name)))?;
+                        match fields {
+                            None => Value::String(variant.clone()),
+                            Some(fields) => {
+                                let fields = self.decode_fields(fields, &format!("{}.{}", path, variant), depth)?;
+                                Value::Object(Map::from_iter([(variant.clone(), fields)]))
+                            }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn decode_fields(&mut self, fields: &Fields, path: &str, depth: usize) -> Result<Value, ErrorResponse> {
+        Ok(match fields {
+            Fields::Named(fields) => fields
+                .iter()
+                .map(|(name, ty)| Ok((name.clone(), self.decode(ty, &format!("{}.{}", path, name), depth + 1)?)))
+                .collect::<Result<Map<_, _>, ErrorResponse>>()?
+                .into(),
+            Fields::Tuple(types) => types
+                .iter()
+                .enumerate()
+                .map(|(index, ty)| self.decode(ty, &format!("{}[{}]", path, index), depth + 1))
+                .collect::<Result<Vec<_>, _>>()?
+                .into(),
+        })
+    }
+}
+
+// A bare array of fields describes a struct; anything else is one type
+fn parse_schema(schema: &Value, types: &[Value]) -> Result<(BorshType, HashMap<String, TypeDef>), ErrorResponse> {
+    let ty = match schema {
+        Value::Array(fields) => BorshType::Struct(parse_fields(fields)?),
+        Value::Null => return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields")),
+        schema => parse_type(schema)?,
+    };
+    Ok((ty, parse_type_defs(types)?))
+}
+
+fn decode_data(data: &RawData, encoding: RawDataEncoding) -> Result<Vec<u8>, ErrorResponse> {
+    let bytes = match (data, encoding) {
+        (RawData::Bytes(bytes), _) => bytes.clone(),
+        (RawData::Encoded(data), RawDataEncoding::Base64) => BASE64.decode(data)
+            .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidEncoding, "Invalid base64 encoding for data"))?,
+        (RawData::Encoded(data), RawDataEncoding::Base58) => bs58::decode(data)
+            .into_vec()
+            .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidEncoding, "Invalid base58 encoding for data"))?,
+        (RawData::Encoded(data), RawDataEncoding::Hex) => hex::decode(data)
+            .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidEncoding, "Invalid hex encoding for data"))?,
+    };
+    if bytes.len() > MAX_BORSH_BYTES {
+        return Err(ErrorResponse::new(format!("Data must be at most {} bytes", MAX_BORSH_BYTES)));
+    }
+    Ok(bytes)
+}
+
+pub fn encode_borsh(request: EncodeBorshRequest) -> Result<SuccessResponse<EncodeBorshResponse>, ErrorResponse> {
+    // Validate inputs
+    let (ty, types) = parse_schema(&request.schema, &request.types)?;
+
+    let mut encoder = Encoder { types: &types, data: Vec::new() };
+    encoder.encode(&ty, &request.value, "value", 0)?;
+
+    Ok(SuccessResponse::new(EncodeBorshResponse {
+        length: encoder.data.len(),
+        data: BASE64.encode(encoder.data),
+    }))
+}
+
+pub fn decode_borsh(request: DecodeBorshRequest) -> Result<SuccessResponse<DecodeBorshResponse>, ErrorResponse> {
+    // Validate inputs
+    let (ty, types) = parse_schema(&request.schema, &request.types)?;
+    let data = decode_data(&request.data, request.data_encoding)?;
+
+    let mut decoder = Decoder { types: &types, data: &data, values: 0 };
+    let value = decoder.decode(&ty, "value", 0)?;
+    if !decoder.data.is_empty() {
+        return Err(ErrorResponse::new(format!("Data has {} bytes left over", decoder.data.len())));
+    }
+
+    Ok(SuccessResponse::new(DecodeBorshResponse { value }))
+}
@@ -0,0 +1,250 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::ToSchema;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::{
+    compute_budget,
+    message::VersionedMessage,
+    program_pack::Pack,
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+    signature::Signature,
+    system_instruction::SystemInstruction,
+    system_program,
+};
+use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding};
+use spl_token::{instruction::TokenInstruction, state::Account as TokenAccount};
+use std::{collections::HashMap, str::FromStr};
+use super::compose::{ComposeTransactionRequest, LookupTableRequest, TransactionVersion};
+use super::compute_budget::{ComputeBudgetSettings, PriorityFee};
+use super::instruction::validate_pubkey;
+use super::registry::Operation;
+use super::response_types::{SuccessResponse, ErrorResponse};
+use super::rpc::{self, rpc_error};
+
+#[derive(Deserialize, ToSchema)]
+pub struct CloneTransactionRequest {
+    pub signature: String,
+    // Accounts to replace in the cloned operations, keyed by the original address
+    #[serde(default)]
+    pub substitutions: HashMap<String, String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SkippedInstruction {
+    pub index: usize,
+    pub program_id: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CloneTransactionResponse {
+    pub signature: String,
+    // Edit as needed and send to /transaction/compose
+    pub request: ComposeTransactionRequest,
+    // Instructions with no matching builder operation
+    pub skipped: Vec<SkippedInstruction>,
+}
+
+async fn fetch_token_account(pubkey: Pubkey) -> Result<Option<TokenAccount>, ErrorResponse> {
+    let account = rpc::read("getAccountInfo", move |client| async move {
+        client.get_account(&pubkey).await
+    })
+        .await
+        .map_err(rpc_error)?;
+
+    if account.owner != spl_token::id() {
+        return Ok(None);
+    }
+    Ok(TokenAccount::unpack(&account.data).ok())
+}
+
+// A token transfer becomes a sendToken operation when both sides are the
+// associated token accounts sendToken would derive
+async fn token_transfer_operation(
+    source: Pubkey,
+    destination: Pubkey,
+    authority: Pubkey,
+    amount: u64,
+) -> Result<Result<Operation, String>, ErrorResponse> {
+    let Some(destination_account) = fetch_token_account(destination).await? else {
+        return Ok(Err(format!("Destination {} is not a token account", destination)));
+    };
+    let mint = destination_account.mint;
+    let owner = destination_account.owner;
+
+    let source_ata = spl_associated_token_account::get_associated_token_address(&authority, &mint);
+    let destination_ata = spl_associated_token_account::get_associated_token_address(&owner, &mint);
+    if source != source_ata || destination != destination_ata {
+        return Ok(Err("Only transfers between associated token accounts can be cloned".to_string()));
+    }
+
+    Ok(Ok(Operation {
+        operation_type: "sendToken".to_string(),
+        params: json!({
+            "destination": owner.to_string(),
+            "mint": mint.to_string(),
+            "owner": authority.to_string(),
+            "amount": amount,
+        }),
+    }))
+}
+
+fn substitute(value: &mut serde_json::Value, substitutions: &HashMap<String, String>) {
+    match value {
+        serde_json::Value::String(text) => {
+            if let Some(replacement) = substitutions.get(text.as_str()) {
+                *text = replacement.clone();
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(|item| substitute(item, substitutions)),
+        serde_json::Value::Object(fields) => fields.values_mut().for_each(|field| substitute(field, substitutions)),
+        _ => {}
+    }
+}
+
+pub async fn clone_transaction(request: CloneTransactionRequest) -> Result<SuccessResponse<CloneTransactionResponse>, ErrorResponse> {
+    // Validate inputs
+    let signature = Signature::from_str(&request.signature)
+        .map_err(|_| ErrorResponse::new("Invalid signature format"))?;
+    for (original, replacement) in &request.substitutions {
+        validate_pubkey(original, "substituted account")?;
+        validate_pubkey(replacement, "substitute account")?;
+    }
+
+    let confirmed = rpc::read("getTransaction", move |client| async move {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(client.commitment()),
+            max_supported_transaction_version: Some(0),
+        };
+        client.get_transaction_with_config(&signature, config).await
+    })
+        .await
+        .map_err(rpc_error)?;
+    let transaction = confirmed
+        .transaction
+        .transaction
+        .decode()
+        .ok_or_else(|| ErrorResponse::new("Invalid transaction format"))?;
+    let message = transaction.message;
+
+    // Accounts loaded from lookup tables follow the static keys, writable first
+    let mut account_keys = message.static_account_keys().to_vec();
+    if let Some(OptionSerializer::Some(loaded)) = confirmed.transaction.meta.as_ref().map(|meta| &meta.loaded_addresses) {
+        for address in loaded.writable.iter().chain(&loaded.readonly) {
+            account_keys.push(validate_pubkey(address, "loaded address")?);
+        }
+    }
+
+    let mut operations = Vec::new();
+    let mut skipped = Vec::new();
+    let mut budget = ComputeBudgetSettings::default();
+    for (index, instruction) in message.instructions().iter().enumerate() {
+        let key = |position: usize| {
+            instruction
+                .accounts
+                .get(position)
+                .and_then(|account| account_keys.get(*account as usize))
+                .copied()
+                .ok_or_else(|| ErrorResponse::new("Instruction references a missing program account"))
+        };
+        let program_id = *account_keys
+            .get(instruction.program_id_index as usize)
+            .ok_or_else(|| ErrorResponse::new("Instruction references a missing program account"))?;
+
+        let operation = if compute_budget::check_id(&program_id) {
+            budget.apply(&instruction.data);
+            continue;
+        } else if program_id == system_program::id() {
+            match limited_deserialize(&instruction.data) {
+                Ok(SystemInstruction::Transfer { lamports }) => Ok(Operation {
+                    operation_type: "sendSol".to_string(),
+                    params: json!({
+                        "from": key(0)?.to_string(),
+                        "to": key(1)?.to_string(),
+                        "lamports": lamports,
+                    }),
+                }),
+                Ok(SystemInstruction::AdvanceNonceAccount) => Err("Replaced by a fresh blockhash".to_string()),
+                _ => Err("No builder operation for this system instruction".to_string()),
+            }
+        } else if program_id == spl_token::id() {
+            match TokenInstruction::unpack(&instruction.data) {
+                Ok(TokenInstruction::Transfer { amount }) => {
+                    token_transfer_operation(key(0)?, key(1)?, key(2)?, amount).await?
+                }
+                Ok(TokenInstruction::TransferChecked { amount, .. }) => {
+                    token_transfer_operation(key(0)?, key(2)?, key(3)?, amount).await?
+                }
+                _ => Err("No builder operation for this token instruction".to_string()),
+            }
+        } else if program_id == spl_memo::id() || program_id == spl_memo::v1::id() {
+            match std::str::from_utf8(&instruction.data) {
+                Ok(memo) => Ok(Operation {
+                    operation_type: "memo".to_string(),
+                    params: json!({
+                        "memo": memo,
+                        "signers": (0..instruction.accounts.len())
+                            .map(|position| key(position).map(|signer| signer.to_string()))
+                            .collect::<Result<Vec<_>, _>>()?,
+                    }),
+                }),
+                Err(_) => Err("Memo is not valid UTF-8".to_string()),
+            }
+        } else {
+            Err("No builder operation for this program".to_string())
+        };
+
+        match operation {
+            Ok(operation) => operations.push(operation),
+            Err(reason) => skipped.push(SkippedInstruction {
+                index,
+                program_id: program_id.to_string(),
+                reason,
+            }),
+        }
+    }
+
+    if operations.is_empty() {
+        return Err(ErrorResponse::new("Transaction has no instructions that can be cloned"));
+    }
+
+    for operation in &mut operations {
+        substitute(&mut operation.params, &request.substitutions);
+    }
+    let fee_payer = account_keys[0].to_string();
+    let fee_payer = request.substitutions.get(&fee_payer).cloned().unwrap_or(fee_payer);
+
+    let (version, address_lookup_tables) = match &message {
+        VersionedMessage::Legacy(_) => (TransactionVersion::Legacy, Vec::new()),
+        VersionedMessage::V0(message) => (
+            TransactionVersion::V0,
+            message
+                .address_table_lookups
+                .iter()
+                .map(|lookup| LookupTableRequest { address: lookup.account_key.to_string(), addresses: None })
+                .collect(),
+        ),
+    };
+
+    let request = ComposeTransactionRequest {
+        fee_payer,
+        recent_blockhash: Some(rpc::latest_blockhash().await?.to_string()),
+        version,
+        address_lookup_tables,
+        operations,
+        priority_fee: budget.unit_price.map(|price| PriorityFee {
+            micro_lamports_per_unit: price,
+            unit_limit: budget.unit_limit,
+        }),
+        nonce_account: None,
+        nonce_authority: None,
+    };
+
+    Ok(SuccessResponse::new(CloneTransactionResponse {
+        signature: signature.to_string(),
+        request,
+        skipped,
+    }))
+}
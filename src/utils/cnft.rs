@@ -0,0 +1,418 @@
+use borsh::BorshSerialize;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+    system_instruction::{self, MAX_PERMITTED_DATA_LENGTH},
+    system_program,
+};
+use super::footprint::{metadata_address, TOKEN_METADATA_PROGRAM_ID};
+use super::instruction::{validate_pubkey, InstructionResponse};
+use super::preload;
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+
+pub const BUBBLEGUM_PROGRAM_ID: Pubkey = pubkey!("BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY");
+pub const ACCOUNT_COMPRESSION_PROGRAM_ID: Pubkey = pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+pub const NOOP_PROGRAM_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+
+// Anchor instruction discriminators, the first 8 bytes of
+// sha256("global:<instruction name>")
+const CREATE_TREE_DISCRIMINATOR: [u8; 8] = [165, 83, 136, 142, 89, 202, 47, 220];
+const MINT_V1_DISCRIMINATOR: [u8; 8] = [145, 98, 192, 118, 184, 147, 118, 104];
+const MINT_TO_COLLECTION_V1_DISCRIMINATOR: [u8; 8] = [153, 18, 178, 47, 197, 158, 86, 15];
+
+// The (max depth, max buffer size) pairs the compression program accepts
+const TREE_SIZES: &[(u32, u32)] = &[
+    (3, 8), (5, 8), (6, 16), (7, 16), (8, 16), (9, 16), (10, 32), (11, 32), (12, 32), (13, 32),
+    (14, 64), (14, 256), (14, 1024), (14, 2048), (15, 64), (16, 64), (17, 64), (18, 64), (19, 64),
+    (20, 64), (20, 256), (20, 1024), (20, 2048), (24, 64), (24, 256), (24, 512), (24, 1024),
+    (24, 2048), (26, 512), (26, 1024), (26, 2048), (30, 512), (30, 1024), (30, 2048),
+];
+
+// Token Metadata limits, which Bubblegum enforces for compressed NFTs too
+const MAX_NAME_LENGTH: usize = 32;
+const MAX_SYMBOL_LENGTH: usize = 10;
+const MAX_URI_LENGTH: usize = 200;
+const MAX_CREATORS: usize = 5;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateTreeRequest {
+    pub payer: String,
+    // New keypair for the tree account, which must sign
+    #[serde(rename = "merkleTree")]
+    pub merkle_tree: String,
+    // Can mint to the tree and delegate that right; defaults to the payer
+    #[serde(rename = "treeCreator")]
+    pub tree_creator: Option<String>,
+    #[serde(rename = "maxDepth")]
+    pub max_depth: u32,
+    #[serde(rename = "maxBufferSize")]
+    pub max_buffer_size: u32,
+    // Upper levels of the tree kept on chain, so proofs can be shorter
+    #[serde(rename = "canopyDepth", default)]
+    pub canopy_depth: u32,
+    // Let anyone mint to the tree
+    pub public: Option<bool>,
+    // Defaults to the rent-exempt minimum for the tree account
+    pub lamports: Option<u64>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreatorArgs {
+    pub address: String,
+    // Percentage of royalties; creators' shares must add up to 100
+    pub share: u8,
+    // Verified creators must sign the mint
+    #[serde(default)]
+    pub verified: bool,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CompressedNftMetadata {
+    pub name: String,
+    #[serde(default)]
+    pub symbol: String,
+    pub uri: String,
+    #[serde(rename = "sellerFeeBasisPoints", default)]
+    pub seller_fee_basis_points: u16,
+    #[serde(rename = "primarySaleHappened", default)]
+    pub primary_sale_happened: bool,
+    #[serde(rename = "isMutable", default = "default_is_mutable")]
+    pub is_mutable: bool,
+    #[serde(default)]
+    pub creators: Vec<CreatorArgs>,
+}
+
+fn default_is_mutable() -> bool {
+    true
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CollectionArgs {
+    pub mint: String,
+    // The collection's update authority, or a delegate with an authority record
+    pub authority: String,
+    #[serde(rename = "authorityRecord")]
+    pub authority_record: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct MintCompressedNftRequest {
+    #[serde(rename = "merkleTree")]
+    pub merkle_tree: String,
+    pub payer: String,
+    // The tree creator or its delegate; any signer for public trees
+    #[serde(rename = "treeAuthority")]
+    pub tree_authority: String,
+    #[serde(rename = "leafOwner")]
+    pub leaf_owner: String,
+    // Defaults to the leaf owner
+    #[serde(rename = "leafDelegate")]
+    pub leaf_delegate: Option<String>,
+    pub metadata: CompressedNftMetadata,
+    // Mints into a verified collection with mint_to_collection_v1
+    pub collection: Option<CollectionArgs>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreateTreeResponse {
+    pub merkle_tree: String,
+    pub tree_config: String,
+    pub space: u64,
+    pub lamports: u64,
+    pub instructions: Vec<InstructionResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MintCompressedNftResponse {
+    #[serde(flatten)]
+    pub instruction: InstructionResponse,
+    pub tree_config: String,
+}
+
+// Bubblegum's MetadataArgs, in its borsh field order
+#[derive(BorshSerialize)]
+struct MetadataArgs {
+    name: String,
+    symbol: String,
+    uri: String,
+    seller_fee_basis_points: u16,
+    primary_sale_happened: bool,
+    is_mutable: bool,
+    edition_nonce: Option<u8>,
+    token_standard: Option<TokenStandard>,
+    collection: Option<Collection>,
+    // Uses are deprecated and never set
+    uses: Option<()>,
+    token_program_version: TokenProgramVersion,
+    creators: Vec<Creator>,
+}
+
+#[derive(BorshSerialize)]
+enum TokenStandard {
+    NonFungible,
+}
+
+#[derive(BorshSerialize)]
+enum TokenProgramVersion {
+    Original,
+}
+
+#[derive(BorshSerialize)]
+struct Collection {
+    verified: bool,
+    key: Pubkey,
+}
+
+#[derive(BorshSerialize)]
+pub struct Creator {
+    pub address: Pubkey,
+    pub verified: bool,
+    pub share: u8,
+}
+
+#[derive(BorshSerialize)]
+struct CreateTreeArgs {
+    max_depth: u32,
+    max_buffer_size: u32,
+    public: Option<bool>,
+}
+
+fn instruction_data(discriminator: [u8; 8], args: &impl BorshSerialize) -> Vec<u8> {
+    let mut data = discriminator.to_vec();
+    args.serialize(&mut data).expect("writing to a Vec cannot fail");
+    data
+}
+
+// Holds the tree's creator, delegate and mint counts
+pub fn tree_config_address(merkle_tree: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[merkle_tree.as_ref()], &BUBBLEGUM_PROGRAM_ID).0
+}
+
+pub fn master_edition_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), mint.as_ref(), b"edition"],
+        &TOKEN_METADATA_PROGRAM_ID,
+    ).0
+}
+
+// The concurrent merkle tree's header, its fixed fields, the change log
+// buffer and rightmost path, then the canopy
+fn tree_account_size(max_depth: u32, max_buffer_size: u32, canopy_depth: u32) -> u64 {
+    let path = 40 + 32 * max_depth as u64;
+    let canopy = ((1u64 << (canopy_depth + 1)) - 2) * 32;
+    56 + 24 + max_buffer_size as u64 * path + path + canopy
+}
+
+pub fn create_tree(request: CreateTreeRequest) -> Result<SuccessResponse<CreateTreeResponse>, ErrorResponse> {
+    // Validate inputs
+    let payer = validate_pubkey(&request.payer, "payer")?;
+    let merkle_tree = validate_pubkey(&request.merkle_tree, "merkle tree")?;
+    let tree_creator = match request.tree_creator.as_deref() {
+        Some(tree_creator) => validate_pubkey(tree_creator, "tree creator")?,
+        None => payer,
+    };
+
+    if payer == merkle_tree {
+        return Err(ErrorResponse::with_code(ApiErrorCode::SameAddress, "Payer and merkle tree cannot be the same"));
+    }
+    if !TREE_SIZES.contains(&(request.max_depth, request.max_buffer_size)) {
+        return Err(ErrorResponse::new(format!(
+            "maxDepth {} with maxBufferSize {} is not a supported tree size",
+            request.max_depth, request.max_buffer_size
+        )));
+    }
+    if request.canopy_depth > request.max_depth {
+        return Err(ErrorResponse::new("canopyDepth cannot be greater than maxDepth"));
+    }
+    let space = tree_account_size(request.max_depth, request.max_buffer_size, request.canopy_depth);
+    if space > MAX_PERMITTED_DATA_LENGTH {
+        return Err(ErrorResponse::new(format!(
+            "Accounts can hold at most {} bytes of data",
+            MAX_PERMITTED_DATA_LENGTH
+        )));
+    }
+
+    let minimum = preload::rent().minimum_balance(space as usize);
+    let lamports = request.lamports.unwrap_or(minimum);
+    if lamports < minimum {
+        return Err(ErrorResponse::new(format!(
+            "An account with {} bytes of data needs at least {} lamports to be rent exempt",
+            space, minimum
+        )));
+    }
+
+    // The tree account is allocated by the system program and handed to the
+    // compression program, which Bubblegum then initializes it through
+    let tree_config = tree_config_address(&merkle_tree);
+    let instructions = [
+        system_instruction::create_account(&payer, &merkle_tree, lamports, space, &ACCOUNT_COMPRESSION_PROGRAM_ID),
+        Instruction {
+            program_id: BUBBLEGUM_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(tree_config, false),
+                AccountMeta::new(merkle_tree, false),
+                AccountMeta::new(payer, true),
+                AccountMeta::new_readonly(tree_creator, true),
+                AccountMeta::new_readonly(NOOP_PROGRAM_ID, false),
+                AccountMeta::new_readonly(ACCOUNT_COMPRESSION_PROGRAM_ID, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+            data: instruction_data(CREATE_TREE_DISCRIMINATOR, &CreateTreeArgs {
+                max_depth: request.max_depth,
+                max_buffer_size: request.max_buffer_size,
+                public: request.public,
+            }),
+        },
+    ];
+
+    Ok(SuccessResponse::new(CreateTreeResponse {
+        merkle_tree: merkle_tree.to_string(),
+        tree_config: tree_config.to_string(),
+        space,
+        lamports,
+        instructions: instructions.iter().map(InstructionResponse::from).collect(),
+    }))
+}
+
+// Also used for uncompressed NFTs, which Token Metadata holds to the same limits
+pub fn validate_metadata_fields(name: &str, symbol: &str, uri: &str, seller_fee_basis_points: u16) -> Result<(), ErrorResponse> {
+    if name.len() > MAX_NAME_LENGTH {
+        return Err(ErrorResponse::new(format!("name can be at most {} bytes", MAX_NAME_LENGTH)));
+    }
+    if symbol.len() > MAX_SYMBOL_LENGTH {
+        return Err(ErrorResponse::new(format!("symbol can be at most {} bytes", MAX_SYMBOL_LENGTH)));
+    }
+    if uri.len() > MAX_URI_LENGTH {
+        return Err(ErrorResponse::new(format!("uri can be at most {} bytes", MAX_URI_LENGTH)));
+    }
+    if seller_fee_basis_points > 10_000 {
+        return Err(ErrorResponse::new("sellerFeeBasisPoints cannot exceed 10000"));
+    }
+    Ok(())
+}
+
+pub fn validate_creators(creators: &[CreatorArgs]) -> Result<Vec<Creator>, ErrorResponse> {
+    if creators.len() > MAX_CREATORS {
+        return Err(ErrorResponse::new(format!("At most {} creators are allowed", MAX_CREATORS)));
+    }
+    if !creators.is_empty() && creators.iter().map(|creator| creator.share as u32).sum::<u32>() != 100 {
+        return Err(ErrorResponse::new("Creator shares must add up to 100"));
+    }
+    creators
+        .iter()
+        .enumerate()
+        .map(|(index, creator)| Ok(Creator {
+            address: validate_pubkey(&creator.address, &format!("creators[{}]", index))?,
+            verified: creator.verified,
+            share: creator.share,
+        }))
+        .collect()
+}
+
+fn validate_metadata(metadata: CompressedNftMetadata, collection: Option<Pubkey>) -> Result<MetadataArgs, ErrorResponse> {
+    validate_metadata_fields(&metadata.name, &metadata.symbol, &metadata.uri, metadata.seller_fee_basis_points)?;
+    let creators = validate_creators(&metadata.creators)?;
+
+    Ok(MetadataArgs {
+        name: metadata.name,
+        symbol: metadata.symbol,
+        uri: metadata.uri,
+        seller_fee_basis_points: metadata.seller_fee_basis_points,
+        primary_sale_happened: metadata.primary_sale_happened,
+        is_mutable: metadata.is_mutable,
+        edition_nonce: None,
+        token_standard: Some(TokenStandard::NonFungible),
+        // Bubblegum verifies the collection itself when minting into it
+        collection: collection.map(|key| Collection { verified: false, key }),
+        uses: None,
+        token_program_version: TokenProgramVersion::Original,
+        creators,
+    })
+}
+
+pub fn mint_compressed_nft(request: MintCompressedNftRequest) -> Result<SuccessResponse<MintCompressedNftResponse>, ErrorResponse> {
+    // Validate inputs
+    let merkle_tree = validate_pubkey(&request.merkle_tree, "merkle tree")?;
+    let payer = validate_pubkey(&request.payer, "payer")?;
+    let tree_authority = validate_pubkey(&request.tree_authority, "tree authority")?;
+    let leaf_owner = validate_pubkey(&request.leaf_owner, "leaf owner")?;
+    let leaf_delegate = match request.leaf_delegate.as_deref() {
+        Some(leaf_delegate) => validate_pubkey(leaf_delegate, "leaf delegate")?,
+        None => leaf_owner,
+    };
+    let collection = request
+        .collection
+        .as_ref()
+        .map(|collection| -> Result<_, ErrorResponse> {
+            Ok((
+                validate_pubkey(&collection.mint, "collection mint")?,
+                validate_pubkey(&collection.authority, "collection authority")?,
+                collection
+                    .authority_record
+                    .as_deref()
+                    .map(|record| validate_pubkey(record, "collection authority record"))
+                    .transpose()?,
+            ))
+        })
+        .transpose()?;
+    let metadata = validate_metadata(request.metadata, collection.map(|(mint, _, _)| mint))?;
+
+    let tree_config = tree_config_address(&merkle_tree);
+    let mut accounts = vec![
+        AccountMeta::new(tree_config, false),
+        AccountMeta::new_readonly(leaf_owner, false),
+        AccountMeta::new_readonly(leaf_delegate, false),
+        AccountMeta::new(merkle_tree, false),
+        AccountMeta::new_readonly(payer, true),
+        AccountMeta::new_readonly(tree_authority, true),
+    ];
+    let discriminator = match collection {
+        Some((mint, authority, authority_record)) => {
+            let bubblegum_signer = Pubkey::find_program_address(&[b"collection_cpi"], &BUBBLEGUM_PROGRAM_ID).0;
+            accounts.extend([
+                AccountMeta::new_readonly(authority, true),
+                // Bubblegum's own id stands in for a missing optional account
+                AccountMeta::new_readonly(authority_record.unwrap_or(BUBBLEGUM_PROGRAM_ID), false),
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new(metadata_address(&mint), false),
+                AccountMeta::new_readonly(master_edition_address(&mint), false),
+                AccountMeta::new_readonly(bubblegum_signer, false),
+                AccountMeta::new_readonly(NOOP_PROGRAM_ID, false),
+                AccountMeta::new_readonly(ACCOUNT_COMPRESSION_PROGRAM_ID, false),
+                AccountMeta::new_readonly(TOKEN_METADATA_PROGRAM_ID, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ]);
+            MINT_TO_COLLECTION_V1_DISCRIMINATOR
+        }
+        None => {
+            accounts.extend([
+                AccountMeta::new_readonly(NOOP_PROGRAM_ID, false),
+                AccountMeta::new_readonly(ACCOUNT_COMPRESSION_PROGRAM_ID, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ]);
+            MINT_V1_DISCRIMINATOR
+        }
+    };
+    // Creators marked verified sign as remaining accounts
+    accounts.extend(
+        metadata
+            .creators
+            .iter()
+            .filter(|creator| creator.verified)
+            .map(|creator| AccountMeta::new_readonly(creator.address, true)),
+    );
+
+    let instruction = Instruction {
+        program_id: BUBBLEGUM_PROGRAM_ID,
+        accounts,
+        data: instruction_data(discriminator, &metadata),
+    };
+
+    Ok(SuccessResponse::new(MintCompressedNftResponse {
+        instruction: InstructionResponse::from(&instruction),
+        tree_config: tree_config.to_string(),
+    }))
+}
@@ -0,0 +1,320 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::{
+    address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount},
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    message::{v0, Message, VersionedMessage},
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::Signature,
+    system_instruction,
+    transaction::{Transaction, VersionedTransaction},
+};
+use std::str::FromStr;
+use super::batch::{build_batch, BatchInstruction, BatchInstructionsRequest};
+use super::compute_budget::PriorityFee;
+use super::heuristics::{self, TransactionWarning};
+use super::instruction::{validate_pubkey, InstructionResponse};
+use super::nonce::fetch_nonce_blockhash;
+use super::program_allowlist::{check_programs, Tenant};
+use super::raw_instruction::{build_raw_instruction_for, RawInstructionRequest};
+use super::registry::Operation;
+use super::timing;
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+use super::rpc::{self, rpc_error};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionVersion {
+    #[default]
+    Legacy,
+    V0,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LookupTableRequest {
+    pub address: String,
+    // Contents of the table; fetched from the cluster when omitted
+    pub addresses: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ComposeTransactionRequest {
+    #[serde(rename = "feePayer")]
+    pub fee_payer: String,
+    // Defaults to the latest blockhash from the configured cluster
+    #[serde(rename = "recentBlockhash")]
+    pub recent_blockhash: Option<String>,
+    #[serde(default)]
+    pub version: TransactionVersion,
+    // Only used for v0 transactions
+    #[serde(rename = "addressLookupTables", default)]
+    pub address_lookup_tables: Vec<LookupTableRequest>,
+    pub operations: Vec<Operation>,
+    #[serde(rename = "priorityFee")]
+    pub priority_fee: Option<PriorityFee>,
+    // Use the blockhash stored in this nonce account instead of a recent one;
+    // recentBlockhash, when given, is taken as the stored nonce value
+    #[serde(rename = "nonceAccount")]
+    pub nonce_account: Option<String>,
+    #[serde(rename = "nonceAuthority")]
+    pub nonce_authority: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ComposeTransactionResponse {
+    pub transaction: String,
+    pub version: String,
+    pub recent_blockhash: String,
+    pub required_signers: Vec<String>,
+    pub instructions: Vec<BatchInstruction>,
+    // Suspicious patterns among the instructions; see heuristics::audit
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<TransactionWarning>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CompileMessageRequest {
+    #[serde(rename = "feePayer")]
+    pub fee_payer: String,
+    // Required, since nothing is fetched from the cluster
+    #[serde(rename = "recentBlockhash")]
+    pub recent_blockhash: String,
+    #[serde(default)]
+    pub version: TransactionVersion,
+    // Only used for v0 messages, and each table's addresses must be given
+    #[serde(rename = "addressLookupTables", default)]
+    pub address_lookup_tables: Vec<LookupTableRequest>,
+    // In the shape /instruction/raw takes
+    pub instructions: Vec<RawInstructionRequest>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CompileMessageResponse {
+    // Base64 serialized message, the bytes each signer signs
+    pub message: String,
+    pub version: String,
+    pub recent_blockhash: String,
+    pub required_signers: Vec<String>,
+    pub account_keys: Vec<String>,
+}
+
+pub fn to_instruction(instruction: &BatchInstruction) -> Result<Instruction, ErrorResponse> {
+    let accounts = instruction
+        .accounts
+        .iter()
+        .map(|account| {
+            let pubkey = validate_pubkey(&account.pubkey, "account")?;
+            Ok(if account.is_writable {
+                AccountMeta::new(pubkey, account.is_signer)
+            } else {
+                AccountMeta::new_readonly(pubkey, account.is_signer)
+            })
+        })
+        .collect::<Result<Vec<_>, ErrorResponse>>()?;
+
+    Ok(Instruction {
+        program_id: validate_pubkey(&instruction.program_id, "program id")?,
+        accounts,
+        data: BASE64
+            .decode(&instruction.instruction_data)
+            .map_err(|_| ErrorResponse::new("Invalid base64 encoding for instruction data"))?,
+    })
+}
+
+async fn resolve_lookup_table(table: &LookupTableRequest) -> Result<AddressLookupTableAccount, ErrorResponse> {
+    let key = validate_pubkey(&table.address, "address lookup table")?;
+
+    let addresses = match &table.addresses {
+        Some(addresses) => addresses
+            .iter()
+            .map(|address| validate_pubkey(address, "lookup table address"))
+            .collect::<Result<Vec<_>, ErrorResponse>>()?,
+        None => {
+            let account = rpc::read("getAccountInfo", move |client| async move { client.get_account(&key).await })
+                .await
+                .map_err(rpc_error)?;
+            AddressLookupTable::deserialize(&account.data)
+                .map_err(|_| ErrorResponse::new(format!("Account {} is not an address lookup table", key)))?
+                .addresses
+                .to_vec()
+        }
+    };
+
+    Ok(AddressLookupTableAccount { key, addresses })
+}
+
+pub fn signer_keys(account_keys: &[Pubkey], num_required_signatures: u8) -> Vec<String> {
+    account_keys[..num_required_signatures as usize]
+        .iter()
+        .map(|key| key.to_string())
+        .collect()
+}
+
+// Build every operation and pack the instructions into one unsigned
+// transaction for the fee payer and the other required signers to sign
+pub async fn compose_transaction(request: ComposeTransactionRequest, tenant: &Tenant) -> Result<SuccessResponse<ComposeTransactionResponse>, ErrorResponse> {
+    // Validate inputs
+    let fee_payer = validate_pubkey(&request.fee_payer, "fee payer")?;
+    let nonce = match (&request.nonce_account, &request.nonce_authority) {
+        (Some(account), Some(authority)) => Some((
+            validate_pubkey(account, "nonce account")?,
+            validate_pubkey(authority, "nonce authority")?,
+        )),
+        (None, None) => None,
+        _ => return Err(ErrorResponse::new("nonceAccount and nonceAuthority must be given together")),
+    };
+    let recent_blockhash = match (&request.recent_blockhash, &nonce) {
+        (Some(blockhash), _) => Hash::from_str(blockhash)
+            .map_err(|_| ErrorResponse::new("Invalid recent blockhash"))?,
+        (None, Some((account, authority))) => fetch_nonce_blockhash(account, authority).await?,
+        (None, None) => rpc::latest_blockhash().await?,
+    };
+
+    let mut instructions = timing::measure("build", || build_batch(BatchInstructionsRequest {
+        operations: request.operations,
+        priority_fee: request.priority_fee,
+    }, tenant))?
+        .data
+        .instructions;
+
+    // The runtime only recognizes a durable nonce transaction when advancing
+    // the nonce is its first instruction
+    if let Some((account, authority)) = nonce {
+        let advance = system_instruction::advance_nonce_account(&account, &authority);
+        check_programs(tenant, [advance.program_id.to_string().as_str()])?;
+        instructions.insert(0, InstructionResponse::from(&advance).into());
+    }
+    let compiled = instructions
+        .iter()
+        .map(to_instruction)
+        .collect::<Result<Vec<_>, ErrorResponse>>()?;
+    let warnings = heuristics::audit(&compiled, Some(&fee_payer));
+
+    let (required_signers, transaction) = match request.version {
+        TransactionVersion::Legacy => {
+            if !request.address_lookup_tables.is_empty() {
+                return Err(ErrorResponse::new("Address lookup tables require a v0 transaction"));
+            }
+            let message = Message::new_with_blockhash(&compiled, Some(&fee_payer), &recent_blockhash);
+            let required_signers = signer_keys(&message.account_keys, message.header.num_required_signatures);
+            let transaction = Transaction::new_unsigned(message);
+            (required_signers, timing::measure("serialization", || bincode::serialize(&transaction)))
+        }
+        TransactionVersion::V0 => {
+            let mut lookup_tables = Vec::with_capacity(request.address_lookup_tables.len());
+            for table in &request.address_lookup_tables {
+                lookup_tables.push(resolve_lookup_table(table).await?);
+            }
+
+            // Signers are always kept in the static account keys, so only
+            // non-signer accounts are loaded from the tables
+            let message = v0::Message::try_compile(&fee_payer, &compiled, &lookup_tables, recent_blockhash)
+                .map_err(|err| ErrorResponse::new(format!("Failed to compile v0 message: {}", err)))?;
+            let num_signers = message.header.num_required_signatures;
+            let required_signers = signer_keys(&message.account_keys, num_signers);
+            let transaction = VersionedTransaction {
+                signatures: vec![Signature::default(); num_signers as usize],
+                message: VersionedMessage::V0(message),
+            };
+            (required_signers, timing::measure("serialization", || bincode::serialize(&transaction)))
+        }
+    };
+
+    let transaction = transaction.map_err(|_| ErrorResponse::new("Failed to serialize transaction"))?;
+    if transaction.len() > PACKET_DATA_SIZE {
+        return Err(ErrorResponse::new(format!(
+            "Transaction is {} bytes, larger than the {} byte limit",
+            transaction.len(),
+            PACKET_DATA_SIZE
+        )));
+    }
+
+    Ok(SuccessResponse::new(ComposeTransactionResponse {
+        transaction: BASE64.encode(&transaction),
+        version: match request.version {
+            TransactionVersion::Legacy => "legacy".to_string(),
+            TransactionVersion::V0 => "0".to_string(),
+        },
+        recent_blockhash: recent_blockhash.to_string(),
+        required_signers,
+        instructions,
+        warnings,
+    }))
+}
+
+// Compiles instructions into a message without a transaction around it, for
+// hardware wallets and other signers that sign the message bytes directly.
+// Nothing is fetched, so the blockhash and any lookup tables come from the
+// caller.
+pub fn compile_message(request: CompileMessageRequest, tenant: &Tenant) -> Result<SuccessResponse<CompileMessageResponse>, ErrorResponse> {
+    // Validate inputs
+    if request.instructions.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
+    }
+    let fee_payer = validate_pubkey(&request.fee_payer, "fee payer")?;
+    let recent_blockhash = Hash::from_str(&request.recent_blockhash)
+        .map_err(|_| ErrorResponse::new("Invalid recent blockhash"))?;
+    let compiled = request
+        .instructions
+        .into_iter()
+        .map(|instruction| to_instruction(&build_raw_instruction_for(instruction, tenant)?.data.into()))
+        .collect::<Result<Vec<_>, ErrorResponse>>()?;
+
+    let (message, account_keys, num_signers) = match request.version {
+        TransactionVersion::Legacy => {
+            if !request.address_lookup_tables.is_empty() {
+                return Err(ErrorResponse::new("Address lookup tables require a v0 transaction"));
+            }
+            let message = Message::new_with_blockhash(&compiled, Some(&fee_payer), &recent_blockhash);
+            let num_signers = message.header.num_required_signatures;
+            (VersionedMessage::Legacy(message.clone()), message.account_keys, num_signers)
+        }
+        TransactionVersion::V0 => {
+            let lookup_tables = request
+                .address_lookup_tables
+                .iter()
+                .map(|table| {
+                    let key = validate_pubkey(&table.address, "address lookup table")?;
+                    let addresses = table
+                        .addresses
+                        .as_ref()
+                        .ok_or_else(|| ErrorResponse::new(format!("Addresses of lookup table {} are required", key)))?
+                        .iter()
+                        .map(|address| validate_pubkey(address, "lookup table address"))
+                        .collect::<Result<Vec<_>, ErrorResponse>>()?;
+                    Ok(AddressLookupTableAccount { key, addresses })
+                })
+                .collect::<Result<Vec<_>, ErrorResponse>>()?;
+            let message = v0::Message::try_compile(&fee_payer, &compiled, &lookup_tables, recent_blockhash)
+                .map_err(|err| ErrorResponse::new(format!("Failed to compile v0 message: {}", err)))?;
+            let num_signers = message.header.num_required_signatures;
+            let account_keys = message.account_keys.clone();
+            (VersionedMessage::V0(message), account_keys, num_signers)
+        }
+    };
+
+    // The message still has to fit in a transaction once signed
+    let serialized = message.serialize();
+    let transaction_len = serialized.len() + 1 + num_signers as usize * 64;
+    if transaction_len > PACKET_DATA_SIZE {
+        return Err(ErrorResponse::new(format!(
+            "Transaction is {} bytes, larger than the {} byte limit",
+            transaction_len,
+            PACKET_DATA_SIZE
+        )));
+    }
+
+    Ok(SuccessResponse::new(CompileMessageResponse {
+        message: BASE64.encode(&serialized),
+        version: match request.version {
+            TransactionVersion::Legacy => "legacy".to_string(),
+            TransactionVersion::V0 => "0".to_string(),
+        },
+        recent_blockhash: recent_blockhash.to_string(),
+        required_signers: signer_keys(&account_keys, num_signers),
+        account_keys: account_keys.iter().map(Pubkey::to_string).collect(),
+    }))
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::{compute_budget::{self, ComputeBudgetInstruction}, instruction::Instruction};
+use super::response_types::ErrorResponse;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+const DEFAULT_COMPUTE_UNITS_PER_INSTRUCTION: u64 = 200_000;
+const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
+
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy)]
+pub struct PriorityFee {
+    #[serde(rename = "microLamportsPerUnit")]
+    pub micro_lamports_per_unit: u64,
+    #[serde(rename = "unitLimit")]
+    pub unit_limit: Option<u32>,
+}
+
+// Compute budget instructions take no accounts
+#[derive(Serialize, ToSchema)]
+pub struct ComputeBudgetInstructionResponse {
+    pub program_id: String,
+    pub instruction_data: String,
+}
+
+// Compute budget settings collected from a transaction's instructions
+#[derive(Default)]
+pub struct ComputeBudgetSettings {
+    pub unit_limit: Option<u32>,
+    pub unit_price: Option<u64>,
+}
+
+impl ComputeBudgetSettings {
+    pub fn apply(&mut self, data: &[u8]) {
+        match data.split_first() {
+            Some((2, rest)) if rest.len() >= 4 => {
+                self.unit_limit = Some(u32::from_le_bytes(rest[..4].try_into().unwrap()));
+            }
+            Some((3, rest)) if rest.len() >= 8 => {
+                self.unit_price = Some(u64::from_le_bytes(rest[..8].try_into().unwrap()));
+            }
+            _ => {}
+        }
+    }
+
+    // Without an explicit limit the runtime allows 200k units for each
+    // non-compute-budget instruction, capped at the per-transaction maximum
+    pub fn compute_units(&self, instruction_count: u64) -> u64 {
+        self.unit_limit
+            .map(u64::from)
+            .unwrap_or((instruction_count * DEFAULT_COMPUTE_UNITS_PER_INSTRUCTION).min(MAX_COMPUTE_UNIT_LIMIT as u64))
+    }
+}
+
+pub fn priority_fee_lamports(micro_lamports_per_unit: u64, compute_units: u64) -> u64 {
+    (micro_lamports_per_unit as u128 * compute_units as u128).div_ceil(MICRO_LAMPORTS_PER_LAMPORT as u128) as u64
+}
+
+// The instructions that set a priority fee, in the order they should precede
+// the rest of the transaction
+pub fn priority_fee_instructions(priority_fee: Option<&PriorityFee>) -> Result<Vec<Instruction>, ErrorResponse> {
+    let Some(priority_fee) = priority_fee else {
+        return Ok(Vec::new());
+    };
+
+    let mut instructions = Vec::with_capacity(2);
+    if let Some(unit_limit) = priority_fee.unit_limit {
+        if unit_limit == 0 || unit_limit > MAX_COMPUTE_UNIT_LIMIT {
+            return Err(ErrorResponse::new(format!(
+                "unitLimit must be between 1 and {}",
+                MAX_COMPUTE_UNIT_LIMIT
+            )));
+        }
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(unit_limit));
+    }
+    instructions.push(ComputeBudgetInstruction::set_compute_unit_price(priority_fee.micro_lamports_per_unit));
+
+    Ok(instructions)
+}
+
+pub fn format_compute_budget_instructions(instructions: &[Instruction]) -> Vec<ComputeBudgetInstructionResponse> {
+    instructions
+        .iter()
+        .map(|instruction| ComputeBudgetInstructionResponse {
+            program_id: instruction.program_id.to_string(),
+            instruction_data: BASE64.encode(&instruction.data),
+        })
+        .collect()
+}
+
+pub fn is_compute_budget_program(program_id: &str) -> bool {
+    program_id == compute_budget::id().to_string()
+}
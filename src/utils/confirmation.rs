@@ -0,0 +1,219 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_client::rpc_config::{RpcSendTransactionConfig, RpcTransactionConfig};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, TransactionConfirmationStatus, TransactionStatus, UiTransactionEncoding,
+};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+use crate::cluster;
+use super::rpc;
+
+// The confirmation engine: a sent transaction is watched until it reaches
+// the commitment the caller asked for, fails, or can no longer land. Until
+// it is confirmed it is sent again now and then, since a leader may drop it.
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const REBROADCAST_INTERVAL: Duration = Duration::from_secs(4);
+// Durable nonce transactions never expire, so watching has to stop somewhere
+const WATCH_TIMEOUT: Duration = Duration::from_secs(300);
+// How long a finished watch is remembered for status lookups
+const OUTCOME_TTL: Duration = Duration::from_secs(60 * 60);
+
+// How settled a transaction must be before a watch ends
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Commitment {
+    Processed,
+    Confirmed,
+    #[default]
+    Finalized,
+}
+
+impl Commitment {
+    fn reached_by(self, status: &TransactionStatus) -> bool {
+        let reached = match status.confirmation_status() {
+            TransactionConfirmationStatus::Processed => Commitment::Processed,
+            TransactionConfirmationStatus::Confirmed => Commitment::Confirmed,
+            TransactionConfirmationStatus::Finalized => Commitment::Finalized,
+        };
+        reached >= self
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Commitment::Processed => "processed",
+            Commitment::Confirmed => "confirmed",
+            Commitment::Finalized => "finalized",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum Outcome {
+    // Reached the commitment that was asked for
+    Landed { slot: u64, commitment: Commitment },
+    // Its blockhash expired before it was seen
+    Expired,
+    // The slot and logs are known when the transaction landed and then failed
+    Failed { slot: Option<u64>, error: String, logs: Vec<String> },
+}
+
+fn outcomes() -> &'static Mutex<HashMap<Signature, (Outcome, Instant)>> {
+    static OUTCOMES: OnceLock<Mutex<HashMap<Signature, (Outcome, Instant)>>> = OnceLock::new();
+    OUTCOMES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn remember(signature: Signature, outcome: &Outcome) {
+    let mut outcomes = outcomes().lock().unwrap();
+    outcomes.retain(|_, (_, finished_at)| finished_at.elapsed() < OUTCOME_TTL);
+    outcomes.insert(signature, (outcome.clone(), Instant::now()));
+}
+
+// How a recent watch ended, for status lookups that find nothing on chain
+pub fn outcome(signature: &Signature) -> Option<Outcome> {
+    outcomes()
+        .lock()
+        .unwrap()
+        .get(signature)
+        .filter(|(_, finished_at)| finished_at.elapsed() < OUTCOME_TTL)
+        .map(|(outcome, _)| outcome.clone())
+}
+
+async fn status(signature: Signature) -> Option<TransactionStatus> {
+    rpc::read("getSignatureStatuses", move |client| async move {
+        client.get_signature_statuses(&[signature]).await.map(|response| response.value)
+    })
+        .await
+        .map_err(|err| tracing::warn!(%signature, error = %rpc::rpc_error(err).error, "Confirmation status check failed"))
+        .ok()
+        .and_then(|mut statuses| statuses.pop().flatten())
+}
+
+async fn blockhash_expired(blockhash: Hash) -> bool {
+    rpc::read("isBlockhashValid", move |client| async move {
+        client.is_blockhash_valid(&blockhash, CommitmentConfig::processed()).await
+    })
+        .await
+        .is_ok_and(|valid| !valid)
+}
+
+// Past the last height its blockhash is valid for when that is known, which
+// saves asking about the blockhash itself
+async fn expired(recent_blockhash: Hash, last_valid_block_height: Option<u64>) -> bool {
+    let Some(last_valid_block_height) = last_valid_block_height else {
+        return blockhash_expired(recent_blockhash).await;
+    };
+    rpc::read("getBlockHeight", |client| async move { client.get_block_height().await })
+        .await
+        .is_ok_and(|height| height > last_valid_block_height)
+}
+
+async fn rebroadcast(transaction: &VersionedTransaction) {
+    let client = rpc::client();
+    // Preflight already passed on the first send, and the engine does its
+    // own retrying
+    let config = RpcSendTransactionConfig {
+        skip_preflight: true,
+        max_retries: Some(0),
+        ..RpcSendTransactionConfig::default()
+    };
+    if let Err(err) = rpc::write("sendTransaction", client, client.send_transaction_with_config(transaction, config)).await {
+        tracing::debug!(signature = %transaction.signatures[0], error = %rpc::rpc_error(err).error, "Rebroadcast failed");
+    }
+}
+
+// Best effort: a failed transaction can only be fetched once confirmed
+async fn logs(signature: Signature) -> Vec<String> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    rpc::read("getTransaction", move |client| async move {
+        client.get_transaction_with_config(&signature, config).await
+    })
+        .await
+        .ok()
+        .and_then(|transaction| transaction.transaction.meta)
+        .and_then(|meta| match meta.log_messages {
+            OptionSerializer::Some(logs) => Some(logs),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+// Watch a sent transaction until it reaches the commitment asked for, fails
+// or expires. One still unseen once its blockhash has expired can no longer
+// land. Runs in the caller's cluster scope.
+pub async fn watch(transaction: VersionedTransaction, commitment: Commitment) -> Outcome {
+    let signature = transaction.signatures[0];
+    let recent_blockhash = *transaction.message.recent_blockhash();
+    let last_valid_block_height = rpc::last_valid_block_height(&recent_blockhash);
+    let outcome = track(&transaction, commitment, recent_blockhash, last_valid_block_height).await;
+    remember(signature, &outcome);
+    outcome
+}
+
+async fn track(transaction: &VersionedTransaction, commitment: Commitment, recent_blockhash: Hash, last_valid_block_height: Option<u64>) -> Outcome {
+    let signature = transaction.signatures[0];
+    let started = Instant::now();
+    let mut sent_at = Instant::now();
+    let mut expired_seen = false;
+    while started.elapsed() < WATCH_TIMEOUT {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let unconfirmed = match status(signature).await {
+            Some(TransactionStatus { slot, err: Some(err), .. }) => {
+                return Outcome::Failed { slot: Some(slot), error: err.to_string(), logs: logs(signature).await };
+            }
+            Some(status) if commitment.reached_by(&status) => {
+                return Outcome::Landed { slot: status.slot, commitment };
+            }
+            // Only processed, so it could still be on a fork that is dropped
+            Some(status) => !Commitment::Confirmed.reached_by(&status),
+            // Looked up once more after expiry, in case it landed in the
+            // blockhash's last slot
+            None if expired_seen => return Outcome::Expired,
+            None => {
+                expired_seen = expired(recent_blockhash, last_valid_block_height).await;
+                !expired_seen
+            }
+        };
+        if unconfirmed && sent_at.elapsed() >= REBROADCAST_INTERVAL {
+            rebroadcast(transaction).await;
+            sent_at = Instant::now();
+        }
+    }
+    Outcome::Failed { slot: None, error: format!("Transaction did not reach {} commitment in time", commitment.name()), logs: Vec::new() }
+}
+
+// Watch in the background, for submissions nobody waits on. Watches in
+// progress are lost when the server stops.
+pub fn spawn(transaction: VersionedTransaction, commitment: Commitment) {
+    tokio::spawn(cluster::scope(cluster::current(), watch(transaction, commitment)));
+}
+
+// Poll until a transaction reaches confirmed commitment, for callers that
+// wait inside a request and so can't wait for finalization. Gives the slot
+// it landed in, or None if it wasn't confirmed in time.
+pub async fn wait_confirmed(signature: Signature, timeout: Duration) -> Result<Option<u64>, String> {
+    let started = Instant::now();
+    while started.elapsed() + POLL_INTERVAL < timeout {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        match status(signature).await {
+            Some(TransactionStatus { err: Some(err), .. }) => return Err(err.to_string()),
+            Some(status) if Commitment::Confirmed.reached_by(&status) => return Ok(Some(status.slot)),
+            _ => {}
+        }
+    }
+    Ok(None)
+}
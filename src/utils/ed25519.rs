@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::{
+    ed25519_instruction::{DATA_START, PUBKEY_SERIALIZED_SIZE, SIGNATURE_SERIALIZED_SIZE},
+    ed25519_program,
+    pubkey::Pubkey,
+    signature::Signature,
+};
+use super::instruction::validate_pubkey;
+use super::message::{decode_message, signing_bytes, MessageEncoding, SigningFormat};
+use super::signing;
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+// Offsets that point at this instruction's own data rather than another
+// instruction in the transaction
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+#[derive(Deserialize, ToSchema)]
+pub struct Ed25519VerifyRequest {
+    pub pubkey: String,
+    pub message: String,
+    // Base64, as returned by /message/sign
+    pub signature: String,
+    // Same meaning as for /message/sign, so its output can be passed through
+    #[serde(default)]
+    pub format: SigningFormat,
+    #[serde(default)]
+    pub encoding: MessageEncoding,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Ed25519VerifyResponse {
+    pub program_id: String,
+    pub instruction_data: String,
+}
+
+fn validate_signature(signature: &str) -> Result<Signature, ErrorResponse> {
+    let sig_bytes = BASE64.decode(signature)
+        .map_err(|_| ErrorResponse::new("Invalid base64 encoding for signature"))?;
+
+    Signature::try_from(sig_bytes.as_slice())
+        .map_err(|_| ErrorResponse::new("Invalid signature format"))
+}
+
+// Same layout as solana_sdk::ed25519_instruction::new_ed25519_instruction,
+// which needs the secret key to produce the signature itself
+fn ed25519_instruction_data(pubkey: &Pubkey, signature: &Signature, message: &[u8]) -> Result<Vec<u8>, ErrorResponse> {
+    let public_key_offset = DATA_START;
+    let signature_offset = public_key_offset + PUBKEY_SERIALIZED_SIZE;
+    let message_data_offset = signature_offset + SIGNATURE_SERIALIZED_SIZE;
+    let message_data_size = u16::try_from(message.len())
+        .ok()
+        .filter(|size| message_data_offset + (*size as usize) <= u16::MAX as usize)
+        .ok_or_else(|| ErrorResponse::new("Message is too long for an Ed25519 program instruction"))?;
+
+    let mut data = Vec::with_capacity(message_data_offset + message.len());
+    // One signature, plus a padding byte so the offsets are aligned
+    data.extend_from_slice(&[1, 0]);
+    for offset in [
+        signature_offset as u16,
+        CURRENT_INSTRUCTION,
+        public_key_offset as u16,
+        CURRENT_INSTRUCTION,
+        message_data_offset as u16,
+        message_data_size,
+        CURRENT_INSTRUCTION,
+    ] {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    data.extend_from_slice(pubkey.as_ref());
+    data.extend_from_slice(signature.as_ref());
+    data.extend_from_slice(message);
+
+    Ok(data)
+}
+
+pub fn build_ed25519_verify(request: Ed25519VerifyRequest) -> Result<SuccessResponse<Ed25519VerifyResponse>, ErrorResponse> {
+    // Validate inputs
+    if request.pubkey.is_empty() || request.message.is_empty() || request.signature.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
+    }
+
+    let pubkey = validate_pubkey(&request.pubkey, "public key")?;
+    let signature = validate_signature(&request.signature)?;
+    let message = signing_bytes(decode_message(&request.message, request.encoding)?, request.format)?;
+
+    // The precompile fails the whole transaction on a bad signature, so
+    // catch it before the caller pays for that
+    if !signing::backend().verify(&pubkey, &message, &signature) {
+        return Err(ErrorResponse::new("Signature does not match the message and public key"));
+    }
+
+    let response = Ed25519VerifyResponse {
+        program_id: ed25519_program::id().to_string(),
+        instruction_data: BASE64.encode(ed25519_instruction_data(&pubkey, &signature, &message)?),
+    };
+
+    Ok(SuccessResponse::new(response))
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+use super::response_types::{SuccessResponse, ErrorResponse};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+// Builder endpoints accept `?encoding=base58` for SDKs that expect base58
+// instruction data, and name the encoding used in every response
+
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InstructionEncoding {
+    #[default]
+    Base64,
+    Base58,
+}
+
+// Instruction data is built as base64 throughout, so it is re-encoded here
+// in the serialized response, whatever shape it has
+fn to_base58(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            // The data of web3.js-shaped instructions
+            let wallet_adapter = fields.contains_key("programId");
+            for (key, field) in fields.iter_mut() {
+                match field {
+                    Value::String(data) if key == "instruction_data" || (wallet_adapter && key == "data") => {
+                        if let Ok(bytes) = BASE64.decode(data.as_bytes()) {
+                            *data = bs58::encode(bytes).into_string();
+                        }
+                    }
+                    field => to_base58(field),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(to_base58),
+        _ => {}
+    }
+}
+
+pub fn encode_instruction_data<T: Serialize>(
+    result: Result<SuccessResponse<T>, ErrorResponse>,
+    encoding: InstructionEncoding,
+) -> Result<SuccessResponse<Value>, ErrorResponse> {
+    result.map(|response| {
+        let mut data = serde_json::to_value(response.data).unwrap();
+        if encoding == InstructionEncoding::Base58 {
+            to_base58(&mut data);
+        }
+        if let Value::Object(fields) = &mut data {
+            fields.insert("encoding".to_string(), serde_json::to_value(encoding).unwrap());
+        }
+        SuccessResponse::new(data)
+    })
+}
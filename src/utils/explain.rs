@@ -0,0 +1,862 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig};
+use solana_sdk::{
+    account::Account,
+    program_utils::limited_deserialize,
+    program_pack::Pack,
+    compute_budget,
+    message::{v0::MessageAddressTableLookup, VersionedMessage},
+    signature::Signature,
+    pubkey,
+    pubkey::Pubkey,
+    system_instruction::SystemInstruction,
+    system_program,
+    transaction::VersionedTransaction,
+};
+use spl_token::{
+    instruction::{AuthorityType, TokenInstruction},
+    state::{Account as TokenAccount, Mint},
+};
+use spl_token_2022::extension::StateWithExtensions;
+use std::collections::BTreeSet;
+use super::compute_budget::{priority_fee_lamports, ComputeBudgetSettings};
+use super::footprint::fetch_accounts;
+use super::i18n::{self, Locale};
+use super::instruction::validate_pubkey;
+use super::mint_config;
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+use super::rpc::{self, rpc_error};
+use super::transaction::decode_transaction;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+const MEMO_V1_PROGRAM_ID: Pubkey = pubkey!("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo");
+
+// Mints common enough that approval UIs should show a symbol instead of an address
+pub const KNOWN_MINTS: &[(Pubkey, &str)] = &[
+    (pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"), "USDC"),
+    (pubkey!("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB"), "USDT"),
+    (pubkey!("So11111111111111111111111111111111111111112"), "wSOL"),
+];
+
+#[derive(Deserialize, ToSchema)]
+pub struct ExplainTransactionRequest {
+    pub transaction: String,
+    // Also simulate the transaction against current chain state and report
+    // how it would move balances
+    #[serde(default)]
+    pub simulate: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ExplainTransactionResponse {
+    pub summary: String,
+    pub actions: Vec<String>,
+    pub fee_payer: String,
+    pub signers: Vec<String>,
+    pub estimated_fee_lamports: u64,
+    // Only with simulate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub simulation: Option<SimulationOutcome>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub balance_changes: Vec<BalanceChange>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SimulationOutcome {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub units_consumed: Option<u64>,
+    pub logs: Vec<String>,
+}
+
+// The net change to one balance of a writable account. Amounts are raw
+// lamports or token base units, as strings since they can exceed what JSON
+// numbers hold exactly.
+#[derive(Serialize, ToSchema)]
+pub struct BalanceChange {
+    pub account: String,
+    // Set for token accounts; SOL balances have no mint
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mint: Option<String>,
+    // The wallet owning a token account
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    pub pre: String,
+    pub post: String,
+    // Signed, e.g. "-500000000"
+    pub change: String,
+    // The change with decimals and a symbol, e.g. "-0.5 SOL"
+    pub description: String,
+}
+
+// Signer and writable flags may be sent along but do not change the decoding
+#[derive(Deserialize, ToSchema)]
+pub struct DecodeInstructionAccount {
+    pub pubkey: String,
+}
+
+// The same shape the instruction endpoints return, so their output can be
+// decoded as is
+#[derive(Deserialize, ToSchema)]
+pub struct DecodeInstructionRequest {
+    #[serde(alias = "programId")]
+    pub program_id: String,
+    // Compute budget instructions are returned without accounts
+    #[serde(default)]
+    pub accounts: Vec<DecodeInstructionAccount>,
+    #[serde(alias = "instructionData")]
+    pub instruction_data: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DecodeInstructionResponse {
+    pub program_id: String,
+    // Set for the programs this server understands, e.g. "SPL Token"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub program: Option<String>,
+    // The instruction's name in its program, e.g. "TransferChecked"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instruction: Option<String>,
+    pub description: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DecodeTransactionRequest {
+    // Base64 legacy or v0 transaction, signed or not
+    pub transaction: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DecodedSignature {
+    pub signer: String,
+    // False while the signer has yet to sign
+    pub present: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DecodedAccount {
+    // None for addresses loaded from a lookup table, which can't be
+    // resolved without fetching the table
+    pub pubkey: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lookup_table: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lookup_index: Option<u8>,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DecodedInstruction {
+    #[serde(flatten)]
+    pub decoded: DecodeInstructionResponse,
+    pub accounts: Vec<DecodedAccount>,
+    pub instruction_data: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DecodeTransactionResponse {
+    // "legacy" or "0"
+    pub version: String,
+    pub fee_payer: String,
+    pub recent_blockhash: String,
+    pub signatures: Vec<DecodedSignature>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub address_lookup_tables: Vec<String>,
+    pub instructions: Vec<DecodedInstruction>,
+}
+
+// Render a catalog template in the requested language
+macro_rules! t {
+    ($locale:expr, $template:literal $(, $arg:expr)* $(,)?) => {
+        i18n::format($locale, $template, &[$($arg.to_string()),*])
+    };
+}
+
+fn short_address(pubkey: &Pubkey) -> String {
+    let address = pubkey.to_string();
+    format!("{}…{}", &address[..3], &address[address.len() - 3..])
+}
+
+fn mint_label(locale: Locale, mint: &Pubkey) -> String {
+    mint_config::symbol(mint)
+        .or_else(|| KNOWN_MINTS.iter().find(|(known, _)| known == mint).map(|(_, symbol)| *symbol))
+        .map(|symbol| symbol.to_string())
+        .unwrap_or_else(|| t!(locale, "mint {}", short_address(mint)))
+}
+
+fn format_amount(amount: u64, decimals: u8) -> String {
+    if decimals == 0 {
+        return amount.to_string();
+    }
+    let divisor = 10u64.pow(decimals as u32);
+    let fraction = format!("{:0width$}", amount % divisor, width = decimals as usize);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        (amount / divisor).to_string()
+    } else {
+        format!("{}.{}", amount / divisor, fraction)
+    }
+}
+
+fn format_sol(lamports: u64) -> String {
+    format!("{} SOL", format_amount(lamports, 9))
+}
+
+// Instruction accounts, with None for accounts loaded from address lookup
+// tables which can't be resolved offline
+type InstructionAccounts = [Option<Pubkey>];
+
+fn account(locale: Locale, accounts: &InstructionAccounts, index: usize) -> String {
+    accounts
+        .get(index)
+        .copied()
+        .flatten()
+        .map(|pubkey| short_address(&pubkey))
+        .unwrap_or_else(|| t!(locale, "an unknown account"))
+}
+
+fn mint(locale: Locale, accounts: &InstructionAccounts, index: usize) -> String {
+    accounts
+        .get(index)
+        .copied()
+        .flatten()
+        .map(|pubkey| mint_label(locale, &pubkey))
+        .unwrap_or_else(|| t!(locale, "an unknown mint"))
+}
+
+fn describe_system(locale: Locale, accounts: &InstructionAccounts, data: &[u8]) -> String {
+    match limited_deserialize::<SystemInstruction>(data) {
+        Ok(SystemInstruction::Transfer { lamports }) => {
+            t!(locale, "Send {} to {}", format_sol(lamports), account(locale, accounts, 1))
+        }
+        Ok(SystemInstruction::TransferWithSeed { lamports, .. }) => {
+            t!(locale, "Send {} to {}", format_sol(lamports), account(locale, accounts, 2))
+        }
+        Ok(SystemInstruction::CreateAccount { lamports, space, owner })
+        | Ok(SystemInstruction::CreateAccountWithSeed { lamports, space, owner, .. }) => t!(
+            locale,
+            "Create account {} funded with {} ({} bytes, owned by {})",
+            account(locale, accounts, 1),
+            format_sol(lamports),
+            space,
+            short_address(&owner),
+        ),
+        Ok(SystemInstruction::Assign { owner }) => {
+            t!(locale, "Assign {} to program {}", account(locale, accounts, 0), short_address(&owner))
+        }
+        Ok(SystemInstruction::Allocate { space }) => {
+            t!(locale, "Allocate {} bytes for {}", space, account(locale, accounts, 0))
+        }
+        Ok(SystemInstruction::AdvanceNonceAccount) => {
+            t!(locale, "Advance durable nonce {}", account(locale, accounts, 0))
+        }
+        Ok(SystemInstruction::WithdrawNonceAccount(lamports)) => t!(
+            locale,
+            "Withdraw {} from nonce account {} to {}",
+            format_sol(lamports),
+            account(locale, accounts, 0),
+            account(locale, accounts, 1),
+        ),
+        Ok(SystemInstruction::InitializeNonceAccount(_)) => {
+            t!(locale, "Initialize nonce account {}", account(locale, accounts, 0))
+        }
+        _ => t!(locale, "Call the System program"),
+    }
+}
+
+fn describe_token(locale: Locale, accounts: &InstructionAccounts, data: &[u8]) -> String {
+    match TokenInstruction::unpack(data) {
+        Ok(TokenInstruction::Transfer { amount }) => t!(
+            locale,
+            "Transfer {} raw token units from {} to {}",
+            amount,
+            account(locale, accounts, 0),
+            account(locale, accounts, 1),
+        ),
+        Ok(TokenInstruction::TransferChecked { amount, decimals }) => t!(
+            locale,
+            "Send {} {} to token account {}",
+            format_amount(amount, decimals),
+            mint(locale, accounts, 1),
+            account(locale, accounts, 2),
+        ),
+        Ok(TokenInstruction::MintTo { amount }) => t!(
+            locale,
+            "Mint {} raw units of {} to {}",
+            amount,
+            mint(locale, accounts, 0),
+            account(locale, accounts, 1),
+        ),
+        Ok(TokenInstruction::MintToChecked { amount, decimals }) => t!(
+            locale,
+            "Mint {} {} to {}",
+            format_amount(amount, decimals),
+            mint(locale, accounts, 0),
+            account(locale, accounts, 1),
+        ),
+        Ok(TokenInstruction::Burn { amount }) => {
+            t!(locale, "Burn {} raw units of {}", amount, mint(locale, accounts, 1))
+        }
+        Ok(TokenInstruction::BurnChecked { amount, decimals }) => {
+            t!(locale, "Burn {} {}", format_amount(amount, decimals), mint(locale, accounts, 1))
+        }
+        Ok(TokenInstruction::Approve { amount }) => t!(
+            locale,
+            "Allow {} to spend {} raw units from {}",
+            account(locale, accounts, 1),
+            amount,
+            account(locale, accounts, 0),
+        ),
+        Ok(TokenInstruction::ApproveChecked { amount, decimals }) => t!(
+            locale,
+            "Allow {} to spend {} {} from {}",
+            account(locale, accounts, 2),
+            format_amount(amount, decimals),
+            mint(locale, accounts, 1),
+            account(locale, accounts, 0),
+        ),
+        Ok(TokenInstruction::Revoke) => {
+            t!(locale, "Revoke the delegate of {}", account(locale, accounts, 0))
+        }
+        Ok(TokenInstruction::CloseAccount) => t!(
+            locale,
+            "Close token account {} and reclaim its rent to {}",
+            account(locale, accounts, 0),
+            account(locale, accounts, 1),
+        ),
+        Ok(TokenInstruction::SetAuthority { authority_type, new_authority }) => {
+            let authority = match authority_type {
+                AuthorityType::MintTokens => t!(locale, "mint"),
+                AuthorityType::FreezeAccount => t!(locale, "freeze"),
+                AuthorityType::AccountOwner => t!(locale, "owner"),
+                AuthorityType::CloseAccount => t!(locale, "close"),
+            };
+            match Option::<Pubkey>::from(new_authority) {
+                Some(new_authority) => t!(
+                    locale,
+                    "Change the {} authority of {} to {}",
+                    authority,
+                    account(locale, accounts, 0),
+                    short_address(&new_authority),
+                ),
+                None => t!(locale, "Remove the {} authority of {}", authority, account(locale, accounts, 0)),
+            }
+        }
+        Ok(TokenInstruction::InitializeMint { decimals, .. })
+        | Ok(TokenInstruction::InitializeMint2 { decimals, .. }) => {
+            t!(locale, "Initialize mint {} with {} decimals", account(locale, accounts, 0), decimals)
+        }
+        Ok(TokenInstruction::InitializeAccount)
+        | Ok(TokenInstruction::InitializeAccount2 { .. })
+        | Ok(TokenInstruction::InitializeAccount3 { .. }) => {
+            t!(locale, "Initialize token account {} for {}", account(locale, accounts, 0), mint(locale, accounts, 1))
+        }
+        Ok(TokenInstruction::SyncNative) => {
+            t!(locale, "Sync wrapped SOL balance of {}", account(locale, accounts, 0))
+        }
+        Ok(TokenInstruction::FreezeAccount) => {
+            t!(locale, "Freeze token account {}", account(locale, accounts, 0))
+        }
+        Ok(TokenInstruction::ThawAccount) => {
+            t!(locale, "Thaw token account {}", account(locale, accounts, 0))
+        }
+        _ => t!(locale, "Call the SPL Token program"),
+    }
+}
+
+fn describe_associated_token_account(locale: Locale, accounts: &InstructionAccounts, data: &[u8]) -> String {
+    // Accounts: payer, associated account, wallet, mint, system program, token program
+    let mint = mint(locale, accounts, 3);
+    let owner = account(locale, accounts, 2);
+    if data.first() == Some(&1) {
+        t!(locale, "Create token account for {} owned by {} (if missing)", mint, owner)
+    } else {
+        t!(locale, "Create token account for {} owned by {}", mint, owner)
+    }
+}
+
+fn describe_memo(locale: Locale, data: &[u8]) -> String {
+    t!(locale, "Attach memo \"{}\"", String::from_utf8_lossy(data))
+}
+
+// Compute budget instructions are a tag byte followed by a little-endian value
+fn compute_budget_value(data: &[u8], len: usize) -> Option<u64> {
+    let bytes = data.get(1..1 + len)?;
+    let mut value = [0u8; 8];
+    value[..len].copy_from_slice(bytes);
+    Some(u64::from_le_bytes(value))
+}
+
+fn describe_compute_budget(locale: Locale, data: &[u8]) -> String {
+    let described = match data.first() {
+        Some(1) => compute_budget_value(data, 4).map(|bytes| t!(locale, "Request a heap of {} bytes", bytes)),
+        Some(2) => compute_budget_value(data, 4).map(|units| t!(locale, "Set the compute unit limit to {}", units)),
+        Some(3) => compute_budget_value(data, 8).map(|price| t!(locale, "Set the compute unit price to {} micro-lamports", price)),
+        Some(4) => compute_budget_value(data, 4).map(|bytes| t!(locale, "Limit loaded account data to {} bytes", bytes)),
+        _ => None,
+    };
+    described.unwrap_or_else(|| t!(locale, "Call the Compute Budget program"))
+}
+
+fn describe(locale: Locale, program_id: &Pubkey, accounts: &InstructionAccounts, data: &[u8]) -> String {
+    if *program_id == system_program::id() {
+        describe_system(locale, accounts, data)
+    } else if *program_id == spl_token::id() || *program_id == TOKEN_2022_PROGRAM_ID {
+        describe_token(locale, accounts, data)
+    } else if *program_id == spl_associated_token_account::id() {
+        describe_associated_token_account(locale, accounts, data)
+    } else if *program_id == MEMO_PROGRAM_ID || *program_id == MEMO_V1_PROGRAM_ID {
+        describe_memo(locale, data)
+    } else if compute_budget::check_id(program_id) {
+        describe_compute_budget(locale, data)
+    } else {
+        t!(locale, "Call program {}", short_address(program_id))
+    }
+}
+
+fn program_name(program_id: &Pubkey) -> Option<&'static str> {
+    if *program_id == system_program::id() {
+        Some("System")
+    } else if *program_id == spl_token::id() {
+        Some("SPL Token")
+    } else if *program_id == TOKEN_2022_PROGRAM_ID {
+        Some("Token-2022")
+    } else if *program_id == spl_associated_token_account::id() {
+        Some("Associated Token Account")
+    } else if *program_id == MEMO_PROGRAM_ID || *program_id == MEMO_V1_PROGRAM_ID {
+        Some("Memo")
+    } else if compute_budget::check_id(program_id) {
+        Some("Compute Budget")
+    } else {
+        None
+    }
+}
+
+// The variant name from an instruction's Debug output, without its fields
+fn variant_name(instruction: &impl std::fmt::Debug) -> String {
+    format!("{:?}", instruction)
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+pub(super) fn instruction_name(program_id: &Pubkey, data: &[u8]) -> Option<String> {
+    if *program_id == system_program::id() {
+        limited_deserialize::<SystemInstruction>(data).ok().map(|instruction| variant_name(&instruction))
+    } else if *program_id == spl_token::id() || *program_id == TOKEN_2022_PROGRAM_ID {
+        TokenInstruction::unpack(data).ok().map(|instruction| variant_name(&instruction))
+    } else if *program_id == spl_associated_token_account::id() {
+        match data.first() {
+            None | Some(0) => Some("Create".to_string()),
+            Some(1) => Some("CreateIdempotent".to_string()),
+            Some(2) => Some("RecoverNested".to_string()),
+            _ => None,
+        }
+    } else if *program_id == MEMO_PROGRAM_ID || *program_id == MEMO_V1_PROGRAM_ID {
+        Some("Memo".to_string())
+    } else if compute_budget::check_id(program_id) {
+        let name = match data.first() {
+            Some(1) => "RequestHeapFrame",
+            Some(2) => "SetComputeUnitLimit",
+            Some(3) => "SetComputeUnitPrice",
+            Some(4) => "SetLoadedAccountsDataSizeLimit",
+            _ => return None,
+        };
+        Some(name.to_string())
+    } else {
+        None
+    }
+}
+
+fn estimate_fee(signatures: u64, budget: &ComputeBudgetSettings, instruction_count: u64) -> u64 {
+    let base_fee = signatures * LAMPORTS_PER_SIGNATURE;
+    let priority_fee = budget
+        .unit_price
+        .map(|price| priority_fee_lamports(price, budget.compute_units(instruction_count)));
+    base_fee + priority_fee.unwrap_or(0)
+}
+
+// A token account's mint, owner and amount
+fn token_balance(account: &Account) -> Option<(Pubkey, Pubkey, u64)> {
+    if account.owner == spl_token::id() {
+        TokenAccount::unpack(&account.data).ok().map(|state| (state.mint, state.owner, state.amount))
+    } else if account.owner == TOKEN_2022_PROGRAM_ID {
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+            .ok()
+            .map(|state| (state.base.mint, state.base.owner, state.base.amount))
+    } else {
+        None
+    }
+}
+
+fn mint_decimals(account: &Account) -> Option<u8> {
+    if account.owner == spl_token::id() {
+        Mint::unpack(&account.data).ok().map(|mint| mint.decimals)
+    } else if account.owner == TOKEN_2022_PROGRAM_ID {
+        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&account.data).ok().map(|state| state.base.decimals)
+    } else {
+        None
+    }
+}
+
+fn signed_change(pre: u64, post: u64, format: impl Fn(u64) -> String) -> String {
+    if post >= pre {
+        format!("+{}", format(post - pre))
+    } else {
+        format!("-{}", format(pre - post))
+    }
+}
+
+// Writable accounts as they are now and as the simulation leaves them; None
+// where an account doesn't exist
+struct Simulated {
+    outcome: SimulationOutcome,
+    addresses: Vec<Pubkey>,
+    pre: Vec<Option<Account>>,
+    post: Vec<Option<Account>>,
+}
+
+// Signatures aren't checked and the blockhash is replaced, so unsigned and
+// stale transactions can be explained too. Accounts loaded from lookup
+// tables are left out.
+async fn simulate(transaction: VersionedTransaction) -> Result<Simulated, ErrorResponse> {
+    let message = &transaction.message;
+    let addresses: Vec<Pubkey> = message
+        .static_account_keys()
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| message.is_maybe_writable(*index))
+        .map(|(_, key)| *key)
+        .collect();
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        accounts: Some(RpcSimulateTransactionAccountsConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            addresses: addresses.iter().map(|address| address.to_string()).collect(),
+        }),
+        ..RpcSimulateTransactionConfig::default()
+    };
+    let simulation = rpc::read("simulateTransaction", move |client| {
+        let (transaction, config) = (transaction.clone(), config.clone());
+        async move { client.simulate_transaction_with_config(&transaction, config).await }
+    });
+
+    let (pre, simulation) = tokio::try_join!(
+        fetch_accounts(&addresses),
+        async { simulation.await.map_err(rpc_error) },
+    )?;
+    let result = simulation.value;
+    let post = result
+        .accounts
+        .map(|accounts| accounts.into_iter().map(|account| account.and_then(|account| account.decode())).collect())
+        .unwrap_or_default();
+
+    Ok(Simulated {
+        outcome: SimulationOutcome {
+            success: result.err.is_none(),
+            error: result.err.map(|err| err.to_string()),
+            units_consumed: result.units_consumed,
+            logs: result.logs.unwrap_or_default(),
+        },
+        addresses,
+        pre,
+        post,
+    })
+}
+
+// Lamport changes, then token amount changes, of accounts the simulation
+// left different
+async fn balance_changes(locale: Locale, simulated: &Simulated) -> Result<Vec<BalanceChange>, ErrorResponse> {
+    // A failed simulation returns no accounts, and nothing would change
+    if simulated.post.len() != simulated.addresses.len() {
+        return Ok(Vec::new());
+    }
+
+    let mut changes = Vec::new();
+    let mut token_changes = Vec::new();
+    for ((address, pre), post) in simulated.addresses.iter().zip(&simulated.pre).zip(&simulated.post) {
+        let lamports = |account: &Option<Account>| account.as_ref().map_or(0, |account| account.lamports);
+        let (pre_lamports, post_lamports) = (lamports(pre), lamports(post));
+        if pre_lamports != post_lamports {
+            changes.push(BalanceChange {
+                account: address.to_string(),
+                mint: None,
+                owner: None,
+                pre: pre_lamports.to_string(),
+                post: post_lamports.to_string(),
+                change: signed_change(pre_lamports, post_lamports, |amount| amount.to_string()),
+                description: format!("{} {}", short_address(address), signed_change(pre_lamports, post_lamports, format_sol)),
+            });
+        }
+
+        // An account created or closed by the transaction holds nothing on
+        // the other side
+        let pre_token = pre.as_ref().and_then(token_balance);
+        let post_token = post.as_ref().and_then(token_balance);
+        let Some((mint, owner, _)) = post_token.or(pre_token) else {
+            continue;
+        };
+        let amount = |token: Option<(Pubkey, Pubkey, u64)>| token.filter(|(held, _, _)| *held == mint).map_or(0, |(_, _, amount)| amount);
+        let (pre_amount, post_amount) = (amount(pre_token), amount(post_token));
+        if pre_amount != post_amount {
+            token_changes.push((*address, mint, owner, pre_amount, post_amount));
+        }
+    }
+
+    // Token amounts are described in whole tokens, which needs the mints
+    let mints: Vec<Pubkey> = token_changes.iter().map(|(_, mint, ..)| *mint).collect::<BTreeSet<_>>().into_iter().collect();
+    let mint_accounts = if mints.iter().all(|mint| mint_config::forced_decimals(mint).is_some()) {
+        vec![None; mints.len()]
+    } else {
+        fetch_accounts(&mints).await?
+    };
+    for (address, mint, owner, pre_amount, post_amount) in token_changes {
+        let index = mints.binary_search(&mint).unwrap_or_default();
+        let decimals = mint_config::forced_decimals(&mint)
+            .or_else(|| mint_accounts[index].as_ref().and_then(mint_decimals))
+            .unwrap_or(0);
+        let amount = signed_change(pre_amount, post_amount, |amount| format_amount(amount, decimals));
+        changes.push(BalanceChange {
+            account: address.to_string(),
+            mint: Some(mint.to_string()),
+            owner: Some(owner.to_string()),
+            pre: pre_amount.to_string(),
+            post: post_amount.to_string(),
+            change: signed_change(pre_amount, post_amount, |amount| amount.to_string()),
+            description: format!("{} {} {}", short_address(&owner), amount, mint_label(locale, &mint)),
+        });
+    }
+    Ok(changes)
+}
+
+pub async fn explain_transaction(request: ExplainTransactionRequest, locale: Locale) -> Result<SuccessResponse<ExplainTransactionResponse>, ErrorResponse> {
+    // Validate inputs
+    if request.transaction.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
+    }
+
+    let transaction = decode_transaction(&request.transaction)?;
+
+    let message = &transaction.message;
+    let account_keys = message.static_account_keys();
+    let fee_payer = account_keys
+        .first()
+        .ok_or_else(|| ErrorResponse::new("Transaction has no fee payer"))?;
+    let num_signers = message.header().num_required_signatures as usize;
+
+    let mut budget = ComputeBudgetSettings::default();
+    let mut actions = Vec::new();
+    let mut non_budget_instructions = 0u64;
+
+    for instruction in message.instructions() {
+        let program_id = account_keys
+            .get(instruction.program_id_index as usize)
+            .ok_or_else(|| ErrorResponse::new("Instruction references a missing program account"))?;
+
+        let accounts: Vec<Option<Pubkey>> = instruction
+            .accounts
+            .iter()
+            .map(|index| account_keys.get(*index as usize).copied())
+            .collect();
+
+        // Compute budget instructions are folded into the fee line rather than listed
+        if compute_budget::check_id(program_id) {
+            budget.apply(&instruction.data);
+            continue;
+        }
+        non_budget_instructions += 1;
+
+        actions.push(describe(locale, program_id, &accounts, &instruction.data));
+    }
+
+    let estimated_fee_lamports = estimate_fee(num_signers as u64, &budget, non_budget_instructions);
+
+    let mut summary_parts: Vec<String> = actions
+        .iter()
+        .enumerate()
+        .map(|(index, action)| {
+            // Only the first clause keeps its capital letter
+            if index == 0 {
+                action.clone()
+            } else {
+                let mut chars = action.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_lowercase().chain(chars).collect())
+                    .unwrap_or_default()
+            }
+        })
+        .collect();
+    let fee_line = t!(locale, "~{} fees", format_sol(estimated_fee_lamports));
+    summary_parts.push(if summary_parts.is_empty() { t!(locale, "Pay {}", fee_line) } else { t!(locale, "pay {}", fee_line) });
+    let signers = account_keys.iter().take(num_signers).map(|key| key.to_string()).collect();
+    let fee_payer = fee_payer.to_string();
+
+    // What the instructions say they do, checked against what running them
+    // actually does
+    let (simulation, balance_changes) = if request.simulate {
+        let simulated = simulate(transaction).await?;
+        let balance_changes = balance_changes(locale, &simulated).await?;
+        if let Some(error) = &simulated.outcome.error {
+            summary_parts.push(t!(locale, "simulation fails: {}", error));
+        } else if !balance_changes.is_empty() {
+            let descriptions: Vec<&str> = balance_changes.iter().map(|change| change.description.as_str()).collect();
+            summary_parts.push(t!(locale, "net balance changes: {}", descriptions.join(", ")));
+        }
+        (Some(simulated.outcome), balance_changes)
+    } else {
+        (None, Vec::new())
+    };
+
+    let response = ExplainTransactionResponse {
+        summary: summary_parts.join("; "),
+        actions,
+        fee_payer,
+        signers,
+        estimated_fee_lamports,
+        simulation,
+        balance_changes,
+    };
+
+    Ok(SuccessResponse::new(response))
+}
+
+// The inverse of the instruction builders: names the program and
+// instruction and describes what it does, as /transaction/explain would
+pub fn decode_instruction(request: DecodeInstructionRequest, locale: Locale) -> Result<SuccessResponse<DecodeInstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    let program_id = validate_pubkey(&request.program_id, "program id")?;
+    let accounts = request.accounts
+        .iter()
+        .map(|account| validate_pubkey(&account.pubkey, "account").map(Some))
+        .collect::<Result<Vec<_>, _>>()?;
+    let data = BASE64
+        .decode(&request.instruction_data)
+        .map_err(|_| ErrorResponse::new("Invalid base64 encoding for instruction data"))?;
+
+    Ok(SuccessResponse::new(decode(locale, &program_id, &accounts, &data)))
+}
+
+fn decode(locale: Locale, program_id: &Pubkey, accounts: &InstructionAccounts, data: &[u8]) -> DecodeInstructionResponse {
+    DecodeInstructionResponse {
+        program_id: program_id.to_string(),
+        program: program_name(program_id).map(str::to_string),
+        instruction: instruction_name(program_id, data),
+        description: describe(locale, program_id, accounts, data),
+    }
+}
+
+// Lookup table and index of each address a v0 message loads, in the order
+// they follow the static keys: every table's writable addresses, then every
+// table's readonly ones
+fn loaded_addresses(lookups: &[MessageAddressTableLookup]) -> Vec<(Pubkey, u8)> {
+    let writable = lookups
+        .iter()
+        .flat_map(|lookup| lookup.writable_indexes.iter().map(|index| (lookup.account_key, *index)));
+    let readonly = lookups
+        .iter()
+        .flat_map(|lookup| lookup.readonly_indexes.iter().map(|index| (lookup.account_key, *index)));
+    writable.chain(readonly).collect()
+}
+
+// Everything a signer should see before signing: who pays, which
+// signatures are still missing, and what each instruction does
+pub fn decode_transaction_details(request: DecodeTransactionRequest, locale: Locale) -> Result<SuccessResponse<DecodeTransactionResponse>, ErrorResponse> {
+    // Validate inputs
+    if request.transaction.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
+    }
+
+    let transaction = decode_transaction(&request.transaction)?;
+
+    let message = &transaction.message;
+    let account_keys = message.static_account_keys();
+    let fee_payer = account_keys
+        .first()
+        .ok_or_else(|| ErrorResponse::new("Transaction has no fee payer"))?;
+    let num_signers = message.header().num_required_signatures as usize;
+    let lookups = message.address_table_lookups().unwrap_or_default();
+    let loaded = loaded_addresses(lookups);
+
+    let signatures = account_keys
+        .iter()
+        .take(num_signers)
+        .enumerate()
+        .map(|(index, signer)| {
+            let signature = transaction
+                .signatures
+                .get(index)
+                .filter(|signature| **signature != Signature::default());
+            DecodedSignature {
+                signer: signer.to_string(),
+                present: signature.is_some(),
+                signature: signature.map(|signature| signature.to_string()),
+            }
+        })
+        .collect();
+
+    let instructions = message
+        .instructions()
+        .iter()
+        .map(|instruction| {
+            let program_id = account_keys
+                .get(instruction.program_id_index as usize)
+                .ok_or_else(|| ErrorResponse::new("Instruction references a missing program account"))?;
+            let accounts: Vec<Option<Pubkey>> = instruction
+                .accounts
+                .iter()
+                .map(|index| account_keys.get(*index as usize).copied())
+                .collect();
+
+            let decoded_accounts = instruction
+                .accounts
+                .iter()
+                .zip(&accounts)
+                .map(|(index, pubkey)| {
+                    let index = *index as usize;
+                    let lookup = index.checked_sub(account_keys.len()).and_then(|loaded_index| loaded.get(loaded_index));
+                    DecodedAccount {
+                        pubkey: pubkey.map(|pubkey| pubkey.to_string()),
+                        lookup_table: lookup.map(|(table, _)| table.to_string()),
+                        lookup_index: lookup.map(|(_, index)| *index),
+                        is_signer: message.is_signer(index),
+                        is_writable: message.is_maybe_writable(index),
+                    }
+                })
+                .collect();
+
+            Ok(DecodedInstruction {
+                decoded: decode(locale, program_id, &accounts, &instruction.data),
+                accounts: decoded_accounts,
+                instruction_data: BASE64.encode(&instruction.data),
+            })
+        })
+        .collect::<Result<Vec<_>, ErrorResponse>>()?;
+
+    let response = DecodeTransactionResponse {
+        version: match message {
+            VersionedMessage::Legacy(_) => "legacy".to_string(),
+            VersionedMessage::V0(_) => "0".to_string(),
+        },
+        fee_payer: fee_payer.to_string(),
+        recent_blockhash: message.recent_blockhash().to_string(),
+        signatures,
+        address_lookup_tables: lookups.iter().map(|lookup| lookup.account_key.to_string()).collect(),
+        instructions,
+    };
+
+    Ok(SuccessResponse::new(response))
+}
@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use solana_sdk::{compute_budget, message::VersionedMessage, pubkey::Pubkey};
+use super::compute_budget::{priority_fee_lamports, ComputeBudgetSettings, PriorityFee};
+use super::instruction::validate_pubkey;
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+use super::rpc::{self, rpc_error};
+use super::transaction::decode_transaction;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+// Recent prioritization fees are sampled at this percentile for the
+// recommended price, which lands most transactions without overpaying
+const RECOMMENDED_FEE_PERCENTILE: usize = 75;
+// Percentiles for the low and high suggestions around the recommended one
+const LOW_FEE_PERCENTILE: usize = 50;
+const HIGH_FEE_PERCENTILE: usize = 90;
+// getRecentPrioritizationFees takes at most this many accounts
+const MAX_PRIORITY_FEE_ACCOUNTS: usize = 128;
+
+#[derive(Deserialize, ToSchema)]
+pub struct TransactionFeeRequest {
+    // Either a base64 serialized transaction or a base64 serialized message
+    pub transaction: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TransactionFeeResponse {
+    // Exact fee the cluster would charge for the message as it stands,
+    // including any priority fee it already sets
+    pub fee_lamports: u64,
+    pub compute_units: u64,
+    pub micro_lamports_per_unit: Option<u64>,
+    pub recommended_micro_lamports_per_unit: u64,
+    pub recommended_priority_fee_lamports: u64,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PriorityFeeQuery {
+    // Comma-separated base58 addresses the transaction will write to. Without
+    // any, the fees are sampled across the whole cluster.
+    pub accounts: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PriorityFeeResponse {
+    pub accounts: Vec<String>,
+    // Recent slots the suggestions are drawn from
+    pub slots_sampled: usize,
+    // Each can be passed as is as a builder's priorityFee
+    pub low: PriorityFee,
+    pub medium: PriorityFee,
+    pub high: PriorityFee,
+}
+
+fn decode_message(message: &str) -> Result<VersionedMessage, ErrorResponse> {
+    let bytes = BASE64.decode(message)
+        .map_err(|_| ErrorResponse::new("Invalid base64 encoding for message"))?;
+    bincode::deserialize(&bytes)
+        .map_err(|_| ErrorResponse::new("Invalid message format"))
+}
+
+fn percentile(mut fees: Vec<u64>, percentile: usize) -> u64 {
+    if fees.is_empty() {
+        return 0;
+    }
+    fees.sort_unstable();
+    let index = (fees.len() * percentile / 100).min(fees.len() - 1);
+    fees[index]
+}
+
+async fn fetch_fee_for_message(message: VersionedMessage) -> Result<u64, ErrorResponse> {
+    rpc::read("getFeeForMessage", move |client| {
+        let message = message.clone();
+        async move {
+            match &message {
+                VersionedMessage::Legacy(message) => client.get_fee_for_message(message).await,
+                VersionedMessage::V0(message) => client.get_fee_for_message(message).await,
+            }
+        }
+    })
+        .await
+        .map_err(rpc_error)
+}
+
+// Prioritization fees paid recently by transactions that wrote to the same
+// accounts, which is what the message will be competing with
+async fn fetch_recent_prioritization_fees(accounts: Vec<Pubkey>) -> Result<Vec<u64>, ErrorResponse> {
+    let fees = rpc::read("getRecentPrioritizationFees", move |client| {
+        let accounts = accounts.clone();
+        async move { client.get_recent_prioritization_fees(&accounts).await }
+    })
+        .await
+        .map_err(rpc_error)?;
+
+    Ok(fees.into_iter().map(|fee| fee.prioritization_fee).collect())
+}
+
+fn suggested(micro_lamports_per_unit: u64) -> PriorityFee {
+    PriorityFee { micro_lamports_per_unit, unit_limit: None }
+}
+
+pub async fn recommend_priority_fees(query: PriorityFeeQuery) -> Result<SuccessResponse<PriorityFeeResponse>, ErrorResponse> {
+    // Validate inputs
+    let accounts = query
+        .accounts
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|account| !account.is_empty())
+        .enumerate()
+        .map(|(index, account)| validate_pubkey(account, &format!("accounts[{}]", index)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if accounts.len() > MAX_PRIORITY_FEE_ACCOUNTS {
+        return Err(ErrorResponse::with_code(
+            ApiErrorCode::BatchTooLarge,
+            format!("At most {} accounts can be sampled at once", MAX_PRIORITY_FEE_ACCOUNTS),
+        ));
+    }
+
+    let fees = fetch_recent_prioritization_fees(accounts.clone()).await?;
+
+    Ok(SuccessResponse::new(PriorityFeeResponse {
+        accounts: accounts.iter().map(|account| account.to_string()).collect(),
+        slots_sampled: fees.len(),
+        low: suggested(percentile(fees.clone(), LOW_FEE_PERCENTILE)),
+        medium: suggested(percentile(fees.clone(), RECOMMENDED_FEE_PERCENTILE)),
+        high: suggested(percentile(fees, HIGH_FEE_PERCENTILE)),
+    }))
+}
+
+pub async fn estimate_transaction_fee(request: TransactionFeeRequest) -> Result<SuccessResponse<TransactionFeeResponse>, ErrorResponse> {
+    // Validate inputs
+    let message = match (&request.transaction, &request.message) {
+        (Some(transaction), None) => decode_transaction(transaction)?.message,
+        (None, Some(message)) => decode_message(message)?,
+        _ => return Err(ErrorResponse::new("Exactly one of transaction or message is required")),
+    };
+
+    let account_keys = message.static_account_keys();
+    let mut budget = ComputeBudgetSettings::default();
+    let mut instruction_count = 0;
+    for instruction in message.instructions() {
+        let program_id = account_keys
+            .get(instruction.program_id_index as usize)
+            .ok_or_else(|| ErrorResponse::new("Instruction references a missing program account"))?;
+        if compute_budget::check_id(program_id) {
+            budget.apply(&instruction.data);
+        } else {
+            instruction_count += 1;
+        }
+    }
+    let compute_units = budget.compute_units(instruction_count);
+
+    let writable_accounts: Vec<Pubkey> = account_keys
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| message.is_maybe_writable(*index))
+        .map(|(_, key)| *key)
+        .collect();
+
+    let (fee_lamports, recent_fees) = tokio::try_join!(
+        fetch_fee_for_message(message.clone()),
+        fetch_recent_prioritization_fees(writable_accounts),
+    )?;
+    let recommended = percentile(recent_fees, RECOMMENDED_FEE_PERCENTILE);
+
+    Ok(SuccessResponse::new(TransactionFeeResponse {
+        fee_lamports,
+        compute_units,
+        micro_lamports_per_unit: budget.unit_price,
+        recommended_micro_lamports_per_unit: recommended,
+        recommended_priority_fee_lamports: priority_fee_lamports(recommended, compute_units),
+    }))
+}
@@ -0,0 +1,248 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+use solana_account_decoder::UiDataSliceConfig;
+use solana_client::{
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{
+    account::Account,
+    nonce::State as NonceState,
+    program_pack::Pack,
+    pubkey,
+    pubkey::Pubkey,
+    stake, system_program,
+};
+use spl_token::state::Account as TokenAccount;
+use super::explain::KNOWN_MINTS;
+use super::instruction::validate_pubkey;
+use super::mint_config;
+use super::response_types::{SuccessResponse, ErrorResponse};
+use super::rpc::{self, rpc_error};
+
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+// getMultipleAccounts accepts at most this many addresses per call
+const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+
+// Offsets of the authorities in stake account data, after the 4-byte state
+// tag and the 8-byte rent-exempt reserve
+const STAKE_STAKER_OFFSET: usize = 12;
+const STAKE_WITHDRAWER_OFFSET: usize = 44;
+// Offset of the authority in nonce account data, after the version and state tags
+const NONCE_AUTHORITY_OFFSET: usize = 8;
+
+#[derive(Serialize, ToSchema)]
+pub struct FootprintTokenAccount {
+    pub mint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    // The owner's associated token account for the mint
+    pub address: String,
+    pub exists: bool,
+    // Raw base units, as a string since it can exceed what JSON numbers hold exactly
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FootprintStakeAccount {
+    pub address: String,
+    pub lamports: u64,
+    pub is_staker: bool,
+    pub is_withdrawer: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FootprintAccount {
+    pub address: String,
+    pub exists: bool,
+    pub lamports: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FootprintError {
+    // Part of the footprint that could not be loaded, e.g. "stake_accounts"
+    pub section: String,
+    pub error: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FootprintResponse {
+    pub pubkey: String,
+    pub lamports: u64,
+    // Associated token accounts for the configured and well-known mints
+    pub token_accounts: Vec<FootprintTokenAccount>,
+    // Stake accounts this key can stake or withdraw from
+    pub stake_accounts: Vec<FootprintStakeAccount>,
+    // Durable nonce accounts this key is the authority of
+    pub nonce_accounts: Vec<FootprintAccount>,
+    // Metaplex metadata PDA, should the key be a mint
+    pub metadata: FootprintAccount,
+    // Sections left empty because the RPC node could not answer for them;
+    // many public nodes refuse program-wide account scans
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FootprintError>,
+}
+
+fn footprint_mints() -> Vec<Pubkey> {
+    let mut mints: Vec<Pubkey> = mint_config::configured_mints().copied().collect();
+    mints.extend(KNOWN_MINTS.iter().map(|(mint, _)| *mint));
+    mints.sort();
+    mints.dedup();
+    mints
+}
+
+fn mint_symbol(mint: &Pubkey) -> Option<String> {
+    mint_config::symbol(mint)
+        .or_else(|| KNOWN_MINTS.iter().find(|(known, _)| known == mint).map(|(_, symbol)| *symbol))
+        .map(str::to_string)
+}
+
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &TOKEN_METADATA_PROGRAM_ID,
+    ).0
+}
+
+pub(super) async fn fetch_accounts(addresses: &[Pubkey]) -> Result<Vec<Option<Account>>, ErrorResponse> {
+    let mut accounts = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(MAX_ACCOUNTS_PER_REQUEST) {
+        let chunk = chunk.to_vec();
+        accounts.extend(
+            rpc::read("getMultipleAccounts", move |client| {
+                let chunk = chunk.clone();
+                async move { client.get_multiple_accounts(&chunk).await }
+            })
+                .await
+                .map_err(rpc_error)?,
+        );
+    }
+    Ok(accounts)
+}
+
+// Addresses and balances of a program's accounts matching the filters. The
+// account data itself is left out of the response.
+pub(super) async fn scan_program(program_id: Pubkey, filters: Vec<RpcFilterType>) -> Result<Vec<(Pubkey, u64)>, ErrorResponse> {
+    let accounts = rpc::read("getProgramAccounts", move |client| {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters.clone()),
+            account_config: RpcAccountInfoConfig {
+                data_slice: Some(UiDataSliceConfig { offset: 0, length: 0 }),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        async move { client.get_program_accounts_with_config(&program_id, config).await }
+    })
+        .await
+        .map_err(rpc_error)?;
+
+    Ok(accounts.into_iter().map(|(address, account)| (address, account.lamports)).collect())
+}
+
+async fn stake_accounts(pubkey: Pubkey) -> Result<Vec<FootprintStakeAccount>, ErrorResponse> {
+    let authority_filter = |offset| vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(offset, pubkey.as_ref()))];
+    let (staked, withdrawable) = tokio::try_join!(
+        scan_program(stake::program::id(), authority_filter(STAKE_STAKER_OFFSET)),
+        scan_program(stake::program::id(), authority_filter(STAKE_WITHDRAWER_OFFSET)),
+    )?;
+
+    let mut accounts: Vec<FootprintStakeAccount> = staked
+        .into_iter()
+        .map(|(address, lamports)| FootprintStakeAccount {
+            address: address.to_string(),
+            lamports,
+            is_staker: true,
+            is_withdrawer: false,
+        })
+        .collect();
+    for (address, lamports) in withdrawable {
+        let address = address.to_string();
+        match accounts.iter_mut().find(|account| account.address == address) {
+            Some(account) => account.is_withdrawer = true,
+            None => accounts.push(FootprintStakeAccount { address, lamports, is_staker: false, is_withdrawer: true }),
+        }
+    }
+    Ok(accounts)
+}
+
+async fn nonce_accounts(pubkey: Pubkey) -> Result<Vec<FootprintAccount>, ErrorResponse> {
+    let filters = vec![
+        RpcFilterType::DataSize(NonceState::size() as u64),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(NONCE_AUTHORITY_OFFSET, pubkey.as_ref())),
+    ];
+    let accounts = scan_program(system_program::id(), filters).await?;
+
+    Ok(accounts
+        .into_iter()
+        .map(|(address, lamports)| FootprintAccount { address: address.to_string(), exists: true, lamports })
+        .collect())
+}
+
+fn section<T>(errors: &mut Vec<FootprintError>, name: &str, result: Result<Vec<T>, ErrorResponse>) -> Vec<T> {
+    result.unwrap_or_else(|err| {
+        errors.push(FootprintError { section: name.to_string(), error: err.error });
+        Vec::new()
+    })
+}
+
+// Everything the key owns or controls that can be found without an indexer:
+// its balance, associated token accounts, stake and nonce accounts it holds
+// authority over, and its metadata PDA
+pub async fn get_footprint(pubkey: String) -> Result<SuccessResponse<FootprintResponse>, ErrorResponse> {
+    // Validate inputs
+    let pubkey = validate_pubkey(&pubkey, "address")?;
+
+    let mints = footprint_mints();
+    let metadata = metadata_address(&pubkey);
+    let mut addresses = vec![pubkey, metadata];
+    addresses.extend(mints.iter().map(|mint| spl_associated_token_account::get_associated_token_address(&pubkey, mint)));
+
+    // The balances are the core of the report; the program scans may fail alone
+    let (accounts, stake_accounts, nonce_accounts) = tokio::join!(
+        fetch_accounts(&addresses),
+        stake_accounts(pubkey),
+        nonce_accounts(pubkey),
+    );
+    let accounts = accounts?;
+
+    let lamports_of = |index: usize| accounts[index].as_ref().map_or(0, |account| account.lamports);
+    let token_accounts = mints
+        .iter()
+        .zip(&addresses[2..])
+        .zip(&accounts[2..])
+        .map(|((mint, address), account)| FootprintTokenAccount {
+            mint: mint.to_string(),
+            symbol: mint_symbol(mint),
+            address: address.to_string(),
+            exists: account.is_some(),
+            amount: account
+                .as_ref()
+                .and_then(|account| account.data.get(..TokenAccount::LEN))
+                .and_then(|data| TokenAccount::unpack_from_slice(data).ok())
+                .map(|state| state.amount.to_string()),
+        })
+        .collect();
+
+    let mut errors = Vec::new();
+    let stake_accounts = section(&mut errors, "stake_accounts", stake_accounts);
+    let nonce_accounts = section(&mut errors, "nonce_accounts", nonce_accounts);
+
+    let response = FootprintResponse {
+        pubkey: pubkey.to_string(),
+        lamports: lamports_of(0),
+        token_accounts,
+        stake_accounts,
+        nonce_accounts,
+        metadata: FootprintAccount {
+            address: metadata.to_string(),
+            exists: accounts[1].is_some(),
+            lamports: lamports_of(1),
+        },
+        errors,
+    };
+
+    Ok(SuccessResponse::new(response))
+}
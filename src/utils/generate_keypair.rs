@@ -1,19 +1,229 @@
-use super::response_types::{ErrorResponse, SuccessResponse};
+use super::audit::{self, AuditOperation};
+use super::response_types::{ApiErrorCode, ErrorResponse, SuccessResponse};
+use bip39::{Language, Mnemonic, MnemonicType, Seed};
 use bs58;
 use serde::{Deserialize, Serialize};
-use solana_sdk::{signature::Keypair, signer::Signer};
+use utoipa::{IntoParams, ToSchema};
+use rayon::prelude::*;
+use solana_sdk::{
+    derivation_path::DerivationPath,
+    signature::{keypair_from_seed, keypair_from_seed_and_derivation_path, Keypair},
+    signer::Signer,
+};
+use tokio::sync::oneshot;
 
-#[derive(Serialize, Deserialize)]
+const MAX_KEYPAIR_COUNT: usize = 1000;
+// Deriving from a recovery phrase runs 2048 rounds of PBKDF2 per keypair
+const MAX_MNEMONIC_KEYPAIR_COUNT: usize = 100;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct KeypairQuery {
+    // 12 or 24 to also return a recovery phrase for the keypair
+    #[serde(rename = "mnemonicWords")]
+    pub mnemonic_words: Option<usize>,
+    // Generate this many keypairs at once, returned as an array; up to 1000,
+    // or 100 with recovery phrases
+    pub count: Option<usize>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct KeypairFromMnemonicRequest {
+    pub mnemonic: String,
+    // Optional BIP39 passphrase, sometimes called the 25th word
+    pub passphrase: Option<String>,
+    // Wallets derive further accounts at m/44'/501'/n'/0'
+    #[serde(rename = "accountIndex")]
+    pub account_index: Option<u32>,
+}
+
+// base58 is what `/keypair` returns, json is the Solana CLI keypair file
+// (an array of 64 bytes) and hex is the same 64 bytes hex encoded
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeypairFormat {
+    Base58,
+    Json,
+    Hex,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ConvertKeypairRequest {
+    pub secret: String,
+    // Detected from the secret when omitted
+    pub from: Option<KeypairFormat>,
+    pub to: KeypairFormat,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ConvertKeypairResponse {
+    pub pubkey: String,
+    pub secret: String,
+    pub format: KeypairFormat,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct KeypairResponse {
     pub pubkey: String,
     pub secret: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mnemonic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub derivation_path: Option<String>,
+}
+
+fn keypair_response(keypair: &Keypair) -> KeypairResponse {
+    KeypairResponse {
+        pubkey: bs58::encode(keypair.pubkey().to_bytes()).into_string(),
+        secret: bs58::encode(keypair.to_bytes()).into_string(),
+        mnemonic: None,
+        derivation_path: None,
+    }
+}
+
+// Derive the keypair at the standard Solana path, the same one Phantom,
+// Solflare and `solana-keygen recover 'prompt://?key=n/0'` use
+fn derive_keypair(mnemonic: &Mnemonic, passphrase: &str, account_index: u32) -> Result<KeypairResponse, ErrorResponse> {
+    let seed = Seed::new(mnemonic, passphrase);
+    let derivation_path = DerivationPath::new_bip44(Some(account_index), Some(0));
+    let keypair = keypair_from_seed_and_derivation_path(seed.as_bytes(), Some(derivation_path))
+        .map_err(|err| ErrorResponse::new(format!("Failed to derive keypair: {}", err)))?;
+
+    Ok(KeypairResponse {
+        derivation_path: Some(format!("m/44'/501'/{}'/0'", account_index)),
+        ..keypair_response(&keypair)
+    })
+}
+
+pub fn generate_keypair(query: KeypairQuery) -> Result<SuccessResponse<KeypairResponse>, ErrorResponse> {
+    let result = new_keypair(query.mnemonic_words).map(SuccessResponse::new);
+    audit::recorded(AuditOperation::GenerateKeypair, result, |keypair| vec![keypair.pubkey.clone()])
 }
 
-pub fn generate_keypair() -> Result<SuccessResponse<KeypairResponse>, ErrorResponse> {
-    let keypair = Keypair::new();
+// Large batches, especially with recovery phrases, take long enough to hold
+// up a tokio worker, so they are generated on the rayon pool
+pub async fn generate_keypairs(query: KeypairQuery) -> Result<SuccessResponse<Vec<KeypairResponse>>, ErrorResponse> {
+    // Validate inputs
+    let count = query.count.unwrap_or(1);
+    let max_count = if query.mnemonic_words.is_some() { MAX_MNEMONIC_KEYPAIR_COUNT } else { MAX_KEYPAIR_COUNT };
+    if count == 0 || count > max_count {
+        return Err(ErrorResponse::new(format!("count must be between 1 and {}", max_count)));
+    }
+
+    let (sender, receiver) = oneshot::channel();
+    rayon::spawn(move || {
+        let keypairs = (0..count)
+            .into_par_iter()
+            .map(|_| new_keypair(query.mnemonic_words))
+            .collect::<Result<Vec<_>, ErrorResponse>>();
+        let _ = sender.send(keypairs);
+    });
+    let result = receiver
+        .await
+        .map_err(|_| ErrorResponse::new("Keypair generation was interrupted"))
+        .and_then(|keypairs| keypairs)
+        .map(SuccessResponse::new);
+    audit::recorded(AuditOperation::GenerateKeypair, result, |keypairs| {
+        keypairs.iter().map(|keypair| keypair.pubkey.clone()).collect()
+    })
+}
+
+fn new_keypair(mnemonic_words: Option<usize>) -> Result<KeypairResponse, ErrorResponse> {
+    let Some(words) = mnemonic_words else {
+        return Ok(keypair_response(&Keypair::new()));
+    };
+
+    let mnemonic_type = match words {
+        12 => MnemonicType::Words12,
+        24 => MnemonicType::Words24,
+        _ => return Err(ErrorResponse::new("mnemonicWords must be 12 or 24")),
+    };
+    let mnemonic = Mnemonic::new(mnemonic_type, Language::English);
+    let response = derive_keypair(&mnemonic, "", 0)?;
+
+    Ok(KeypairResponse {
+        mnemonic: Some(mnemonic.into_phrase()),
+        ..response
+    })
+}
+
+pub fn keypair_from_mnemonic(request: KeypairFromMnemonicRequest) -> Result<SuccessResponse<KeypairResponse>, ErrorResponse> {
+    let result = restore_keypair(request);
+    audit::recorded(AuditOperation::KeypairFromMnemonic, result, |keypair| vec![keypair.pubkey.clone()])
+}
+
+fn restore_keypair(request: KeypairFromMnemonicRequest) -> Result<SuccessResponse<KeypairResponse>, ErrorResponse> {
+    // Validate inputs
+    let phrase = request.mnemonic.split_whitespace().collect::<Vec<_>>().join(" ");
+    if phrase.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
+    }
+    let mnemonic = Mnemonic::from_phrase(&phrase, Language::English)
+        .map_err(|err| ErrorResponse::new(format!("Invalid mnemonic: {}", err)))?;
+
+    let response = derive_keypair(
+        &mnemonic,
+        request.passphrase.as_deref().unwrap_or(""),
+        request.account_index.unwrap_or(0),
+    )?;
+
+    Ok(SuccessResponse::new(response))
+}
+
+fn detect_format(secret: &str) -> KeypairFormat {
+    if secret.starts_with('[') {
+        KeypairFormat::Json
+    } else if secret.len() == 128 && secret.chars().all(|c| c.is_ascii_hexdigit()) {
+        KeypairFormat::Hex
+    } else {
+        KeypairFormat::Base58
+    }
+}
+
+fn decode_secret(secret: &str, format: KeypairFormat) -> Result<Vec<u8>, ErrorResponse> {
+    match format {
+        KeypairFormat::Base58 => bs58::decode(secret)
+            .into_vec()
+            .map_err(|_| ErrorResponse::new("Invalid base58 encoding for secret key")),
+        KeypairFormat::Json => serde_json::from_str::<Vec<u8>>(secret)
+            .map_err(|_| ErrorResponse::new("Invalid JSON byte array for secret key")),
+        KeypairFormat::Hex => hex::decode(secret)
+            .map_err(|_| ErrorResponse::new("Invalid hex encoding for secret key")),
+    }
+}
+
+fn encode_secret(bytes: &[u8], format: KeypairFormat) -> String {
+    match format {
+        KeypairFormat::Base58 => bs58::encode(bytes).into_string(),
+        KeypairFormat::Json => serde_json::to_string(bytes).unwrap(),
+        KeypairFormat::Hex => hex::encode(bytes),
+    }
+}
+
+pub fn convert_keypair(request: ConvertKeypairRequest) -> Result<SuccessResponse<ConvertKeypairResponse>, ErrorResponse> {
+    // Validate inputs
+    let secret = request.secret.trim();
+    if secret.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
+    }
+
+    let format = request.from.unwrap_or_else(|| detect_format(secret));
+    let bytes = decode_secret(secret, format)?;
+    if bytes.len() != 64 {
+        return Err(ErrorResponse::new("Invalid secret key format"));
+    }
 
-    let pubkey = bs58::encode(keypair.pubkey().to_bytes()).into_string();
-    let secret = bs58::encode(keypair.to_bytes()).into_string();
+    // The last 32 bytes are the public key; make sure they belong to the
+    // secret rather than trusting them
+    let keypair = keypair_from_seed(&bytes[..32])
+        .map_err(|_| ErrorResponse::new("Invalid secret key format"))?;
+    if keypair.pubkey().to_bytes()[..] != bytes[32..] {
+        return Err(ErrorResponse::new("Public key does not match the secret key"));
+    }
 
-    Ok(SuccessResponse::new(KeypairResponse { pubkey, secret }))
+    Ok(SuccessResponse::new(ConvertKeypairResponse {
+        pubkey: keypair.pubkey().to_string(),
+        secret: encode_secret(&keypair.to_bytes(), request.to),
+        format: request.to,
+    }))
 }
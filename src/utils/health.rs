@@ -0,0 +1,110 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+use std::time::Instant;
+use crate::config::{self, AppConfig};
+use super::response_types::SuccessResponse;
+use super::preload;
+use super::rpc;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RpcMode {
+    Live,
+    // RPC calls are answered by the in-memory mock; nothing reaches a cluster
+    Mock,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct HealthResponse {
+    pub status: &'static str,
+    pub rpc_mode: RpcMode,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CheckStatus {
+    pub ok: bool,
+    pub latency_ms: Option<u128>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BlockhashCacheStatus {
+    pub ok: bool,
+    pub age_ms: Option<u128>,
+    pub blockhash: Option<String>,
+    pub last_valid_block_height: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    pub rpc: Option<CheckStatus>,
+    pub blockhash_cache: BlockhashCacheStatus,
+    // Whether startup preloading has finished, when it is enabled
+    pub preloaded: Option<bool>,
+    // State of each configured RPC provider, in order of preference
+    pub rpc_providers: Vec<rpc::ProviderStatus>,
+    // Hit and miss counts since startup; informational, never affects readiness
+    pub caches: Vec<rpc::CacheStats>,
+}
+
+pub fn health() -> SuccessResponse<HealthResponse> {
+    let rpc_mode = if config::get().mock_rpc { RpcMode::Mock } else { RpcMode::Live };
+    SuccessResponse::new(HealthResponse { status: "ok", rpc_mode })
+}
+
+async fn check_rpc() -> CheckStatus {
+    let started = Instant::now();
+    match rpc::read("getHealth", |client| client.get_health()).await {
+        Ok(()) => CheckStatus {
+            ok: true,
+            latency_ms: Some(started.elapsed().as_millis()),
+            error: None,
+        },
+        Err(err) => CheckStatus {
+            ok: false,
+            latency_ms: Some(started.elapsed().as_millis()),
+            error: Some(rpc::rpc_error(err).error),
+        },
+    }
+}
+
+fn check_blockhash_cache() -> BlockhashCacheStatus {
+    match rpc::cached_blockhash() {
+        Some(cached) => BlockhashCacheStatus {
+            ok: cached.is_fresh(),
+            age_ms: Some(cached.fetched_at.elapsed().as_millis()),
+            blockhash: Some(cached.blockhash.to_string()),
+            last_valid_block_height: Some(cached.last_valid_block_height),
+        },
+        None => BlockhashCacheStatus {
+            ok: false,
+            age_ms: None,
+            blockhash: None,
+            last_valid_block_height: None,
+        },
+    }
+}
+
+// The RPC round trip can be turned off with READINESS_RPC_CHECK=false for
+// deployments where the provider rate-limits health probes
+pub async fn ready(config: &AppConfig) -> SuccessResponse<ReadinessResponse> {
+    let rpc = if config.readiness_rpc_check { Some(check_rpc().await) } else { None };
+    let blockhash_cache = check_blockhash_cache();
+    let preloaded = config.preload_on_startup.then(preload::is_preloaded);
+
+    let ready = rpc.as_ref().map(|check| check.ok).unwrap_or(true)
+        && blockhash_cache.ok
+        && preloaded.unwrap_or(true);
+
+    let response = ReadinessResponse {
+        ready,
+        rpc,
+        blockhash_cache,
+        preloaded,
+        rpc_providers: rpc::provider_statuses(),
+        caches: rpc::cache_stats(),
+    };
+
+    SuccessResponse::new(response)
+}
@@ -0,0 +1,188 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+use solana_sdk::{
+    instruction::Instruction,
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+    stake::{
+        self,
+        instruction::StakeInstruction,
+        state::StakeAuthorize,
+    },
+    system_instruction::SystemInstruction,
+    system_program,
+    vote::{
+        self,
+        instruction::VoteInstruction,
+        state::VoteAuthorize,
+    },
+};
+use spl_token::instruction::{AuthorityType, TokenInstruction};
+use std::collections::{HashMap, HashSet};
+use super::i18n::{self, Locale};
+
+#[derive(Serialize, ToSchema, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningCode {
+    // An authority is handed to a key that does not sign the transaction
+    UnknownAuthority,
+    // A token delegate is approved for u64::MAX
+    UnlimitedApproval,
+    // Funds go to an account someone else created earlier in the transaction
+    FreshRecipient,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TransactionWarning {
+    // Index into the response's instructions
+    pub instruction_index: usize,
+    pub code: WarningCode,
+    pub message: String,
+}
+
+fn warning(instruction_index: usize, code: WarningCode, message: String) -> TransactionWarning {
+    TransactionWarning { instruction_index, code, message }
+}
+
+fn account_at(instruction: &Instruction, index: usize) -> Option<Pubkey> {
+    instruction.accounts.get(index).map(|account| account.pubkey)
+}
+
+fn unknown_authority(
+    index: usize,
+    signers: &HashSet<Pubkey>,
+    authority: &str,
+    target: Option<Pubkey>,
+    new_authority: Pubkey,
+) -> Option<TransactionWarning> {
+    let target = target?;
+    (!signers.contains(&new_authority)).then(|| warning(
+        index,
+        WarningCode::UnknownAuthority,
+        format!(
+            "Instruction {} hands the {} authority of {} to {}, which does not sign this transaction",
+            index, authority, target, new_authority
+        ),
+    ))
+}
+
+fn unlimited_approval(index: usize, instruction: &Instruction, delegate_index: usize) -> Option<TransactionWarning> {
+    let source = account_at(instruction, 0)?;
+    let delegate = account_at(instruction, delegate_index)?;
+    Some(warning(
+        index,
+        WarningCode::UnlimitedApproval,
+        format!("Instruction {} lets {} spend an unlimited amount from {}", index, delegate, source),
+    ))
+}
+
+// The sender and recipient of a SOL or token transfer
+fn transfer_parties(instruction: &Instruction) -> Option<(Pubkey, Pubkey)> {
+    if instruction.program_id == system_program::id() {
+        return match limited_deserialize::<SystemInstruction>(&instruction.data) {
+            Ok(SystemInstruction::Transfer { .. }) => Some((account_at(instruction, 0)?, account_at(instruction, 1)?)),
+            _ => None,
+        };
+    }
+    if instruction.program_id == spl_token::id() {
+        // The authority is the sender, since the source is a token account
+        return match TokenInstruction::unpack(&instruction.data) {
+            Ok(TokenInstruction::Transfer { .. }) => Some((account_at(instruction, 2)?, account_at(instruction, 1)?)),
+            Ok(TokenInstruction::TransferChecked { .. }) => Some((account_at(instruction, 3)?, account_at(instruction, 2)?)),
+            _ => None,
+        };
+    }
+    None
+}
+
+// Flags patterns common in drainer transactions. These are hints for the
+// person approving the transaction, not reasons to refuse building it:
+// - an authority handed to a key that does not sign the transaction
+// - a token delegate approved for the maximum possible amount
+// - funds sent to an account that someone other than the sender created
+//   earlier in the same transaction. Associated token accounts are not
+//   counted, since creating one for the recipient is how new holders are paid.
+pub fn audit(instructions: &[Instruction], fee_payer: Option<&Pubkey>) -> Vec<TransactionWarning> {
+    let signers: HashSet<Pubkey> = instructions
+        .iter()
+        .flat_map(|instruction| instruction.accounts.iter())
+        .filter(|account| account.is_signer)
+        .map(|account| account.pubkey)
+        .chain(fee_payer.copied())
+        .collect();
+    // Accounts created by the system program, and who paid for them
+    let mut created: HashMap<Pubkey, Pubkey> = HashMap::new();
+    let mut warnings = Vec::new();
+
+    for (index, instruction) in instructions.iter().enumerate() {
+        if let Some((sender, recipient)) = transfer_parties(instruction)
+            && created.get(&recipient).is_some_and(|funder| *funder != sender)
+        {
+            warnings.push(warning(
+                index,
+                WarningCode::FreshRecipient,
+                format!(
+                    "Instruction {} sends funds to {}, which another account created earlier in this transaction",
+                    index, recipient
+                ),
+            ));
+        }
+
+        let program_id = instruction.program_id;
+        if program_id == system_program::id() {
+            if let Ok(SystemInstruction::CreateAccount { .. } | SystemInstruction::CreateAccountWithSeed { .. }) =
+                limited_deserialize::<SystemInstruction>(&instruction.data)
+                && let (Some(funder), Some(new_account)) = (account_at(instruction, 0), account_at(instruction, 1))
+            {
+                created.insert(new_account, funder);
+            }
+        } else if program_id == spl_token::id() {
+            match TokenInstruction::unpack(&instruction.data) {
+                Ok(TokenInstruction::SetAuthority { authority_type, new_authority }) => {
+                    let authority = match authority_type {
+                        AuthorityType::MintTokens => "mint",
+                        AuthorityType::FreezeAccount => "freeze",
+                        AuthorityType::AccountOwner => "owner",
+                        AuthorityType::CloseAccount => "close",
+                    };
+                    if let Some(new_authority) = Option::<Pubkey>::from(new_authority) {
+                        warnings.extend(unknown_authority(index, &signers, authority, account_at(instruction, 0), new_authority));
+                    }
+                }
+                // Approve lists the delegate second, ApproveChecked third after the mint
+                Ok(TokenInstruction::Approve { amount: u64::MAX }) => {
+                    warnings.extend(unlimited_approval(index, instruction, 1));
+                }
+                Ok(TokenInstruction::ApproveChecked { amount: u64::MAX, .. }) => {
+                    warnings.extend(unlimited_approval(index, instruction, 2));
+                }
+                _ => {}
+            }
+        } else if program_id == stake::program::id() {
+            if let Ok(StakeInstruction::Authorize(new_authority, kind)) = limited_deserialize(&instruction.data) {
+                let authority = match kind {
+                    StakeAuthorize::Staker => "staker",
+                    StakeAuthorize::Withdrawer => "withdrawer",
+                };
+                warnings.extend(unknown_authority(index, &signers, authority, account_at(instruction, 0), new_authority));
+            }
+        } else if program_id == vote::program::id()
+            && let Ok(VoteInstruction::Authorize(new_authority, kind)) = limited_deserialize(&instruction.data)
+        {
+            let authority = match kind {
+                VoteAuthorize::Voter => "voter",
+                VoteAuthorize::Withdrawer => "withdrawer",
+            };
+            warnings.extend(unknown_authority(index, &signers, authority, account_at(instruction, 0), new_authority));
+        }
+    }
+
+    warnings
+}
+
+// Warnings are built in English, like errors, and translated on the way out
+pub fn localize(warnings: &mut [TransactionWarning], locale: Locale) {
+    for warning in warnings {
+        warning.message = i18n::translate(locale, &warning.message);
+    }
+}
@@ -0,0 +1,433 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header::ACCEPT_LANGUAGE, request::Parts, HeaderMap},
+};
+use std::convert::Infallible;
+
+// Message catalogs are keyed by the English text, with `{}` marking values
+// substituted at runtime. Translations must keep the placeholders in the
+// same order. To add a language, add a Locale variant and a catalog.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+}
+
+const ES: &[(&str, &str)] = &[
+    // Validation and request errors
+    ("{} is not allowed to close token account {}", "{} no tiene permiso para cerrar la cuenta de token {}"),
+    ("{} is not a required signer of this transaction", "{} no es un firmante requerido de esta transacción"),
+    ("Account {} is not a usable nonce account: {}", "La cuenta {} no es una cuenta nonce utilizable: {}"),
+    ("Account {} is not an SPL token mint", "La cuenta {} no es un mint de SPL Token"),
+    ("Account {} is not an SPL token account", "La cuenta {} no es una cuenta de token SPL"),
+    ("Account {} is not a Token-2022 mint", "La cuenta {} no es un mint de Token-2022"),
+    ("Account {} is not an address lookup table", "La cuenta {} no es una tabla de búsqueda de direcciones"),
+    ("Address lookup tables require a v0 transaction", "Las tablas de búsqueda de direcciones requieren una transacción v0"),
+    ("Accounts can hold at most {} bytes of data", "Las cuentas pueden contener como máximo {} bytes de datos"),
+    ("Amount exceeds maximum reasonable transfer", "El monto excede la transferencia máxima razonable"),
+    ("Amount must be greater than 0 lamports", "El monto debe ser mayor que 0 lamports"),
+    ("Amount {} exceeds the total supply of mint {} ({})", "El monto {} supera el suministro total del mint {} ({})"),
+    ("Amount must be greater than 0", "El monto debe ser mayor que 0"),
+    ("An account with {} bytes of data needs at least {} lamports to be rent exempt", "Una cuenta con {} bytes de datos necesita al menos {} lamports para estar exenta de renta"),
+    ("At least one message is required", "Se requiere al menos un mensaje"),
+    ("At least one source token account is required", "Se requiere al menos una cuenta de token de origen"),
+    ("At least one operation is required", "Se requiere al menos una operación"),
+    ("At most {} messages can be verified at once", "Se pueden verificar como máximo {} mensajes a la vez"),
+    ("At most {} operations are allowed per batch", "Se permiten como máximo {} operaciones por lote"),
+    ("Commission must be between 0 and 100", "La comisión debe estar entre 0 y 100"),
+    ("Decimals are required for mint {}", "Los decimales son obligatorios para el mint {}"),
+    ("Decimals must be between 0 and 9", "Los decimales deben estar entre 0 y 9"),
+    ("Exactly one of account or mint is required", "Se requiere exactamente uno de account o mint"),
+    ("Exactly one of transaction or message is required", "Se requiere exactamente uno de transaction o message"),
+    ("Failed to compile v0 message: {}", "No se pudo compilar el mensaje v0: {}"),
+    ("Failed to create mint instruction: {}", "No se pudo crear la instrucción de acuñación: {}"),
+    ("Failed to create token instruction: {}", "No se pudo crear la instrucción del token: {}"),
+    ("Failed to derive keypair: {}", "No se pudo derivar el par de claves: {}"),
+    ("Failed to create transfer instruction: {}", "No se pudo crear la instrucción de transferencia: {}"),
+    ("Fee cannot exceed the transfer amount", "La comisión no puede superar el monto de la transferencia"),
+    ("Failed to serialize transaction", "No se pudo serializar la transacción"),
+    ("Funding address and nonce account cannot be the same", "La dirección de financiación y la cuenta nonce no pueden ser la misma"),
+    ("Funding address and stake account cannot be the same", "La dirección de financiación y la cuenta de stake no pueden ser la misma"),
+    ("Funding address and vote account cannot be the same", "La dirección de financiación y la cuenta de voto no pueden ser la misma"),
+    ("A prefix or suffix is required", "Se requiere un prefijo o un sufijo"),
+    ("Instruction references a missing program account", "La instrucción hace referencia a una cuenta de programa inexistente"),
+    ("Invalid base58 encoding for public key", "Codificación base58 inválida para la clave pública"),
+    ("Invalid base58 encoding for secret key", "Codificación base58 inválida para la clave secreta"),
+    ("Invalid base58 encoding for {}", "Codificación base58 inválida para {}"),
+    ("Invalid base64 encoding for signature", "Codificación base64 inválida para la firma"),
+    ("Invalid base58 encoding for signature", "Codificación base58 inválida para la firma"),
+    ("Signature is neither base64 nor base58 encoded", "La firma no está codificada en base64 ni en base58"),
+    ("Invalid base64 encoding for instruction data", "Codificación base64 inválida para los datos de la instrucción"),
+    ("No IDL is registered for program {}", "No hay ningún IDL registrado para el programa {}"),
+    ("The IDL has no instruction named {}", "El IDL no tiene ninguna instrucción llamada {}"),
+    ("Account {} is required", "La cuenta {} es obligatoria"),
+    ("programId is required when the IDL has no address", "programId es obligatorio cuando el IDL no tiene dirección"),
+    ("At most {} IDLs can be registered", "Se pueden registrar como máximo {} IDL"),
+    ("Addresses of lookup table {} are required", "Las direcciones de la tabla de búsqueda {} son obligatorias"),
+    ("Seeds can be at most 32 bytes", "Las semillas pueden tener como máximo 32 bytes"),
+    ("The owner cannot be a program derived address marker", "El propietario no puede ser el marcador de direcciones derivadas de programas"),
+    ("Invalid seed", "Semilla inválida"),
+    ("Invalid schema: {}", "Esquema inválido: {}"),
+    ("{} is nested too deeply", "{} está anidado demasiado profundamente"),
+    ("Type {} is not defined", "El tipo {} no está definido"),
+    ("Data ends before {}", "Los datos terminan antes de {}"),
+    ("Data has {} bytes left over", "Sobran {} bytes en los datos"),
+    ("Data must be at most {} bytes", "Los datos deben tener como máximo {} bytes"),
+    ("Data decodes to more than {} values", "Los datos se decodifican en más de {} valores"),
+    ("Invalid base64 encoding for data", "Codificación base64 inválida para los datos"),
+    ("Invalid base58 encoding for data", "Codificación base58 inválida para los datos"),
+    ("Invalid hex encoding for data", "Codificación hex inválida para los datos"),
+    ("Invalid base58 encoding for instruction data", "Codificación base58 inválida para los datos de la instrucción"),
+    ("Invalid hex encoding for instruction data", "Codificación hex inválida para los datos de la instrucción"),
+    ("Instruction data must be at most {} bytes", "Los datos de la instrucción deben tener como máximo {} bytes"),
+    ("At most {} accounts can be passed to an instruction", "Se pueden pasar como máximo {} cuentas a una instrucción"),
+    ("Invalid base64 encoding for message", "Codificación base64 inválida para el mensaje"),
+    ("Invalid base64 encoding for transaction", "Codificación base64 inválida para la transacción"),
+    ("Invalid parameters: {}", "Parámetros inválidos: {}"),
+    ("Invalid message format", "Formato de mensaje inválido"),
+    ("Invalid hex encoding for message", "Codificación hexadecimal inválida para el mensaje"),
+    ("Invalid hex encoding for secret key", "Codificación hexadecimal inválida para la clave secreta"),
+    ("Invalid JSON byte array for secret key", "Arreglo de bytes JSON inválido para la clave secreta"),
+    ("Invalid mnemonic: {}", "Frase mnemotécnica inválida: {}"),
+    ("Invalid public key format for {}", "Formato de clave pública inválido para {}"),
+    ("Invalid public key format", "Formato de clave pública inválido"),
+    ("Invalid recent blockhash", "Blockhash reciente inválido"),
+    ("Invalid secret key format", "Formato de clave secreta inválido"),
+    ("Invalid signature format", "Formato de firma inválido"),
+    ("Invalid transaction format", "Formato de transacción inválido"),
+    ("Give fee, or transferFeeBasisPoints and maximumFee to calculate it", "Indica fee, o transferFeeBasisPoints y maximumFee para calcularla"),
+    ("Memo cannot be empty", "El memo no puede estar vacío"),
+    ("Memo must be at most {} bytes", "El memo debe tener como máximo {} bytes"),
+    ("Message cannot be empty", "El mensaje no puede estar vacío"),
+    ("Message is too long for a Secp256k1 program instruction", "El mensaje es demasiado largo para una instrucción del programa Secp256k1"),
+    ("Invalid hex encoding for signature", "Codificación hexadecimal inválida para la firma"),
+    ("Invalid hex encoding for Ethereum address", "Codificación hexadecimal inválida para la dirección de Ethereum"),
+    ("Ethereum addresses must be 20 bytes", "Las direcciones de Ethereum deben tener 20 bytes"),
+    ("recoveryId is required with a 64-byte signature", "recoveryId es obligatorio con una firma de 64 bytes"),
+    ("recoveryId must be 0 or 1, or 27 or 28", "recoveryId debe ser 0 o 1, o 27 o 28"),
+    ("Signature does not match the message and Ethereum address", "La firma no coincide con el mensaje y la dirección de Ethereum"),
+    ("Message is too long for an Ed25519 program instruction", "El mensaje es demasiado largo para una instrucción del programa Ed25519"),
+    ("Mint {} has no metadata", "El mint {} no tiene metadatos"),
+    ("Invalid .sol domain {}", "Dominio .sol no válido: {}"),
+    ("Domain {} is not registered", "El dominio {} no está registrado"),
+    ("payer is required with extensions or initialSupply", "payer es obligatorio con extensiones o initialSupply"),
+    ("Account {} is not a stake pool", "La cuenta {} no es un stake pool"),
+    ("Validator list {} was not found", "No se encontró la lista de validadores {}"),
+    ("Stake account {} is not delegated", "La cuenta de stake {} no está delegada"),
+    ("Validator {} is not in the stake pool", "El validador {} no está en el stake pool"),
+    ("tokenMetadata needs a name and symbol", "tokenMetadata necesita un nombre y un símbolo"),
+    ("tokenMetadata is stored in the mint, so metadataPointer must point at the mint", "tokenMetadata se guarda en el mint, así que metadataPointer debe apuntar al mint"),
+    ("{} is neither a .sol domain nor an address", "{} no es ni un dominio .sol ni una dirección"),
+    ("Metadata account {} could not be read", "No se pudo leer la cuenta de metadatos {}"),
+    ("Mint {} has no transfer fee", "El mint {} no tiene comisión de transferencia"),
+    ("Mint {} must use {} decimals", "El mint {} debe usar {} decimales"),
+    ("Missing required fields", "Faltan campos obligatorios"),
+    ("Nonce account and destination addresses cannot be the same", "La cuenta nonce y la dirección de destino no pueden ser la misma"),
+    ("Nonce account {} is controlled by {}, not the given nonce authority", "La cuenta nonce {} está controlada por {}, no por la autoridad indicada"),
+    ("Nonce accounts need at least {} lamports to be rent exempt", "Las cuentas nonce necesitan al menos {} lamports para estar exentas de renta"),
+    ("No completed epochs on this cluster yet", "Todavía no hay épocas completadas en este clúster"),
+    ("No pending sign-in challenge for this public key", "No hay un desafío de inicio de sesión pendiente para esta clave pública"),
+    ("Off-chain messages must be UTF-8 text of at most {} bytes", "Los mensajes fuera de la cadena deben ser texto UTF-8 de como máximo {} bytes"),
+    ("Only one priorityFee may be set per batch", "Solo se puede establecer un priorityFee por lote"),
+    ("Operation {} ({}): {}", "Operación {} ({}): {}"),
+    ("Owner and delegate addresses cannot be the same", "Las direcciones del propietario y del delegado no pueden ser la misma"),
+    ("Owner and destination addresses cannot be the same", "Las direcciones del propietario y del destino no pueden ser la misma"),
+    ("Payer and new account cannot be the same", "El pagador y la nueva cuenta no pueden ser la misma"),
+    ("Program {} is not allowed for this tenant", "El programa {} no está permitido para este inquilino"),
+    ("Public key does not match the secret key", "La clave pública no coincide con la clave secreta"),
+    ("RPC request failed: {}", "La solicitud RPC falló: {}"),
+    ("Rate limit exceeded, try again later", "Límite de solicitudes excedido, inténtalo más tarde"),
+    ("Sender and recipient addresses cannot be the same", "Las direcciones del remitente y del destinatario no pueden ser la misma"),
+    ("Sign-in challenge has expired", "El desafío de inicio de sesión ha expirado"),
+    ("Signature does not match the message and public key", "La firma no coincide con el mensaje y la clave pública"),
+    ("Signature does not match the sign-in challenge", "La firma no coincide con el desafío de inicio de sesión"),
+    ("Request timed out", "La solicitud excedió el tiempo de espera"),
+    ("Request body is too large", "El cuerpo de la solicitud es demasiado grande"),
+    ("Transaction is missing signatures from {}", "A la transacción le faltan firmas de {}"),
+    ("Transaction has an invalid signature", "La transacción tiene una firma no válida"),
+    ("Transaction sponsorship is not enabled on this server", "El patrocinio de transacciones no está habilitado en este servidor"),
+    ("The fee payer must be the sponsor once the transaction is signed", "El pagador de comisiones debe ser el patrocinador una vez firmada la transacción"),
+    ("Sponsored transactions cannot use address lookup tables", "Las transacciones patrocinadas no pueden usar tablas de búsqueda de direcciones"),
+    ("Sponsored transactions must be at most {} bytes", "Las transacciones patrocinadas deben tener como máximo {} bytes"),
+    ("Sponsored transactions can have at most {} signatures", "Las transacciones patrocinadas pueden tener como máximo {} firmas"),
+    ("The sponsor can only be the fee payer", "El patrocinador solo puede ser el pagador de comisiones"),
+    ("{} instructions of program {} are not sponsored", "Las instrucciones {} del programa {} no están patrocinadas"),
+    ("Sponsored priority fees are limited to {} lamports", "Las comisiones de prioridad patrocinadas están limitadas a {} lamports"),
+    ("Give either secret or keyId, not both", "Indica secret o keyId, no ambos"),
+    ("The key vault is not enabled on this server", "El almacén de claves no está habilitado en este servidor"),
+    ("Unknown vault key {}", "Clave del almacén desconocida: {}"),
+    ("Failed to write the key vault: {}", "No se pudo escribir el almacén de claves: {}"),
+    ("Unknown API key {}", "Clave de API desconocida: {}"),
+    ("API key {} is no longer valid", "La clave de API {} ya no es válida"),
+    ("API key {} was already rotated to {}", "La clave de API {} ya se rotó a {}"),
+    ("Failed to write the API key store: {}", "No se pudo escribir el almacén de claves de API: {}"),
+    ("Vault key {} is paused", "La clave del almacén {} está en pausa"),
+    ("Vault key {} is revoked", "La clave del almacén {} está revocada"),
+    ("Vault key {} is revoked and cannot change state", "La clave del almacén {} está revocada y no puede cambiar de estado"),
+    ("signerBackend needs a keyId", "signerBackend necesita un keyId"),
+    ("Remote signer {} failed: {}", "El firmante remoto {} falló: {}"),
+    ("Invalid key id for {}: {}", "Id de clave inválido para {}: {}"),
+    ("Key {} is not allowed for {}", "La clave {} no está permitida para {}"),
+    ("Job queue is full, try again later", "La cola de trabajos está llena, inténtalo más tarde"),
+    ("Unknown or expired job", "Trabajo desconocido o expirado"),
+    ("Unknown or expired pending transaction", "Transacción pendiente desconocida o expirada"),
+    ("Too many pending transactions, try again later", "Hay demasiadas transacciones pendientes, inténtalo más tarde"),
+    ("Give signatures or a signed transaction", "Indica firmas o una transacción firmada"),
+    ("The signature from {} is not over this transaction", "La firma de {} no corresponde a esta transacción"),
+    ("The signed transaction's message differs from the pending transaction's", "El mensaje de la transacción firmada difiere del de la transacción pendiente"),
+    ("refreshBlockhash needs secretKeys for {}", "refreshBlockhash necesita secretKeys para {}"),
+    ("cluster must be mainnet-beta, devnet, testnet, localnet or an allowed RPC URL", "cluster debe ser mainnet-beta, devnet, testnet, localnet o una URL RPC permitida"),
+    ("tokenProgram must be the SPL Token or Token-2022 program", "tokenProgram debe ser el programa SPL Token o Token-2022"),
+    ("space is required", "space es obligatorio"),
+    ("recentSlot is required", "recentSlot es obligatorio"),
+    ("At least one address is required", "Se requiere al menos una dirección"),
+    ("Failed to read the audit log: {}", "No se pudo leer el registro de auditoría: {}"),
+    ("A valid X-API-Key header is required", "Se requiere un encabezado X-API-Key válido"),
+    ("This API key does not have the {} scope", "Esta clave de API no tiene el alcance {}"),
+    ("At most {} accounts can be sampled at once", "Se pueden muestrear como máximo {} cuentas a la vez"),
+    ("At most {} addresses can be added per extension", "Se pueden añadir como máximo {} direcciones por extensión"),
+    ("Lookup table and recipient addresses cannot be the same", "La tabla de búsqueda y la dirección del destinatario no pueden ser la misma"),
+    ("No lookup table exists at {}", "No existe ninguna tabla de búsqueda en {}"),
+    ("No account exists at {}", "No existe ninguna cuenta en {}"),
+    ("Airdrops are only available on devnet, testnet and localnet", "Los airdrops solo están disponibles en devnet, testnet y localnet"),
+    ("Airdrop {} failed: {}", "El airdrop {} falló: {}"),
+    ("{} is not an address lookup table", "{} no es una tabla de búsqueda de direcciones"),
+    ("Payer and merkle tree cannot be the same", "El pagador y el árbol de Merkle no pueden ser la misma cuenta"),
+    ("maxDepth {} with maxBufferSize {} is not a supported tree size", "maxDepth {} con maxBufferSize {} no es un tamaño de árbol admitido"),
+    ("canopyDepth cannot be greater than maxDepth", "canopyDepth no puede ser mayor que maxDepth"),
+    ("name can be at most {} bytes", "name puede tener como máximo {} bytes"),
+    ("symbol can be at most {} bytes", "symbol puede tener como máximo {} bytes"),
+    ("uri can be at most {} bytes", "uri puede tener como máximo {} bytes"),
+    ("sellerFeeBasisPoints cannot exceed 10000", "sellerFeeBasisPoints no puede superar 10000"),
+    ("At most {} creators are allowed", "Se permiten como máximo {} creadores"),
+    ("Creator shares must add up to 100", "Las participaciones de los creadores deben sumar 100"),
+    ("The mint must be a new account of its own", "El mint debe ser una cuenta nueva y propia"),
+    ("Creator {} can only be verified by signing afterwards; only the authority can be verified at mint", "El creador {} solo puede verificarse firmando después; solo la autoridad puede verificarse al acuñar"),
+    ("Solana Pay is not enabled on this server", "Solana Pay no está habilitado en este servidor"),
+    ("The payer cannot be the recipient", "El pagador no puede ser el destinatario"),
+    ("Webhooks are not enabled on this server", "Los webhooks no están habilitados en este servidor"),
+    ("callbackUrl must be a public address", "callbackUrl debe ser una dirección pública"),
+    ("callbackUrl must be an http or https URL", "callbackUrl debe ser una URL http o https"),
+    ("Idempotency store unavailable: {}", "El almacén de idempotencia no está disponible: {}"),
+    ("Idempotency-Key must be 1 to {} visible ASCII characters", "Idempotency-Key debe tener de 1 a {} caracteres ASCII visibles"),
+    ("Idempotency-Key was already used for a different request", "Idempotency-Key ya se usó para una solicitud diferente"),
+    ("A request with this Idempotency-Key is still in progress", "Una solicitud con este Idempotency-Key todavía está en curso"),
+    ("Transaction signature count does not match its message header", "El número de firmas de la transacción no coincide con la cabecera del mensaje"),
+    ("Transaction has fewer account keys than required signers", "La transacción tiene menos claves de cuenta que firmantes requeridos"),
+    ("Transaction is malformed: {}", "La transacción está mal formada: {}"),
+    ("Token account and destination addresses cannot be the same", "La cuenta de token y la dirección de destino no pueden ser la misma"),
+    ("Token account {} still holds {} tokens; its balance must be zero before it can be closed", "La cuenta de token {} todavía tiene {} tokens; su saldo debe ser cero antes de poder cerrarla"),
+    ("Too many vanity searches are running, try again later", "Hay demasiadas búsquedas de direcciones personalizadas en curso, inténtalo más tarde"),
+    ("Transfers of mint {} are disabled", "Las transferencias del mint {} están deshabilitadas"),
+    ("Transfers of mint {} are limited to {} per transfer", "Las transferencias del mint {} están limitadas a {} por transferencia"),
+    ("Stake account and destination addresses cannot be the same", "La cuenta de stake y la dirección de destino no pueden ser la misma"),
+    ("Stake account and split stake account cannot be the same", "La cuenta de stake y la cuenta de stake dividida no pueden ser la misma"),
+    ("Stake accounts need at least {} lamports to be rent exempt", "Las cuentas de stake necesitan al menos {} lamports para estar exentas de renta"),
+    ("Transfer fee calculation overflowed", "El cálculo de la comisión de transferencia se desbordó"),
+    ("Transaction has no instructions that can be cloned", "La transacción no tiene instrucciones que se puedan clonar"),
+    ("Transaction has no fee payer", "La transacción no tiene pagador de comisiones"),
+    ("Transaction is {} bytes, larger than the {} byte limit", "La transacción ocupa {} bytes, más que el límite de {} bytes"),
+    ("Unknown or expired vanity job", "Trabajo de dirección personalizada desconocido o expirado"),
+    ("Unknown operation type '{}'", "Tipo de operación desconocido '{}'"),
+    ("Vote account and destination addresses cannot be the same", "La cuenta de voto y la dirección de destino no pueden ser la misma"),
+    ("authorizeType must be either 'voter' or 'withdrawer'", "authorizeType debe ser 'voter' o 'withdrawer'"),
+    ("epochs must be between 1 and {}", "epochs debe estar entre 1 y {}"),
+    ("count must be between 1 and {}", "count debe estar entre 1 y {}"),
+    ("Keypair generation was interrupted", "La generación de pares de claves se interrumpió"),
+    ("Verification was interrupted", "La verificación se interrumpió"),
+    ("maxAttempts must be between 1 and {}", "maxAttempts debe estar entre 1 y {}"),
+    ("mnemonicWords must be 12 or 24", "mnemonicWords debe ser 12 o 24"),
+    ("prefix and suffix may be at most {} characters combined", "prefix y suffix pueden tener como máximo {} caracteres en total"),
+    ("{} contains '{}', which never appears in base58 addresses", "{} contiene '{}', que nunca aparece en direcciones base58"),
+    ("destination and amount must be given together", "destination y amount deben indicarse juntos"),
+    ("destination and withdrawWithheldAuthority must be given together", "destination y withdrawWithheldAuthority deben indicarse juntos"),
+    ("transferFeeBasisPoints must be at most {}", "transferFeeBasisPoints debe ser como máximo {}"),
+    ("nonceAccount and nonceAuthority must be given together", "nonceAccount y nonceAuthority deben indicarse juntos"),
+    ("unitLimit must be between 1 and {}", "unitLimit debe estar entre 1 y {}"),
+    // Transaction explanations
+    ("an unknown account", "una cuenta desconocida"),
+    ("an unknown mint", "un mint desconocido"),
+    ("mint {}", "mint {}"),
+    ("Send {} to {}", "Enviar {} a {}"),
+    ("Create account {} funded with {} ({} bytes, owned by {})", "Crear la cuenta {} con {} ({} bytes, propiedad de {})"),
+    ("Assign {} to program {}", "Asignar {} al programa {}"),
+    ("Allocate {} bytes for {}", "Reservar {} bytes para {}"),
+    ("Advance durable nonce {}", "Avanzar el nonce duradero {}"),
+    ("Withdraw {} from nonce account {} to {}", "Retirar {} de la cuenta nonce {} a {}"),
+    ("Initialize nonce account {}", "Inicializar la cuenta nonce {}"),
+    ("Call the System program", "Llamar al programa System"),
+    ("Transfer {} raw token units from {} to {}", "Transferir {} unidades base de token de {} a {}"),
+    ("Send {} {} to token account {}", "Enviar {} {} a la cuenta de token {}"),
+    ("Mint {} raw units of {} to {}", "Acuñar {} unidades base de {} en {}"),
+    ("Mint {} {} to {}", "Acuñar {} {} en {}"),
+    ("Burn {} raw units of {}", "Quemar {} unidades base de {}"),
+    ("Burn {} {}", "Quemar {} {}"),
+    ("Allow {} to spend {} raw units from {}", "Permitir que {} gaste {} unidades base de {}"),
+    ("Allow {} to spend {} {} from {}", "Permitir que {} gaste {} {} de {}"),
+    ("Revoke the delegate of {}", "Revocar el delegado de {}"),
+    ("Close token account {} and reclaim its rent to {}", "Cerrar la cuenta de token {} y recuperar su renta en {}"),
+    ("Change the {} authority of {} to {}", "Cambiar la autoridad de {} de {} a {}"),
+    ("Remove the {} authority of {}", "Eliminar la autoridad de {} de {}"),
+    ("mint", "acuñación"),
+    ("freeze", "congelación"),
+    ("owner", "propietario"),
+    ("close", "cierre"),
+    ("Initialize mint {} with {} decimals", "Inicializar el mint {} con {} decimales"),
+    ("Initialize token account {} for {}", "Inicializar la cuenta de token {} para {}"),
+    ("Sync wrapped SOL balance of {}", "Sincronizar el saldo de SOL envuelto de {}"),
+    ("Freeze token account {}", "Congelar la cuenta de token {}"),
+    ("Thaw token account {}", "Descongelar la cuenta de token {}"),
+    ("Call the SPL Token program", "Llamar al programa SPL Token"),
+    ("Create token account for {} owned by {}", "Crear una cuenta de token para {} propiedad de {}"),
+    ("Create token account for {} owned by {} (if missing)", "Crear una cuenta de token para {} propiedad de {} (si no existe)"),
+    ("Attach memo \"{}\"", "Adjuntar el memo \"{}\""),
+    ("Request a heap of {} bytes", "Solicitar un heap de {} bytes"),
+    ("Set the compute unit limit to {}", "Fijar el límite de unidades de cómputo en {}"),
+    ("Set the compute unit price to {} micro-lamports", "Fijar el precio de la unidad de cómputo en {} micro-lamports"),
+    ("Limit loaded account data to {} bytes", "Limitar los datos de cuentas cargadas a {} bytes"),
+    ("Call the Compute Budget program", "Llamar al programa Compute Budget"),
+    ("Call program {}", "Llamar al programa {}"),
+    // Transaction warnings
+    ("Instruction {} hands the {} authority of {} to {}, which does not sign this transaction", "La instrucción {} entrega la autoridad de {} de {} a {}, que no firma esta transacción"),
+    ("Instruction {} lets {} spend an unlimited amount from {}", "La instrucción {} permite que {} gaste una cantidad ilimitada de {}"),
+    ("Instruction {} sends funds to {}, which another account created earlier in this transaction", "La instrucción {} envía fondos a {}, que otra cuenta creó antes en esta transacción"),
+    ("staker", "staker"),
+    ("withdrawer", "retiro"),
+    ("voter", "votante"),
+    ("~{} fees", "~{} de comisiones"),
+    ("Pay {}", "Pagar {}"),
+    ("pay {}", "pagar {}"),
+    ("simulation fails: {}", "la simulación falla: {}"),
+    ("net balance changes: {}", "cambios netos de saldo: {}"),
+];
+
+impl Locale {
+    fn from_tag(tag: &str) -> Option<Locale> {
+        let primary = tag.split(['-', '_']).next()?.trim().to_ascii_lowercase();
+        match primary.as_str() {
+            "en" => Some(Locale::En),
+            "es" => Some(Locale::Es),
+            _ => None,
+        }
+    }
+
+    // Pick the supported language with the highest q-value, e.g.
+    // "fr-CH, es;q=0.9, en;q=0.8" selects Spanish
+    pub fn from_accept_language(header: &str) -> Locale {
+        header
+            .split(',')
+            .filter_map(|entry| {
+                let mut parts = entry.split(';');
+                let locale = Locale::from_tag(parts.next()?)?;
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                Some((locale, quality))
+            })
+            .filter(|(_, quality)| *quality > 0.0)
+            .fold(None, |best: Option<(Locale, f32)>, candidate| match best {
+                Some(best) if best.1 >= candidate.1 => Some(best),
+                _ => Some(candidate),
+            })
+            .map(|(locale, _)| locale)
+            .unwrap_or_default()
+    }
+
+    pub fn from_headers(headers: &HeaderMap) -> Locale {
+        headers
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .map(Locale::from_accept_language)
+            .unwrap_or_default()
+    }
+
+    fn catalog(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::En => &[],
+            Locale::Es => ES,
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Locale {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Locale::from_headers(&parts.headers))
+    }
+}
+
+fn substitute(template: &str, args: &[String]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut pieces = template.split("{}").peekable();
+    while let Some(piece) = pieces.next() {
+        result.push_str(piece);
+        if pieces.peek().is_some() {
+            result.push_str(args.next().map(String::as_str).unwrap_or(""));
+        }
+    }
+    result
+}
+
+// Match a rendered message against a template, returning the substituted values
+fn match_template(template: &str, message: &str) -> Option<Vec<String>> {
+    let literals: Vec<&str> = template.split("{}").collect();
+    if literals.len() == 1 {
+        return None;
+    }
+
+    let mut rest = message.strip_prefix(literals[0])?;
+    let mut args = Vec::with_capacity(literals.len() - 1);
+    for (index, literal) in literals.iter().enumerate().skip(1) {
+        let end = if index == literals.len() - 1 {
+            if !rest.ends_with(literal) {
+                return None;
+            }
+            rest.len() - literal.len()
+        } else if literal.is_empty() {
+            return None;
+        } else {
+            rest.find(literal)?
+        };
+        args.push(rest[..end].to_string());
+        rest = &rest[end + literal.len()..];
+    }
+    Some(args)
+}
+
+// Translate a fully rendered message, such as an ErrorResponse built with
+// format!, falling back to the original text when no entry matches
+pub fn translate(locale: Locale, message: &str) -> String {
+    let catalog = locale.catalog();
+    if let Some((_, translated)) = catalog.iter().find(|(key, _)| *key == message) {
+        return translated.to_string();
+    }
+
+    catalog
+        .iter()
+        .find_map(|(key, translated)| {
+            let args = match_template(key, message)?;
+            // Substituted values may themselves be catalog messages, as with
+            // batch errors that wrap a builder's error
+            let args: Vec<String> = args.iter().map(|arg| translate(locale, arg)).collect();
+            Some(substitute(translated, &args))
+        })
+        .unwrap_or_else(|| message.to_string())
+}
+
+// Render a catalog template with the given values in the requested language
+pub fn format(locale: Locale, template: &str, args: &[String]) -> String {
+    let translated = locale
+        .catalog()
+        .iter()
+        .find(|(key, _)| *key == template)
+        .map(|(_, translated)| *translated)
+        .unwrap_or(template);
+    substitute(translated, args)
+}
@@ -0,0 +1,353 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use std::{
+    collections::HashMap,
+    sync::{OnceLock, RwLock},
+};
+use super::borsh_schema::{array_field, lookup, parse_type, parse_type_defs, same_name, snake_case, BorshType, Encoder, TypeDef};
+use super::instruction::{validate_pubkey, InstructionResponse};
+use super::program_allowlist::{check_programs, Tenant};
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+
+// Anchor IDLs registered with POST /idl, kept in memory by program id. Both
+// the 0.30 format, which carries each instruction's discriminator, and the
+// older one, where it is derived from the instruction name, are accepted.
+const MAX_IDLS: usize = 1000;
+
+#[derive(Deserialize, ToSchema)]
+pub struct RegisterIdlRequest {
+    #[schema(value_type = Object)]
+    pub idl: Value,
+    // Needed when the IDL names no address of its own
+    #[serde(rename = "programId")]
+    pub program_id: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct IdlInstructionRequest {
+    // As written in the IDL, in camelCase or snake_case
+    pub name: String,
+    // Instruction arguments by name, as JSON. Integers wider than 53 bits may
+    // be given as strings, bytes as base64 or an array, and public keys in
+    // base58.
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub args: Map<String, Value>,
+    // Account addresses by name; accounts with a fixed address or PDA seeds
+    // the server can follow may be left out
+    #[serde(default)]
+    pub accounts: HashMap<String, String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct IdlResponse {
+    pub program_id: String,
+    pub name: String,
+    pub instructions: Vec<String>,
+    #[schema(value_type = Object)]
+    pub idl: Value,
+}
+
+enum Seed {
+    Const(Vec<u8>),
+    Account(String),
+    Arg(String),
+}
+
+struct Pda {
+    seeds: Vec<Seed>,
+    // Derived under the instruction's program unless given
+    program: Option<Pubkey>,
+}
+
+struct IdlAccount {
+    name: String,
+    writable: bool,
+    signer: bool,
+    optional: bool,
+    address: Option<Pubkey>,
+    pda: Option<Pda>,
+}
+
+struct IdlInstruction {
+    name: String,
+    discriminator: Vec<u8>,
+    accounts: Vec<IdlAccount>,
+    args: Vec<(String, BorshType)>,
+}
+
+struct Idl {
+    name: String,
+    instructions: Vec<IdlInstruction>,
+    types: HashMap<String, TypeDef>,
+    raw: Value,
+}
+
+static IDLS: OnceLock<RwLock<HashMap<Pubkey, Idl>>> = OnceLock::new();
+
+fn idls() -> &'static RwLock<HashMap<Pubkey, Idl>> {
+    IDLS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+fn invalid(message: impl Into<String>) -> ErrorResponse {
+    ErrorResponse::new(format!("Invalid IDL: {}", message.into()))
+}
+
+fn str_field<'a>(value: &'a Value, field: &str) -> Result<&'a str, ErrorResponse> {
+    value[field].as_str().ok_or_else(|| invalid(format!("missing {}", field)))
+}
+
+fn flag(value: &Value, names: &[&str]) -> bool {
+    names.iter().any(|name| value[name].as_bool().unwrap_or(false))
+}
+
+fn parse_bytes(value: &Value) -> Result<Vec<u8>, ErrorResponse> {
+    value
+        .as_array()
+        .ok_or_else(|| invalid("seed values must be byte arrays"))?
+        .iter()
+        .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()).ok_or_else(|| invalid("seed values must be byte arrays")))
+        .collect()
+}
+
+fn parse_pda(value: &Value) -> Result<Pda, ErrorResponse> {
+    let seed = |seed: &Value| -> Result<Seed, ErrorResponse> {
+        match str_field(seed, "kind")? {
+            "const" => parse_bytes(&seed["value"]).map(Seed::Const),
+            "account" => Ok(Seed::Account(str_field(seed, "path")?.to_string())),
+            "arg" => Ok(Seed::Arg(str_field(seed, "path")?.to_string())),
+            other => Err(invalid(format!("unsupported seed kind '{}'", other))),
+        }
+    };
+    let program = match value.get("program") {
+        Some(program) if program["kind"] == "const" => {
+            let bytes = parse_bytes(&program["value"])?;
+            Some(Pubkey::try_from(bytes.as_slice()).map_err(|_| invalid("PDA program must be 32 bytes"))?)
+        }
+        // Programs taken from another account aren't followed
+        Some(_) => return Err(invalid("only constant PDA programs are supported")),
+        None => None,
+    };
+    Ok(Pda { seeds: array_field(value, "seeds").iter().map(seed).collect::<Result<_, _>>()?, program })
+}
+
+// Older IDLs nest groups of accounts, which the program sees flattened
+fn parse_accounts(accounts: &[Value], parsed: &mut Vec<IdlAccount>) -> Result<(), ErrorResponse> {
+    for account in accounts {
+        if let Some(group) = account.get("accounts").and_then(Value::as_array) {
+            parse_accounts(group, parsed)?;
+            continue;
+        }
+        parsed.push(IdlAccount {
+            name: str_field(account, "name")?.to_string(),
+            writable: flag(account, &["writable", "isMut"]),
+            signer: flag(account, &["signer", "isSigner"]),
+            optional: flag(account, &["optional", "isOptional"]),
+            address: account
+                .get("address")
+                .and_then(Value::as_str)
+                .map(|address| address.parse().map_err(|_| invalid(format!("bad address for account {}", address))))
+                .transpose()?,
+            pda: account.get("pda").map(parse_pda).transpose()?,
+        });
+    }
+    Ok(())
+}
+
+fn parse_instruction(value: &Value) -> Result<IdlInstruction, ErrorResponse> {
+    let name = str_field(value, "name")?.to_string();
+    let discriminator = match value.get("discriminator") {
+        Some(discriminator) => parse_bytes(discriminator)?,
+        // Anchor's sighash for IDLs older than 0.30
+        None => Sha256::digest(format!("global:{}", snake_case(&name)).as_bytes())[..8].to_vec(),
+    };
+    let mut accounts = Vec::new();
+    parse_accounts(array_field(value, "accounts"), &mut accounts)?;
+    let args = array_field(value, "args")
+        .iter()
+        .map(|arg| Ok((str_field(arg, "name")?.to_string(), parse_type(&arg["type"])?)))
+        .collect::<Result<_, ErrorResponse>>()?;
+    Ok(IdlInstruction { name, discriminator, accounts, args })
+}
+
+fn parse_idl(raw: Value) -> Result<(Option<Pubkey>, Idl), ErrorResponse> {
+    let address = raw
+        .get("address")
+        .or_else(|| raw["metadata"].get("address"))
+        .and_then(Value::as_str)
+        .map(|address| address.parse::<Pubkey>().map_err(|_| invalid("address is not a public key")))
+        .transpose()?;
+    let name = raw["metadata"]["name"].as_str().or_else(|| raw["name"].as_str()).unwrap_or_default().to_string();
+    let instructions = array_field(&raw, "instructions").iter().map(parse_instruction).collect::<Result<Vec<_>, _>>()?;
+    if instructions.is_empty() {
+        return Err(invalid("no instructions"));
+    }
+    let types = parse_type_defs(array_field(&raw, "types"))?;
+    Ok((address, Idl { name, instructions, types, raw }))
+}
+
+fn idl_response(program_id: &Pubkey, idl: &Idl) -> IdlResponse {
+    IdlResponse {
+        program_id: program_id.to_string(),
+        name: idl.name.clone(),
+        instructions: idl.instructions.iter().map(|instruction| instruction.name.clone()).collect(),
+        idl: idl.raw.clone(),
+    }
+}
+
+// Registering a program's IDL again replaces the earlier one
+pub fn register_idl(request: RegisterIdlRequest) -> Result<SuccessResponse<IdlResponse>, ErrorResponse> {
+    // Validate inputs
+    let (address, idl) = parse_idl(request.idl)?;
+    let program_id = match (request.program_id.as_deref(), address) {
+        (Some(program_id), _) => validate_pubkey(program_id, "program id")?,
+        (None, Some(address)) => address,
+        (None, None) => return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "programId is required when the IDL has no address")),
+    };
+
+    let mut idls = idls().write().unwrap();
+    if idls.len() >= MAX_IDLS && !idls.contains_key(&program_id) {
+        return Err(ErrorResponse::new(format!("At most {} IDLs can be registered", MAX_IDLS)));
+    }
+    let response = idl_response(&program_id, &idl);
+    idls.insert(program_id, idl);
+
+    Ok(SuccessResponse::new(response))
+}
+
+pub fn get_idl(program: String) -> Result<SuccessResponse<IdlResponse>, ErrorResponse> {
+    let program_id = validate_pubkey(&program, "program id")?;
+    let idls = idls().read().unwrap();
+    let idl = idls.get(&program_id).ok_or_else(|| no_idl(&program_id))?;
+    Ok(SuccessResponse::new(idl_response(&program_id, idl)))
+}
+
+fn no_idl(program_id: &Pubkey) -> ErrorResponse {
+    ErrorResponse::new(format!("No IDL is registered for program {}", program_id))
+}
+
+// Anchor seeds an argument by its bytes, without borsh's length prefix
+fn arg_seed(idl: &Idl, instruction: &IdlInstruction, args: &Map<String, Value>, path: &str) -> Result<Vec<u8>, ErrorResponse> {
+    let (name, ty) = instruction
+        .args
+        .iter()
+        .find(|(name, _)| same_name(name, path))
+        .ok_or_else(|| invalid(format!("PDA seed names unknown argument {}", path)))?;
+    let mut encoder = Encoder { types: &idl.types, data: Vec::new() };
+    encoder.encode(ty, lookup(args, name).unwrap_or(&Value::Null), name, 0)?;
+    Ok(match ty {
+        BorshType::String | BorshType::Bytes => encoder.data.split_off(4),
+        _ => encoder.data,
+    })
+}
+
+// Accounts the caller named, then fixed addresses, then PDAs whose seeds are
+// known. PDAs may be seeded by other accounts, so passes repeat until
+// nothing more resolves.
+fn resolve_accounts(
+    program_id: &Pubkey,
+    idl: &Idl,
+    instruction: &IdlInstruction,
+    request: &IdlInstructionRequest,
+) -> Result<Vec<AccountMeta>, ErrorResponse> {
+    let mut resolved: Vec<Option<Pubkey>> = instruction
+        .accounts
+        .iter()
+        .map(|account| {
+            lookup(&request.accounts, &account.name)
+                .map(|address| validate_pubkey(address, &account.name))
+                .transpose()
+                .map(|given| given.or(account.address))
+        })
+        .collect::<Result<_, _>>()?;
+
+    loop {
+        let mut progress = false;
+        for (index, account) in instruction.accounts.iter().enumerate() {
+            let Some(pda) = account.pda.as_ref().filter(|_| resolved[index].is_none()) else {
+                continue;
+            };
+            let mut seeds = Vec::with_capacity(pda.seeds.len());
+            for seed in &pda.seeds {
+                seeds.push(match seed {
+                    Seed::Const(bytes) => bytes.clone(),
+                    Seed::Arg(path) => arg_seed(idl, instruction, &request.args, path)?,
+                    // Seeds from fields of an account's data can't be followed
+                    Seed::Account(path) => match instruction.accounts.iter().position(|other| same_name(&other.name, path)) {
+                        Some(other) => match resolved[other] {
+                            Some(address) => address.to_bytes().to_vec(),
+                            None => break,
+                        },
+                        None => break,
+                    },
+                });
+            }
+            if seeds.len() == pda.seeds.len() {
+                let seeds: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+                resolved[index] = Some(Pubkey::find_program_address(&seeds, &pda.program.unwrap_or(*program_id)).0);
+                progress = true;
+            }
+        }
+        if !progress {
+            break;
+        }
+    }
+
+    // An account the caller left out is named ahead of the PDAs it seeds
+    if let Some((account, _)) = instruction
+        .accounts
+        .iter()
+        .zip(&resolved)
+        .find(|(account, address)| address.is_none() && account.pda.is_none() && !account.optional)
+    {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, format!("Account {} is required", account.name)));
+    }
+
+    instruction
+        .accounts
+        .iter()
+        .zip(resolved)
+        .map(|(account, address)| match address {
+            Some(pubkey) => Ok(AccountMeta { pubkey, is_signer: account.signer, is_writable: account.writable }),
+            // Anchor takes the program id in place of an omitted optional account
+            None if account.optional => Ok(AccountMeta::new_readonly(*program_id, false)),
+            None => Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, format!("Account {} is required", account.name))),
+        })
+        .collect()
+}
+
+pub fn build_idl_instruction(program: String, request: IdlInstructionRequest) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    let program_id = validate_pubkey(&program, "program id")?;
+    let idls = idls().read().unwrap();
+    let idl = idls.get(&program_id).ok_or_else(|| no_idl(&program_id))?;
+    let instruction = idl
+        .instructions
+        .iter()
+        .find(|instruction| same_name(&instruction.name, &request.name))
+        .ok_or_else(|| ErrorResponse::new(format!("The IDL has no instruction named {}", request.name)))?;
+
+    let mut encoder = Encoder { types: &idl.types, data: instruction.discriminator.clone() };
+    for (name, ty) in &instruction.args {
+        encoder.encode(ty, lookup(&request.args, name).unwrap_or(&Value::Null), name, 0)?;
+    }
+    let accounts = resolve_accounts(&program_id, idl, instruction, &request)?;
+
+    let instruction = Instruction { program_id, accounts, data: encoder.data };
+
+    Ok(SuccessResponse::new(InstructionResponse::from(&instruction)))
+}
+
+// The HTTP endpoint holds the program to the tenant's allowlist, as raw
+// instructions are
+pub fn build_idl_instruction_for(program: String, request: IdlInstructionRequest, tenant: &Tenant) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    let response = build_idl_instruction(program, request)?;
+    check_programs(tenant, [response.data.program_id.as_str()])?;
+    Ok(response)
+}
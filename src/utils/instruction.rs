@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::Keypair,
+};
+use super::response_types::{ApiErrorCode, ErrorResponse};
+use bs58;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+#[derive(Serialize, Deserialize, ToSchema, Clone)]
+pub struct AccountInfo {
+    pub pubkey: String,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl From<&AccountMeta> for AccountInfo {
+    fn from(account: &AccountMeta) -> Self {
+        AccountInfo {
+            pubkey: bs58::encode(account.pubkey.to_bytes()).into_string(),
+            is_signer: account.is_signer,
+            is_writable: account.is_writable,
+        }
+    }
+}
+
+// A single instruction for the client to put in a transaction
+#[derive(Serialize, ToSchema)]
+pub struct InstructionResponse {
+    pub program_id: String,
+    pub accounts: Vec<AccountInfo>,
+    pub instruction_data: String,
+}
+
+// Built from the instruction itself, so the accounts returned are always
+// the ones the program will be given
+impl From<&Instruction> for InstructionResponse {
+    fn from(instruction: &Instruction) -> Self {
+        InstructionResponse {
+            program_id: instruction.program_id.to_string(),
+            accounts: instruction.accounts.iter().map(AccountInfo::from).collect(),
+            instruction_data: BASE64.encode(&instruction.data),
+        }
+    }
+}
+
+pub fn validate_pubkey(key: &str, field_name: &str) -> Result<Pubkey, ErrorResponse> {
+    bs58::decode(key)
+        .into_vec()
+        .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidPubkey, format!("Invalid base58 encoding for {}", field_name)))
+        .and_then(|bytes|
+            Pubkey::try_from(bytes.as_slice())
+                .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidPubkey, format!("Invalid public key format for {}", field_name)))
+        )
+}
+
+pub fn validate_secret_key(secret: &str) -> Result<Keypair, ErrorResponse> {
+    let secret_bytes = bs58::decode(secret)
+        .into_vec()
+        .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidSecret, "Invalid base58 encoding for secret key"))?;
+
+    Keypair::from_bytes(&secret_bytes)
+        .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidSecret, "Invalid secret key format"))
+}
+
+// Token amounts, in base units
+pub fn validate_amount(amount: u64) -> Result<(), ErrorResponse> {
+    if amount == 0 {
+        return Err(ErrorResponse::with_code(ApiErrorCode::AmountZero, "Amount must be greater than 0"));
+    }
+    Ok(())
+}
+
+pub fn validate_lamports(lamports: u64) -> Result<(), ErrorResponse> {
+    if lamports == 0 {
+        return Err(ErrorResponse::with_code(ApiErrorCode::AmountZero, "Amount must be greater than 0 lamports"));
+    }
+
+    // Check if amount is reasonable (less than total supply)
+    // Solana total supply is ~500M SOL = 500M * 10^9 lamports
+    const MAX_REASONABLE_LAMPORTS: u64 = 500_000_000 * 1_000_000_000;
+    if lamports > MAX_REASONABLE_LAMPORTS {
+        return Err(ErrorResponse::with_code(ApiErrorCode::AmountTooLarge, "Amount exceeds maximum reasonable transfer"));
+    }
+
+    Ok(())
+}
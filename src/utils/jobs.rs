@@ -0,0 +1,285 @@
+use rand::{distributions::Alphanumeric, Rng};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::{
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::mpsc;
+use crate::cluster::{self, Target};
+use crate::config;
+use crate::shutdown;
+use super::confirmation::{self, Commitment, Outcome};
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+use super::rpc;
+use super::transaction::{broadcast, decode_transaction, required_signers, sign_with, verify_signatures};
+use super::signer::{self, SignerBackend, SigningKey};
+use super::webhook;
+
+// Jobs waiting for a worker; submissions beyond this are turned away
+const QUEUE_CAPACITY: usize = 1024;
+const JOB_TTL_SECS: u64 = 60 * 60;
+const JOB_ID_LEN: usize = 24;
+
+#[derive(Deserialize, ToSchema)]
+pub struct SubmitJobRequest {
+    // Base64; signatures missing from it must come from secretKeys or keyIds
+    pub transaction: String,
+    #[serde(rename = "secretKeys", default)]
+    pub secret_keys: Vec<String>,
+    // Keys held by signerBackend
+    #[serde(rename = "keyIds", default)]
+    pub key_ids: Vec<String>,
+    #[serde(rename = "signerBackend", default)]
+    pub signer_backend: SignerBackend,
+    // Replace the blockhash with a fresh one before signing. Needs keys for
+    // every required signer, and is not for durable nonce transactions.
+    #[serde(rename = "refreshBlockhash", default)]
+    pub refresh_blockhash: bool,
+    #[serde(rename = "skipPreflight", default)]
+    pub skip_preflight: bool,
+    #[serde(rename = "callbackUrl")]
+    pub callback_url: Option<String>,
+    // How far the job watches the transaction before it is done
+    #[serde(default)]
+    pub commitment: Commitment,
+}
+
+#[derive(Serialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    // Being signed and broadcast
+    Processing,
+    // Broadcast, waiting for the commitment asked for
+    Submitted,
+    // Reached processed or confirmed commitment, when that was asked for
+    Confirmed,
+    Finalized,
+    // The blockhash expired before the transaction landed
+    Expired,
+    Failed,
+}
+
+#[derive(Serialize, ToSchema, Clone)]
+pub struct JobResponse {
+    pub job_id: String,
+    pub status: JobStatus,
+    pub commitment: Commitment,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    // Program logs when the transaction failed on chain
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<String>,
+}
+
+struct Job {
+    response: JobResponse,
+    // Set once the job has finished
+    expires_at: Option<u64>,
+}
+
+// What a worker needs to run a job; the keys never leave the queue
+struct Work {
+    job_id: String,
+    transaction: VersionedTransaction,
+    keys: Vec<SigningKey>,
+    refresh_blockhash: bool,
+    skip_preflight: bool,
+    commitment: Commitment,
+    callback_url: Option<Url>,
+    // The cluster the job was submitted for
+    cluster: Option<Target>,
+}
+
+static JOBS: OnceLock<Mutex<HashMap<String, Job>>> = OnceLock::new();
+
+fn jobs() -> &'static Mutex<HashMap<String, Job>> {
+    JOBS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn update(job_id: &str, change: impl FnOnce(&mut JobResponse)) {
+    if let Some(job) = jobs().lock().unwrap().get_mut(job_id) {
+        change(&mut job.response);
+        if matches!(job.response.status, JobStatus::Confirmed | JobStatus::Finalized | JobStatus::Expired | JobStatus::Failed) {
+            job.expires_at = Some(now() + JOB_TTL_SECS);
+        }
+    }
+}
+
+// Workers are started with the first job, sharing one queue. The queue and
+// the jobs live in this instance's memory, so each job is run, and its
+// transaction rebroadcast, only by the instance it was submitted to; there is
+// nothing for several instances to run twice and no leader to elect. Moving
+// jobs to a shared store such as the Redis used for Idempotency-Key records
+// would need a lease on each job, or a leader running the workers and the
+// rebroadcasts in confirmation::watch.
+fn queue() -> &'static mpsc::Sender<Work> {
+    static QUEUE: OnceLock<mpsc::Sender<Work>> = OnceLock::new();
+    QUEUE.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<Work>(QUEUE_CAPACITY);
+        let receiver = Arc::new(tokio::sync::Mutex::new(receiver));
+        for _ in 0..config::get().job_workers.max(1) {
+            let receiver = receiver.clone();
+            tokio::spawn(async move {
+                loop {
+                    let Some(work) = receiver.lock().await.recv().await else {
+                        return;
+                    };
+                    // Tracked, so shutdown waits for a job being broadcast
+                    shutdown::detached(cluster::scope(work.cluster.clone(), run(work))).await;
+                }
+            });
+        }
+        sender
+    })
+}
+
+async fn sign_and_broadcast(work: &mut Work) -> Result<Signature, ErrorResponse> {
+    if work.refresh_blockhash {
+        let blockhash = rpc::latest_blockhash().await?;
+        work.transaction.message.set_recent_blockhash(blockhash);
+    }
+    sign_with(&mut work.transaction, &work.keys).await?;
+    verify_signatures(&work.transaction)?;
+    broadcast(&work.transaction, work.skip_preflight).await
+}
+
+// Workers only sign and broadcast. Confirmation can take minutes, so it is
+// watched on its own task rather than holding a worker.
+async fn run(mut work: Work) {
+    update(&work.job_id, |job| job.status = JobStatus::Processing);
+    let signature = match sign_and_broadcast(&mut work).await {
+        Ok(signature) => signature,
+        Err(err) => {
+            update(&work.job_id, |job| {
+                job.status = JobStatus::Failed;
+                job.error = Some(err.error);
+            });
+            return;
+        }
+    };
+    update(&work.job_id, |job| {
+        job.status = JobStatus::Submitted;
+        job.signature = Some(signature.to_string());
+    });
+
+    tokio::spawn(cluster::scope(work.cluster.clone(), async move {
+        let outcome = confirmation::watch(work.transaction, work.commitment).await;
+        update(&work.job_id, |job| match &outcome {
+            Outcome::Landed { slot, commitment } => {
+                job.status = if *commitment == Commitment::Finalized { JobStatus::Finalized } else { JobStatus::Confirmed };
+                job.slot = Some(*slot);
+            }
+            Outcome::Expired => {
+                job.status = JobStatus::Expired;
+                job.error = Some("Transaction expired before it was confirmed".to_string());
+            }
+            Outcome::Failed { slot, error, logs } => {
+                job.status = JobStatus::Failed;
+                job.slot = *slot;
+                job.error = Some(error.clone());
+                job.logs = logs.clone();
+            }
+        });
+        if let Some(url) = work.callback_url {
+            webhook::deliver(&url, signature, &outcome).await;
+        }
+    }));
+}
+
+// Everything that can be checked without the network is checked here, so
+// the caller hears about a bad request now rather than from a failed job
+pub async fn submit_job(request: SubmitJobRequest) -> Result<SuccessResponse<JobResponse>, ErrorResponse> {
+    // Validate inputs
+    if request.transaction.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
+    }
+    let transaction = decode_transaction(&request.transaction)?;
+    let keys = signer::keys(&request.secret_keys, &request.key_ids, request.signer_backend).await?;
+    let signers = required_signers(&transaction)?;
+    if let Some(key) = keys.iter().find(|key| !signers.contains(&key.pubkey())) {
+        return Err(ErrorResponse::new(format!("{} is not a required signer of this transaction", key.pubkey())));
+    }
+    let unsigned: Vec<String> = signers
+        .iter()
+        .zip(&transaction.signatures)
+        .filter(|(key, signature)| {
+            (request.refresh_blockhash || **signature == Signature::default())
+                && !keys.iter().any(|signing_key| signing_key.pubkey() == **key)
+        })
+        .map(|(key, _)| key.to_string())
+        .collect();
+    if !unsigned.is_empty() && request.refresh_blockhash {
+        return Err(ErrorResponse::new(format!("refreshBlockhash needs secretKeys for {}", unsigned.join(", "))));
+    }
+    if !unsigned.is_empty() {
+        return Err(ErrorResponse::new(format!("Transaction is missing signatures from {}", unsigned.join(", "))));
+    }
+    let callback_url = request.callback_url.as_deref().map(webhook::validate_callback_url).transpose()?;
+
+    let job_id: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(JOB_ID_LEN)
+        .map(char::from)
+        .collect();
+    let response = JobResponse {
+        job_id: job_id.clone(),
+        status: JobStatus::Queued,
+        commitment: request.commitment,
+        signature: None,
+        slot: None,
+        error: None,
+        logs: Vec::new(),
+    };
+    {
+        let mut jobs = jobs().lock().unwrap();
+        let now = now();
+        jobs.retain(|_, job| job.expires_at.is_none_or(|expires_at| expires_at > now));
+        jobs.insert(job_id.clone(), Job { response: response.clone(), expires_at: None });
+    }
+
+    let work = Work {
+        job_id: job_id.clone(),
+        transaction,
+        keys,
+        refresh_blockhash: request.refresh_blockhash,
+        skip_preflight: request.skip_preflight,
+        commitment: request.commitment,
+        callback_url,
+        cluster: cluster::current(),
+    };
+    if queue().try_send(work).is_err() {
+        jobs().lock().unwrap().remove(&job_id);
+        return Err(ErrorResponse::with_code(ApiErrorCode::RateLimited, "Job queue is full, try again later"));
+    }
+
+    Ok(SuccessResponse::new(response))
+}
+
+pub fn get_job(job_id: String) -> Result<SuccessResponse<JobResponse>, ErrorResponse> {
+    jobs()
+        .lock()
+        .unwrap()
+        .get(&job_id)
+        .filter(|job| job.expires_at.is_none_or(|expires_at| expires_at > now()))
+        .map(|job| SuccessResponse::new(job.response.clone()))
+        .ok_or_else(|| ErrorResponse::new("Unknown or expired job"))
+}
@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::{
+    address_lookup_table::{
+        self,
+        instruction as lookup_table_instruction,
+        state::AddressLookupTable,
+    },
+    clock::Slot,
+    commitment_config::CommitmentConfig,
+};
+use super::instruction::{validate_pubkey, InstructionResponse};
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+use super::rpc::{self, rpc_error};
+
+// More than this many new addresses don't fit in one transaction alongside
+// the extend instruction's accounts and signatures
+const MAX_ADDRESSES_PER_EXTEND: usize = 30;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateLookupTableRequest {
+    pub authority: String,
+    pub payer: String,
+    // The table's address is derived from the authority and this slot, which
+    // must still be in the SlotHashes sysvar when the transaction lands.
+    // POST /alt/create uses the latest finalized slot when it is omitted.
+    #[serde(rename = "recentSlot")]
+    pub recent_slot: Option<Slot>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ExtendLookupTableRequest {
+    #[serde(rename = "lookupTable")]
+    pub lookup_table: String,
+    pub authority: String,
+    // Funds the rent for the added addresses; only optional when the table
+    // already holds enough lamports
+    pub payer: Option<String>,
+    pub addresses: Vec<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DeactivateLookupTableRequest {
+    #[serde(rename = "lookupTable")]
+    pub lookup_table: String,
+    pub authority: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CloseLookupTableRequest {
+    #[serde(rename = "lookupTable")]
+    pub lookup_table: String,
+    pub authority: String,
+    // Receives the table's lamports; defaults to the authority
+    pub recipient: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreateLookupTableResponse {
+    #[serde(flatten)]
+    pub instruction: InstructionResponse,
+    pub lookup_table: String,
+    pub recent_slot: Slot,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LookupTableResponse {
+    pub address: String,
+    // None once the table is frozen
+    pub authority: Option<String>,
+    // False once deactivation has started; the table can be closed when the
+    // deactivation slot is no longer recent
+    pub active: bool,
+    pub deactivation_slot: Option<Slot>,
+    pub last_extended_slot: Slot,
+    pub addresses: Vec<String>,
+}
+
+pub fn create_lookup_table(request: CreateLookupTableRequest) -> Result<SuccessResponse<CreateLookupTableResponse>, ErrorResponse> {
+    // Validate inputs
+    let authority = validate_pubkey(&request.authority, "authority")?;
+    let payer = validate_pubkey(&request.payer, "payer")?;
+    let recent_slot = request
+        .recent_slot
+        .ok_or_else(|| ErrorResponse::with_code(ApiErrorCode::MissingFields, "recentSlot is required"))?;
+
+    // The authority no longer has to sign table creation
+    let (instruction, lookup_table) = lookup_table_instruction::create_lookup_table(authority, payer, recent_slot);
+
+    Ok(SuccessResponse::new(CreateLookupTableResponse {
+        instruction: InstructionResponse::from(&instruction),
+        lookup_table: lookup_table.to_string(),
+        recent_slot,
+    }))
+}
+
+// Fills in a missing recentSlot with the latest finalized slot, which is
+// sure to be in SlotHashes
+pub async fn create_lookup_table_with_slot(mut request: CreateLookupTableRequest) -> Result<SuccessResponse<CreateLookupTableResponse>, ErrorResponse> {
+    if request.recent_slot.is_none() {
+        let slot = rpc::read("getSlot", |client| async move {
+            client.get_slot_with_commitment(CommitmentConfig::finalized()).await
+        })
+            .await
+            .map_err(rpc_error)?;
+        request.recent_slot = Some(slot);
+    }
+
+    create_lookup_table(request)
+}
+
+pub fn extend_lookup_table(request: ExtendLookupTableRequest) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    let lookup_table = validate_pubkey(&request.lookup_table, "lookup table")?;
+    let authority = validate_pubkey(&request.authority, "authority")?;
+    let payer = request.payer.as_deref().map(|payer| validate_pubkey(payer, "payer")).transpose()?;
+    if request.addresses.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "At least one address is required"));
+    }
+    if request.addresses.len() > MAX_ADDRESSES_PER_EXTEND {
+        return Err(ErrorResponse::with_code(
+            ApiErrorCode::BatchTooLarge,
+            format!("At most {} addresses can be added per extension", MAX_ADDRESSES_PER_EXTEND),
+        ));
+    }
+    let addresses = request
+        .addresses
+        .iter()
+        .enumerate()
+        .map(|(index, address)| validate_pubkey(address, &format!("addresses[{}]", index)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let instruction = lookup_table_instruction::extend_lookup_table(lookup_table, authority, payer, addresses);
+
+    Ok(SuccessResponse::new(InstructionResponse::from(&instruction)))
+}
+
+pub fn deactivate_lookup_table(request: DeactivateLookupTableRequest) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    let lookup_table = validate_pubkey(&request.lookup_table, "lookup table")?;
+    let authority = validate_pubkey(&request.authority, "authority")?;
+
+    let instruction = lookup_table_instruction::deactivate_lookup_table(lookup_table, authority);
+
+    Ok(SuccessResponse::new(InstructionResponse::from(&instruction)))
+}
+
+// Only succeeds on chain once the table has been deactivated and its
+// deactivation slot has left SlotHashes, about 512 slots later
+pub fn close_lookup_table(request: CloseLookupTableRequest) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    let lookup_table = validate_pubkey(&request.lookup_table, "lookup table")?;
+    let authority = validate_pubkey(&request.authority, "authority")?;
+    let recipient = match request.recipient.as_deref() {
+        Some(recipient) => validate_pubkey(recipient, "recipient")?,
+        None => authority,
+    };
+
+    if lookup_table == recipient {
+        return Err(ErrorResponse::with_code(ApiErrorCode::SameAddress, "Lookup table and recipient addresses cannot be the same"));
+    }
+
+    let instruction = lookup_table_instruction::close_lookup_table(lookup_table, authority, recipient);
+
+    Ok(SuccessResponse::new(InstructionResponse::from(&instruction)))
+}
+
+// Not cached, since tables are extended and deactivated over time
+pub async fn get_lookup_table(address: String) -> Result<SuccessResponse<LookupTableResponse>, ErrorResponse> {
+    // Validate inputs
+    let address = validate_pubkey(&address, "lookup table")?;
+
+    let account = rpc::read("getAccountInfo", move |client| async move {
+        client.get_account_with_commitment(&address, client.commitment()).await.map(|response| response.value)
+    })
+        .await
+        .map_err(rpc_error)?
+        .ok_or_else(|| ErrorResponse::with_code(ApiErrorCode::InvalidAccount, format!("No lookup table exists at {}", address)))?;
+    let table = Some(&account)
+        .filter(|account| account.owner == address_lookup_table::program::id())
+        .and_then(|account| AddressLookupTable::deserialize(&account.data).ok())
+        .ok_or_else(|| ErrorResponse::with_code(ApiErrorCode::InvalidAccount, format!("{} is not an address lookup table", address)))?;
+
+    let active = table.meta.deactivation_slot == Slot::MAX;
+    Ok(SuccessResponse::new(LookupTableResponse {
+        address: address.to_string(),
+        authority: table.meta.authority.map(|authority| authority.to_string()),
+        active,
+        deactivation_slot: (!active).then_some(table.meta.deactivation_slot),
+        last_extended_slot: table.meta.last_extended_slot,
+        addresses: table.addresses.iter().map(|address| address.to_string()).collect(),
+    }))
+}
@@ -0,0 +1,45 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use super::instruction::{validate_pubkey, InstructionResponse};
+use super::response_types::{SuccessResponse, ErrorResponse};
+
+// Longest memo that still fits in a transaction with one signature and a
+// single memo instruction
+const MAX_MEMO_LEN: usize = 566;
+
+#[derive(Deserialize, ToSchema)]
+pub struct MemoRequest {
+    pub memo: String,
+    // Accounts that must sign the transaction for the memo to be accepted
+    #[serde(default)]
+    pub signers: Vec<String>,
+}
+
+fn validate_memo(memo: &str) -> Result<(), ErrorResponse> {
+    if memo.is_empty() {
+        return Err(ErrorResponse::new("Memo cannot be empty"));
+    }
+    if memo.len() > MAX_MEMO_LEN {
+        return Err(ErrorResponse::new(format!("Memo must be at most {} bytes", MAX_MEMO_LEN)));
+    }
+    Ok(())
+}
+
+pub fn memo_instruction(memo: &str, signers: &[Pubkey]) -> Result<Instruction, ErrorResponse> {
+    validate_memo(memo)?;
+    let signers: Vec<&Pubkey> = signers.iter().collect();
+    Ok(spl_memo::build_memo(memo.as_bytes(), &signers))
+}
+
+pub fn build_memo(request: MemoRequest) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    let signers = request.signers
+        .iter()
+        .map(|signer| validate_pubkey(signer, "memo signer"))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let instruction = memo_instruction(&request.memo, &signers)?;
+
+    Ok(SuccessResponse::new(InstructionResponse::from(&instruction)))
+}
@@ -1,108 +1,270 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use solana_sdk::{
-    signature::{Keypair, Signer, Signature},
-    pubkey::Pubkey,
+    offchain_message::OffchainMessage,
+    signature::Signature,
 };
-use super::response_types::{SuccessResponse, ErrorResponse};
+use super::audit::{self, AuditOperation};
+use super::i18n::{self, Locale};
+use super::instruction::{validate_pubkey, validate_secret_key};
+use super::signing::{self, DalekBatchBackend, SignatureBackend, SignedMessage};
+use super::timing;
+use super::signer::{self, SignerBackend, SigningKey};
+use super::response_types::{ApiErrorCode, FieldErrors, SuccessResponse, ErrorResponse};
 use bs58;
-use base64;
+use rayon::prelude::*;
+use tokio::sync::oneshot;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 
-#[derive(Deserialize)]
+const MAX_BATCH_MESSAGES: usize = 256;
+
+// Version of the off-chain message header written by the offchain format
+const OFFCHAIN_MESSAGE_VERSION: u8 = 0;
+
+#[derive(Deserialize, ToSchema, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningFormat {
+    // Sign the UTF-8 bytes of the message as-is
+    #[default]
+    Raw,
+    // Prefix the message with the "\xffsolana offchain" signing domain and
+    // version header, as wallets implementing signMessage do
+    Offchain,
+}
+
+#[derive(Deserialize, ToSchema, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageEncoding {
+    #[default]
+    Utf8,
+    Base64,
+    Hex,
+}
+
+#[derive(Deserialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureEncoding {
+    Base64,
+    // What explorers and the Solana CLI show
+    Base58,
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct SignMessageRequest {
     pub message: String,
+    #[serde(default)]
     pub secret: String,
+    // A key held by signerBackend, in place of secret
+    #[serde(rename = "keyId")]
+    pub key_id: Option<String>,
+    #[serde(rename = "signerBackend", default)]
+    pub signer_backend: SignerBackend,
+    #[serde(default)]
+    pub format: SigningFormat,
+    // How `message` is encoded; base64 and hex carry arbitrary bytes
+    #[serde(default)]
+    pub encoding: MessageEncoding,
+    // How the returned signature is encoded; base64 by default
+    #[serde(rename = "signatureEncoding")]
+    pub signature_encoding: Option<SignatureEncoding>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct VerifyMessageRequest {
     pub message: String,
     pub signature: String,
     pub pubkey: String,
+    #[serde(default)]
+    pub format: SigningFormat,
+    // How `message` is encoded; base64 and hex carry arbitrary bytes
+    #[serde(default)]
+    pub encoding: MessageEncoding,
+    // How `signature` is encoded; detected from the signature when omitted
+    #[serde(rename = "signatureEncoding")]
+    pub signature_encoding: Option<SignatureEncoding>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct VerifyMessageBatchRequest {
+    pub messages: Vec<VerifyMessageRequest>,
+    // Check the signatures with one batch verification even when the
+    // configured backend verifies them one at a time
+    #[serde(default)]
+    pub batch: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct SignMessageResponse {
     pub signature: String,
     pub public_key: String,
     pub message: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct VerifyMessageResponse {
     pub valid: bool,
     pub message: String,
     pub pubkey: String,
 }
 
-fn validate_secret_key(secret: &str) -> Result<Keypair, ErrorResponse> {
-    let secret_bytes = bs58::decode(secret)
-        .into_vec()
-        .map_err(|_| ErrorResponse::new("Invalid base58 encoding for secret key"))?;
+#[derive(Serialize, ToSchema)]
+pub struct VerifyMessageBatchResult {
+    pub valid: bool,
+    pub message: String,
+    pub pubkey: String,
+    // Why the item could not be verified, such as a malformed signature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
 
-    Keypair::from_bytes(&secret_bytes)
-        .map_err(|_| ErrorResponse::new("Invalid secret key format"))
+#[derive(Serialize, ToSchema)]
+pub struct VerifyMessageBatchResponse {
+    // In the same order as the request's messages
+    pub results: Vec<VerifyMessageBatchResult>,
+    // Every item was well-formed and its signature valid
+    #[serde(rename = "allValid")]
+    pub all_valid: bool,
 }
 
 fn validate_message(message: &str) -> Result<(), ErrorResponse> {
     if message.is_empty() {
-        return Err(ErrorResponse::new("Message cannot be empty"));
+        return Err(ErrorResponse::with_code(ApiErrorCode::InvalidMessage, "Message cannot be empty"));
     }
     Ok(())
 }
 
-fn validate_signature(signature: &str) -> Result<Signature, ErrorResponse> {
-    let sig_bytes = base64::decode(signature)
-        .map_err(|_| ErrorResponse::new("Invalid base64 encoding for signature"))?;
-    
+pub fn decode_message(message: &str, encoding: MessageEncoding) -> Result<Vec<u8>, ErrorResponse> {
+    let bytes = match encoding {
+        MessageEncoding::Utf8 => message.as_bytes().to_vec(),
+        MessageEncoding::Base64 => BASE64.decode(message)
+            .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidEncoding, "Invalid base64 encoding for message"))?,
+        MessageEncoding::Hex => hex::decode(message)
+            .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidEncoding, "Invalid hex encoding for message"))?,
+    };
+    if bytes.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::InvalidMessage, "Message cannot be empty"));
+    }
+    Ok(bytes)
+}
+
+// Bytes that are actually signed for the message in the given format
+pub fn signing_bytes(message: Vec<u8>, format: SigningFormat) -> Result<Vec<u8>, ErrorResponse> {
+    match format {
+        SigningFormat::Raw => Ok(message),
+        SigningFormat::Offchain => OffchainMessage::new(OFFCHAIN_MESSAGE_VERSION, &message)
+            .and_then(|message| message.serialize())
+            .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidMessage, format!(
+                "Off-chain messages must be UTF-8 text of at most {} bytes",
+                solana_sdk::offchain_message::v0::OffchainMessage::MAX_LEN
+            ))),
+    }
+}
+
+// A 64 byte signature is 88 base64 characters ending in "==", which base58
+// never contains, so at most one of the two decodes to 64 bytes
+fn detect_signature_encoding(signature: &str) -> Option<SignatureEncoding> {
+    if BASE64.decode(signature).is_ok_and(|bytes| bytes.len() == 64) {
+        Some(SignatureEncoding::Base64)
+    } else if bs58::decode(signature).into_vec().is_ok_and(|bytes| bytes.len() == 64) {
+        Some(SignatureEncoding::Base58)
+    } else {
+        None
+    }
+}
+
+pub fn validate_signature(signature: &str, encoding: Option<SignatureEncoding>) -> Result<Signature, ErrorResponse> {
+    let sig_bytes = match encoding.or_else(|| detect_signature_encoding(signature)) {
+        Some(SignatureEncoding::Base64) => BASE64.decode(signature)
+            .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidSignature, "Invalid base64 encoding for signature"))?,
+        Some(SignatureEncoding::Base58) => bs58::decode(signature)
+            .into_vec()
+            .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidSignature, "Invalid base58 encoding for signature"))?,
+        None => return Err(ErrorResponse::with_code(
+            ApiErrorCode::InvalidSignature,
+            "Signature is neither base64 nor base58 encoded",
+        )),
+    };
+
     Signature::try_from(sig_bytes.as_slice())
-        .map_err(|_| ErrorResponse::new("Invalid signature format"))
+        .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidSignature, "Invalid signature format"))
 }
 
-fn validate_pubkey(pubkey: &str) -> Result<Pubkey, ErrorResponse> {
-    let pubkey_bytes = bs58::decode(pubkey)
-        .into_vec()
-        .map_err(|_| ErrorResponse::new("Invalid base58 encoding for public key"))?;
+fn encode_signature(signature: &Signature, encoding: SignatureEncoding) -> String {
+    match encoding {
+        SignatureEncoding::Base64 => BASE64.encode(signature.as_ref()),
+        SignatureEncoding::Base58 => signature.to_string(),
+    }
+}
 
-    Pubkey::try_from(pubkey_bytes.as_slice())
-        .map_err(|_| ErrorResponse::new("Invalid public key format"))
+pub async fn sign_message(request: SignMessageRequest) -> Result<SuccessResponse<SignMessageResponse>, ErrorResponse> {
+    let result = sign(request).await;
+    audit::recorded(AuditOperation::SignMessage, result, |signed| vec![signed.public_key.clone()])
 }
 
-pub fn sign_message(request: SignMessageRequest) -> Result<SuccessResponse<SignMessageResponse>, ErrorResponse> {
+async fn sign(request: SignMessageRequest) -> Result<SuccessResponse<SignMessageResponse>, ErrorResponse> {
     // Validate inputs
-    if request.message.is_empty() || request.secret.is_empty() {
-        return Err(ErrorResponse::new("Missing required fields"));
+    if request.message.is_empty() || (request.secret.is_empty() && request.key_id.is_none()) {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
+    }
+    if !request.secret.is_empty() && request.key_id.is_some() {
+        return Err(ErrorResponse::new("Give either secret or keyId, not both"));
+    }
+
+    if request.signer_backend != SignerBackend::Local && request.key_id.is_none() {
+        return Err(ErrorResponse::new("signerBackend needs a keyId"));
     }
 
-    validate_message(&request.message)?;
-    let keypair = validate_secret_key(&request.secret)?;
+    let mut fields = FieldErrors::default();
+    fields.check("message", validate_message(&request.message));
+    // The key has no placeholder value, so it is only taken once valid
+    let key = match &request.key_id {
+        Some(key_id) => fields.check("keyId", signer::key(key_id, request.signer_backend).await.map(Some)),
+        None => fields.check("secret", validate_secret_key(&request.secret).map(|keypair| Some(SigningKey::Local(keypair)))),
+    };
+    fields.finish()?;
+    let key = key.unwrap();
 
     // Sign the message
-    let message_bytes = request.message.as_bytes();
-    let signature = keypair.sign_message(message_bytes);
-    
+    let message_bytes = signing_bytes(decode_message(&request.message, request.encoding)?, request.format)?;
+    let signing = timing::start("signing");
+    let signature = key.sign(&message_bytes).await?;
+    drop(signing);
+
     // Format the response
     let response = SignMessageResponse {
-        signature: base64::encode(signature.as_ref()),
-        public_key: bs58::encode(keypair.pubkey().to_bytes()).into_string(),
+        signature: encode_signature(&signature, request.signature_encoding.unwrap_or(SignatureEncoding::Base64)),
+        public_key: bs58::encode(key.pubkey().to_bytes()).into_string(),
         message: request.message,
     };
 
     Ok(SuccessResponse::new(response))
 }
 
-pub fn verify_message(request: VerifyMessageRequest) -> Result<SuccessResponse<VerifyMessageResponse>, ErrorResponse> {
-    // Validate inputs
+fn signed_message(request: &VerifyMessageRequest) -> Result<SignedMessage, ErrorResponse> {
     if request.message.is_empty() || request.signature.is_empty() || request.pubkey.is_empty() {
-        return Err(ErrorResponse::new("Missing required fields"));
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
     }
 
-    validate_message(&request.message)?;
-    let signature = validate_signature(&request.signature)?;
-    let pubkey = validate_pubkey(&request.pubkey)?;
+    let mut fields = FieldErrors::default();
+    fields.check("message", validate_message(&request.message));
+    let signature = fields.check("signature", validate_signature(&request.signature, request.signature_encoding));
+    let pubkey = fields.check("pubkey", validate_pubkey(&request.pubkey, "public key"));
+    fields.finish()?;
+    Ok(SignedMessage {
+        signature,
+        pubkey,
+        message: signing_bytes(decode_message(&request.message, request.encoding)?, request.format)?,
+    })
+}
+
+pub fn verify_message(request: VerifyMessageRequest) -> Result<SuccessResponse<VerifyMessageResponse>, ErrorResponse> {
+    // Validate inputs
+    let signed = signed_message(&request)?;
 
     // Verify the signature
-    let message_bytes = request.message.as_bytes();
-    let valid = signature.verify(&pubkey.to_bytes(), message_bytes);
+    let valid = timing::measure("verification", || {
+        signing::backend().verify(&signed.pubkey, &signed.message, &signed.signature)
+    });
 
     // Format the response
     let response = VerifyMessageResponse {
@@ -112,4 +274,65 @@ pub fn verify_message(request: VerifyMessageRequest) -> Result<SuccessResponse<V
     };
 
     Ok(SuccessResponse::new(response))
-} 
\ No newline at end of file
+}
+
+// A full batch takes long enough to decode and verify that it would hold up
+// a tokio worker, so both happen on the rayon pool
+pub async fn verify_message_batch(request: VerifyMessageBatchRequest, locale: Locale) -> Result<SuccessResponse<VerifyMessageBatchResponse>, ErrorResponse> {
+    // Validate inputs
+    if request.messages.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "At least one message is required"));
+    }
+    if request.messages.len() > MAX_BATCH_MESSAGES {
+        return Err(ErrorResponse::with_code(ApiErrorCode::BatchTooLarge, format!("At most {} messages can be verified at once", MAX_BATCH_MESSAGES)));
+    }
+
+    // Verify the signatures
+    let (sender, receiver) = oneshot::channel();
+    let timer = timing::start("verification");
+    rayon::spawn(move || {
+        let _ = sender.send(verify_batch(request, locale));
+    });
+    let results = receiver
+        .await
+        .map_err(|_| ErrorResponse::new("Verification was interrupted"))?;
+    drop(timer);
+
+    // Format the response
+    let response = VerifyMessageBatchResponse {
+        all_valid: results.iter().all(|result| result.valid),
+        results,
+    };
+
+    Ok(SuccessResponse::new(response))
+}
+
+fn verify_batch(request: VerifyMessageBatchRequest, locale: Locale) -> Vec<VerifyMessageBatchResult> {
+    // A malformed item fails on its own rather than failing the whole batch
+    let decoded: Vec<Result<SignedMessage, ErrorResponse>> = request.messages.par_iter().map(signed_message).collect();
+    let mut signed = Vec::with_capacity(decoded.len());
+    let errors: Vec<Option<String>> = decoded
+        .into_iter()
+        .map(|decoded| match decoded {
+            Ok(message) => {
+                signed.push(message);
+                None
+            }
+            Err(err) => Some(i18n::translate(locale, &err.error)),
+        })
+        .collect();
+
+    let backend: &dyn SignatureBackend = if request.batch { &DalekBatchBackend } else { signing::backend() };
+    let mut valid = backend.verify_batch(&signed).into_iter();
+
+    request.messages
+        .into_iter()
+        .zip(errors)
+        .map(|(message, error)| VerifyMessageBatchResult {
+            valid: error.is_none() && valid.next().unwrap_or(false),
+            message: message.message,
+            pubkey: message.pubkey,
+            error,
+        })
+        .collect()
+}
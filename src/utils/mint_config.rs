@@ -0,0 +1,85 @@
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use std::{collections::HashMap, env, str::FromStr, sync::OnceLock};
+use super::response_types::ErrorResponse;
+
+// Business rules for specific mints, read from MINT_OVERRIDES as JSON keyed
+// by mint address, e.g.
+// {"EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v": {"decimals": 6, "symbol": "USDC", "maxTransferAmount": 1000000000}}.
+// Mints without an entry have no overrides.
+#[derive(Deserialize, Default)]
+pub struct MintOverride {
+    pub decimals: Option<u8>,
+    pub symbol: Option<String>,
+    #[serde(rename = "transferDisabled", default)]
+    pub transfer_disabled: bool,
+    // In base units
+    #[serde(rename = "maxTransferAmount")]
+    pub max_transfer_amount: Option<u64>,
+}
+
+static OVERRIDES: OnceLock<HashMap<Pubkey, MintOverride>> = OnceLock::new();
+
+fn overrides() -> &'static HashMap<Pubkey, MintOverride> {
+    OVERRIDES.get_or_init(|| {
+        let Ok(config) = env::var("MINT_OVERRIDES") else {
+            return HashMap::new();
+        };
+        let overrides: HashMap<String, MintOverride> = serde_json::from_str(&config).unwrap_or_else(|err| {
+            panic!("MINT_OVERRIDES is not a JSON object of mint overrides: {}", err)
+        });
+        overrides
+            .into_iter()
+            .map(|(mint, config)| {
+                let mint = Pubkey::from_str(&mint)
+                    .unwrap_or_else(|_| panic!("MINT_OVERRIDES has an invalid mint address: {}", mint));
+                (mint, config)
+            })
+            .collect()
+    })
+}
+
+pub fn mint_override(mint: &Pubkey) -> Option<&'static MintOverride> {
+    overrides().get(mint)
+}
+
+pub fn configured_mints() -> impl Iterator<Item = &'static Pubkey> {
+    overrides().keys()
+}
+
+pub fn forced_decimals(mint: &Pubkey) -> Option<u8> {
+    mint_override(mint).and_then(|config| config.decimals)
+}
+
+pub fn symbol(mint: &Pubkey) -> Option<&'static str> {
+    mint_override(mint).and_then(|config| config.symbol.as_deref())
+}
+
+pub fn check_decimals(mint: &Pubkey, decimals: u8) -> Result<(), ErrorResponse> {
+    match forced_decimals(mint) {
+        Some(forced) if forced != decimals => Err(ErrorResponse::new(format!(
+            "Mint {} must use {} decimals",
+            mint, forced
+        ))),
+        _ => Ok(()),
+    }
+}
+
+pub fn check_transfer(mint: &Pubkey, amount: u64) -> Result<(), ErrorResponse> {
+    let Some(config) = mint_override(mint) else {
+        return Ok(());
+    };
+
+    if config.transfer_disabled {
+        return Err(ErrorResponse::new(format!("Transfers of mint {} are disabled", mint)));
+    }
+    if let Some(max) = config.max_transfer_amount
+        && amount > max
+    {
+        return Err(ErrorResponse::new(format!(
+            "Transfers of mint {} are limited to {} per transfer",
+            mint, max
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::{json, Value};
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
+use solana_client::{
+    client_error::Result as ClientResult,
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::RpcClientConfig,
+    rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
+    rpc_response::{Response, RpcBlockhash, RpcResponseContext, RpcSimulateTransactionResult, RpcVersionInfo},
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use solana_sdk::{
+    account::Account,
+    commitment_config::CommitmentConfig,
+    epoch_info::EpochInfo,
+    hash::{hashv, Hash},
+    message::VersionedMessage,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::Signature,
+    system_program,
+    transaction::VersionedTransaction,
+};
+use solana_transaction_status::{TransactionConfirmationStatus, TransactionStatus};
+use std::{
+    collections::HashSet,
+    sync::{Mutex, OnceLock},
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+// Mock mode answers RPC calls from memory instead of a cluster, so clients
+// can run end-to-end tests against the server without devnet. Answers are
+// deterministic: the chain never moves past a fixed slot and blockhash,
+// every address is a system account holding the same balance, and anything
+// sent lands finalized at once. Methods it doesn't know fail with a JSON-RPC
+// method-not-found error.
+
+// Shown in place of a provider URL
+pub const URL: &str = "mock://rpc";
+
+const SLOT: u64 = 1_000;
+const BLOCK_HEIGHT: u64 = 900;
+// Blockhashes stay valid for 150 blocks on a real cluster
+const LAST_VALID_BLOCK_HEIGHT: u64 = BLOCK_HEIGHT + 150;
+const BALANCE: u64 = 10 * LAMPORTS_PER_SOL;
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+fn blockhash() -> Hash {
+    hashv(&[b"mock blockhash"])
+}
+
+// Signatures of transactions sent or airdrops requested, which report as
+// finalized from then on
+fn landed() -> &'static Mutex<HashSet<Signature>> {
+    static LANDED: OnceLock<Mutex<HashSet<Signature>>> = OnceLock::new();
+    LANDED.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+pub fn client() -> RpcClient {
+    RpcClient::new_sender(MockSender, RpcClientConfig::with_commitment(CommitmentConfig::confirmed()))
+}
+
+struct MockSender;
+
+fn with_context(value: impl Serialize) -> Value {
+    serde_json::to_value(Response { context: RpcResponseContext { slot: SLOT, api_version: None }, value }).unwrap()
+}
+
+fn account(address: &Value) -> Option<UiAccount> {
+    let pubkey = address.as_str()?.parse::<Pubkey>().ok()?;
+    let account = Account { lamports: BALANCE, data: Vec::new(), owner: system_program::id(), executable: false, rent_epoch: 0 };
+    Some(UiAccount::encode(&pubkey, &account, UiAccountEncoding::Base64, None, None))
+}
+
+fn accounts(addresses: &Value) -> Option<Vec<UiAccount>> {
+    addresses.as_array()?.iter().map(account).collect()
+}
+
+// Transactions and messages arrive as base64, or as base58 when the config
+// says so
+fn decode<T: serde::de::DeserializeOwned>(params: &Value) -> Option<T> {
+    let encoded = params[0].as_str()?;
+    let bytes = match params[1]["encoding"].as_str() {
+        Some("base58") => bs58::decode(encoded).into_vec().ok()?,
+        _ => BASE64.decode(encoded).ok()?,
+    };
+    bincode::deserialize(&bytes).ok()
+}
+
+fn status(signature: &Value) -> Option<TransactionStatus> {
+    let signature = signature.as_str()?.parse::<Signature>().ok()?;
+    landed().lock().unwrap().contains(&signature).then_some(TransactionStatus {
+        slot: SLOT,
+        confirmations: None,
+        status: Ok(()),
+        err: None,
+        confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+    })
+}
+
+// Airdrops have no transaction to take a signature from, so one is made up
+// from the request
+fn airdrop_signature(params: &Value) -> Signature {
+    let request = params.to_string();
+    let first = hashv(&[b"mock airdrop", request.as_bytes()]);
+    let second = hashv(&[first.as_ref()]);
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(first.as_ref());
+    bytes[32..].copy_from_slice(second.as_ref());
+    Signature::from(bytes)
+}
+
+enum Unanswered {
+    MethodNotFound,
+    InvalidParams,
+}
+
+fn answer(method: &str, params: &Value) -> Result<Value, Unanswered> {
+    let invalid = || Unanswered::InvalidParams;
+    Ok(match method {
+        "getHealth" => json!("ok"),
+        "getVersion" => json!(RpcVersionInfo { solana_core: "1.18.26".to_string(), feature_set: None }),
+        "getGenesisHash" => json!(hashv(&[b"mock genesis"]).to_string()),
+        "getSlot" => json!(SLOT),
+        "getBlockHeight" => json!(BLOCK_HEIGHT),
+        "getEpochInfo" => json!(EpochInfo {
+            epoch: 0,
+            slot_index: SLOT,
+            slots_in_epoch: 432_000,
+            absolute_slot: SLOT,
+            block_height: BLOCK_HEIGHT,
+            transaction_count: None,
+        }),
+        "getLatestBlockhash" => with_context(RpcBlockhash {
+            blockhash: blockhash().to_string(),
+            last_valid_block_height: LAST_VALID_BLOCK_HEIGHT,
+        }),
+        "isBlockhashValid" => with_context(params[0].as_str() == Some(blockhash().to_string().as_str())),
+        "getBalance" => with_context(BALANCE),
+        "getAccountInfo" => with_context(account(&params[0]).ok_or_else(invalid)?),
+        "getMultipleAccounts" => with_context(accounts(&params[0]).ok_or_else(invalid)?),
+        "getProgramAccounts" | "getSignaturesForAddress" | "getRecentPrioritizationFees" => json!([]),
+        "getTokenAccountsByOwner" => with_context(Vec::<Value>::new()),
+        "getMinimumBalanceForRentExemption" => {
+            let size = params[0].as_u64().ok_or_else(invalid)?;
+            json!(Rent::default().minimum_balance(size as usize))
+        }
+        "getFeeForMessage" => {
+            let message: VersionedMessage = decode(params).ok_or_else(invalid)?;
+            with_context(Some(message.header().num_required_signatures as u64 * LAMPORTS_PER_SIGNATURE))
+        }
+        "getSignatureStatuses" => {
+            let signatures = params[0].as_array().ok_or_else(invalid)?;
+            with_context(signatures.iter().map(status).collect::<Vec<_>>())
+        }
+        "sendTransaction" => {
+            let transaction: VersionedTransaction = decode(params).ok_or_else(invalid)?;
+            let signature = *transaction.signatures.first().ok_or_else(invalid)?;
+            landed().lock().unwrap().insert(signature);
+            json!(signature.to_string())
+        }
+        "requestAirdrop" => {
+            let signature = airdrop_signature(params);
+            landed().lock().unwrap().insert(signature);
+            json!(signature.to_string())
+        }
+        // Succeeds without running anything, so accounts come back as they
+        // were
+        "simulateTransaction" => {
+            let _: VersionedTransaction = decode(params).ok_or_else(invalid)?;
+            let accounts = match params[1]["accounts"].get("addresses") {
+                Some(addresses) => Some(accounts(addresses).ok_or_else(invalid)?.into_iter().map(Some).collect()),
+                None => None,
+            };
+            with_context(RpcSimulateTransactionResult {
+                err: None,
+                logs: Some(Vec::new()),
+                accounts,
+                units_consumed: None,
+                return_data: None,
+                inner_instructions: None,
+            })
+        }
+        _ => return Err(Unanswered::MethodNotFound),
+    })
+}
+
+#[async_trait]
+impl RpcSender for MockSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let method = request.to_string();
+        let (code, message) = match answer(&method, &params) {
+            Ok(value) => return Ok(value),
+            Err(Unanswered::MethodNotFound) => (METHOD_NOT_FOUND, format!("{} is not supported in mock mode", method)),
+            Err(Unanswered::InvalidParams) => (INVALID_PARAMS, format!("Invalid params for {}", method)),
+        };
+        Err(RpcError::RpcResponseError { code, message, data: RpcResponseErrorData::Empty }.into())
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        RpcTransportStats::default()
+    }
+
+    fn url(&self) -> String {
+        URL.to_string()
+    }
+}
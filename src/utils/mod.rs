@@ -1,11 +1,118 @@
+pub mod account;
+pub mod address;
+pub mod airdrop;
+pub mod audit;
+pub mod balance;
+pub mod batch;
+pub mod blockhash;
+pub mod borsh_schema;
+pub mod clone;
+pub mod cnft;
+pub mod compose;
+pub mod compute_budget;
+pub mod confirmation;
+pub mod ed25519;
+pub mod encoding;
+pub mod explain;
+pub mod fee;
+pub mod footprint;
+// Debug builds only; release builds don't serve fixtures
+#[cfg(debug_assertions)]
+pub mod fixtures;
 pub mod generate_keypair;
+pub mod health;
+pub mod idl;
+pub mod heuristics;
+pub mod i18n;
+pub mod instruction;
+pub mod jobs;
+pub mod lookup_table;
 pub mod response_types;
 pub mod token;
+pub mod token_2022;
+pub mod token_metadata;
+pub mod transaction;
+pub mod memo;
 pub mod message;
+pub mod mock_rpc;
+pub mod mint_config;
+pub mod multisig;
+pub mod nft;
+pub mod nonce;
+pub mod pay;
+pub mod pending;
+pub mod preload;
+pub mod program_allowlist;
+pub mod raw_instruction;
+pub mod registry;
+pub mod rewards;
+pub mod rpc;
+pub mod secp256k1;
+pub mod shadow_rpc;
+pub mod signing;
+pub mod signer;
+pub mod siws;
+pub mod sns;
+pub mod sponsor;
+pub mod stake;
+pub mod stake_pool;
+pub mod timing;
 pub mod transfer;
+pub mod vanity;
+pub mod vault;
+pub mod vote;
+pub mod wallet_adapter;
+pub mod webhook;
+pub mod wrapped_sol;
 
+pub use account::*;
+pub use address::*;
+pub use airdrop::*;
+pub use audit::{get_audit_log, AuditEntry, AuditOperation, AuditQuery, AuditResponse};
+pub use balance::*;
+pub use batch::*;
+pub use blockhash::*;
+pub use borsh_schema::{decode_borsh, encode_borsh, DecodeBorshRequest, DecodeBorshResponse, EncodeBorshRequest, EncodeBorshResponse};
+pub use clone::*;
+pub use cnft::*;
+pub use compose::*;
+pub use ed25519::*;
+pub use encoding::*;
+pub use explain::*;
+pub use fee::*;
+pub use footprint::*;
 pub use generate_keypair::*;
+pub use health::*;
+pub use idl::{build_idl_instruction_for, get_idl, register_idl, IdlInstructionRequest, IdlResponse, RegisterIdlRequest};
+pub use instruction::*;
+pub use jobs::*;
+pub use lookup_table::*;
 pub use response_types::*;
 pub use token::*;
+pub use token_2022::*;
+pub use token_metadata::*;
+pub use transaction::*;
+pub use memo::*;
 pub use message::*;
+pub use multisig::*;
+pub use nft::*;
+pub use nonce::*;
+pub use raw_instruction::*;
+pub use pay::{pay_metadata, pay_transaction, PayMetadataResponse, PayTransactionRequest, PayTransactionResponse};
+pub use pending::*;
+pub use rewards::*;
+pub use secp256k1::*;
+pub use siws::*;
+pub use sns::{resolve_name, ResolveNameResponse, ResolvedDomain, ResolvedName};
+pub use sponsor::*;
+pub use stake::*;
+pub use stake_pool::*;
 pub use transfer::*;
+pub use vanity::*;
+pub use vault::{
+    store_key, get_key, update_key_state, delete_key, StoreKeyRequest, StoreKeyResponse, KeyState, UpdateKeyStateRequest,
+    VaultKeyResponse, DeleteKeyResponse,
+};
+pub use vote::*;
+pub use wallet_adapter::*;
+pub use wrapped_sol::*;
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use super::compose::ComposeTransactionResponse;
+use super::heuristics::TransactionWarning;
+use super::response_types::{SuccessResponse, ErrorResponse};
+use super::transaction::decode_transaction;
+
+// `/transaction/compose?format=multisig` returns the composed message in the
+// form multisig UIs such as Squads import as a proposal: the serialized
+// message in base58 plus enough metadata to review it. Compose with the
+// multisig vault as feePayer so the proposal executes from the vault.
+
+#[derive(Deserialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+pub enum ComposeFormat {
+    #[serde(rename = "multisig")]
+    Multisig,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ComposeFormatQuery {
+    // Omit for the default response shape
+    pub format: Option<ComposeFormat>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MultisigLookupTable {
+    pub address: String,
+    pub writable_indexes: Vec<u8>,
+    pub readonly_indexes: Vec<u8>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MultisigProposalResponse {
+    // Base58 serialized message, without signatures
+    pub message: String,
+    pub version: String,
+    pub fee_payer: String,
+    pub recent_blockhash: String,
+    pub required_signers: Vec<String>,
+    pub program_ids: Vec<String>,
+    pub instruction_count: usize,
+    pub address_lookup_tables: Vec<MultisigLookupTable>,
+    // Carried over from the composed transaction for reviewers
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<TransactionWarning>,
+}
+
+pub fn to_multisig_proposal(
+    result: Result<SuccessResponse<ComposeTransactionResponse>, ErrorResponse>,
+) -> Result<SuccessResponse<MultisigProposalResponse>, ErrorResponse> {
+    let response = result?.data;
+    let message = decode_transaction(&response.transaction)?.message;
+
+    let account_keys = message.static_account_keys();
+    let mut program_ids: Vec<String> = Vec::new();
+    for instruction in message.instructions() {
+        let program_id = instruction.program_id(account_keys).to_string();
+        if !program_ids.contains(&program_id) {
+            program_ids.push(program_id);
+        }
+    }
+
+    let address_lookup_tables = message
+        .address_table_lookups()
+        .unwrap_or_default()
+        .iter()
+        .map(|lookup| MultisigLookupTable {
+            address: lookup.account_key.to_string(),
+            writable_indexes: lookup.writable_indexes.clone(),
+            readonly_indexes: lookup.readonly_indexes.clone(),
+        })
+        .collect();
+
+    Ok(SuccessResponse::new(MultisigProposalResponse {
+        message: bs58::encode(message.serialize()).into_string(),
+        version: response.version,
+        fee_payer: account_keys[0].to_string(),
+        recent_blockhash: response.recent_blockhash,
+        required_signers: response.required_signers,
+        program_ids,
+        instruction_count: message.instructions().len(),
+        address_lookup_tables,
+        warnings: response.warnings,
+    }))
+}
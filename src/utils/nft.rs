@@ -0,0 +1,213 @@
+use borsh::BorshSerialize;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::{
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    packet::PACKET_DATA_SIZE,
+    program_pack::Pack,
+    system_instruction,
+    system_program,
+    transaction::Transaction,
+};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use spl_token::instruction as token_instruction;
+use std::str::FromStr;
+use super::cnft::{master_edition_address, validate_creators, validate_metadata_fields, Creator, CreatorArgs};
+use super::compose::signer_keys;
+use super::footprint::{metadata_address, TOKEN_METADATA_PROGRAM_ID};
+use super::instruction::{validate_pubkey, InstructionResponse};
+use super::preload;
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+use super::token::associated_token_address;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+// Token Metadata instruction tags
+const CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+const CREATE_MASTER_EDITION_V3: u8 = 17;
+
+#[derive(Deserialize, ToSchema)]
+pub struct MintNftRequest {
+    // Pays for every account and is the fee payer of the compiled transaction
+    pub payer: String,
+    // New keypair for the mint, which must sign
+    pub mint: String,
+    // Receives the NFT; defaults to the payer
+    pub owner: Option<String>,
+    // Mint and update authority; defaults to the payer
+    pub authority: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub symbol: String,
+    pub uri: String,
+    #[serde(rename = "sellerFeeBasisPoints", default)]
+    pub seller_fee_basis_points: u16,
+    #[serde(default)]
+    pub creators: Vec<CreatorArgs>,
+    #[serde(rename = "isMutable", default = "default_is_mutable")]
+    pub is_mutable: bool,
+    // Prints allowed from the master edition; none by default, and
+    // unlimited when null
+    #[serde(rename = "maxSupply", default = "default_max_supply")]
+    pub max_supply: Option<u64>,
+    // Compile the instructions into an unsigned legacy transaction
+    #[serde(rename = "recentBlockhash")]
+    pub recent_blockhash: Option<String>,
+}
+
+fn default_is_mutable() -> bool {
+    true
+}
+
+fn default_max_supply() -> Option<u64> {
+    Some(0)
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MintNftResponse {
+    pub mint: String,
+    pub token_account: String,
+    pub metadata: String,
+    pub master_edition: String,
+    // In the order they must appear in the transaction
+    pub instructions: Vec<InstructionResponse>,
+    // Base64, set when a recentBlockhash was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub required_signers: Vec<String>,
+}
+
+// Token Metadata's CreateMetadataAccountArgsV3, in its borsh field order
+#[derive(BorshSerialize)]
+struct CreateMetadataAccountArgs {
+    name: String,
+    symbol: String,
+    uri: String,
+    seller_fee_basis_points: u16,
+    creators: Option<Vec<Creator>>,
+    // Collections, uses and collection details are never set here
+    collection: Option<()>,
+    uses: Option<()>,
+    is_mutable: bool,
+    collection_details: Option<()>,
+}
+
+fn token_metadata_data(tag: u8, args: &impl BorshSerialize) -> Vec<u8> {
+    let mut data = vec![tag];
+    args.serialize(&mut data).expect("writing to a Vec cannot fail");
+    data
+}
+
+pub fn mint_nft(request: MintNftRequest) -> Result<SuccessResponse<MintNftResponse>, ErrorResponse> {
+    // Validate inputs
+    let payer = validate_pubkey(&request.payer, "payer")?;
+    let mint = validate_pubkey(&request.mint, "mint")?;
+    let owner = match request.owner.as_deref() {
+        Some(owner) => validate_pubkey(owner, "owner")?,
+        None => payer,
+    };
+    let authority = match request.authority.as_deref() {
+        Some(authority) => validate_pubkey(authority, "authority")?,
+        None => payer,
+    };
+    if mint == payer || mint == owner || mint == authority {
+        return Err(ErrorResponse::with_code(ApiErrorCode::SameAddress, "The mint must be a new account of its own"));
+    }
+    validate_metadata_fields(&request.name, &request.symbol, &request.uri, request.seller_fee_basis_points)?;
+    let creators = validate_creators(&request.creators)?;
+    // Token Metadata only lets a creator be verified by signing, and the
+    // authority is the only creator that signs here
+    if let Some(creator) = creators.iter().find(|creator| creator.verified && creator.address != authority) {
+        return Err(ErrorResponse::new(format!(
+            "Creator {} can only be verified by signing afterwards; only the authority can be verified at mint",
+            creator.address
+        )));
+    }
+    let recent_blockhash = request
+        .recent_blockhash
+        .as_deref()
+        .map(|blockhash| Hash::from_str(blockhash).map_err(|_| ErrorResponse::new("Invalid recent blockhash")))
+        .transpose()?;
+
+    let token_account = associated_token_address(&owner, &mint, &spl_token::id());
+    let metadata = metadata_address(&mint);
+    let master_edition = master_edition_address(&mint);
+    let mint_lamports = preload::rent().minimum_balance(spl_token::state::Mint::LEN);
+
+    let instruction_error =
+        |err| ErrorResponse::with_code(ApiErrorCode::InstructionFailed, format!("Failed to create token instruction: {}", err));
+    let instructions = vec![
+        system_instruction::create_account(&payer, &mint, mint_lamports, spl_token::state::Mint::LEN as u64, &spl_token::id()),
+        // Creating the master edition takes over both authorities, so the
+        // freeze authority is set for it to take
+        token_instruction::initialize_mint2(&spl_token::id(), &mint, &authority, Some(&authority), 0).map_err(instruction_error)?,
+        create_associated_token_account_idempotent(&payer, &owner, &mint, &spl_token::id()),
+        token_instruction::mint_to_checked(&spl_token::id(), &mint, &token_account, &authority, &[], 1, 0).map_err(instruction_error)?,
+        Instruction {
+            program_id: TOKEN_METADATA_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(metadata, false),
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new_readonly(authority, true),
+                AccountMeta::new(payer, true),
+                AccountMeta::new_readonly(authority, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+            data: token_metadata_data(CREATE_METADATA_ACCOUNT_V3, &CreateMetadataAccountArgs {
+                name: request.name,
+                symbol: request.symbol,
+                uri: request.uri,
+                seller_fee_basis_points: request.seller_fee_basis_points,
+                creators: (!creators.is_empty()).then_some(creators),
+                collection: None,
+                uses: None,
+                is_mutable: request.is_mutable,
+                collection_details: None,
+            }),
+        },
+        Instruction {
+            program_id: TOKEN_METADATA_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(master_edition, false),
+                AccountMeta::new(mint, false),
+                AccountMeta::new_readonly(authority, true),
+                AccountMeta::new_readonly(authority, true),
+                AccountMeta::new(payer, true),
+                AccountMeta::new(metadata, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+            data: token_metadata_data(CREATE_MASTER_EDITION_V3, &request.max_supply),
+        },
+    ];
+
+    let (transaction, required_signers) = match recent_blockhash {
+        Some(recent_blockhash) => {
+            let message = Message::new_with_blockhash(&instructions, Some(&payer), &recent_blockhash);
+            let required_signers = signer_keys(&message.account_keys, message.header.num_required_signatures);
+            let transaction = bincode::serialize(&Transaction::new_unsigned(message))
+                .map_err(|_| ErrorResponse::new("Failed to serialize transaction"))?;
+            if transaction.len() > PACKET_DATA_SIZE {
+                return Err(ErrorResponse::new(format!(
+                    "Transaction is {} bytes, larger than the {} byte limit",
+                    transaction.len(),
+                    PACKET_DATA_SIZE
+                )));
+            }
+            (Some(BASE64.encode(&transaction)), required_signers)
+        }
+        None => (None, Vec::new()),
+    };
+
+    Ok(SuccessResponse::new(MintNftResponse {
+        mint: mint.to_string(),
+        token_account: token_account.to_string(),
+        metadata: metadata.to_string(),
+        master_edition: master_edition.to_string(),
+        instructions: instructions.iter().map(InstructionResponse::from).collect(),
+        transaction,
+        required_signers,
+    }))
+}
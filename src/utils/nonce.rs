@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_client::nonblocking::nonce_utils;
+use solana_sdk::{
+    hash::Hash,
+    nonce::State as NonceState,
+    pubkey::Pubkey,
+    system_instruction,
+};
+use super::instruction::{validate_pubkey, InstructionResponse};
+use super::preload;
+use super::response_types::{SuccessResponse, ErrorResponse};
+use super::rpc::{self, rpc_error};
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateNonceAccountRequest {
+    pub from: String,
+    #[serde(rename = "nonceAccount")]
+    pub nonce_account: String,
+    pub authority: String,
+    // Defaults to the rent-exempt minimum for a nonce account
+    pub lamports: Option<u64>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AdvanceNonceRequest {
+    #[serde(rename = "nonceAccount")]
+    pub nonce_account: String,
+    pub authority: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct WithdrawNonceRequest {
+    #[serde(rename = "nonceAccount")]
+    pub nonce_account: String,
+    pub authority: String,
+    pub destination: String,
+    pub lamports: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreateNonceAccountResponse {
+    pub nonce_account: String,
+    pub lamports: u64,
+    pub instructions: Vec<InstructionResponse>,
+}
+
+pub fn create_nonce_account(request: CreateNonceAccountRequest) -> Result<SuccessResponse<CreateNonceAccountResponse>, ErrorResponse> {
+    // Validate inputs
+    let from = validate_pubkey(&request.from, "funding address")?;
+    let nonce_account = validate_pubkey(&request.nonce_account, "nonce account")?;
+    let authority = validate_pubkey(&request.authority, "nonce authority")?;
+
+    if from == nonce_account {
+        return Err(ErrorResponse::new("Funding address and nonce account cannot be the same"));
+    }
+
+    let minimum = preload::rent().minimum_balance(NonceState::size());
+    let lamports = request.lamports.unwrap_or(minimum);
+    if lamports < minimum {
+        return Err(ErrorResponse::new(format!(
+            "Nonce accounts need at least {} lamports to be rent exempt",
+            minimum
+        )));
+    }
+
+    // System create_account followed by InitializeNonceAccount
+    let instructions = system_instruction::create_nonce_account(&from, &nonce_account, &authority, lamports);
+
+    let response = CreateNonceAccountResponse {
+        nonce_account: nonce_account.to_string(),
+        lamports,
+        instructions: instructions.iter().map(InstructionResponse::from).collect(),
+    };
+
+    Ok(SuccessResponse::new(response))
+}
+
+pub fn advance_nonce(request: AdvanceNonceRequest) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    let nonce_account = validate_pubkey(&request.nonce_account, "nonce account")?;
+    let authority = validate_pubkey(&request.authority, "nonce authority")?;
+
+    let instruction = system_instruction::advance_nonce_account(&nonce_account, &authority);
+
+    Ok(SuccessResponse::new(InstructionResponse::from(&instruction)))
+}
+
+pub fn withdraw_nonce(request: WithdrawNonceRequest) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    let nonce_account = validate_pubkey(&request.nonce_account, "nonce account")?;
+    let authority = validate_pubkey(&request.authority, "nonce authority")?;
+    let destination = validate_pubkey(&request.destination, "destination address")?;
+    if request.lamports == 0 {
+        return Err(ErrorResponse::new("Amount must be greater than 0 lamports"));
+    }
+
+    if nonce_account == destination {
+        return Err(ErrorResponse::new("Nonce account and destination addresses cannot be the same"));
+    }
+
+    let instruction = system_instruction::withdraw_nonce_account(
+        &nonce_account,
+        &authority,
+        &destination,
+        request.lamports,
+    );
+
+    Ok(SuccessResponse::new(InstructionResponse::from(&instruction)))
+}
+
+// The blockhash currently stored in a nonce account, after checking that it
+// is initialized and controlled by the expected authority
+pub async fn fetch_nonce_blockhash(nonce_account: &Pubkey, authority: &Pubkey) -> Result<Hash, ErrorResponse> {
+    let nonce_pubkey = *nonce_account;
+    let account = rpc::read("getAccountInfo", move |client| async move {
+        client.get_account(&nonce_pubkey).await
+    })
+        .await
+        .map_err(rpc_error)?;
+    let data = nonce_utils::data_from_account(&account)
+        .map_err(|err| ErrorResponse::new(format!("Account {} is not a usable nonce account: {}", nonce_account, err)))?;
+
+    if data.authority != *authority {
+        return Err(ErrorResponse::new(format!(
+            "Nonce account {} is controlled by {}, not the given nonce authority",
+            nonce_account, data.authority
+        )));
+    }
+
+    Ok(data.blockhash())
+}
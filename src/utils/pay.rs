@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::{
+    instruction::AccountMeta,
+    message::Message,
+    pubkey::Pubkey,
+    system_instruction,
+    transaction::Transaction,
+};
+use spl_token::instruction as token_instruction;
+use crate::config;
+use super::instruction::validate_pubkey;
+use super::mint_config;
+use super::preload;
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+use super::rpc;
+use super::token::{associated_token_address, fetch_mint};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+// Solana Pay transaction requests: a wallet scanning
+// solana:https://<host>/v1/pay/<reference> reads the label and icon with a
+// GET, then POSTs the payer's account to get a transaction to sign. The
+// payment is fixed by the server's configuration, and the reference is
+// attached to the transfer so the merchant can find it on chain.
+
+#[derive(Serialize, ToSchema)]
+pub struct PayMetadataResponse {
+    pub label: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PayTransactionRequest {
+    // The wallet paying, which is also the fee payer
+    pub account: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PayTransactionResponse {
+    // Base64 unsigned legacy transaction
+    pub transaction: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+struct Payment {
+    recipient: Pubkey,
+    amount: u64,
+    spl_token: Option<Pubkey>,
+}
+
+pub fn is_enabled() -> bool {
+    let config = config::get();
+    config.pay_recipient.is_some() && config.pay_amount.is_some()
+}
+
+fn payment() -> Result<Payment, ErrorResponse> {
+    let config = config::get();
+    let (Some(recipient), Some(amount)) = (&config.pay_recipient, config.pay_amount) else {
+        return Err(ErrorResponse::new("Solana Pay is not enabled on this server"));
+    };
+    if amount == 0 {
+        return Err(ErrorResponse::with_code(ApiErrorCode::AmountZero, "Amount must be greater than 0"));
+    }
+    Ok(Payment {
+        recipient: validate_pubkey(recipient, "recipient")?,
+        amount,
+        spl_token: config.pay_spl_token.as_deref().map(|mint| validate_pubkey(mint, "spl-token")).transpose()?,
+    })
+}
+
+pub fn pay_metadata() -> Result<SuccessResponse<PayMetadataResponse>, ErrorResponse> {
+    payment()?;
+    let config = config::get();
+    Ok(SuccessResponse::new(PayMetadataResponse {
+        label: config.pay_label.clone(),
+        icon: config.pay_icon.clone(),
+    }))
+}
+
+pub async fn pay_transaction(reference: String, request: PayTransactionRequest) -> Result<SuccessResponse<PayTransactionResponse>, ErrorResponse> {
+    // Validate inputs
+    let payment = payment()?;
+    let reference = validate_pubkey(&reference, "reference")?;
+    if request.account.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
+    }
+    let account = validate_pubkey(&request.account, "account")?;
+    if account == payment.recipient {
+        return Err(ErrorResponse::with_code(ApiErrorCode::SameAddress, "The payer cannot be the recipient"));
+    }
+
+    let mut transfer = match payment.spl_token {
+        None => system_instruction::transfer(&account, &payment.recipient, payment.amount),
+        Some(mint) => {
+            mint_config::check_transfer(&mint, payment.amount)?;
+            let decimals = match mint_config::forced_decimals(&mint).or_else(|| preload::cached_mint_decimals(&mint)) {
+                Some(decimals) => decimals,
+                None => fetch_mint(mint).await?.decimals,
+            };
+            token_instruction::transfer_checked(
+                &spl_token::id(),
+                &associated_token_address(&account, &mint, &spl_token::id()),
+                &mint,
+                &associated_token_address(&payment.recipient, &mint, &spl_token::id()),
+                &account,
+                &[],
+                payment.amount,
+                decimals,
+            ).map_err(|e| ErrorResponse::with_code(ApiErrorCode::InstructionFailed, format!("Failed to create transfer instruction: {}", e)))?
+        }
+    };
+    transfer.accounts.push(AccountMeta::new_readonly(reference, false));
+
+    let recent_blockhash = rpc::latest_blockhash().await?;
+    let message = Message::new_with_blockhash(&[transfer], Some(&account), &recent_blockhash);
+    let transaction = bincode::serialize(&Transaction::new_unsigned(message))
+        .map_err(|_| ErrorResponse::new("Failed to serialize transaction"))?;
+
+    Ok(SuccessResponse::new(PayTransactionResponse {
+        transaction: BASE64.encode(transaction),
+        message: config::get().pay_message.clone(),
+    }))
+}
@@ -0,0 +1,214 @@
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use super::instruction::validate_pubkey;
+use super::message::{validate_signature, SignatureEncoding};
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+use super::transaction::{decode_transaction, missing_signers, required_signers};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+// Pending transactions are dropped this long after they were created,
+// whether or not every signature arrived
+const PENDING_TTL_SECS: u64 = 60 * 60;
+const MAX_PENDING: usize = 10_000;
+const PENDING_ID_LEN: usize = 24;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreatePendingTransactionRequest {
+    // Base64; any signatures already on it are kept once verified
+    pub transaction: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PartialSignature {
+    pub pubkey: String,
+    // Base64 or base58, detected unless signatureEncoding says which
+    pub signature: String,
+    #[serde(rename = "signatureEncoding")]
+    pub signature_encoding: Option<SignatureEncoding>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AddSignaturesRequest {
+    // Detached signatures over the transaction's message
+    #[serde(default)]
+    pub signatures: Vec<PartialSignature>,
+    // The same transaction as signed by a wallet, base64; its signatures
+    // are merged in
+    pub transaction: Option<String>,
+}
+
+#[derive(Serialize, ToSchema, Clone)]
+pub struct PendingTransactionResponse {
+    pub id: String,
+    // Base64, carrying every signature collected so far; ready to submit
+    // once complete
+    pub transaction: String,
+    pub required_signers: Vec<String>,
+    pub signed_by: Vec<String>,
+    pub missing_signers: Vec<String>,
+    pub complete: bool,
+    // Unix seconds
+    pub expires_at: u64,
+}
+
+struct Pending {
+    transaction: VersionedTransaction,
+    expires_at: u64,
+}
+
+static PENDING: OnceLock<Mutex<HashMap<String, Pending>>> = OnceLock::new();
+
+fn pending() -> &'static Mutex<HashMap<String, Pending>> {
+    PENDING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn unknown() -> ErrorResponse {
+    ErrorResponse::new("Unknown or expired pending transaction")
+}
+
+fn respond(id: &str, pending: &Pending) -> Result<PendingTransactionResponse, ErrorResponse> {
+    let required_signers = required_signers(&pending.transaction)?;
+    let missing_signers = missing_signers(&pending.transaction);
+    let transaction = bincode::serialize(&pending.transaction)
+        .map_err(|_| ErrorResponse::new("Failed to serialize transaction"))?;
+    Ok(PendingTransactionResponse {
+        id: id.to_string(),
+        transaction: BASE64.encode(transaction),
+        required_signers: required_signers.iter().map(Pubkey::to_string).collect(),
+        signed_by: required_signers
+            .iter()
+            .map(Pubkey::to_string)
+            .filter(|signer| !missing_signers.contains(signer))
+            .collect(),
+        complete: missing_signers.is_empty(),
+        missing_signers,
+        expires_at: pending.expires_at,
+    })
+}
+
+// Places the signature at the signer's index once it is shown to be over
+// this transaction's message
+fn add_signature(transaction: &mut VersionedTransaction, message: &[u8], pubkey: Pubkey, signature: Signature) -> Result<(), ErrorResponse> {
+    let index = required_signers(transaction)?
+        .iter()
+        .position(|key| *key == pubkey)
+        .ok_or_else(|| ErrorResponse::new(format!("{} is not a required signer of this transaction", pubkey)))?;
+    if !signature.verify(pubkey.as_ref(), message) {
+        return Err(ErrorResponse::with_code(
+            ApiErrorCode::InvalidSignature,
+            format!("The signature from {} is not over this transaction", pubkey),
+        ));
+    }
+    transaction.signatures[index] = signature;
+    Ok(())
+}
+
+// Signatures already present are checked the same way as ones added later,
+// so a bad one can't sit on the record as if it were collected
+fn verified_signatures(transaction: &VersionedTransaction) -> Result<Vec<(Pubkey, Signature)>, ErrorResponse> {
+    let message = transaction.message.serialize();
+    let signed: Vec<(Pubkey, Signature)> = required_signers(transaction)?
+        .iter()
+        .zip(&transaction.signatures)
+        .filter(|(_, signature)| **signature != Signature::default())
+        .map(|(key, signature)| (*key, *signature))
+        .collect();
+    if let Some((key, _)) = signed.iter().find(|(key, signature)| !signature.verify(key.as_ref(), &message)) {
+        return Err(ErrorResponse::with_code(ApiErrorCode::InvalidSignature, format!("The signature from {} is not over this transaction", key)));
+    }
+    Ok(signed)
+}
+
+pub fn create_pending_transaction(request: CreatePendingTransactionRequest) -> Result<SuccessResponse<PendingTransactionResponse>, ErrorResponse> {
+    // Validate inputs
+    if request.transaction.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
+    }
+    let transaction = decode_transaction(&request.transaction)?;
+    verified_signatures(&transaction)?;
+
+    let id: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(PENDING_ID_LEN)
+        .map(char::from)
+        .collect();
+    let entry = Pending { transaction, expires_at: now() + PENDING_TTL_SECS };
+    let response = respond(&id, &entry)?;
+
+    let mut pending = pending().lock().unwrap();
+    let now = now();
+    pending.retain(|_, entry| entry.expires_at > now);
+    if pending.len() >= MAX_PENDING {
+        return Err(ErrorResponse::with_code(ApiErrorCode::RateLimited, "Too many pending transactions, try again later"));
+    }
+    pending.insert(id, entry);
+
+    Ok(SuccessResponse::new(response))
+}
+
+pub fn add_signatures(id: String, request: AddSignaturesRequest) -> Result<SuccessResponse<PendingTransactionResponse>, ErrorResponse> {
+    // Validate inputs
+    if request.signatures.is_empty() && request.transaction.is_none() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Give signatures or a signed transaction"));
+    }
+    let mut signatures = request
+        .signatures
+        .iter()
+        .enumerate()
+        .map(|(index, partial)| {
+            let pubkey = validate_pubkey(&partial.pubkey, &format!("signatures[{}].pubkey", index))?;
+            let signature = validate_signature(&partial.signature, partial.signature_encoding)?;
+            Ok((pubkey, signature))
+        })
+        .collect::<Result<Vec<_>, ErrorResponse>>()?;
+    let signed = request.transaction.as_deref().map(decode_transaction).transpose()?;
+
+    let mut pending = pending().lock().unwrap();
+    let entry = pending
+        .get_mut(&id)
+        .filter(|entry| entry.expires_at > now())
+        .ok_or_else(unknown)?;
+    if let Some(signed) = signed {
+        if signed.message != entry.transaction.message {
+            return Err(ErrorResponse::new("The signed transaction's message differs from the pending transaction's"));
+        }
+        signatures.extend(verified_signatures(&signed)?);
+    }
+
+    // Every signature is checked before any is kept
+    let message = entry.transaction.message.serialize();
+    let mut transaction = entry.transaction.clone();
+    for (pubkey, signature) in signatures {
+        add_signature(&mut transaction, &message, pubkey, signature)?;
+    }
+    entry.transaction = transaction;
+
+    Ok(SuccessResponse::new(respond(&id, entry)?))
+}
+
+pub fn get_pending_transaction(id: String) -> Result<SuccessResponse<PendingTransactionResponse>, ErrorResponse> {
+    let pending = pending().lock().unwrap();
+    let entry = pending
+        .get(&id)
+        .filter(|entry| entry.expires_at > now())
+        .ok_or_else(unknown)?;
+    Ok(SuccessResponse::new(respond(&id, entry)?))
+}
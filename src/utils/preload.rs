@@ -0,0 +1,100 @@
+use solana_sdk::{
+    account::from_account,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar,
+};
+use spl_token::state::Mint;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    OnceLock,
+};
+use crate::config;
+use super::explain::KNOWN_MINTS;
+use super::mint_config;
+use super::response_types::ErrorResponse;
+use super::rpc::{self, rpc_error};
+
+// getMultipleAccounts accepts at most this many addresses per call
+const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+
+static RENT: OnceLock<Rent> = OnceLock::new();
+static PRELOADED: AtomicBool = AtomicBool::new(false);
+
+pub fn is_preloaded() -> bool {
+    PRELOADED.load(Ordering::Relaxed)
+}
+
+// Cluster rent parameters, falling back to the defaults until they are loaded
+pub fn rent() -> Rent {
+    RENT.get().cloned().unwrap_or_default()
+}
+
+pub fn cached_mint_decimals(mint: &Pubkey) -> Option<u8> {
+    rpc::mint_decimals().get(mint)
+}
+
+pub fn cache_mint_decimals(mint: Pubkey, decimals: u8) {
+    rpc::mint_decimals().insert(mint, decimals);
+}
+
+async fn load_rent() -> Result<(), ErrorResponse> {
+    let account = rpc::read("getAccountInfo", |client| async move { client.get_account(&sysvar::rent::id()).await })
+        .await
+        .map_err(rpc_error)?;
+    let rent: Rent = from_account(&account)
+        .ok_or_else(|| ErrorResponse::new("Rent sysvar has an unexpected layout"))?;
+    let _ = RENT.set(rent);
+    Ok(())
+}
+
+// Decimals of the configured mints and the well-known token list
+async fn load_mint_decimals() -> Result<(), ErrorResponse> {
+    let mut mints: Vec<Pubkey> = mint_config::configured_mints().copied().collect();
+    mints.extend(KNOWN_MINTS.iter().map(|(mint, _)| *mint));
+    mints.sort();
+    mints.dedup();
+
+    for chunk in mints.chunks(MAX_ACCOUNTS_PER_REQUEST) {
+        let addresses = chunk.to_vec();
+        let accounts = rpc::read("getMultipleAccounts", move |client| {
+            let addresses = addresses.clone();
+            async move { client.get_multiple_accounts(&addresses).await }
+        })
+            .await
+            .map_err(rpc_error)?;
+
+        // Token-2022 mints share the base layout, with extensions after it
+        for (mint, account) in chunk.iter().zip(accounts) {
+            if let Some(state) = account
+                .and_then(|account| account.data.get(..Mint::LEN).and_then(|data| Mint::unpack_from_slice(data).ok()))
+            {
+                cache_mint_decimals(*mint, state.decimals);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Warm the caches the first requests after a deploy would otherwise fill,
+// then mark the server as preloaded so /ready starts passing
+// Preloading is opt-in with PRELOAD_ON_STARTUP=true, since it delays
+// readiness until the RPC node answers
+pub fn spawn_preloader() {
+    if !config::get().preload_on_startup {
+        return;
+    }
+
+    tokio::spawn(async {
+        let (rent, mints, blockhash) = tokio::join!(load_rent(), load_mint_decimals(), rpc::refresh_blockhash());
+        for (step, result) in [("rent", rent), ("mint decimals", mints), ("blockhash", blockhash.map(|_| ()))] {
+            if let Err(err) = result {
+                tracing::warn!(step, error = %err.error, "failed to preload data");
+            }
+        }
+        PRELOADED.store(true, Ordering::Relaxed);
+        tracing::info!("startup preload finished");
+    });
+}
@@ -0,0 +1,79 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderMap},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    env,
+    sync::OnceLock,
+};
+use super::response_types::ErrorResponse;
+use super::siws::verify_session_token;
+
+const DEFAULT_TENANT: &str = "default";
+
+// Program ids each tenant may target, read from PROGRAM_ALLOWLISTS as JSON,
+// e.g. {"default": ["11111111111111111111111111111111"], "<wallet>": [...]}.
+// Tenants without an entry fall back to "default"; when neither exists, or
+// the variable is unset, every program is allowed.
+static ALLOWLISTS: OnceLock<HashMap<String, HashSet<String>>> = OnceLock::new();
+
+fn allowlists() -> &'static HashMap<String, HashSet<String>> {
+    ALLOWLISTS.get_or_init(|| {
+        let Ok(config) = env::var("PROGRAM_ALLOWLISTS") else {
+            return HashMap::new();
+        };
+        serde_json::from_str(&config).unwrap_or_else(|err| {
+            panic!("PROGRAM_ALLOWLISTS is not a JSON object of program id lists: {}", err)
+        })
+    })
+}
+
+// The caller's tenant: the wallet of a SIWS session, or "default"
+pub struct Tenant(pub String);
+
+impl Tenant {
+    pub fn from_headers(headers: &HeaderMap) -> Tenant {
+        let wallet = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(verify_session_token);
+
+        Tenant(wallet.map_or_else(|| DEFAULT_TENANT.to_string(), |wallet| wallet.to_string()))
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Tenant {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Tenant::from_headers(&parts.headers))
+    }
+}
+
+// Reject instructions that target a program outside the tenant's allowlist,
+// recording each violation in the audit log
+pub fn check_programs<'a>(tenant: &Tenant, program_ids: impl IntoIterator<Item = &'a str>) -> Result<(), ErrorResponse> {
+    let allowlists = allowlists();
+    let Some(allowed) = allowlists.get(&tenant.0).or_else(|| allowlists.get(DEFAULT_TENANT)) else {
+        return Ok(());
+    };
+
+    for program_id in program_ids {
+        if !allowed.contains(program_id) {
+            tracing::warn!(
+                target: "audit",
+                tenant = %tenant.0,
+                program_id,
+                "rejected instruction for a program outside the tenant allowlist"
+            );
+            return Err(ErrorResponse::new(format!("Program {} is not allowed for this tenant", program_id)));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    packet::PACKET_DATA_SIZE,
+};
+use super::instruction::{validate_pubkey, InstructionResponse};
+use super::program_allowlist::{check_programs, Tenant};
+use super::response_types::{ApiErrorCode, FieldErrors, SuccessResponse, ErrorResponse};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+// A transaction can't lock more accounts than this, so no instruction in
+// one can name more
+const MAX_RAW_ACCOUNTS: usize = 64;
+
+#[derive(Deserialize, ToSchema)]
+pub struct RawAccount {
+    pub pubkey: String,
+    #[serde(rename = "isSigner", default)]
+    pub is_signer: bool,
+    #[serde(rename = "isWritable", default)]
+    pub is_writable: bool,
+}
+
+#[derive(Deserialize, ToSchema, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RawDataEncoding {
+    #[default]
+    Base64,
+    Base58,
+    Hex,
+}
+
+// Either an encoded string or the bytes themselves, such as [2, 0, 0, 0]
+#[derive(Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum RawData {
+    Bytes(Vec<u8>),
+    Encoded(String),
+}
+
+impl Default for RawData {
+    fn default() -> Self {
+        RawData::Bytes(Vec::new())
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RawInstructionRequest {
+    #[serde(rename = "programId")]
+    pub program_id: String,
+    // In the order the program expects them
+    #[serde(default)]
+    pub accounts: Vec<RawAccount>,
+    #[serde(default)]
+    pub data: RawData,
+    // How `data` is encoded when it is a string
+    #[serde(rename = "dataEncoding", default)]
+    pub data_encoding: RawDataEncoding,
+}
+
+fn decode_data(data: &RawData, encoding: RawDataEncoding) -> Result<Vec<u8>, ErrorResponse> {
+    let bytes = match (data, encoding) {
+        (RawData::Bytes(bytes), _) => bytes.clone(),
+        (RawData::Encoded(data), RawDataEncoding::Base64) => BASE64.decode(data)
+            .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidEncoding, "Invalid base64 encoding for instruction data"))?,
+        (RawData::Encoded(data), RawDataEncoding::Base58) => bs58::decode(data)
+            .into_vec()
+            .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidEncoding, "Invalid base58 encoding for instruction data"))?,
+        (RawData::Encoded(data), RawDataEncoding::Hex) => hex::decode(data)
+            .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidEncoding, "Invalid hex encoding for instruction data"))?,
+    };
+    if bytes.len() > PACKET_DATA_SIZE {
+        return Err(ErrorResponse::new(format!("Instruction data must be at most {} bytes", PACKET_DATA_SIZE)));
+    }
+    Ok(bytes)
+}
+
+// Builds the instruction exactly as described; nothing is known about the
+// program, so only the shape of the request can be checked
+pub fn build_raw_instruction(request: RawInstructionRequest) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    if request.program_id.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
+    }
+    if request.accounts.len() > MAX_RAW_ACCOUNTS {
+        return Err(ErrorResponse::with_code(
+            ApiErrorCode::BatchTooLarge,
+            format!("At most {} accounts can be passed to an instruction", MAX_RAW_ACCOUNTS),
+        ));
+    }
+
+    let mut fields = FieldErrors::default();
+    let program_id = fields.check("programId", validate_pubkey(&request.program_id, "program id"));
+    let accounts: Vec<AccountMeta> = request
+        .accounts
+        .iter()
+        .enumerate()
+        .map(|(index, account)| {
+            let field = format!("accounts[{}].pubkey", index);
+            let pubkey = fields.check(&field, validate_pubkey(&account.pubkey, &field));
+            AccountMeta { pubkey, is_signer: account.is_signer, is_writable: account.is_writable }
+        })
+        .collect();
+    let data = fields.check("data", decode_data(&request.data, request.data_encoding));
+    fields.finish()?;
+
+    let instruction = Instruction { program_id, accounts, data };
+
+    Ok(SuccessResponse::new(InstructionResponse::from(&instruction)))
+}
+
+// The HTTP endpoint holds the program to the tenant's allowlist, as batches
+// and composed transactions are
+pub fn build_raw_instruction_for(request: RawInstructionRequest, tenant: &Tenant) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    let response = build_raw_instruction(request)?;
+    check_programs(tenant, [response.data.program_id.as_str()])?;
+    Ok(response)
+}
@@ -0,0 +1,227 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{borrow::Cow, sync::OnceLock};
+use utoipa::{
+    openapi::{
+        schema::{AllOfBuilder, Discriminator, ObjectBuilder, OneOfBuilder, Schema, Type},
+        Ref, RefOr,
+    },
+    PartialSchema, ToSchema,
+};
+use super::account::{create_account, create_account_with_seed};
+use super::batch::{BatchInstruction, IntoInstructions};
+use super::cnft::{create_tree, mint_compressed_nft};
+use super::lookup_table::{close_lookup_table, create_lookup_table, deactivate_lookup_table, extend_lookup_table};
+use super::memo::build_memo;
+use super::nft::mint_nft;
+use super::nonce::{advance_nonce, create_nonce_account, withdraw_nonce};
+use super::raw_instruction::build_raw_instruction;
+use super::response_types::{SuccessResponse, ErrorResponse};
+use super::stake::{create_stake_account, deactivate_stake, delegate_stake, split_stake, withdraw_stake};
+use super::token::{approve_token, close_token_account, create_token, mint_token, revoke_token, send_token};
+use super::token_2022::{harvest_transfer_fees, initialize_transfer_fee_mint, transfer_with_fee};
+use super::transfer::{send_sol, transfer_with_seed};
+use super::wrapped_sol::{unwrap_sol, wrap_sol};
+use super::vote::{authorize_vote, create_vote_account, update_commission, withdraw_from_vote_account};
+
+// An operation that can be composed into a transaction. Every registered
+// builder is accepted by /instructions/batch, /transaction/compose, and the
+// CLI, and its parameters are published in the OpenAPI spec.
+pub trait InstructionBuilder: Send + Sync {
+    // Value of the `type` tag that selects this builder
+    fn name(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    // Name of the parameters schema under #/components/schemas
+    fn schema_name(&self) -> String;
+
+    // The parameters schema and every schema it references
+    fn schemas(&self) -> Vec<(String, RefOr<Schema>)>;
+
+    fn build(&self, params: serde_json::Value) -> Result<Vec<BatchInstruction>, ErrorResponse>;
+}
+
+// Adapts a `fn(Request) -> Result<SuccessResponse<Response>, ErrorResponse>`
+// builder, the shape every instruction module already uses
+pub struct FnBuilder<Req, Res> {
+    name: &'static str,
+    description: &'static str,
+    build: fn(Req) -> Result<SuccessResponse<Res>, ErrorResponse>,
+}
+
+impl<Req, Res> FnBuilder<Req, Res> {
+    pub fn new(
+        name: &'static str,
+        description: &'static str,
+        build: fn(Req) -> Result<SuccessResponse<Res>, ErrorResponse>,
+    ) -> Self {
+        FnBuilder { name, description, build }
+    }
+}
+
+impl<Req, Res> InstructionBuilder for FnBuilder<Req, Res>
+where
+    Req: DeserializeOwned + ToSchema,
+    Res: IntoInstructions,
+{
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn schema_name(&self) -> String {
+        Req::name().into_owned()
+    }
+
+    fn schemas(&self) -> Vec<(String, RefOr<Schema>)> {
+        let mut schemas = vec![(Req::name().into_owned(), Req::schema())];
+        Req::schemas(&mut schemas);
+        schemas
+    }
+
+    fn build(&self, params: serde_json::Value) -> Result<Vec<BatchInstruction>, ErrorResponse> {
+        let request: Req = serde_json::from_value(params)
+            .map_err(|err| ErrorResponse::new(format!("Invalid parameters: {}", err)))?;
+        Ok((self.build)(request)?.data.into_instructions())
+    }
+}
+
+pub struct Registry {
+    builders: Vec<Box<dyn InstructionBuilder>>,
+}
+
+impl Registry {
+    fn new() -> Self {
+        Registry { builders: Vec::new() }
+    }
+
+    pub fn register(&mut self, builder: impl InstructionBuilder + 'static) {
+        assert!(
+            self.get(builder.name()).is_none(),
+            "instruction builder '{}' is registered twice",
+            builder.name()
+        );
+        self.builders.push(Box::new(builder));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn InstructionBuilder> {
+        self.builders
+            .iter()
+            .find(|builder| builder.name() == name)
+            .map(|builder| builder.as_ref())
+    }
+
+    pub fn builders(&self) -> impl Iterator<Item = &dyn InstructionBuilder> {
+        self.builders.iter().map(|builder| builder.as_ref())
+    }
+}
+
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+// Builders are registered here once; extensions behind a cargo feature
+// register under a matching #[cfg(feature = "...")]
+pub fn registry() -> &'static Registry {
+    REGISTRY.get_or_init(|| {
+        let mut registry = Registry::new();
+        registry.register(FnBuilder::new("sendSol", "Transfer SOL between system accounts", send_sol));
+        registry.register(FnBuilder::new("sendToken", "Transfer SPL tokens between associated token accounts", send_token));
+        registry.register(FnBuilder::new("createToken", "Initialize an SPL Token mint", create_token));
+        registry.register(FnBuilder::new("mintToken", "Mint SPL tokens to a token account", mint_token));
+        registry.register(FnBuilder::new("initializeTransferFeeMint", "Create a Token-2022 mint with the transfer fee extension", initialize_transfer_fee_mint));
+        registry.register(FnBuilder::new("harvestTransferFees", "Move withheld Token-2022 transfer fees to the mint and optionally withdraw them", harvest_transfer_fees));
+        registry.register(FnBuilder::new("transferWithFee", "Transfer Token-2022 tokens from a mint that charges a transfer fee", transfer_with_fee));
+        registry.register(FnBuilder::new("approveToken", "Let a delegate transfer up to an allowance from a token account", approve_token));
+        registry.register(FnBuilder::new("revokeToken", "Remove a token account's delegate", revoke_token));
+        registry.register(FnBuilder::new("closeTokenAccount", "Close an empty SPL token account and reclaim its rent", close_token_account));
+        registry.register(FnBuilder::new("createVoteAccount", "Create and initialize a vote account", create_vote_account));
+        registry.register(FnBuilder::new("authorizeVote", "Change a vote account's voter or withdrawer", authorize_vote));
+        registry.register(FnBuilder::new("updateCommission", "Update a vote account's commission", update_commission));
+        registry.register(FnBuilder::new("withdrawVoteAccount", "Withdraw lamports from a vote account", withdraw_from_vote_account));
+        registry.register(FnBuilder::new("createNonceAccount", "Create and initialize a durable nonce account", create_nonce_account));
+        registry.register(FnBuilder::new("advanceNonce", "Advance the blockhash stored in a nonce account", advance_nonce));
+        registry.register(FnBuilder::new("withdrawNonce", "Withdraw lamports from a nonce account", withdraw_nonce));
+        registry.register(FnBuilder::new("createStakeAccount", "Create and initialize a stake account", create_stake_account));
+        registry.register(FnBuilder::new("delegateStake", "Delegate a stake account to a vote account", delegate_stake));
+        registry.register(FnBuilder::new("deactivateStake", "Deactivate a delegated stake account", deactivate_stake));
+        registry.register(FnBuilder::new("withdrawStake", "Withdraw lamports from a stake account", withdraw_stake));
+        registry.register(FnBuilder::new("splitStake", "Split part of a stake account into a new one", split_stake));
+        registry.register(FnBuilder::new("wrapSol", "Wrap SOL into the owner's wrapped SOL token account", wrap_sol));
+        registry.register(FnBuilder::new("unwrapSol", "Close the owner's wrapped SOL token account, unwrapping its balance", unwrap_sol));
+        registry.register(FnBuilder::new("createAccount", "Create a system account owned by a program", create_account));
+        registry.register(FnBuilder::new("createAccountWithSeed", "Create a system account at an address derived from a base key and seed", create_account_with_seed));
+        registry.register(FnBuilder::new("transferWithSeed", "Transfer SOL out of an address derived from a base key and seed", transfer_with_seed));
+        registry.register(FnBuilder::new("memo", "Record a memo with the SPL Memo program", build_memo));
+        registry.register(FnBuilder::new("createLookupTable", "Create an address lookup table for a recent slot", create_lookup_table));
+        registry.register(FnBuilder::new("extendLookupTable", "Add addresses to an address lookup table", extend_lookup_table));
+        registry.register(FnBuilder::new("deactivateLookupTable", "Deactivate an address lookup table so it can later be closed", deactivate_lookup_table));
+        registry.register(FnBuilder::new("closeLookupTable", "Close a deactivated address lookup table and reclaim its rent", close_lookup_table));
+        registry.register(FnBuilder::new("createCompressedNftTree", "Create a Bubblegum merkle tree for compressed NFTs", create_tree));
+        registry.register(FnBuilder::new("mintCompressedNft", "Mint a compressed NFT into a Bubblegum tree, optionally into a verified collection", mint_compressed_nft));
+        registry.register(FnBuilder::new("mintNft", "Create a mint, mint one token to the owner, and add Token Metadata and a master edition", mint_nft));
+        registry.register(FnBuilder::new("rawInstruction", "Call any program with the given accounts and instruction data", build_raw_instruction));
+        registry
+    })
+}
+
+// A single operation, tagged with the name of a registered builder and
+// carrying that builder's parameters alongside the tag
+#[derive(Serialize, Deserialize)]
+pub struct Operation {
+    #[serde(rename = "type")]
+    pub operation_type: String,
+    #[serde(flatten)]
+    pub params: serde_json::Value,
+}
+
+impl Operation {
+    pub fn build(self) -> Result<Vec<BatchInstruction>, ErrorResponse> {
+        let builder = registry()
+            .get(&self.operation_type)
+            .ok_or_else(|| ErrorResponse::new(format!("Unknown operation type '{}'", self.operation_type)))?;
+        builder.build(self.params)
+    }
+}
+
+// The schema is generated from the registry, so new builders show up in the
+// spec without further changes
+impl PartialSchema for Operation {
+    fn schema() -> RefOr<Schema> {
+        let mut one_of = OneOfBuilder::new();
+        let mut discriminator = Discriminator::new("type");
+
+        for builder in registry().builders() {
+            let reference = format!("#/components/schemas/{}", builder.schema_name());
+            let tag = ObjectBuilder::new()
+                .property(
+                    "type",
+                    ObjectBuilder::new().schema_type(Type::String).enum_values(Some([builder.name()])),
+                )
+                .required("type");
+
+            one_of = one_of.item(
+                AllOfBuilder::new()
+                    .item(tag)
+                    .item(Ref::new(reference.clone()))
+                    .description(Some(builder.description())),
+            );
+            discriminator.mapping.insert(builder.name().to_string(), reference);
+        }
+
+        one_of.discriminator(Some(discriminator)).into()
+    }
+}
+
+impl ToSchema for Operation {
+    fn name() -> Cow<'static, str> {
+        Cow::Borrowed("Operation")
+    }
+
+    fn schemas(schemas: &mut Vec<(String, RefOr<Schema>)>) {
+        for builder in registry().builders() {
+            schemas.extend(builder.schemas());
+        }
+    }
+}
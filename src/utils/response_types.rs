@@ -1,15 +1,69 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use super::i18n::{self, Locale};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct SuccessResponse<T> {
     pub success: bool,
     pub data: T,
 }
 
-#[derive(Serialize, Deserialize)]
+// Stable identifiers for failures, so clients can branch on the kind of
+// error without parsing the message, which may be translated
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ApiErrorCode {
+    // Anything without a more specific code
+    InvalidRequest,
+    MissingFields,
+    InvalidPubkey,
+    InvalidSecret,
+    InvalidSignature,
+    // Base58, base64 or hex that doesn't decode
+    InvalidEncoding,
+    InvalidMessage,
+    // A transaction that doesn't deserialize or whose header and keys
+    // don't agree
+    InvalidTransaction,
+    InvalidDecimals,
+    AmountZero,
+    AmountTooLarge,
+    SameAddress,
+    // An on-chain account isn't the kind the request needs
+    InvalidAccount,
+    NotAuthorized,
+    // Missing or unknown X-API-Key
+    InvalidApiKey,
+    AccountNotEmpty,
+    BatchTooLarge,
+    InstructionFailed,
+    RpcUnavailable,
+    // A remote signer named by signerBackend failed or isn't configured
+    SignerUnavailable,
+    RateLimited,
+    Timeout,
+    BodyTooLarge,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Clone)]
+pub struct FieldError {
+    // Name of the request field, as sent
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct ErrorResponse {
     pub success: bool,
     pub error: String,
+    pub code: ApiErrorCode,
+    // Every invalid field when a request has more than one problem; error and
+    // code describe the first of them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    // Matches the X-Request-Id response header and the request's log lines
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl<T> SuccessResponse<T> {
@@ -23,9 +77,55 @@ impl<T> SuccessResponse<T> {
 
 impl ErrorResponse {
     pub fn new(error: impl Into<String>) -> Self {
+        Self::with_code(ApiErrorCode::InvalidRequest, error)
+    }
+
+    pub fn with_code(code: ApiErrorCode, error: impl Into<String>) -> Self {
         Self {
             success: false,
             error: error.into(),
+            code,
+            errors: Vec::new(),
+            request_id: crate::request_id::current(),
+        }
+    }
+
+    // The message and field messages in the caller's language
+    pub fn translated(mut self, locale: Locale) -> Self {
+        self.error = i18n::translate(locale, &self.error);
+        for field_error in &mut self.errors {
+            field_error.message = i18n::translate(locale, &field_error.message);
         }
+        self
+    }
+}
+
+// Collects the problems with a request's fields so they are all reported at
+// once, instead of one per attempt
+#[derive(Default)]
+pub struct FieldErrors {
+    errors: Vec<(ApiErrorCode, FieldError)>,
+}
+
+impl FieldErrors {
+    // The validated value, or a placeholder when the field is invalid; the
+    // placeholder is never used, since finish() fails first
+    pub fn check<T: Default>(&mut self, field: &str, result: Result<T, ErrorResponse>) -> T {
+        result.unwrap_or_else(|err| {
+            self.errors.push((err.code, FieldError {
+                field: field.to_string(),
+                message: err.error,
+            }));
+            T::default()
+        })
+    }
+
+    pub fn finish(self) -> Result<(), ErrorResponse> {
+        let Some((code, first)) = self.errors.first() else {
+            return Ok(());
+        };
+        let mut err = ErrorResponse::with_code(*code, first.message.clone());
+        err.errors = self.errors.into_iter().map(|(_, error)| error).collect();
+        Err(err)
     }
 } 
\ No newline at end of file
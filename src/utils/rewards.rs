@@ -0,0 +1,151 @@
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use solana_client::rpc_response::RpcInflationReward;
+use solana_sdk::{clock::DEFAULT_MS_PER_SLOT, pubkey::Pubkey};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+use crate::cluster::{self, Target};
+use super::instruction::validate_pubkey;
+use super::response_types::{SuccessResponse, ErrorResponse};
+use super::rpc;
+
+const DEFAULT_EPOCHS: u64 = 10;
+const MAX_EPOCHS: u64 = 50;
+const MAX_CACHE_ENTRIES: usize = 10_000;
+const SECONDS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
+
+// Rewards for a completed epoch never change, so they are cached per
+// (cluster, account, epoch) for the lifetime of the process
+type RewardCache = Mutex<HashMap<(Option<Target>, Pubkey, u64), Option<RpcInflationReward>>>;
+
+static REWARD_CACHE: OnceLock<RewardCache> = OnceLock::new();
+
+#[derive(Deserialize, IntoParams)]
+pub struct StakeRewardsQuery {
+    pub epochs: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EpochReward {
+    pub epoch: u64,
+    pub effective_slot: u64,
+    pub amount: u64,
+    pub post_balance: u64,
+    pub commission: Option<u8>,
+    pub rate: f64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct StakeRewardsResponse {
+    pub stake_account: String,
+    pub current_epoch: u64,
+    pub epochs_scanned: u64,
+    pub rewards: Vec<EpochReward>,
+    pub total_rewards: u64,
+    pub apy: Option<f64>,
+}
+
+fn validate_epochs(epochs: Option<u64>) -> Result<u64, ErrorResponse> {
+    let epochs = epochs.unwrap_or(DEFAULT_EPOCHS);
+    if epochs == 0 || epochs > MAX_EPOCHS {
+        return Err(ErrorResponse::new(format!("epochs must be between 1 and {}", MAX_EPOCHS)));
+    }
+    Ok(epochs)
+}
+
+fn cache() -> &'static RewardCache {
+    REWARD_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+async fn fetch_reward(stake_account: Pubkey, epoch: u64, current_epoch: u64) -> Result<Option<RpcInflationReward>, ErrorResponse> {
+    let key = (cluster::current(), stake_account, epoch);
+    if let Some(cached) = cache().lock().unwrap().get(&key) {
+        return Ok(cached.clone());
+    }
+
+    let reward = rpc::read("getInflationReward", move |client| async move {
+        client.get_inflation_reward(&[stake_account], Some(epoch)).await
+    })
+        .await
+        .map_err(rpc::rpc_error)?
+        .pop()
+        .flatten();
+
+    // Rewards for the previous epoch may still be in the middle of being paid
+    // out, so an empty answer is only final for older epochs
+    if reward.is_some() || epoch + 1 < current_epoch {
+        let mut cache = cache().lock().unwrap();
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            cache.clear();
+        }
+        cache.insert(key, reward.clone());
+    }
+
+    Ok(reward)
+}
+
+// Annualize the compounded per-epoch growth observed since the first reward
+fn realized_apy(rewards: &[EpochReward], last_epoch: u64, slots_per_epoch: u64) -> Option<f64> {
+    let first_epoch = rewards.iter().map(|reward| reward.epoch).min()?;
+    let epochs_observed = (last_epoch - first_epoch + 1) as f64;
+
+    let epoch_seconds = slots_per_epoch as f64 * DEFAULT_MS_PER_SLOT as f64 / 1000.0;
+    let epochs_per_year = SECONDS_PER_YEAR / epoch_seconds;
+
+    let growth: f64 = rewards.iter().map(|reward| 1.0 + reward.rate).product();
+    Some(growth.powf(epochs_per_year / epochs_observed) - 1.0)
+}
+
+pub async fn get_stake_rewards(stake_account: String, query: StakeRewardsQuery) -> Result<SuccessResponse<StakeRewardsResponse>, ErrorResponse> {
+    // Validate inputs
+    let stake_pubkey = validate_pubkey(&stake_account, "stake account")?;
+    let epochs = validate_epochs(query.epochs)?;
+
+    let epoch_info = rpc::read("getEpochInfo", |client| client.get_epoch_info())
+        .await
+        .map_err(rpc::rpc_error)?;
+    let epoch_schedule = rpc::read("getEpochSchedule", |client| client.get_epoch_schedule())
+        .await
+        .map_err(rpc::rpc_error)?;
+
+    // Only completed epochs have rewards
+    let current_epoch = epoch_info.epoch;
+    if current_epoch == 0 {
+        return Err(ErrorResponse::new("No completed epochs on this cluster yet"));
+    }
+    let last_epoch = current_epoch - 1;
+    let first_epoch = last_epoch.saturating_sub(epochs - 1);
+
+    let results = join_all(
+        (first_epoch..=last_epoch).map(|epoch| fetch_reward(stake_pubkey, epoch, current_epoch)),
+    ).await;
+
+    let mut rewards = Vec::new();
+    for reward in results {
+        if let Some(reward) = reward? {
+            let principal = reward.post_balance.saturating_sub(reward.amount);
+            rewards.push(EpochReward {
+                epoch: reward.epoch,
+                effective_slot: reward.effective_slot,
+                amount: reward.amount,
+                post_balance: reward.post_balance,
+                commission: reward.commission,
+                rate: if principal == 0 { 0.0 } else { reward.amount as f64 / principal as f64 },
+            });
+        }
+    }
+
+    let response = StakeRewardsResponse {
+        stake_account: stake_pubkey.to_string(),
+        current_epoch,
+        epochs_scanned: last_epoch - first_epoch + 1,
+        total_rewards: rewards.iter().map(|reward| reward.amount).sum(),
+        apy: realized_apy(&rewards, last_epoch, epoch_schedule.slots_per_epoch),
+        rewards,
+    };
+
+    Ok(SuccessResponse::new(response))
+}
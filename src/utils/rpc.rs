@@ -0,0 +1,574 @@
+use moka::sync::Cache;
+use rand::Rng;
+use serde::Serialize;
+use utoipa::ToSchema;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    nonblocking::rpc_client::RpcClient,
+    rpc_request::RpcError,
+};
+use solana_sdk::{account::Account, commitment_config::CommitmentConfig, hash::Hash, pubkey::Pubkey};
+use std::{
+    collections::HashMap,
+    future::Future,
+    hash::Hash as CacheKey,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, OnceLock, RwLock,
+    },
+    time::{Duration, Instant},
+};
+use tracing::{Instrument, Span};
+use crate::cluster::{self, Target};
+use crate::config;
+use super::mock_rpc;
+use super::shadow_rpc;
+use super::timing;
+use super::response_types::{ApiErrorCode, ErrorResponse};
+
+// How often the background task refreshes the latest blockhash, and how old
+// a cached blockhash may get before the cache is considered cold
+pub const BLOCKHASH_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+pub const BLOCKHASH_MAX_AGE: Duration = Duration::from_secs(60);
+
+// How often providers are probed with getHealth when there is more than one
+const PROVIDER_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+// How long a provider is passed over after failing, or after rate-limiting
+// us, unless a health check finds it working again sooner
+const FAILURE_BENCH: Duration = Duration::from_secs(10);
+const RATE_LIMIT_BENCH: Duration = Duration::from_secs(30);
+// Backoff before the first retry, doubling for each one after it
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(200);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(2);
+
+// Rent parameters only change through a feature activation
+const RENT_MINIMUM_TTL: Duration = Duration::from_secs(60 * 60);
+// Accounts can be created or closed at any time, so lookups are only reused
+// briefly, long enough to absorb bursts of requests for the same address
+const ACCOUNT_TTL: Duration = Duration::from_secs(30);
+const MAX_CACHED_ACCOUNTS: u64 = 10_000;
+const MAX_CACHED_MINTS: u64 = 10_000;
+
+static DEFAULT_ENDPOINT: OnceLock<Endpoint> = OnceLock::new();
+static CLUSTER_ENDPOINTS: OnceLock<Mutex<HashMap<Target, &'static Endpoint>>> = OnceLock::new();
+
+// Providers and caches for one cluster, since blockhashes and accounts
+// differ between them. Clusters picked by requests get theirs on first use,
+// kept for the life of the server; there can only be as many as the named
+// clusters and CLUSTER_RPC_URLS.
+struct Endpoint {
+    providers: Vec<Provider>,
+    blockhash: RwLock<Option<CachedBlockhash>>,
+    blockhash_counters: Counters,
+    rent_minimums: LookupCache<usize, u64>,
+    mint_decimals: LookupCache<Pubkey, u8>,
+    accounts: LookupCache<Pubkey, Option<Account>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CacheStats {
+    pub name: &'static str,
+    pub entries: u64,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Counters {
+    const fn new() -> Self {
+        Counters {
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, hit: bool) {
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn stats(&self, name: &'static str, entries: u64) -> CacheStats {
+        CacheStats {
+            name,
+            entries,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// RPC answers kept for reuse, counting how often a lookup was served from
+// the cache so operators can see whether it is paying off
+pub struct LookupCache<K, V> {
+    name: &'static str,
+    cache: Cache<K, V>,
+    counters: Counters,
+}
+
+impl<K, V> LookupCache<K, V>
+where
+    K: CacheKey + Eq + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+{
+    // Entries never expire without a TTL, only make way for newer ones
+    fn new(name: &'static str, capacity: u64, ttl: Option<Duration>) -> Self {
+        let builder = Cache::builder().max_capacity(capacity);
+        let cache = match ttl {
+            Some(ttl) => builder.time_to_live(ttl).build(),
+            None => builder.build(),
+        };
+        LookupCache { name, cache, counters: Counters::new() }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let value = self.cache.get(key);
+        self.counters.record(value.is_some());
+        value
+    }
+
+    // Checks for an entry without counting it as a lookup
+    pub fn contains(&self, key: &K) -> bool {
+        self.cache.contains_key(key)
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        self.cache.insert(key, value);
+    }
+
+    // The cached value, or else the fetched one, which is kept when the
+    // fetch succeeds
+    pub async fn get_or_fetch<E>(&self, key: K, fetch: impl Future<Output = Result<V, E>>) -> Result<V, E> {
+        if let Some(value) = self.get(&key) {
+            return Ok(value);
+        }
+        let value = fetch.await?;
+        self.insert(key, value.clone());
+        Ok(value)
+    }
+
+    fn stats(&self) -> CacheStats {
+        // Entry counts lag behind inserts until pending maintenance runs
+        self.cache.run_pending_tasks();
+        self.counters.stats(self.name, self.cache.entry_count())
+    }
+}
+
+impl Endpoint {
+    fn new(urls: Vec<String>) -> Self {
+        // In mock mode every cluster is answered by the same mock
+        let mock = config::get().mock_rpc;
+        let urls = if mock { vec![mock_rpc::URL.to_string()] } else { urls };
+        Endpoint {
+            providers: urls
+                .into_iter()
+                .map(|url| Provider {
+                    client: if mock {
+                        mock_rpc::client()
+                    } else {
+                        RpcClient::new_with_commitment(url.clone(), CommitmentConfig::confirmed())
+                    },
+                    url,
+                    state: Mutex::new(ProviderState::default()),
+                })
+                .collect(),
+            blockhash: RwLock::new(None),
+            blockhash_counters: Counters::new(),
+            rent_minimums: LookupCache::new("rent_minimums", 1_000, Some(RENT_MINIMUM_TTL)),
+            // Decimals never change once a mint is initialized, so entries
+            // never expire
+            mint_decimals: LookupCache::new("mint_decimals", MAX_CACHED_MINTS, None),
+            accounts: LookupCache::new("accounts", MAX_CACHED_ACCOUNTS, Some(ACCOUNT_TTL)),
+        }
+    }
+
+    // The first provider in order of preference that isn't benched, or else
+    // the one due back soonest
+    fn provider(&self) -> &Provider {
+        let now = Instant::now();
+        self.providers.iter().find(|provider| provider.is_available(now)).unwrap_or_else(|| {
+            self.providers
+                .iter()
+                .min_by_key(|provider| provider.state.lock().unwrap().benched_until)
+                .unwrap()
+        })
+    }
+}
+
+// The configured cluster's, which background tasks keep warm and /health
+// reports on
+fn default_endpoint() -> &'static Endpoint {
+    DEFAULT_ENDPOINT.get_or_init(|| Endpoint::new(config::get().rpc_urls()))
+}
+
+// The current request's cluster's
+fn endpoint() -> &'static Endpoint {
+    let Some(target) = cluster::current() else {
+        return default_endpoint();
+    };
+    let mut endpoints = CLUSTER_ENDPOINTS.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap();
+    endpoints
+        .entry(target)
+        .or_insert_with_key(|target| Box::leak(Box::new(Endpoint::new(vec![target.rpc_url().to_string()]))))
+}
+
+// Rent-exempt minimums by account size
+pub fn rent_minimums() -> &'static LookupCache<usize, u64> {
+    &endpoint().rent_minimums
+}
+
+pub fn mint_decimals() -> &'static LookupCache<Pubkey, u8> {
+    &endpoint().mint_decimals
+}
+
+// Accounts fetched to check whether they exist and what they hold; None
+// records that there was no account at the address
+pub fn accounts() -> &'static LookupCache<Pubkey, Option<Account>> {
+    &endpoint().accounts
+}
+
+pub fn cache_stats() -> Vec<CacheStats> {
+    let endpoint = default_endpoint();
+    let blockhash_entries = u64::from(cached_blockhash().is_some());
+    vec![
+        endpoint.blockhash_counters.stats("blockhash", blockhash_entries),
+        endpoint.rent_minimums.stats(),
+        endpoint.mint_decimals.stats(),
+        endpoint.accounts.stats(),
+    ]
+}
+
+#[derive(Clone, Copy)]
+pub struct CachedBlockhash {
+    pub blockhash: Hash,
+    pub last_valid_block_height: u64,
+    pub fetched_at: Instant,
+}
+
+impl CachedBlockhash {
+    pub fn is_fresh(&self) -> bool {
+        self.fetched_at.elapsed() <= BLOCKHASH_MAX_AGE
+    }
+}
+
+struct Provider {
+    url: String,
+    client: RpcClient,
+    state: Mutex<ProviderState>,
+}
+
+#[derive(Default)]
+struct ProviderState {
+    benched_until: Option<Instant>,
+    consecutive_failures: u32,
+    rate_limited: bool,
+    last_error: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ProviderStatus {
+    // Scheme and host only, since provider URLs often carry an API key
+    pub url: String,
+    // False while the provider is passed over after a failure
+    pub available: bool,
+    pub rate_limited: bool,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    pub retry_in_ms: Option<u128>,
+}
+
+enum Failure {
+    RateLimited,
+    Transient,
+    // The provider answered; asking another would get the same answer
+    Final,
+}
+
+fn classify(err: &ClientError) -> Failure {
+    match err.kind() {
+        // Connection failures and HTTP error statuses; JSON-RPC errors come
+        // back with a 200
+        ClientErrorKind::Reqwest(err) if err.status().is_some_and(|status| status.as_u16() == 429) => Failure::RateLimited,
+        ClientErrorKind::Reqwest(_) => Failure::Transient,
+        ClientErrorKind::Io(_) => Failure::Transient,
+        // Node unhealthy or behind, and internal errors
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code: -32005 | -32603, .. }) => Failure::Transient,
+        ClientErrorKind::RpcError(RpcError::RpcRequestError(_)) => Failure::Transient,
+        _ => Failure::Final,
+    }
+}
+
+// Host part of a URL, dropping any credentials, path and query
+pub fn display_url(url: &str) -> String {
+    let (scheme, rest) = url.split_once("://").unwrap_or(("", url));
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = host.rsplit('@').next().unwrap_or_default();
+    if scheme.is_empty() { host.to_string() } else { format!("{}://{}", scheme, host) }
+}
+
+// Transport errors quote the request URL, so URLs in messages are cut down
+// to their host before they are logged or returned
+fn redact_urls(message: &str) -> String {
+    message
+        .split(' ')
+        .map(|word| {
+            if !word.contains("://") {
+                return word.to_string();
+            }
+            let start = word.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(0);
+            let (prefix, rest) = word.split_at(start);
+            let end = rest.find([')', ']', '"', '\'', ',']).unwrap_or(rest.len());
+            format!("{}{}{}", prefix, display_url(&rest[..end]), &rest[end..])
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl Provider {
+    fn is_available(&self, now: Instant) -> bool {
+        self.state.lock().unwrap().benched_until.is_none_or(|until| until <= now)
+    }
+
+    fn succeeded(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.benched_until.is_some() {
+            tracing::info!(provider = %display_url(&self.url), "rpc provider available again");
+        }
+        *state = ProviderState::default();
+    }
+
+    fn failed(&self, err: &ClientError, rate_limited: bool) {
+        let bench = if rate_limited { RATE_LIMIT_BENCH } else { FAILURE_BENCH };
+        let mut state = self.state.lock().unwrap();
+        state.benched_until = Some(Instant::now() + bench);
+        state.consecutive_failures += 1;
+        state.rate_limited = rate_limited;
+        state.last_error = Some(redact_urls(&err.to_string()));
+        tracing::warn!(
+            provider = %display_url(&self.url),
+            rate_limited,
+            error = %redact_urls(&err.to_string()),
+            bench_secs = bench.as_secs(),
+            "rpc provider failed, failing over"
+        );
+    }
+
+    fn status(&self, now: Instant) -> ProviderStatus {
+        let state = self.state.lock().unwrap();
+        let retry_in = state.benched_until.and_then(|until| until.checked_duration_since(now));
+        ProviderStatus {
+            url: display_url(&self.url),
+            available: retry_in.is_none(),
+            rate_limited: state.rate_limited,
+            consecutive_failures: state.consecutive_failures,
+            last_error: state.last_error.clone(),
+            retry_in_ms: retry_in.map(|retry_in| retry_in.as_millis()),
+        }
+    }
+}
+
+pub fn provider_statuses() -> Vec<ProviderStatus> {
+    let now = Instant::now();
+    default_endpoint().providers.iter().map(|provider| provider.status(now)).collect()
+}
+
+// The preferred endpoint from SOLANA_RPC_URL, or the public endpoint of
+// SOLANA_CLUSTER when it is unset, unless the request picked another cluster
+pub fn rpc_url() -> String {
+    match cluster::current() {
+        Some(target) => target.rpc_url().to_string(),
+        None => config::get().rpc_url(),
+    }
+}
+
+// Pubsub endpoint for subscriptions; defaults to the RPC URL with a ws scheme,
+// so providers that use a different host or port need SOLANA_WS_URL
+pub fn ws_url() -> String {
+    let configured = config::get().ws_url.clone().filter(|_| cluster::current().is_none());
+    configured.unwrap_or_else(|| {
+        let url = rpc_url();
+        if let Some(rest) = url.strip_prefix("https://") {
+            format!("wss://{}", rest)
+        } else if let Some(rest) = url.strip_prefix("http://") {
+            format!("ws://{}", rest)
+        } else {
+            url
+        }
+    })
+}
+
+// Client of the provider currently preferred, for calls that aren't retried
+// such as submissions
+pub fn client() -> &'static RpcClient {
+    &endpoint().provider().client
+}
+
+// One attempt at an RPC call, so slow providers stand out in traces
+fn span(method: &'static str, url: &str, retry: u32) -> Span {
+    tracing::info_span!(
+        "rpc",
+        rpc.method = method,
+        rpc.endpoint = %display_url(url),
+        cluster = %cluster::label(),
+        retry,
+    )
+}
+
+// Times and traces a call that isn't retried, such as a write, on a client
+// from client()
+pub async fn write<F: Future>(method: &'static str, client: &RpcClient, call: F) -> F::Output {
+    timing::rpc(method, call).instrument(span(method, &client.url(), 0)).await
+}
+
+fn retry_delay(attempt: u32) -> Duration {
+    let delay = RETRY_BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt)).min(RETRY_MAX_DELAY);
+    // Jitter keeps instances that failed together from retrying together
+    delay + RETRY_BASE_DELAY.mul_f64(rand::thread_rng().gen_range(0.0..0.5))
+}
+
+// Reads are idempotent, so one that fails with a transient error or a rate
+// limit is retried with backoff. The failing provider is benched, so the
+// retry goes to the next one when there is another.
+async fn with_failover<T, F, Fut>(method: &'static str, call: &F) -> ClientResult<T>
+where
+    F: Fn(&'static RpcClient) -> Fut,
+    Fut: Future<Output = ClientResult<T>>,
+{
+    let max_retries = config::get().rpc_max_retries;
+    let endpoint = endpoint();
+    let mut attempt = 0;
+    loop {
+        let provider = endpoint.provider();
+        let span = span(method, &provider.url, attempt);
+        let result = timing::rpc(method, call(&provider.client)).instrument(span).await;
+        let err = match result {
+            Ok(value) => {
+                provider.succeeded();
+                return Ok(value);
+            }
+            Err(err) => err,
+        };
+        match classify(&err) {
+            Failure::Final => return Err(err),
+            Failure::RateLimited => provider.failed(&err, true),
+            Failure::Transient => provider.failed(&err, false),
+        }
+        if attempt >= max_retries {
+            return Err(err);
+        }
+        tokio::time::sleep(retry_delay(attempt)).await;
+        attempt += 1;
+    }
+}
+
+// Run a read with retries and failover, mirroring a sample of reads to the
+// shadow provider when one is configured. The shadow provider is on the
+// configured cluster, so reads for other clusters aren't mirrored.
+pub async fn read<T, F, Fut>(method: &'static str, call: F) -> ClientResult<T>
+where
+    T: Serialize + Send + 'static,
+    F: Fn(&'static RpcClient) -> Fut,
+    Fut: Future<Output = ClientResult<T>> + Send + 'static,
+{
+    let started = Instant::now();
+    let result = with_failover(method, &call).await;
+    if cluster::current().is_none() {
+        shadow_rpc::mirror(method, &result, started.elapsed(), call);
+    }
+    result
+}
+
+// With more than one provider, probe each one in the background so a benched
+// provider is put back in rotation as soon as it recovers
+pub fn spawn_provider_health_checks() {
+    if default_endpoint().providers.len() < 2 {
+        return;
+    }
+
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(PROVIDER_HEALTH_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let checks = default_endpoint().providers.iter().map(|provider| async move {
+                match provider.client.get_health().await {
+                    Ok(()) => provider.succeeded(),
+                    Err(err) => {
+                        let rate_limited = matches!(classify(&err), Failure::RateLimited);
+                        provider.failed(&err, rate_limited);
+                    }
+                }
+            });
+            futures::future::join_all(checks).await;
+        }
+    });
+}
+
+pub fn rpc_error(err: impl std::fmt::Display) -> ErrorResponse {
+    ErrorResponse::with_code(ApiErrorCode::RpcUnavailable, format!("RPC request failed: {}", redact_urls(&err.to_string())))
+}
+
+// The configured cluster's, which is kept warm
+pub fn cached_blockhash() -> Option<CachedBlockhash> {
+    *default_endpoint().blockhash.read().unwrap()
+}
+
+// The height a blockhash stays valid until, when it is the one cached for
+// the current cluster
+pub fn last_valid_block_height(blockhash: &Hash) -> Option<u64> {
+    endpoint()
+        .blockhash
+        .read()
+        .unwrap()
+        .filter(|cached| cached.blockhash == *blockhash)
+        .map(|cached| cached.last_valid_block_height)
+}
+
+// The cached blockhash while it is fresh, otherwise a newly fetched one
+pub async fn latest_blockhash() -> Result<Hash, ErrorResponse> {
+    Ok(latest_cached_blockhash().await?.blockhash)
+}
+
+// Like latest_blockhash, with the height it stays valid until and its age
+pub async fn latest_cached_blockhash() -> Result<CachedBlockhash, ErrorResponse> {
+    let endpoint = endpoint();
+    let cached = endpoint.blockhash.read().unwrap().filter(CachedBlockhash::is_fresh);
+    endpoint.blockhash_counters.record(cached.is_some());
+    match cached {
+        Some(cached) => Ok(cached),
+        None => refresh_blockhash().await,
+    }
+}
+
+pub async fn refresh_blockhash() -> Result<CachedBlockhash, ErrorResponse> {
+    // Not mirrored, since the shadow provider's blockhash would never match
+    let (blockhash, last_valid_block_height) = with_failover("getLatestBlockhash", &|client: &'static RpcClient| {
+        client.get_latest_blockhash_with_commitment(client.commitment())
+    })
+        .await
+        .map_err(rpc_error)?;
+
+    let cached = CachedBlockhash {
+        blockhash,
+        last_valid_block_height,
+        fetched_at: Instant::now(),
+    };
+    *endpoint().blockhash.write().unwrap() = Some(cached);
+
+    Ok(cached)
+}
+
+// Keep the blockhash cache warm in the background for the lifetime of the server
+pub fn spawn_blockhash_refresher() {
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(BLOCKHASH_REFRESH_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(err) = refresh_blockhash().await {
+                tracing::warn!(error = %err.error, "failed to refresh blockhash cache");
+            }
+        }
+    });
+}
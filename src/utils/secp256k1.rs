@@ -0,0 +1,250 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::{
+    keccak,
+    secp256k1_instruction::{
+        construct_eth_pubkey, DATA_START, HASHED_PUBKEY_SERIALIZED_SIZE, SIGNATURE_SERIALIZED_SIZE,
+    },
+    secp256k1_program,
+};
+use super::audit::{self, AuditOperation};
+use super::message::{decode_message, MessageEncoding};
+use super::response_types::{ApiErrorCode, FieldErrors, SuccessResponse, ErrorResponse};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+// Ethereum keys sign the keccak256 hash of a message, and the native
+// Secp256k1 program checks such signatures against the 20-byte Ethereum
+// address rather than the public key. Keys, addresses and signatures are hex,
+// with or without 0x, as Ethereum tooling writes them.
+
+#[derive(Deserialize, ToSchema)]
+pub struct Secp256k1SignRequest {
+    pub message: String,
+    // The 32-byte private key
+    pub secret: String,
+    #[serde(default)]
+    pub encoding: MessageEncoding,
+    // Sign "\x19Ethereum Signed Message:\n" + length + message, as
+    // personal_sign does, instead of the message alone
+    #[serde(rename = "ethereumPrefix", default)]
+    pub ethereum_prefix: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Secp256k1SignResponse {
+    // r, s and v, with v = 27 + recovery id as Ethereum writes it
+    pub signature: String,
+    pub recovery_id: u8,
+    pub eth_address: String,
+    pub message: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct Secp256k1VerifyRequest {
+    pub message: String,
+    // 65 bytes with v last, or 64 bytes alongside recoveryId
+    pub signature: String,
+    #[serde(rename = "recoveryId")]
+    pub recovery_id: Option<u8>,
+    #[serde(rename = "ethAddress")]
+    pub eth_address: String,
+    #[serde(default)]
+    pub encoding: MessageEncoding,
+    #[serde(rename = "ethereumPrefix", default)]
+    pub ethereum_prefix: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Secp256k1VerifyResponse {
+    pub valid: bool,
+    pub message: String,
+    pub eth_address: String,
+    // The address that signed, when the signature recovers to one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovered_address: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct Secp256k1InstructionRequest {
+    #[serde(flatten)]
+    pub signed: Secp256k1VerifyRequest,
+    // Position of this instruction in the transaction. The program reads its
+    // own data by index, so the instruction must end up here; 0 by default.
+    #[serde(rename = "instructionIndex", default)]
+    pub instruction_index: u8,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Secp256k1InstructionResponse {
+    pub program_id: String,
+    pub instruction_data: String,
+}
+
+struct SignedMessage {
+    message: Vec<u8>,
+    signature: libsecp256k1::Signature,
+    recovery_id: libsecp256k1::RecoveryId,
+    eth_address: [u8; HASHED_PUBKEY_SERIALIZED_SIZE],
+}
+
+fn decode_hex(value: &str, field_name: &str) -> Result<Vec<u8>, ErrorResponse> {
+    let value = value.strip_prefix("0x").unwrap_or(value);
+    hex::decode(value).map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidEncoding, format!("Invalid hex encoding for {}", field_name)))
+}
+
+fn validate_secret(secret: &str) -> Result<libsecp256k1::SecretKey, ErrorResponse> {
+    let bytes = decode_hex(secret, "secret key")?;
+    libsecp256k1::SecretKey::parse_slice(&bytes)
+        .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidSecret, "Invalid secret key format"))
+}
+
+fn validate_eth_address(address: &str) -> Result<[u8; HASHED_PUBKEY_SERIALIZED_SIZE], ErrorResponse> {
+    decode_hex(address, "Ethereum address")?
+        .try_into()
+        .map_err(|_| ErrorResponse::new("Ethereum addresses must be 20 bytes"))
+}
+
+// The recovery id comes last in a 65-byte signature, either as is or
+// offset by 27
+fn validate_signature(signature: &str, recovery_id: Option<u8>) -> Result<(libsecp256k1::Signature, libsecp256k1::RecoveryId), ErrorResponse> {
+    let bytes = decode_hex(signature, "signature")?;
+    let (signature, recovery_id) = match (bytes.len(), recovery_id) {
+        (65, None) => (&bytes[..64], bytes[64]),
+        (64, Some(recovery_id)) => (&bytes[..], recovery_id),
+        (64, None) => return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "recoveryId is required with a 64-byte signature")),
+        _ => return Err(ErrorResponse::with_code(ApiErrorCode::InvalidSignature, "Invalid signature format")),
+    };
+    let recovery_id = libsecp256k1::RecoveryId::parse(if recovery_id >= 27 { recovery_id - 27 } else { recovery_id })
+        .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidSignature, "recoveryId must be 0 or 1, or 27 or 28"))?;
+    // Parsed the way the program parses it
+    let signature = libsecp256k1::Signature::parse_standard_slice(signature)
+        .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidSignature, "Invalid signature format"))?;
+    Ok((signature, recovery_id))
+}
+
+fn message_bytes(message: &str, encoding: MessageEncoding, ethereum_prefix: bool) -> Result<Vec<u8>, ErrorResponse> {
+    let message = decode_message(message, encoding)?;
+    if !ethereum_prefix {
+        return Ok(message);
+    }
+    let mut prefixed = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+    prefixed.extend(message);
+    Ok(prefixed)
+}
+
+fn message_hash(message: &[u8]) -> libsecp256k1::Message {
+    libsecp256k1::Message::parse(&keccak::hash(message).to_bytes())
+}
+
+fn format_address(address: &[u8]) -> String {
+    format!("0x{}", hex::encode(address))
+}
+
+fn recover_address(signed: &SignedMessage) -> Option<[u8; HASHED_PUBKEY_SERIALIZED_SIZE]> {
+    libsecp256k1::recover(&message_hash(&signed.message), &signed.signature, &signed.recovery_id)
+        .ok()
+        .map(|pubkey| construct_eth_pubkey(&pubkey))
+}
+
+fn signed_message(request: &Secp256k1VerifyRequest) -> Result<SignedMessage, ErrorResponse> {
+    if request.message.is_empty() || request.signature.is_empty() || request.eth_address.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
+    }
+
+    let mut fields = FieldErrors::default();
+    let message = fields.check("message", message_bytes(&request.message, request.encoding, request.ethereum_prefix));
+    let signature = fields.check("signature", validate_signature(&request.signature, request.recovery_id).map(Some));
+    let eth_address = fields.check("ethAddress", validate_eth_address(&request.eth_address));
+    fields.finish()?;
+    let (signature, recovery_id) = signature.unwrap();
+
+    Ok(SignedMessage { message, signature, recovery_id, eth_address })
+}
+
+pub fn sign_message_secp256k1(request: Secp256k1SignRequest) -> Result<SuccessResponse<Secp256k1SignResponse>, ErrorResponse> {
+    let result = sign(request);
+    audit::recorded(AuditOperation::SignMessage, result, |signed| vec![signed.eth_address.clone()])
+}
+
+fn sign(request: Secp256k1SignRequest) -> Result<SuccessResponse<Secp256k1SignResponse>, ErrorResponse> {
+    // Validate inputs
+    if request.message.is_empty() || request.secret.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
+    }
+
+    let mut fields = FieldErrors::default();
+    let message = fields.check("message", message_bytes(&request.message, request.encoding, request.ethereum_prefix));
+    let secret = fields.check("secret", validate_secret(&request.secret).map(Some));
+    fields.finish()?;
+    let secret = secret.unwrap();
+
+    // Sign the message
+    let (signature, recovery_id) = libsecp256k1::sign(&message_hash(&message), &secret);
+    let mut bytes = signature.serialize().to_vec();
+    bytes.push(27 + recovery_id.serialize());
+
+    Ok(SuccessResponse::new(Secp256k1SignResponse {
+        signature: format!("0x{}", hex::encode(bytes)),
+        recovery_id: recovery_id.serialize(),
+        eth_address: format_address(&construct_eth_pubkey(&libsecp256k1::PublicKey::from_secret_key(&secret))),
+        message: request.message,
+    }))
+}
+
+pub fn verify_message_secp256k1(request: Secp256k1VerifyRequest) -> Result<SuccessResponse<Secp256k1VerifyResponse>, ErrorResponse> {
+    // Validate inputs
+    let signed = signed_message(&request)?;
+
+    let recovered = recover_address(&signed);
+
+    Ok(SuccessResponse::new(Secp256k1VerifyResponse {
+        valid: recovered == Some(signed.eth_address),
+        message: request.message,
+        eth_address: format_address(&signed.eth_address),
+        recovered_address: recovered.as_ref().map(|address| format_address(address)),
+    }))
+}
+
+// Same layout as solana_sdk::secp256k1_instruction::new_secp256k1_instruction,
+// which needs the private key to produce the signature itself
+fn secp256k1_instruction_data(signed: &SignedMessage, instruction_index: u8) -> Result<Vec<u8>, ErrorResponse> {
+    let eth_address_offset = DATA_START;
+    let signature_offset = eth_address_offset + HASHED_PUBKEY_SERIALIZED_SIZE;
+    let message_data_offset = signature_offset + SIGNATURE_SERIALIZED_SIZE + 1;
+    let message_data_size = u16::try_from(signed.message.len())
+        .ok()
+        .filter(|size| message_data_offset + (*size as usize) <= u16::MAX as usize)
+        .ok_or_else(|| ErrorResponse::new("Message is too long for a Secp256k1 program instruction"))?;
+
+    let mut data = Vec::with_capacity(message_data_offset + signed.message.len());
+    data.push(1);
+    data.extend_from_slice(&(signature_offset as u16).to_le_bytes());
+    data.push(instruction_index);
+    data.extend_from_slice(&(eth_address_offset as u16).to_le_bytes());
+    data.push(instruction_index);
+    data.extend_from_slice(&(message_data_offset as u16).to_le_bytes());
+    data.extend_from_slice(&message_data_size.to_le_bytes());
+    data.push(instruction_index);
+    data.extend_from_slice(&signed.eth_address);
+    data.extend_from_slice(&signed.signature.serialize());
+    data.push(signed.recovery_id.serialize());
+    data.extend_from_slice(&signed.message);
+
+    Ok(data)
+}
+
+pub fn build_secp256k1_verify(request: Secp256k1InstructionRequest) -> Result<SuccessResponse<Secp256k1InstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    let signed = signed_message(&request.signed)?;
+
+    // The precompile fails the whole transaction on a bad signature, so
+    // catch it before the caller pays for that
+    if recover_address(&signed) != Some(signed.eth_address) {
+        return Err(ErrorResponse::new("Signature does not match the message and Ethereum address"));
+    }
+
+    Ok(SuccessResponse::new(Secp256k1InstructionResponse {
+        program_id: secp256k1_program::id().to_string(),
+        instruction_data: BASE64.encode(secp256k1_instruction_data(&signed, request.instruction_index)?),
+    }))
+}
@@ -0,0 +1,98 @@
+use rand::Rng;
+use serde::Serialize;
+use solana_client::{client_error::Result as ClientResult, nonblocking::rpc_client::RpcClient};
+use std::{
+    env,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+    time::{Duration, Instant},
+};
+use crate::config;
+
+// Shadow mode mirrors a sample of read requests to a second provider and
+// logs whether its answers and latency match the primary's. It is enabled by
+// setting SHADOW_RPC_URL, with SHADOW_RPC_SAMPLE_RATE (0.0 to 1.0, default
+// 0.1) controlling the share of reads that are mirrored.
+
+const DEFAULT_SAMPLE_RATE: f64 = 0.1;
+
+struct Shadow {
+    client: RpcClient,
+    sample_rate: f64,
+}
+
+static SHADOW: OnceLock<Option<Shadow>> = OnceLock::new();
+static COMPARED: AtomicU64 = AtomicU64::new(0);
+static DIVERGED: AtomicU64 = AtomicU64::new(0);
+
+fn shadow() -> Option<&'static Shadow> {
+    SHADOW
+        .get_or_init(|| {
+            // Comparing a mock's answers with a real provider's tells nothing
+            if config::get().mock_rpc {
+                return None;
+            }
+            let url = env::var("SHADOW_RPC_URL").ok()?;
+            let sample_rate = env::var("SHADOW_RPC_SAMPLE_RATE")
+                .ok()
+                .and_then(|rate| rate.parse::<f64>().ok())
+                .unwrap_or(DEFAULT_SAMPLE_RATE)
+                .clamp(0.0, 1.0);
+            Some(Shadow {
+                client: RpcClient::new_with_commitment(url, super::rpc::client().commitment()),
+                sample_rate,
+            })
+        })
+        .as_ref()
+}
+
+// Answers are compared as JSON so any serializable RPC result works, and
+// errors only need to agree that the call failed
+fn outcome<T: Serialize>(result: &ClientResult<T>) -> Option<serde_json::Value> {
+    result.as_ref().ok().and_then(|value| serde_json::to_value(value).ok())
+}
+
+// Repeat a read against the shadow provider in the background and log how
+// its answer and latency compare with the primary's
+pub fn mirror<T, F, Fut>(method: &'static str, primary: &ClientResult<T>, primary_latency: Duration, call: F)
+where
+    T: Serialize + Send + 'static,
+    F: FnOnce(&'static RpcClient) -> Fut,
+    Fut: Future<Output = ClientResult<T>> + Send + 'static,
+{
+    let Some(shadow) = shadow() else {
+        return;
+    };
+    if !rand::thread_rng().gen_bool(shadow.sample_rate) {
+        return;
+    }
+
+    let expected = outcome(primary);
+    let primary_ok = primary.is_ok();
+    let request = call(&shadow.client);
+
+    tokio::spawn(async move {
+        let started = Instant::now();
+        let result = request.await;
+        let shadow_latency = started.elapsed();
+
+        let matched = result.is_ok() == primary_ok && outcome(&result) == expected;
+        let compared = COMPARED.fetch_add(1, Ordering::Relaxed) + 1;
+        let diverged = DIVERGED.fetch_add(u64::from(!matched), Ordering::Relaxed) + u64::from(!matched);
+
+        tracing::info!(
+            target: "shadow_rpc",
+            method,
+            matched,
+            primary_ms = primary_latency.as_secs_f64() * 1000.0,
+            shadow_ms = shadow_latency.as_secs_f64() * 1000.0,
+            shadow_error = result.as_ref().err().map(|err| err.to_string()),
+            compared,
+            diverged,
+            "shadow rpc comparison"
+        );
+    });
+}
@@ -0,0 +1,389 @@
+use hmac::{Hmac, Mac};
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+use std::{
+    collections::HashMap,
+    env,
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+use crate::config;
+use super::response_types::{ApiErrorCode, ErrorResponse};
+use super::signing;
+use super::instruction::validate_secret_key;
+use super::vault;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(10);
+// DER prefix of an Ed25519 SubjectPublicKeyInfo, which KMS returns keys in
+const ED25519_SPKI_PREFIX: [u8; 12] = [0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00];
+
+// Where the keys named by keyId live. Secrets sent with a request are
+// always signed with in memory.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum SignerBackend {
+    // The server's own key vault, see vault.rs
+    #[default]
+    Local,
+    // A HashiCorp Vault transit engine holding ed25519 keys
+    VaultTransit,
+    // An AWS KMS key with the ECC_NIST_EDWARDS25519 key spec
+    AwsKms,
+    // A signing service speaking the small protocol in sidecar_sign below,
+    // such as a threshold signer
+    Sidecar,
+}
+
+impl SignerBackend {
+    fn name(&self) -> &'static str {
+        match self {
+            SignerBackend::Local => "local",
+            SignerBackend::VaultTransit => "vault-transit",
+            SignerBackend::AwsKms => "aws-kms",
+            SignerBackend::Sidecar => "sidecar",
+        }
+    }
+}
+
+// A key a request signs with: a keypair in memory, or one that never
+// leaves a remote signer
+pub enum SigningKey {
+    Local(Keypair),
+    Remote { backend: SignerBackend, key_id: String, pubkey: Pubkey },
+}
+
+impl SigningKey {
+    pub fn pubkey(&self) -> Pubkey {
+        match self {
+            SigningKey::Local(keypair) => keypair.pubkey(),
+            SigningKey::Remote { pubkey, .. } => *pubkey,
+        }
+    }
+
+    // Remote signatures are checked before use, so a misconfigured or
+    // rotated key fails here rather than as a rejected transaction
+    pub async fn sign(&self, message: &[u8]) -> Result<Signature, ErrorResponse> {
+        let (backend, key_id, pubkey) = match self {
+            SigningKey::Local(keypair) => return Ok(signing::backend().sign(keypair, message)),
+            SigningKey::Remote { backend, key_id, pubkey } => (backend, key_id, pubkey),
+        };
+        let signature = match backend {
+            SignerBackend::Local => unreachable!("vault keys are decrypted into local keys"),
+            SignerBackend::VaultTransit => transit_sign(key_id, message).await,
+            SignerBackend::AwsKms => kms_sign(key_id, message).await,
+            SignerBackend::Sidecar => sidecar_sign(key_id, message).await,
+        }
+        .map_err(|err| remote_error(*backend, err))?;
+        if !signing::backend().verify(pubkey, message, &signature) {
+            return Err(remote_error(*backend, format!("signature from key {} does not verify against {}", key_id, pubkey)));
+        }
+        Ok(signature)
+    }
+}
+
+fn remote_error(backend: SignerBackend, err: impl std::fmt::Display) -> ErrorResponse {
+    ErrorResponse::with_code(ApiErrorCode::SignerUnavailable, format!("Remote signer {} failed: {}", backend.name(), err))
+}
+
+fn http_client() -> &'static Client {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    CLIENT.get_or_init(|| Client::builder().timeout(REMOTE_TIMEOUT).build().expect("signer HTTP client builds"))
+}
+
+async fn send(request: RequestBuilder) -> Result<Value, String> {
+    let response = request.send().await.map_err(|err| err.without_url().to_string())?;
+    let status = response.status();
+    let body: Value = response.json().await.map_err(|err| err.without_url().to_string())?;
+    if !status.is_success() {
+        return Err(format!("{}: {}", status, body));
+    }
+    Ok(body)
+}
+
+fn not_configured(setting: &str) -> String {
+    format!("{} is not set on this server", setting)
+}
+
+fn decode_signature(encoded: &str) -> Result<Signature, String> {
+    BASE64
+        .decode(encoded)
+        .ok()
+        .and_then(|bytes| Signature::try_from(bytes.as_slice()).ok())
+        .ok_or_else(|| "response has no valid ed25519 signature".to_string())
+}
+
+fn decode_pubkey(bytes: &[u8]) -> Result<Pubkey, String> {
+    Pubkey::try_from(bytes).map_err(|_| "response has no valid ed25519 public key".to_string())
+}
+
+// HashiCorp Vault transit: the key's latest version signs, and its public
+// key is read from the key's metadata
+
+fn transit_request(method: reqwest::Method, path: &str) -> Result<RequestBuilder, String> {
+    let config = config::get();
+    let addr = config.transit_addr.as_deref().ok_or_else(|| not_configured("HASHICORP_VAULT_ADDR"))?;
+    let token = config.transit_token.as_deref().ok_or_else(|| not_configured("HASHICORP_VAULT_TOKEN"))?;
+    let url = format!("{}/v1/{}/{}", addr.trim_end_matches('/'), config.transit_mount, path);
+    Ok(http_client().request(method, url).header("X-Vault-Token", token))
+}
+
+async fn transit_public_key(key_id: &str) -> Result<Pubkey, String> {
+    let body = send(transit_request(reqwest::Method::GET, &format!("keys/{}", key_id))?).await?;
+    let data = &body["data"];
+    if data["type"] != "ed25519" {
+        return Err(format!("key {} is not an ed25519 key", key_id));
+    }
+    let latest = data["latest_version"].as_u64().unwrap_or(1).to_string();
+    let encoded = data["keys"][latest.as_str()]["public_key"].as_str().unwrap_or_default();
+    decode_pubkey(&BASE64.decode(encoded).unwrap_or_default())
+}
+
+async fn transit_sign(key_id: &str, message: &[u8]) -> Result<Signature, String> {
+    let request = transit_request(reqwest::Method::POST, &format!("sign/{}", key_id))?
+        .json(&json!({ "input": BASE64.encode(message) }));
+    let body = send(request).await?;
+    // Signatures come back as "vault:v<version>:<base64>"
+    let signature = body["data"]["signature"].as_str().unwrap_or_default();
+    decode_signature(signature.rsplit(':').next().unwrap_or_default())
+}
+
+// AWS KMS, called through its JSON API with Signature Version 4 request
+// signing. Credentials come from the standard AWS environment variables.
+
+fn sigv4_key(secret: &str, date: &str, region: &str) -> Vec<u8> {
+    let hmac = |key: &[u8], data: &str| {
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+        mac.update(data.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    };
+    let key = hmac(format!("AWS4{}", secret).as_bytes(), date);
+    let key = hmac(&key, region);
+    let key = hmac(&key, "kms");
+    hmac(&key, "aws4_request")
+}
+
+async fn kms_call(target: &str, payload: Value) -> Result<Value, String> {
+    let config = config::get();
+    let region = config.kms_region.as_deref().ok_or_else(|| not_configured("AWS_REGION"))?;
+    let access_key = env::var("AWS_ACCESS_KEY_ID").map_err(|_| not_configured("AWS_ACCESS_KEY_ID"))?;
+    let secret_key = env::var("AWS_SECRET_ACCESS_KEY").map_err(|_| not_configured("AWS_SECRET_ACCESS_KEY"))?;
+    let session_token = env::var("AWS_SESSION_TOKEN").ok();
+    let endpoint = config.kms_endpoint.clone().unwrap_or_else(|| format!("https://kms.{}.amazonaws.com", region));
+    let host = reqwest::Url::parse(&endpoint)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| match url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        }))
+        .ok_or_else(|| format!("KMS_ENDPOINT {} is not a valid URL", endpoint))?;
+
+    let body = payload.to_string();
+    let now = chrono::Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let target = format!("TrentService.{}", target);
+
+    // Headers must be signed in sorted order
+    let mut headers = vec![
+        ("content-type", "application/x-amz-json-1.1".to_string()),
+        ("host", host),
+        ("x-amz-date", amz_date.clone()),
+    ];
+    if let Some(token) = &session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+    headers.push(("x-amz-target", target));
+    let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value)).collect();
+    let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+    let canonical_request = format!(
+        "POST\n/\n\n{}\n{}\n{}",
+        canonical_headers, signed_headers, hex::encode(Sha256::digest(body.as_bytes()))
+    );
+    let scope = format!("{}/{}/kms/aws4_request", date, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date, scope, hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+    let mut mac = HmacSha256::new_from_slice(&sigv4_key(&secret_key, &date, region)).expect("HMAC accepts keys of any length");
+    mac.update(string_to_sign.as_bytes());
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        access_key, scope, signed_headers, hex::encode(mac.finalize().into_bytes())
+    );
+
+    let mut request = http_client().post(&endpoint).header("authorization", authorization);
+    // reqwest sets host itself
+    for (name, value) in headers.into_iter().filter(|(name, _)| *name != "host") {
+        request = request.header(name, value);
+    }
+    send(request.body(body)).await
+}
+
+async fn kms_public_key(key_id: &str) -> Result<Pubkey, String> {
+    let body = kms_call("GetPublicKey", json!({ "KeyId": key_id })).await?;
+    let der = BASE64.decode(body["PublicKey"].as_str().unwrap_or_default()).unwrap_or_default();
+    match der.strip_prefix(&ED25519_SPKI_PREFIX[..]) {
+        Some(key) => decode_pubkey(key),
+        None => Err(format!("key {} is not an ed25519 key", key_id)),
+    }
+}
+
+async fn kms_sign(key_id: &str, message: &[u8]) -> Result<Signature, String> {
+    let payload = json!({
+        "KeyId": key_id,
+        "Message": BASE64.encode(message),
+        "MessageType": "RAW",
+        "SigningAlgorithm": "ED25519_SHA_512",
+    });
+    let body = kms_call("Sign", payload).await?;
+    decode_signature(body["Signature"].as_str().unwrap_or_default())
+}
+
+// Sidecar protocol, for signers without a client here:
+//   GET  {url}/keys/{keyId} -> {"pubkey": base58}
+//   POST {url}/sign {"keyId", "message": base64} -> {"signature": base64}
+
+fn sidecar_url(path: &str) -> Result<String, String> {
+    let url = config::get().signer_sidecar_url.as_deref().ok_or_else(|| not_configured("SIGNER_SIDECAR_URL"))?;
+    Ok(format!("{}/{}", url.trim_end_matches('/'), path))
+}
+
+async fn sidecar_public_key(key_id: &str) -> Result<Pubkey, String> {
+    let body = send(http_client().get(sidecar_url(&format!("keys/{}", key_id))?)).await?;
+    let encoded = body["pubkey"].as_str().unwrap_or_default();
+    decode_pubkey(&bs58::decode(encoded).into_vec().unwrap_or_default())
+}
+
+async fn sidecar_sign(key_id: &str, message: &[u8]) -> Result<Signature, String> {
+    let request = http_client()
+        .post(sidecar_url("sign")?)
+        .json(&json!({ "keyId": key_id, "message": BASE64.encode(message) }));
+    let body = send(request).await?;
+    decode_signature(body["signature"].as_str().unwrap_or_default())
+}
+
+// Public keys of remote keys, fetched once; a key's address can't change
+// without it becoming a different key
+fn public_keys() -> &'static Mutex<HashMap<(SignerBackend, String), Pubkey>> {
+    static PUBLIC_KEYS: OnceLock<Mutex<HashMap<(SignerBackend, String), Pubkey>>> = OnceLock::new();
+    PUBLIC_KEYS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn is_uuid(id: &str) -> bool {
+    id.len() == 36
+        && id.char_indices().all(|(i, c)| if [8, 13, 18, 23].contains(&i) { c == '-' } else { c.is_ascii_hexdigit() })
+}
+
+// Key ids, multi-region key ids, aliases, and key or alias ARNs, the forms
+// KMS takes as a KeyId
+fn is_kms_key_id(key_id: &str) -> bool {
+    let (resource, is_arn) = match key_id.strip_prefix("arn:") {
+        Some(arn) => {
+            let parts: Vec<&str> = arn.splitn(5, ':').collect();
+            let [partition, "kms", region, account, resource] = parts[..] else {
+                return false;
+            };
+            let region_valid = !region.is_empty() && region.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+            let account_valid = account.len() == 12 && account.chars().all(|c| c.is_ascii_digit());
+            if !partition.starts_with("aws") || !region_valid || !account_valid {
+                return false;
+            }
+            (resource, true)
+        }
+        None => (key_id, false),
+    };
+
+    if let Some(alias) = resource.strip_prefix("alias/") {
+        return !alias.is_empty() && alias.len() <= 250 && alias.chars().all(|c| c.is_ascii_alphanumeric() || "/_-".contains(c));
+    }
+    let id = match (is_arn, resource.strip_prefix("key/")) {
+        (true, Some(id)) => id,
+        (true, None) => return false,
+        (false, _) => resource,
+    };
+    match id.strip_prefix("mrk-") {
+        Some(hex) => hex.len() == 32 && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => is_uuid(id),
+    }
+}
+
+// The sidecar only exposes the keys it was set up to, so any it holds may
+// be named; transit and KMS credentials can reach more than this server's
+// keys, so only the configured ones may be
+fn allowed_key_ids(backend: SignerBackend) -> Option<&'static [String]> {
+    let config = config::get();
+    match backend {
+        SignerBackend::VaultTransit => Some(&config.transit_key_ids),
+        SignerBackend::AwsKms => Some(&config.kms_key_ids),
+        SignerBackend::Local | SignerBackend::Sidecar => None,
+    }
+}
+
+async fn remote_key(backend: SignerBackend, key_id: &str) -> Result<SigningKey, ErrorResponse> {
+    // Transit and sidecar keys go into URL paths, so nothing may climb out
+    // of the key's own path. KMS ids only go in request bodies, but must
+    // still be one of the forms KMS takes.
+    let valid = match backend {
+        SignerBackend::AwsKms => is_kms_key_id(key_id),
+        _ => {
+            !key_id.is_empty()
+                && key_id.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+                && !key_id.contains("..")
+        }
+    };
+    if !valid {
+        return Err(ErrorResponse::new(format!("Invalid key id for {}: {}", backend.name(), key_id)));
+    }
+    if allowed_key_ids(backend).is_some_and(|allowed| !allowed.iter().any(|allowed| allowed == key_id)) {
+        return Err(ErrorResponse::with_code(
+            ApiErrorCode::NotAuthorized,
+            format!("Key {} is not allowed for {}", key_id, backend.name()),
+        ));
+    }
+    let cache_key = (backend, key_id.to_string());
+    let cached = public_keys().lock().unwrap().get(&cache_key).copied();
+    let pubkey = match cached {
+        Some(pubkey) => pubkey,
+        None => {
+            let pubkey = match backend {
+                SignerBackend::Local => unreachable!("vault keys are decrypted into local keys"),
+                SignerBackend::VaultTransit => transit_public_key(key_id).await,
+                SignerBackend::AwsKms => kms_public_key(key_id).await,
+                SignerBackend::Sidecar => sidecar_public_key(key_id).await,
+            }
+            .map_err(|err| remote_error(backend, err))?;
+            public_keys().lock().unwrap().insert(cache_key, pubkey);
+            pubkey
+        }
+    };
+    Ok(SigningKey::Remote { backend, key_id: key_id.to_string(), pubkey })
+}
+
+pub async fn key(key_id: &str, backend: SignerBackend) -> Result<SigningKey, ErrorResponse> {
+    match backend {
+        SignerBackend::Local => vault::keypair(key_id).map(SigningKey::Local),
+        backend => remote_key(backend, key_id).await,
+    }
+}
+
+// Keys for endpoints taking both secrets and key ids, secrets first
+pub async fn keys(secrets: &[String], key_ids: &[String], backend: SignerBackend) -> Result<Vec<SigningKey>, ErrorResponse> {
+    let mut keys = secrets
+        .iter()
+        .map(|secret| validate_secret_key(secret).map(SigningKey::Local))
+        .collect::<Result<Vec<_>, ErrorResponse>>()?;
+    for key_id in key_ids {
+        keys.push(key(key_id, backend).await?);
+    }
+    Ok(keys)
+}
@@ -0,0 +1,127 @@
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use rayon::prelude::*;
+use std::{env, sync::OnceLock};
+
+// The signing primitive behind every endpoint that signs or verifies.
+// Backends are selected with SIGNATURE_BACKEND; new ones (an HSM, or an
+// experimental scheme) implement this trait and are added to backend() below.
+pub trait SignatureBackend: Send + Sync {
+    // Value of SIGNATURE_BACKEND that selects this backend
+    fn name(&self) -> &'static str;
+
+    fn sign(&self, keypair: &Keypair, message: &[u8]) -> Signature;
+
+    fn verify(&self, pubkey: &Pubkey, message: &[u8], signature: &Signature) -> bool;
+
+    // One result per message, in order. Backends without a faster batch
+    // primitive verify each message on its own, spread across the rayon pool.
+    fn verify_batch(&self, messages: &[SignedMessage]) -> Vec<bool> {
+        messages
+            .par_iter()
+            .map(|signed| self.verify(&signed.pubkey, &signed.message, &signed.signature))
+            .collect()
+    }
+}
+
+pub struct SignedMessage {
+    pub pubkey: Pubkey,
+    pub message: Vec<u8>,
+    pub signature: Signature,
+}
+
+// Signs and verifies with the Solana SDK, which rejects weak public keys
+// and non-canonical signatures
+pub struct SdkBackend;
+
+impl SignatureBackend for SdkBackend {
+    fn name(&self) -> &'static str {
+        "sdk"
+    }
+
+    fn sign(&self, keypair: &Keypair, message: &[u8]) -> Signature {
+        keypair.sign_message(message)
+    }
+
+    fn verify(&self, pubkey: &Pubkey, message: &[u8], signature: &Signature) -> bool {
+        signature.verify(pubkey.as_ref(), message)
+    }
+}
+
+// Verifies batches with ed25519-dalek's batch API, which checks all the
+// signatures in one multiscalar multiplication. A failed batch only says that
+// some signature is bad, so it is rechecked one message at a time to find
+// which. Single operations go through the SDK.
+pub struct DalekBatchBackend;
+
+impl DalekBatchBackend {
+    fn batch_valid(messages: &[SignedMessage]) -> bool {
+        let mut public_keys = Vec::with_capacity(messages.len());
+        let mut signatures = Vec::with_capacity(messages.len());
+        for signed in messages {
+            let (Ok(public_key), Ok(signature)) = (
+                ed25519_dalek::PublicKey::from_bytes(signed.pubkey.as_ref()),
+                ed25519_dalek::Signature::from_bytes(signed.signature.as_ref()),
+            ) else {
+                return false;
+            };
+            public_keys.push(public_key);
+            signatures.push(signature);
+        }
+        let contents: Vec<&[u8]> = messages.iter().map(|signed| signed.message.as_slice()).collect();
+
+        ed25519_dalek::verify_batch(&contents, &signatures, &public_keys).is_ok()
+    }
+}
+
+impl SignatureBackend for DalekBatchBackend {
+    fn name(&self) -> &'static str {
+        "dalek-batch"
+    }
+
+    fn sign(&self, keypair: &Keypair, message: &[u8]) -> Signature {
+        SdkBackend.sign(keypair, message)
+    }
+
+    fn verify(&self, pubkey: &Pubkey, message: &[u8], signature: &Signature) -> bool {
+        SdkBackend.verify(pubkey, message, signature)
+    }
+
+    // The SDK verifies strictly, rejecting small-order keys and R points,
+    // which batch verification accepts. Batches containing either are
+    // verified one message at a time so both backends give the same answers.
+    fn verify_batch(&self, messages: &[SignedMessage]) -> Vec<bool> {
+        if messages.iter().all(|signed| !has_small_order_point(signed)) && Self::batch_valid(messages) {
+            return vec![true; messages.len()];
+        }
+        SdkBackend.verify_batch(messages)
+    }
+}
+
+fn has_small_order_point(signed: &SignedMessage) -> bool {
+    let signature: &[u8] = signed.signature.as_ref();
+    [signed.pubkey.as_ref(), &signature[..32]].into_iter().any(|bytes| {
+        CompressedEdwardsY::from_slice(bytes)
+            .decompress()
+            .is_none_or(|point| point.is_small_order())
+    })
+}
+
+static BACKEND: OnceLock<Box<dyn SignatureBackend>> = OnceLock::new();
+
+// SIGNATURE_BACKEND=sdk (the default) or dalek-batch
+pub fn backend() -> &'static dyn SignatureBackend {
+    BACKEND
+        .get_or_init(|| {
+            let name = env::var("SIGNATURE_BACKEND").unwrap_or_else(|_| "sdk".to_string());
+            let backends: Vec<Box<dyn SignatureBackend>> = vec![Box::new(SdkBackend), Box::new(DalekBatchBackend)];
+            backends
+                .into_iter()
+                .find(|backend| backend.name() == name)
+                .unwrap_or_else(|| panic!("SIGNATURE_BACKEND '{}' is not a known signature backend", name))
+        })
+        .as_ref()
+}
@@ -0,0 +1,176 @@
+use hmac::{Hmac, Mac};
+use moka::sync::Cache;
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use sha2::Sha256;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::{
+    env,
+    sync::OnceLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use super::instruction::validate_pubkey;
+use super::signing;
+use super::response_types::{SuccessResponse, ErrorResponse};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+// Sign-In With Solana: the client signs a server-issued challenge with its
+// wallet and receives a session token proving ownership of the pubkey
+
+const DOMAIN: &str = "rust-server-superdev.fly.dev";
+const CHALLENGE_TTL_SECS: u64 = 5 * 60;
+const SESSION_TTL_SECS: u64 = 24 * 60 * 60;
+const NONCE_LEN: usize = 16;
+// Outstanding challenges beyond this are evicted, oldest first
+const MAX_PENDING_CHALLENGES: u64 = 100_000;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Clone)]
+struct PendingChallenge {
+    pubkey: Pubkey,
+    message: String,
+    expires_at: u64,
+}
+
+// Keyed by nonce, so requesting a challenge for a pubkey never replaces one
+// its owner is already signing
+static CHALLENGES: OnceLock<Cache<String, PendingChallenge>> = OnceLock::new();
+static SESSION_SECRET: OnceLock<Vec<u8>> = OnceLock::new();
+
+#[derive(Deserialize, ToSchema)]
+pub struct SiwsChallengeRequest {
+    pub pubkey: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SiwsVerifyRequest {
+    pub pubkey: String,
+    pub nonce: String,
+    pub signature: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SiwsChallengeResponse {
+    pub message: String,
+    pub nonce: String,
+    pub expires_at: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SiwsSessionResponse {
+    pub pubkey: String,
+    pub token: String,
+    pub expires_at: u64,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn challenges() -> &'static Cache<String, PendingChallenge> {
+    CHALLENGES.get_or_init(|| {
+        Cache::builder()
+            .max_capacity(MAX_PENDING_CHALLENGES)
+            .time_to_live(Duration::from_secs(CHALLENGE_TTL_SECS))
+            .build()
+    })
+}
+
+// Tokens only need to survive restarts when SESSION_SECRET is configured
+fn session_secret() -> &'static [u8] {
+    SESSION_SECRET.get_or_init(|| {
+        env::var("SESSION_SECRET")
+            .map(String::into_bytes)
+            .unwrap_or_else(|_| {
+                let mut secret = vec![0u8; 32];
+                rand::thread_rng().fill(secret.as_mut_slice());
+                secret
+            })
+    })
+}
+
+fn sign_token_payload(payload: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(session_secret()).expect("HMAC accepts any key length");
+    mac.update(payload.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn validate_signature(signature: &str) -> Result<Signature, ErrorResponse> {
+    let sig_bytes = BASE64.decode(signature)
+        .map_err(|_| ErrorResponse::new("Invalid base64 encoding for signature"))?;
+
+    Signature::try_from(sig_bytes.as_slice())
+        .map_err(|_| ErrorResponse::new("Invalid signature format"))
+}
+
+pub fn siws_challenge(request: SiwsChallengeRequest) -> Result<SuccessResponse<SiwsChallengeResponse>, ErrorResponse> {
+    let pubkey = validate_pubkey(&request.pubkey, "public key")?;
+
+    let nonce: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(NONCE_LEN)
+        .map(char::from)
+        .collect();
+    let issued_at = now();
+    let expires_at = issued_at + CHALLENGE_TTL_SECS;
+
+    let message = format!(
+        "{} wants you to sign in with your Solana account:\n{}\n\nNonce: {}\nIssued At: {}\nExpiration Time: {}",
+        DOMAIN, pubkey, nonce, issued_at, expires_at,
+    );
+
+    challenges().insert(nonce.clone(), PendingChallenge { pubkey, message: message.clone(), expires_at });
+
+    Ok(SuccessResponse::new(SiwsChallengeResponse { message, nonce, expires_at }))
+}
+
+pub fn siws_verify(request: SiwsVerifyRequest) -> Result<SuccessResponse<SiwsSessionResponse>, ErrorResponse> {
+    let pubkey = validate_pubkey(&request.pubkey, "public key")?;
+    let signature = validate_signature(&request.signature)?;
+
+    // Each challenge can only be used once, and only by the pubkey it was
+    // issued to
+    let challenge = challenges()
+        .remove(&request.nonce)
+        .filter(|challenge| challenge.pubkey == pubkey)
+        .ok_or_else(|| ErrorResponse::new("No pending sign-in challenge for this public key"))?;
+
+    if challenge.expires_at <= now() {
+        return Err(ErrorResponse::new("Sign-in challenge has expired"));
+    }
+
+    if !signing::backend().verify(&pubkey, challenge.message.as_bytes(), &signature) {
+        return Err(ErrorResponse::new("Signature does not match the sign-in challenge"));
+    }
+
+    let expires_at = now() + SESSION_TTL_SECS;
+    let payload = format!("{}.{}", pubkey, expires_at);
+    let token = format!("{}.{}", payload, sign_token_payload(&payload));
+
+    Ok(SuccessResponse::new(SiwsSessionResponse {
+        pubkey: pubkey.to_string(),
+        token,
+        expires_at,
+    }))
+}
+
+// Returns the wallet proven by a session token, if the token is valid and unexpired
+pub fn verify_session_token(token: &str) -> Option<Pubkey> {
+    let (payload, mac) = token.rsplit_once('.')?;
+    let (pubkey, expires_at) = payload.split_once('.')?;
+
+    let mut verifier = HmacSha256::new_from_slice(session_secret()).ok()?;
+    verifier.update(payload.as_bytes());
+    verifier.verify_slice(&hex::decode(mac).ok()?).ok()?;
+
+    if expires_at.parse::<u64>().ok()? <= now() {
+        return None;
+    }
+
+    validate_pubkey(pubkey, "public key").ok()
+}
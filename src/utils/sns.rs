@@ -0,0 +1,184 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{account::Account, hash::hashv, pubkey, pubkey::Pubkey};
+use super::footprint::{fetch_accounts, scan_program};
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+
+// Solana Name Service: a .sol domain is a name account derived from the
+// hashed name under the .sol root, and resolves to the account's owner. A
+// reverse lookup account, derived from the name account's address, holds the
+// name back as text.
+
+const NAME_PROGRAM_ID: Pubkey = pubkey!("namesLPneVptA9Z5rqUDD9tMTWEJwofgaYwp8cawRkX");
+const SOL_ROOT: Pubkey = pubkey!("58PwtjSDuFHuUkYjH9BYnnQKHfwo9reZhC2zMJv9JPkx");
+const REVERSE_LOOKUP_CLASS: Pubkey = pubkey!("33m47vH6Eav6jr5Ry86XjhRft2jRBLDnDgPSHoquXi2Z");
+const HASH_PREFIX: &str = "SPL Name Service";
+const DOMAIN_SUFFIX: &str = ".sol";
+
+// Every name account starts with its parent, owner and class
+const OWNER_OFFSET: usize = 32;
+const HEADER_LEN: usize = 96;
+
+#[derive(Serialize, ToSchema)]
+pub struct ResolvedDomain {
+    pub domain: String,
+    pub name_account: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ResolveNameResponse {
+    // The wallet a domain resolves to, or the one that was looked up
+    pub address: String,
+    // Set when a domain was resolved
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_account: Option<String>,
+    // The .sol domains an address owns, for reverse lookups
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domains: Option<Vec<ResolvedDomain>>,
+}
+
+// A .sol name given in place of an address, and the address it resolved to
+#[derive(Serialize, ToSchema, Clone)]
+pub struct ResolvedName {
+    pub name: String,
+    pub address: String,
+}
+
+pub fn is_domain(value: &str) -> bool {
+    value.to_ascii_lowercase().ends_with(DOMAIN_SUFFIX)
+}
+
+fn name_account(hashed_name: &[u8], class: Option<&Pubkey>, parent: Option<&Pubkey>) -> Pubkey {
+    let default = Pubkey::default();
+    Pubkey::find_program_address(
+        &[hashed_name, class.unwrap_or(&default).as_ref(), parent.unwrap_or(&default).as_ref()],
+        &NAME_PROGRAM_ID,
+    ).0
+}
+
+fn hashed_name(name: &str) -> [u8; 32] {
+    hashv(&[HASH_PREFIX.as_bytes(), name.as_bytes()]).to_bytes()
+}
+
+fn reverse_lookup_account(name_account: &Pubkey) -> Pubkey {
+    self::name_account(&hashed_name(&name_account.to_string()), Some(&REVERSE_LOOKUP_CLASS), None)
+}
+
+// The name account of a domain or one-level subdomain. Subdomain labels are
+// hashed with a leading NUL.
+fn domain_account(domain: &str) -> Result<Pubkey, ErrorResponse> {
+    let invalid = || ErrorResponse::new(format!("Invalid .sol domain {}", domain));
+    let lowercase = domain.to_ascii_lowercase();
+    let name = lowercase.strip_suffix(DOMAIN_SUFFIX).ok_or_else(invalid)?;
+    let labels: Vec<&str> = name.split('.').collect();
+    if labels.iter().any(|label| label.is_empty()) {
+        return Err(invalid());
+    }
+    match labels.as_slice() {
+        [domain] => Ok(name_account(&hashed_name(domain), None, Some(&SOL_ROOT))),
+        [subdomain, domain] => {
+            let parent = name_account(&hashed_name(domain), None, Some(&SOL_ROOT));
+            Ok(name_account(&hashed_name(&format!("\0{}", subdomain)), None, Some(&parent)))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn record_owner(account: &Account) -> Option<Pubkey> {
+    if account.owner != NAME_PROGRAM_ID {
+        return None;
+    }
+    account
+        .data
+        .get(OWNER_OFFSET..OWNER_OFFSET + 32)
+        .and_then(|owner| Pubkey::try_from(owner).ok())
+}
+
+// Reverse lookup data is a u32 length and that many bytes of name
+fn reverse_lookup_name(account: &Account) -> Option<String> {
+    let data = account.data.get(HEADER_LEN..)?;
+    let len = u32::from_le_bytes(data.get(..4)?.try_into().ok()?) as usize;
+    String::from_utf8(data.get(4..4 + len)?.to_vec()).ok()
+}
+
+// The wallet a .sol domain belongs to
+pub async fn resolve_domain(domain: &str) -> Result<(Pubkey, Pubkey), ErrorResponse> {
+    let account = domain_account(domain)?;
+    fetch_accounts(&[account])
+        .await?
+        .pop()
+        .flatten()
+        .as_ref()
+        .and_then(record_owner)
+        .map(|owner| (owner, account))
+        .ok_or_else(|| ErrorResponse::with_code(ApiErrorCode::InvalidAccount, format!("Domain {} is not registered", domain)))
+}
+
+// Resolve a .sol name given in place of an address, replacing it with the
+// address so the builders can validate it as usual
+pub async fn resolve_recipient(value: &mut String) -> Result<Option<ResolvedName>, ErrorResponse> {
+    if !is_domain(value) {
+        return Ok(None);
+    }
+    let (address, _) = resolve_domain(value).await?;
+    let name = std::mem::replace(value, address.to_string());
+    Ok(Some(ResolvedName { name, address: value.clone() }))
+}
+
+// Top-level domains owned by an address, named through their reverse lookup
+// accounts. Domains without one are left out.
+async fn owned_domains(owner: Pubkey) -> Result<Vec<ResolvedDomain>, ErrorResponse> {
+    let filters = vec![
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, SOL_ROOT.as_ref())),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(OWNER_OFFSET, owner.as_ref())),
+    ];
+    let mut name_accounts: Vec<Pubkey> = scan_program(NAME_PROGRAM_ID, filters)
+        .await?
+        .into_iter()
+        .map(|(address, _)| address)
+        .collect();
+    name_accounts.sort();
+
+    let reverse_accounts: Vec<Pubkey> = name_accounts.iter().map(reverse_lookup_account).collect();
+    let reverse = fetch_accounts(&reverse_accounts).await?;
+    Ok(name_accounts
+        .iter()
+        .zip(reverse)
+        .filter_map(|(account, reverse)| {
+            let name = reverse.as_ref().and_then(reverse_lookup_name)?;
+            Some(ResolvedDomain { domain: format!("{}{}", name, DOMAIN_SUFFIX), name_account: account.to_string() })
+        })
+        .collect())
+}
+
+// A .sol domain resolves to its owner; an address resolves to the domains it
+// owns
+pub async fn resolve_name(name: String) -> Result<SuccessResponse<ResolveNameResponse>, ErrorResponse> {
+    // Validate inputs
+    if name.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
+    }
+
+    if is_domain(&name) {
+        let (address, account) = resolve_domain(&name).await?;
+        return Ok(SuccessResponse::new(ResolveNameResponse {
+            address: address.to_string(),
+            domain: Some(name.to_ascii_lowercase()),
+            name_account: Some(account.to_string()),
+            domains: None,
+        }));
+    }
+
+    let owner = name
+        .parse::<Pubkey>()
+        .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidPubkey, format!("{} is neither a .sol domain nor an address", name)))?;
+    Ok(SuccessResponse::new(ResolveNameResponse {
+        address: owner.to_string(),
+        domain: None,
+        name_account: None,
+        domains: Some(owned_domains(owner).await?),
+    }))
+}
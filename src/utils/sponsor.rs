@@ -0,0 +1,299 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::{
+    compute_budget,
+    instruction::{AccountMeta, Instruction},
+    message::{v0, Message, VersionedMessage},
+    packet::PACKET_DATA_SIZE,
+    pubkey,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::VersionedTransaction,
+};
+use std::{collections::HashMap, env, str::FromStr, sync::OnceLock};
+use crate::auth::{self, Scope};
+use crate::config;
+use crate::shutdown;
+use super::audit::{self, AuditOperation};
+use super::compute_budget::{priority_fee_lamports, ComputeBudgetSettings};
+use super::confirmation::{self, Commitment};
+use super::explain::instruction_name;
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+use super::signer::SigningKey;
+use super::instruction::validate_secret_key;
+use super::transaction::{broadcast, decode_transaction, missing_signers, sign_with, verify_signatures};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+// Sponsored transactions: the server's own key pays the fee for
+// transactions its users build, so they need no SOL. What it will pay for is
+// read from SPONSOR_POLICY as JSON, e.g.
+// {"instructions": {"TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA": ["Transfer", "TransferChecked"]},
+//  "maxTransactionBytes": 600, "maxSignatures": 2, "maxPriorityFeeLamports": 10000}.
+// Instructions are named as /instruction/decode names them, and an empty
+// list allows every instruction of the program. Without the variable only
+// SPL Token transfers, memos and compute budget settings are sponsored.
+
+const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+const DEFAULT_MAX_SIGNATURES: usize = 2;
+const DEFAULT_MAX_PRIORITY_FEE_LAMPORTS: u64 = 10_000;
+
+#[derive(Deserialize)]
+struct SponsorPolicy {
+    // Program id to the instruction names allowed for it
+    instructions: HashMap<String, Vec<String>>,
+    #[serde(rename = "maxTransactionBytes", default = "default_max_transaction_bytes")]
+    max_transaction_bytes: usize,
+    // Counting the fee payer's, since each signature adds to the fee
+    #[serde(rename = "maxSignatures", default = "default_max_signatures")]
+    max_signatures: usize,
+    #[serde(rename = "maxPriorityFeeLamports", default = "default_max_priority_fee_lamports")]
+    max_priority_fee_lamports: u64,
+}
+
+fn default_max_transaction_bytes() -> usize {
+    PACKET_DATA_SIZE
+}
+
+fn default_max_signatures() -> usize {
+    DEFAULT_MAX_SIGNATURES
+}
+
+fn default_max_priority_fee_lamports() -> u64 {
+    DEFAULT_MAX_PRIORITY_FEE_LAMPORTS
+}
+
+impl Default for SponsorPolicy {
+    fn default() -> Self {
+        let allowed = |program_id: Pubkey, names: &[&str]| {
+            (program_id.to_string(), names.iter().map(|name| name.to_string()).collect())
+        };
+        SponsorPolicy {
+            instructions: HashMap::from([
+                allowed(spl_token::id(), &["Transfer", "TransferChecked"]),
+                allowed(MEMO_PROGRAM_ID, &[]),
+                allowed(compute_budget::id(), &["SetComputeUnitLimit", "SetComputeUnitPrice"]),
+            ]),
+            max_transaction_bytes: default_max_transaction_bytes(),
+            max_signatures: default_max_signatures(),
+            max_priority_fee_lamports: default_max_priority_fee_lamports(),
+        }
+    }
+}
+
+static POLICY: OnceLock<SponsorPolicy> = OnceLock::new();
+
+fn policy() -> &'static SponsorPolicy {
+    POLICY.get_or_init(|| {
+        let Ok(config) = env::var("SPONSOR_POLICY") else {
+            return SponsorPolicy::default();
+        };
+        let policy: SponsorPolicy = serde_json::from_str(&config).unwrap_or_else(|err| {
+            panic!("SPONSOR_POLICY is not a JSON sponsorship policy: {}", err)
+        });
+        if let Some(program_id) = policy.instructions.keys().find(|program_id| Pubkey::from_str(program_id).is_err()) {
+            panic!("SPONSOR_POLICY has an invalid program id: {}", program_id);
+        }
+        policy
+    })
+}
+
+fn sponsor() -> Result<&'static Keypair, ErrorResponse> {
+    static SPONSOR: OnceLock<Option<Keypair>> = OnceLock::new();
+    SPONSOR
+        .get_or_init(|| {
+            config::get().sponsor_secret_key.as_deref().map(|secret| {
+                validate_secret_key(secret).unwrap_or_else(|err| panic!("SPONSOR_SECRET_KEY is invalid: {}", err.error))
+            })
+        })
+        .as_ref()
+        .ok_or_else(|| ErrorResponse::new("Transaction sponsorship is not enabled on this server"))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SponsorInfoResponse {
+    // Set this as the fee payer, or leave it to /transaction/sponsor
+    pub fee_payer: String,
+    // Program id to the instructions sponsored for it; empty allows all
+    pub instructions: HashMap<String, Vec<String>>,
+    pub max_transaction_bytes: usize,
+    pub max_signatures: usize,
+    pub max_priority_fee_lamports: u64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SponsorTransactionRequest {
+    // Base64 legacy or v0 transaction. When its fee payer is not the
+    // sponsor and nothing is signed yet, the sponsor is made the fee payer
+    // and the transaction comes back for its signers.
+    pub transaction: String,
+    // Broadcast once every signature is present
+    #[serde(default)]
+    pub submit: bool,
+    #[serde(rename = "skipPreflight", default)]
+    pub skip_preflight: bool,
+    // How far a submitted transaction is watched
+    #[serde(default)]
+    pub commitment: Commitment,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SponsorTransactionResponse {
+    // Base64, signed by the fee payer
+    pub transaction: String,
+    pub fee_payer: String,
+    pub missing_signers: Vec<String>,
+    // Set once the transaction has been broadcast
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+pub fn get_sponsor() -> Result<SuccessResponse<SponsorInfoResponse>, ErrorResponse> {
+    let sponsor = sponsor()?;
+    let policy = policy();
+    Ok(SuccessResponse::new(SponsorInfoResponse {
+        fee_payer: sponsor.pubkey().to_string(),
+        instructions: policy.instructions.clone(),
+        max_transaction_bytes: policy.max_transaction_bytes,
+        max_signatures: policy.max_signatures,
+        max_priority_fee_lamports: policy.max_priority_fee_lamports,
+    }))
+}
+
+fn instructions(message: &VersionedMessage) -> Vec<Instruction> {
+    let keys = message.static_account_keys();
+    message
+        .instructions()
+        .iter()
+        .map(|instruction| Instruction {
+            program_id: keys[instruction.program_id_index as usize],
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|&index| AccountMeta {
+                    pubkey: keys[index as usize],
+                    is_signer: message.is_signer(index as usize),
+                    is_writable: message.is_maybe_writable(index as usize),
+                })
+                .collect(),
+            data: instruction.data.clone(),
+        })
+        .collect()
+}
+
+// Recompile with the sponsor paying, keeping the message version. Only
+// possible before anyone signs, since it changes the message.
+fn with_fee_payer(transaction: &VersionedTransaction, fee_payer: &Pubkey) -> Result<VersionedTransaction, ErrorResponse> {
+    if transaction.signatures.iter().any(|signature| *signature != Signature::default()) {
+        return Err(ErrorResponse::new("The fee payer must be the sponsor once the transaction is signed"));
+    }
+    let instructions = instructions(&transaction.message);
+    let blockhash = *transaction.message.recent_blockhash();
+    let message = match transaction.message {
+        VersionedMessage::Legacy(_) => VersionedMessage::Legacy(Message::new_with_blockhash(&instructions, Some(fee_payer), &blockhash)),
+        VersionedMessage::V0(_) => VersionedMessage::V0(
+            v0::Message::try_compile(fee_payer, &instructions, &[], blockhash)
+                .map_err(|err| ErrorResponse::new(format!("Failed to compile message: {}", err)))?,
+        ),
+    };
+    let signatures = vec![Signature::default(); message.header().num_required_signatures as usize];
+    Ok(VersionedTransaction { signatures, message })
+}
+
+// The sponsor only pays the fee: it may not appear in any instruction,
+// where it could be made to sign a transfer out of its own account
+fn check_policy(transaction: &VersionedTransaction, fee_payer: &Pubkey) -> Result<(), ErrorResponse> {
+    let policy = policy();
+    let message = &transaction.message;
+    let size = bincode::serialized_size(transaction).unwrap_or(u64::MAX) as usize;
+    if size > policy.max_transaction_bytes {
+        return Err(ErrorResponse::new(format!("Sponsored transactions must be at most {} bytes", policy.max_transaction_bytes)));
+    }
+    if transaction.signatures.len() > policy.max_signatures {
+        return Err(ErrorResponse::new(format!("Sponsored transactions can have at most {} signatures", policy.max_signatures)));
+    }
+
+    let mut budget = ComputeBudgetSettings::default();
+    let instructions = instructions(message);
+    for instruction in &instructions {
+        if instruction.accounts.iter().any(|account| account.pubkey == *fee_payer) {
+            return Err(ErrorResponse::new("The sponsor can only be the fee payer"));
+        }
+        let program_id = instruction.program_id.to_string();
+        let name = instruction_name(&instruction.program_id, &instruction.data);
+        let allowed = policy.instructions.get(&program_id).is_some_and(|names| {
+            names.is_empty() || name.as_ref().is_some_and(|name| names.contains(name))
+        });
+        if !allowed {
+            return Err(ErrorResponse::new(format!(
+                "{} instructions of program {} are not sponsored",
+                name.as_deref().unwrap_or("Unknown"),
+                program_id,
+            )));
+        }
+        if compute_budget::check_id(&instruction.program_id) {
+            budget.apply(&instruction.data);
+        }
+    }
+
+    let instruction_count = instructions.iter().filter(|instruction| !compute_budget::check_id(&instruction.program_id)).count();
+    let priority_fee = priority_fee_lamports(budget.unit_price.unwrap_or(0), budget.compute_units(instruction_count as u64));
+    if priority_fee > policy.max_priority_fee_lamports {
+        return Err(ErrorResponse::new(format!("Sponsored priority fees are limited to {} lamports", policy.max_priority_fee_lamports)));
+    }
+    Ok(())
+}
+
+// Finishes on its own task, so a sponsored transaction that was signed or
+// sent is still watched and audited if the client disconnects
+pub async fn sponsor_transaction(request: SponsorTransactionRequest) -> Result<SuccessResponse<SponsorTransactionResponse>, ErrorResponse> {
+    shutdown::detached(async move {
+        let result = sponsor_and_sign(request).await;
+        audit::recorded(AuditOperation::SponsorTransaction, result, |sponsored| vec![sponsored.fee_payer.clone()])
+    })
+        .await
+}
+
+async fn sponsor_and_sign(request: SponsorTransactionRequest) -> Result<SuccessResponse<SponsorTransactionResponse>, ErrorResponse> {
+    // Validate inputs
+    if request.transaction.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
+    }
+    if request.submit && !auth::permits(Scope::Submit) {
+        return Err(auth::missing_scope(Scope::Submit));
+    }
+    let sponsor = sponsor()?;
+    let fee_payer = sponsor.pubkey();
+    let mut transaction = decode_transaction(&request.transaction)?;
+    // Loaded addresses can't be checked without fetching the tables
+    if transaction.message.address_table_lookups().is_some_and(|lookups| !lookups.is_empty()) {
+        return Err(ErrorResponse::new("Sponsored transactions cannot use address lookup tables"));
+    }
+    // Every account index is in range from here on
+    transaction.sanitize().map_err(|_| ErrorResponse::new("Invalid transaction format"))?;
+    if transaction.message.static_account_keys().first() != Some(&fee_payer) {
+        transaction = with_fee_payer(&transaction, &fee_payer)?;
+    }
+    check_policy(&transaction, &fee_payer)?;
+
+    sign_with(&mut transaction, &[SigningKey::Local(sponsor.insecure_clone())]).await?;
+    let missing_signers = missing_signers(&transaction);
+
+    let mut signature = None;
+    if request.submit {
+        verify_signatures(&transaction)?;
+        let sent = broadcast(&transaction, request.skip_preflight).await?;
+        confirmation::spawn(transaction.clone(), request.commitment);
+        signature = Some(sent.to_string());
+    }
+
+    let serialized = bincode::serialize(&transaction)
+        .map_err(|_| ErrorResponse::new("Failed to serialize transaction"))?;
+
+    Ok(SuccessResponse::new(SponsorTransactionResponse {
+        transaction: BASE64.encode(serialized),
+        fee_payer: fee_payer.to_string(),
+        missing_signers,
+        signature,
+    }))
+}
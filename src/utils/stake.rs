@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::{
+    stake::{
+        instruction as stake_instruction,
+        state::{Authorized, Lockup, StakeStateV2},
+    },
+    system_instruction,
+};
+use super::instruction::{validate_pubkey, InstructionResponse};
+use super::preload;
+use super::response_types::{SuccessResponse, ErrorResponse};
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateStakeAccountRequest {
+    pub from: String,
+    #[serde(rename = "stakeAccount")]
+    pub stake_account: String,
+    pub staker: String,
+    pub withdrawer: String,
+    pub lamports: u64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DelegateStakeRequest {
+    #[serde(rename = "stakeAccount")]
+    pub stake_account: String,
+    pub staker: String,
+    #[serde(rename = "voteAccount")]
+    pub vote_account: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DeactivateStakeRequest {
+    #[serde(rename = "stakeAccount")]
+    pub stake_account: String,
+    pub staker: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct WithdrawStakeRequest {
+    #[serde(rename = "stakeAccount")]
+    pub stake_account: String,
+    pub withdrawer: String,
+    pub destination: String,
+    pub lamports: u64,
+    // Required while the stake account's lockup is in force
+    pub custodian: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SplitStakeRequest {
+    #[serde(rename = "stakeAccount")]
+    pub stake_account: String,
+    pub staker: String,
+    #[serde(rename = "splitStakeAccount")]
+    pub split_stake_account: String,
+    pub lamports: u64,
+    // Funds the new account's rent-exempt reserve first, which the stake
+    // program requires of split destinations
+    #[serde(rename = "rentPayer")]
+    pub rent_payer: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreateStakeAccountResponse {
+    pub stake_account: String,
+    pub instructions: Vec<InstructionResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SplitStakeResponse {
+    pub split_stake_account: String,
+    pub instructions: Vec<InstructionResponse>,
+}
+
+fn validate_lamports(lamports: u64) -> Result<(), ErrorResponse> {
+    if lamports == 0 {
+        return Err(ErrorResponse::new("Amount must be greater than 0 lamports"));
+    }
+    Ok(())
+}
+
+fn stake_rent_exempt_minimum() -> u64 {
+    preload::rent().minimum_balance(StakeStateV2::size_of())
+}
+
+pub fn create_stake_account(request: CreateStakeAccountRequest) -> Result<SuccessResponse<CreateStakeAccountResponse>, ErrorResponse> {
+    // Validate inputs
+    let from = validate_pubkey(&request.from, "funding address")?;
+    let stake_account = validate_pubkey(&request.stake_account, "stake account")?;
+    let staker = validate_pubkey(&request.staker, "stake authority")?;
+    let withdrawer = validate_pubkey(&request.withdrawer, "withdraw authority")?;
+
+    if from == stake_account {
+        return Err(ErrorResponse::new("Funding address and stake account cannot be the same"));
+    }
+
+    let minimum = stake_rent_exempt_minimum();
+    if request.lamports < minimum {
+        return Err(ErrorResponse::new(format!(
+            "Stake accounts need at least {} lamports to be rent exempt",
+            minimum
+        )));
+    }
+
+    // System create_account followed by the stake program's Initialize
+    let instructions = stake_instruction::create_account(
+        &from,
+        &stake_account,
+        &Authorized { staker, withdrawer },
+        &Lockup::default(),
+        request.lamports,
+    );
+
+    let response = CreateStakeAccountResponse {
+        stake_account: stake_account.to_string(),
+        instructions: instructions.iter().map(InstructionResponse::from).collect(),
+    };
+
+    Ok(SuccessResponse::new(response))
+}
+
+pub fn delegate_stake(request: DelegateStakeRequest) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    let stake_account = validate_pubkey(&request.stake_account, "stake account")?;
+    let staker = validate_pubkey(&request.staker, "stake authority")?;
+    let vote_account = validate_pubkey(&request.vote_account, "vote account")?;
+
+    let instruction = stake_instruction::delegate_stake(&stake_account, &staker, &vote_account);
+
+    Ok(SuccessResponse::new(InstructionResponse::from(&instruction)))
+}
+
+pub fn deactivate_stake(request: DeactivateStakeRequest) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    let stake_account = validate_pubkey(&request.stake_account, "stake account")?;
+    let staker = validate_pubkey(&request.staker, "stake authority")?;
+
+    let instruction = stake_instruction::deactivate_stake(&stake_account, &staker);
+
+    Ok(SuccessResponse::new(InstructionResponse::from(&instruction)))
+}
+
+pub fn withdraw_stake(request: WithdrawStakeRequest) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    let stake_account = validate_pubkey(&request.stake_account, "stake account")?;
+    let withdrawer = validate_pubkey(&request.withdrawer, "withdraw authority")?;
+    let destination = validate_pubkey(&request.destination, "destination address")?;
+    let custodian = request.custodian
+        .as_deref()
+        .map(|custodian| validate_pubkey(custodian, "lockup custodian"))
+        .transpose()?;
+    validate_lamports(request.lamports)?;
+
+    if stake_account == destination {
+        return Err(ErrorResponse::new("Stake account and destination addresses cannot be the same"));
+    }
+
+    let instruction = stake_instruction::withdraw(
+        &stake_account,
+        &withdrawer,
+        &destination,
+        request.lamports,
+        custodian.as_ref(),
+    );
+
+    Ok(SuccessResponse::new(InstructionResponse::from(&instruction)))
+}
+
+pub fn split_stake(request: SplitStakeRequest) -> Result<SuccessResponse<SplitStakeResponse>, ErrorResponse> {
+    // Validate inputs
+    let stake_account = validate_pubkey(&request.stake_account, "stake account")?;
+    let staker = validate_pubkey(&request.staker, "stake authority")?;
+    let split_stake_account = validate_pubkey(&request.split_stake_account, "split stake account")?;
+    let rent_payer = request.rent_payer
+        .as_deref()
+        .map(|payer| validate_pubkey(payer, "rent payer"))
+        .transpose()?;
+    validate_lamports(request.lamports)?;
+
+    if stake_account == split_stake_account {
+        return Err(ErrorResponse::new("Stake account and split stake account cannot be the same"));
+    }
+
+    // Allocate and assign the new account, then move the stake into it
+    let mut instructions = Vec::new();
+    if let Some(rent_payer) = rent_payer {
+        instructions.push(system_instruction::transfer(&rent_payer, &split_stake_account, stake_rent_exempt_minimum()));
+    }
+    instructions.extend(stake_instruction::split(&stake_account, &staker, request.lamports, &split_stake_account));
+
+    let response = SplitStakeResponse {
+        split_stake_account: split_stake_account.to_string(),
+        instructions: instructions.iter().map(InstructionResponse::from).collect(),
+    };
+
+    Ok(SuccessResponse::new(response))
+}
@@ -0,0 +1,486 @@
+use borsh::BorshDeserialize;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+    stake::{self, instruction as stake_instruction, state::{StakeAuthorize, StakeStateV2}},
+    system_instruction,
+    system_program,
+    sysvar,
+};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use std::num::NonZeroU32;
+use super::footprint::fetch_accounts;
+use super::instruction::{validate_amount, validate_pubkey, InstructionResponse};
+use super::preload;
+use super::response_types::{ApiErrorCode, FieldErrors, SuccessResponse, ErrorResponse};
+use super::token::associated_token_address;
+
+// SPL Stake Pool: deposits of SOL or stake mint pool tokens, and pool tokens
+// are burned to withdraw SOL from the reserve or stake from a validator.
+// Every builder reads the pool account first for the accounts the program
+// wants alongside it.
+
+const STAKE_POOL_PROGRAM_ID: Pubkey = pubkey!("SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy");
+
+// StakePoolInstruction tags
+const DEPOSIT_STAKE: u8 = 9;
+const WITHDRAW_STAKE: u8 = 10;
+const DEPOSIT_SOL: u8 = 14;
+const WITHDRAW_SOL: u8 = 16;
+
+const STAKE_POOL_ACCOUNT_TYPE: u8 = 1;
+
+// A validator list is a 5-byte header and a vector of fixed-size entries
+const VALIDATOR_LIST_ENTRIES_OFFSET: usize = 9;
+const VALIDATOR_ENTRY_LEN: usize = 73;
+const VALIDATOR_ACTIVE_STAKE_OFFSET: usize = 0;
+const VALIDATOR_SEED_SUFFIX_OFFSET: usize = 36;
+const VALIDATOR_STATUS_OFFSET: usize = 40;
+const VALIDATOR_VOTE_OFFSET: usize = 41;
+const VALIDATOR_STATUS_ACTIVE: u8 = 0;
+
+#[derive(Deserialize, ToSchema)]
+pub struct StakePoolDepositSolRequest {
+    #[serde(rename = "stakePool")]
+    pub stake_pool: String,
+    // Pays the lamports and must sign
+    pub from: String,
+    pub lamports: u64,
+    // Receives the pool tokens; from's associated token account, created if
+    // needed, when omitted
+    #[serde(rename = "poolTokenAccount")]
+    pub pool_token_account: Option<String>,
+    // Pool token account paid the referral fee; the receiving account when
+    // omitted
+    pub referrer: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct StakePoolWithdrawSolRequest {
+    #[serde(rename = "stakePool")]
+    pub stake_pool: String,
+    // Owns the pool tokens and must sign
+    pub authority: String,
+    #[serde(rename = "poolTokens")]
+    pub pool_tokens: u64,
+    // Receives the lamports; the authority when omitted
+    pub destination: Option<String>,
+    // Burned from; the authority's associated token account when omitted
+    #[serde(rename = "poolTokenAccount")]
+    pub pool_token_account: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct StakePoolDepositStakeRequest {
+    #[serde(rename = "stakePool")]
+    pub stake_pool: String,
+    // An active stake account delegated to one of the pool's validators
+    #[serde(rename = "stakeAccount")]
+    pub stake_account: String,
+    // The stake account's withdraw authority, which hands both authorities
+    // to the pool and must sign
+    #[serde(rename = "withdrawAuthority")]
+    pub withdraw_authority: String,
+    // Receives the pool tokens; the withdraw authority's associated token
+    // account, created if needed, when omitted
+    #[serde(rename = "poolTokenAccount")]
+    pub pool_token_account: Option<String>,
+    pub referrer: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct StakePoolWithdrawStakeRequest {
+    #[serde(rename = "stakePool")]
+    pub stake_pool: String,
+    // Owns the pool tokens and must sign
+    pub authority: String,
+    #[serde(rename = "poolTokens")]
+    pub pool_tokens: u64,
+    // New keypair for the stake account split off to the withdrawer, which
+    // must sign
+    #[serde(rename = "stakeReceiver")]
+    pub stake_receiver: String,
+    // Staker and withdrawer of the new stake account; the authority when
+    // omitted
+    #[serde(rename = "newStakeAuthority")]
+    pub new_stake_authority: Option<String>,
+    // Funds the new stake account's rent; the authority when omitted
+    pub payer: Option<String>,
+    // Validator to take the stake from. When omitted, the pool's preferred
+    // withdraw validator, or else the one with the most active stake, or
+    // else the reserve.
+    #[serde(rename = "validatorVoteAccount")]
+    pub validator_vote_account: Option<String>,
+    #[serde(rename = "poolTokenAccount")]
+    pub pool_token_account: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct StakePoolResponse {
+    pub stake_pool: String,
+    pub pool_mint: String,
+    // The pool token account minted to or burned from
+    pub pool_token_account: String,
+    // In the order they must appear in the transaction
+    pub instructions: Vec<InstructionResponse>,
+}
+
+#[derive(BorshDeserialize)]
+struct Lockup {
+    _unix_timestamp: i64,
+    _epoch: u64,
+    _custodian: Pubkey,
+}
+
+#[derive(BorshDeserialize)]
+struct Fee {
+    _denominator: u64,
+    _numerator: u64,
+}
+
+// A fee change scheduled for one or two epochs from now
+#[derive(BorshDeserialize)]
+enum FutureEpochFee {
+    None,
+    One { _fee: Fee },
+    Two { _fee: Fee },
+}
+
+// The fields of a stake pool account up to the withdraw authority for SOL,
+// which is all that is read
+#[derive(BorshDeserialize)]
+struct StakePool {
+    account_type: u8,
+    _manager: Pubkey,
+    _staker: Pubkey,
+    stake_deposit_authority: Pubkey,
+    _stake_withdraw_bump_seed: u8,
+    validator_list: Pubkey,
+    reserve_stake: Pubkey,
+    pool_mint: Pubkey,
+    manager_fee_account: Pubkey,
+    token_program_id: Pubkey,
+    _total_lamports: u64,
+    _pool_token_supply: u64,
+    _last_update_epoch: u64,
+    _lockup: Lockup,
+    _epoch_fee: Fee,
+    _next_epoch_fee: FutureEpochFee,
+    _preferred_deposit_validator: Option<Pubkey>,
+    preferred_withdraw_validator: Option<Pubkey>,
+    _stake_deposit_fee: Fee,
+    _stake_withdrawal_fee: Fee,
+    _next_stake_withdrawal_fee: FutureEpochFee,
+    _stake_referral_fee: u8,
+    sol_deposit_authority: Option<Pubkey>,
+    _sol_deposit_fee: Fee,
+    _sol_referral_fee: u8,
+    sol_withdraw_authority: Option<Pubkey>,
+}
+
+struct ValidatorEntry {
+    vote_account: Pubkey,
+    seed_suffix: u32,
+    active_stake: u64,
+    active: bool,
+}
+
+fn validator_entries(account: &Account) -> Vec<ValidatorEntry> {
+    let entries = account.data.get(VALIDATOR_LIST_ENTRIES_OFFSET - 4..VALIDATOR_LIST_ENTRIES_OFFSET)
+        .map(|len| u32::from_le_bytes(len.try_into().unwrap()) as usize)
+        .unwrap_or_default();
+    account.data
+        .get(VALIDATOR_LIST_ENTRIES_OFFSET..)
+        .unwrap_or_default()
+        .chunks_exact(VALIDATOR_ENTRY_LEN)
+        .take(entries)
+        .map(|entry| {
+            let u64_at = |offset: usize| u64::from_le_bytes(entry[offset..offset + 8].try_into().unwrap());
+            ValidatorEntry {
+                vote_account: Pubkey::try_from(&entry[VALIDATOR_VOTE_OFFSET..VALIDATOR_VOTE_OFFSET + 32]).unwrap(),
+                seed_suffix: u32::from_le_bytes(entry[VALIDATOR_SEED_SUFFIX_OFFSET..VALIDATOR_SEED_SUFFIX_OFFSET + 4].try_into().unwrap()),
+                active_stake: u64_at(VALIDATOR_ACTIVE_STAKE_OFFSET),
+                active: entry[VALIDATOR_STATUS_OFFSET] == VALIDATOR_STATUS_ACTIVE,
+            }
+        })
+        .collect()
+}
+
+fn withdraw_authority(stake_pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[stake_pool.as_ref(), b"withdraw"], &STAKE_POOL_PROGRAM_ID).0
+}
+
+fn default_deposit_authority(stake_pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[stake_pool.as_ref(), b"deposit"], &STAKE_POOL_PROGRAM_ID).0
+}
+
+fn validator_stake_address(stake_pool: &Pubkey, validator: &ValidatorEntry) -> Pubkey {
+    let suffix = NonZeroU32::new(validator.seed_suffix).map(|suffix| suffix.get().to_le_bytes());
+    let mut seeds = vec![validator.vote_account.as_ref(), stake_pool.as_ref()];
+    if let Some(suffix) = &suffix {
+        seeds.push(suffix);
+    }
+    Pubkey::find_program_address(&seeds, &STAKE_POOL_PROGRAM_ID).0
+}
+
+fn instruction_data(tag: u8, amount: Option<u64>) -> Vec<u8> {
+    let mut data = vec![tag];
+    if let Some(amount) = amount {
+        data.extend_from_slice(&amount.to_le_bytes());
+    }
+    data
+}
+
+// The pool account, and any others asked for alongside it in the same call
+async fn fetch_stake_pool(stake_pool: &Pubkey, others: &[Pubkey]) -> Result<(StakePool, Vec<Option<Account>>), ErrorResponse> {
+    let not_a_pool = || ErrorResponse::with_code(ApiErrorCode::InvalidAccount, format!("Account {} is not a stake pool", stake_pool));
+    let addresses: Vec<Pubkey> = std::iter::once(*stake_pool).chain(others.iter().copied()).collect();
+    let mut accounts = fetch_accounts(&addresses).await?;
+    let pool = accounts.remove(0).ok_or_else(not_a_pool)?;
+    if pool.owner != STAKE_POOL_PROGRAM_ID {
+        return Err(not_a_pool());
+    }
+    let state = StakePool::deserialize(&mut pool.data.as_slice()).map_err(|_| not_a_pool())?;
+    if state.account_type != STAKE_POOL_ACCOUNT_TYPE {
+        return Err(not_a_pool());
+    }
+    Ok((state, accounts))
+}
+
+async fn fetch_validators(pool: &StakePool) -> Result<Vec<ValidatorEntry>, ErrorResponse> {
+    let account = fetch_accounts(&[pool.validator_list])
+        .await?
+        .pop()
+        .flatten()
+        .ok_or_else(|| ErrorResponse::new(format!("Validator list {} was not found", pool.validator_list)))?;
+    Ok(validator_entries(&account))
+}
+
+// The given pool token account, or the owner's associated token account
+// along with the instruction creating it when the owner pays for it
+fn pool_token_target(account: &Option<String>, owner: &Pubkey, pool: &StakePool, create: bool) -> Result<(Pubkey, Option<Instruction>), ErrorResponse> {
+    match account {
+        Some(account) => Ok((validate_pubkey(account, "poolTokenAccount")?, None)),
+        None => Ok((
+            associated_token_address(owner, &pool.pool_mint, &pool.token_program_id),
+            create.then(|| create_associated_token_account_idempotent(owner, owner, &pool.pool_mint, &pool.token_program_id)),
+        )),
+    }
+}
+
+fn stake_pool_response(stake_pool: &Pubkey, pool: &StakePool, pool_token_account: &Pubkey, instructions: &[Instruction]) -> SuccessResponse<StakePoolResponse> {
+    SuccessResponse::new(StakePoolResponse {
+        stake_pool: stake_pool.to_string(),
+        pool_mint: pool.pool_mint.to_string(),
+        pool_token_account: pool_token_account.to_string(),
+        instructions: instructions.iter().map(InstructionResponse::from).collect(),
+    })
+}
+
+pub async fn stake_pool_deposit_sol(request: StakePoolDepositSolRequest) -> Result<SuccessResponse<StakePoolResponse>, ErrorResponse> {
+    // Validate inputs
+    let mut fields = FieldErrors::default();
+    let stake_pool = fields.check("stakePool", validate_pubkey(&request.stake_pool, "stakePool"));
+    let from = fields.check("from", validate_pubkey(&request.from, "from"));
+    fields.check("lamports", validate_amount(request.lamports));
+    fields.finish()?;
+
+    let (pool, _) = fetch_stake_pool(&stake_pool, &[]).await?;
+    let (pool_token_account, create_account) = pool_token_target(&request.pool_token_account, &from, &pool, true)?;
+    let referrer = match &request.referrer {
+        Some(referrer) => validate_pubkey(referrer, "referrer")?,
+        None => pool_token_account,
+    };
+
+    let mut accounts = vec![
+        AccountMeta::new(stake_pool, false),
+        AccountMeta::new_readonly(withdraw_authority(&stake_pool), false),
+        AccountMeta::new(pool.reserve_stake, false),
+        AccountMeta::new(from, true),
+        AccountMeta::new(pool_token_account, false),
+        AccountMeta::new(pool.manager_fee_account, false),
+        AccountMeta::new(referrer, false),
+        AccountMeta::new(pool.pool_mint, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(pool.token_program_id, false),
+    ];
+    // Pools restricting SOL deposits need their deposit authority to sign too
+    if let Some(authority) = pool.sol_deposit_authority {
+        accounts.push(AccountMeta::new_readonly(authority, true));
+    }
+    let deposit = Instruction::new_with_bytes(STAKE_POOL_PROGRAM_ID, &instruction_data(DEPOSIT_SOL, Some(request.lamports)), accounts);
+
+    let instructions: Vec<Instruction> = create_account.into_iter().chain(std::iter::once(deposit)).collect();
+    Ok(stake_pool_response(&stake_pool, &pool, &pool_token_account, &instructions))
+}
+
+pub async fn stake_pool_withdraw_sol(request: StakePoolWithdrawSolRequest) -> Result<SuccessResponse<StakePoolResponse>, ErrorResponse> {
+    // Validate inputs
+    let mut fields = FieldErrors::default();
+    let stake_pool = fields.check("stakePool", validate_pubkey(&request.stake_pool, "stakePool"));
+    let authority = fields.check("authority", validate_pubkey(&request.authority, "authority"));
+    fields.check("poolTokens", validate_amount(request.pool_tokens));
+    let destination = match &request.destination {
+        Some(destination) => fields.check("destination", validate_pubkey(destination, "destination")),
+        None => authority,
+    };
+    fields.finish()?;
+
+    let (pool, _) = fetch_stake_pool(&stake_pool, &[]).await?;
+    let (pool_token_account, _) = pool_token_target(&request.pool_token_account, &authority, &pool, false)?;
+
+    let mut accounts = vec![
+        AccountMeta::new(stake_pool, false),
+        AccountMeta::new_readonly(withdraw_authority(&stake_pool), false),
+        AccountMeta::new_readonly(authority, true),
+        AccountMeta::new(pool_token_account, false),
+        AccountMeta::new(pool.reserve_stake, false),
+        AccountMeta::new(destination, false),
+        AccountMeta::new(pool.manager_fee_account, false),
+        AccountMeta::new(pool.pool_mint, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(sysvar::stake_history::id(), false),
+        AccountMeta::new_readonly(stake::program::id(), false),
+        AccountMeta::new_readonly(pool.token_program_id, false),
+    ];
+    if let Some(sol_authority) = pool.sol_withdraw_authority {
+        accounts.push(AccountMeta::new_readonly(sol_authority, true));
+    }
+    let withdraw = Instruction::new_with_bytes(STAKE_POOL_PROGRAM_ID, &instruction_data(WITHDRAW_SOL, Some(request.pool_tokens)), accounts);
+
+    Ok(stake_pool_response(&stake_pool, &pool, &pool_token_account, &[withdraw]))
+}
+
+// The stake account's authorities go to the pool's deposit authority, which
+// then hands the stake to the validator's pool stake account
+pub async fn stake_pool_deposit_stake(request: StakePoolDepositStakeRequest) -> Result<SuccessResponse<StakePoolResponse>, ErrorResponse> {
+    // Validate inputs
+    let mut fields = FieldErrors::default();
+    let stake_pool = fields.check("stakePool", validate_pubkey(&request.stake_pool, "stakePool"));
+    let stake_account = fields.check("stakeAccount", validate_pubkey(&request.stake_account, "stakeAccount"));
+    let withdraw_authority_key = fields.check("withdrawAuthority", validate_pubkey(&request.withdraw_authority, "withdrawAuthority"));
+    fields.finish()?;
+
+    let (pool, mut accounts) = fetch_stake_pool(&stake_pool, &[stake_account]).await?;
+    let voter = accounts
+        .pop()
+        .flatten()
+        .filter(|account| account.owner == stake::program::id())
+        .and_then(|account| bincode::deserialize::<StakeStateV2>(&account.data).ok())
+        .and_then(|state| state.delegation())
+        .map(|delegation| delegation.voter_pubkey)
+        .ok_or_else(|| ErrorResponse::with_code(ApiErrorCode::InvalidAccount, format!("Stake account {} is not delegated", stake_account)))?;
+    let validators = fetch_validators(&pool).await?;
+    let validator = validators
+        .iter()
+        .find(|validator| validator.vote_account == voter)
+        .ok_or_else(|| ErrorResponse::new(format!("Validator {} is not in the stake pool", voter)))?;
+
+    let (pool_token_account, create_account) = pool_token_target(&request.pool_token_account, &withdraw_authority_key, &pool, true)?;
+    let referrer = match &request.referrer {
+        Some(referrer) => validate_pubkey(referrer, "referrer")?,
+        None => pool_token_account,
+    };
+
+    // A pool with its own deposit authority has it sign the deposit; the
+    // default one is a program address the pool signs for itself
+    let deposit_authority = pool.stake_deposit_authority;
+    let custom_authority = deposit_authority != default_deposit_authority(&stake_pool);
+    let mut instructions: Vec<Instruction> = create_account.into_iter().collect();
+    for authorize in [StakeAuthorize::Staker, StakeAuthorize::Withdrawer] {
+        instructions.push(stake_instruction::authorize(&stake_account, &withdraw_authority_key, &deposit_authority, authorize, None));
+    }
+    instructions.push(Instruction::new_with_bytes(STAKE_POOL_PROGRAM_ID, &instruction_data(DEPOSIT_STAKE, None), vec![
+        AccountMeta::new(stake_pool, false),
+        AccountMeta::new(pool.validator_list, false),
+        AccountMeta::new_readonly(deposit_authority, custom_authority),
+        AccountMeta::new_readonly(withdraw_authority(&stake_pool), false),
+        AccountMeta::new(stake_account, false),
+        AccountMeta::new(validator_stake_address(&stake_pool, validator), false),
+        AccountMeta::new(pool.reserve_stake, false),
+        AccountMeta::new(pool_token_account, false),
+        AccountMeta::new(pool.manager_fee_account, false),
+        AccountMeta::new(referrer, false),
+        AccountMeta::new(pool.pool_mint, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(sysvar::stake_history::id(), false),
+        AccountMeta::new_readonly(pool.token_program_id, false),
+        AccountMeta::new_readonly(stake::program::id(), false),
+    ]));
+
+    Ok(stake_pool_response(&stake_pool, &pool, &pool_token_account, &instructions))
+}
+
+// Stake is split off a validator's pool stake account, or the reserve, into
+// a new stake account owned by the withdrawer
+pub async fn stake_pool_withdraw_stake(request: StakePoolWithdrawStakeRequest) -> Result<SuccessResponse<StakePoolResponse>, ErrorResponse> {
+    // Validate inputs
+    let mut fields = FieldErrors::default();
+    let stake_pool = fields.check("stakePool", validate_pubkey(&request.stake_pool, "stakePool"));
+    let authority = fields.check("authority", validate_pubkey(&request.authority, "authority"));
+    let stake_receiver = fields.check("stakeReceiver", validate_pubkey(&request.stake_receiver, "stakeReceiver"));
+    fields.check("poolTokens", validate_amount(request.pool_tokens));
+    let new_stake_authority = match &request.new_stake_authority {
+        Some(new_authority) => fields.check("newStakeAuthority", validate_pubkey(new_authority, "newStakeAuthority")),
+        None => authority,
+    };
+    let payer = match &request.payer {
+        Some(payer) => fields.check("payer", validate_pubkey(payer, "payer")),
+        None => authority,
+    };
+    let vote_account = request
+        .validator_vote_account
+        .as_ref()
+        .map(|vote_account| fields.check("validatorVoteAccount", validate_pubkey(vote_account, "validatorVoteAccount")));
+    fields.finish()?;
+
+    let (pool, _) = fetch_stake_pool(&stake_pool, &[]).await?;
+    let validators = fetch_validators(&pool).await?;
+    let validator = match vote_account.or(pool.preferred_withdraw_validator) {
+        Some(vote_account) => Some(
+            validators
+                .iter()
+                .find(|validator| validator.vote_account == vote_account)
+                .ok_or_else(|| ErrorResponse::new(format!("Validator {} is not in the stake pool", vote_account)))?,
+        ),
+        None => validators
+            .iter()
+            .filter(|validator| validator.active && validator.active_stake > 0)
+            .max_by_key(|validator| validator.active_stake),
+    };
+    let split_from = match validator {
+        Some(validator) => validator_stake_address(&stake_pool, validator),
+        None => pool.reserve_stake,
+    };
+    let (pool_token_account, _) = pool_token_target(&request.pool_token_account, &authority, &pool, false)?;
+
+    let space = StakeStateV2::size_of();
+    let create_receiver = system_instruction::create_account(
+        &payer,
+        &stake_receiver,
+        preload::rent().minimum_balance(space),
+        space as u64,
+        &stake::program::id(),
+    );
+    let withdraw = Instruction::new_with_bytes(STAKE_POOL_PROGRAM_ID, &instruction_data(WITHDRAW_STAKE, Some(request.pool_tokens)), vec![
+        AccountMeta::new(stake_pool, false),
+        AccountMeta::new(pool.validator_list, false),
+        AccountMeta::new_readonly(withdraw_authority(&stake_pool), false),
+        AccountMeta::new(split_from, false),
+        AccountMeta::new(stake_receiver, false),
+        AccountMeta::new_readonly(new_stake_authority, false),
+        AccountMeta::new_readonly(authority, true),
+        AccountMeta::new(pool_token_account, false),
+        AccountMeta::new(pool.manager_fee_account, false),
+        AccountMeta::new(pool.pool_mint, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(pool.token_program_id, false),
+        AccountMeta::new_readonly(stake::program::id(), false),
+    ]);
+
+    Ok(stake_pool_response(&stake_pool, &pool, &pool_token_account, &[create_receiver, withdraw]))
+}
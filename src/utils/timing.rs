@@ -0,0 +1,95 @@
+use axum::{body::Body, http::Request, middleware::Next, response::Response};
+use serde::Serialize;
+use std::{cell::RefCell, future::Future, time::Instant};
+
+// Requests sent with `X-Debug-Timing: 1` collect a breakdown of where their
+// time went, which `respond` adds to the JSON body as `timings`
+
+#[derive(Serialize, Clone)]
+pub struct PhaseTiming {
+    pub phase: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<&'static str>,
+    pub ms: f64,
+}
+
+#[derive(Serialize)]
+pub struct Timings {
+    pub total_ms: f64,
+    pub phases: Vec<PhaseTiming>,
+}
+
+struct Collector {
+    started: Instant,
+    phases: Vec<PhaseTiming>,
+}
+
+tokio::task_local! {
+    static COLLECTOR: RefCell<Collector>;
+}
+
+fn elapsed_ms(started: Instant) -> f64 {
+    started.elapsed().as_secs_f64() * 1000.0
+}
+
+fn record(phase: &'static str, method: Option<&'static str>, started: Instant) {
+    let ms = elapsed_ms(started);
+    let _ = COLLECTOR.try_with(|collector| {
+        collector.borrow_mut().phases.push(PhaseTiming { phase, method, ms });
+    });
+}
+
+// Records the time until it is dropped
+pub struct Timer {
+    phase: &'static str,
+    started: Instant,
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        record(self.phase, None, self.started);
+    }
+}
+
+pub fn start(phase: &'static str) -> Timer {
+    Timer { phase, started: Instant::now() }
+}
+
+pub fn measure<T>(phase: &'static str, f: impl FnOnce() -> T) -> T {
+    let _timer = start(phase);
+    f()
+}
+
+// Time an RPC call, labelled with its JSON-RPC method
+pub async fn rpc<F: Future>(method: &'static str, call: F) -> F::Output {
+    let started = Instant::now();
+    let output = call.await;
+    record("rpc", Some(method), started);
+    output
+}
+
+// The breakdown so far, when the current request asked for one
+pub fn snapshot() -> Option<Timings> {
+    COLLECTOR
+        .try_with(|collector| {
+            let collector = collector.borrow();
+            Timings {
+                total_ms: elapsed_ms(collector.started),
+                phases: collector.phases.clone(),
+            }
+        })
+        .ok()
+}
+
+pub async fn collect(request: Request<Body>, next: Next) -> Response {
+    let enabled = request
+        .headers()
+        .get("x-debug-timing")
+        .is_some_and(|value| value == "1");
+    if !enabled {
+        return next.run(request).await;
+    }
+
+    let collector = Collector { started: Instant::now(), phases: Vec::new() };
+    COLLECTOR.scope(RefCell::new(collector), next.run(request)).await
+}
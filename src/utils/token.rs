@@ -1,211 +1,437 @@
 use serde::{Deserialize, Serialize};
-use solana_sdk::{
-    instruction::{AccountMeta, Instruction},
-    pubkey::Pubkey,
-    system_program,
-    sysvar::rent,
+use utoipa::ToSchema;
+use solana_sdk::{instruction::Instruction, program_pack::Pack, pubkey::Pubkey, system_instruction};
+use spl_token::{instruction as token_instruction, state::{Account as TokenAccount, Mint}};
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use super::instruction::{validate_amount, validate_pubkey, InstructionResponse};
+use super::compute_budget::{
+    format_compute_budget_instructions, priority_fee_instructions, ComputeBudgetInstructionResponse, PriorityFee,
 };
-use spl_token::{instruction as token_instruction, state::Account as TokenAccount};
-use spl_associated_token_account::instruction as ata_instruction;
-use super::response_types::{SuccessResponse, ErrorResponse};
-use bs58;
-use base64;
+use super::memo::memo_instruction;
+use super::mint_config;
+use super::preload;
+use super::response_types::{ApiErrorCode, FieldErrors, SuccessResponse, ErrorResponse};
+use super::rpc::{self, rpc_error};
+use super::sns::{resolve_recipient, ResolvedName};
+use super::token_2022::{extended_mint, ExtendedMint, MintExtensions};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateTokenRequest {
     #[serde(rename = "mintAuthority")]
     pub mint_authority: String,
     pub mint: String,
     pub decimals: u8,
+    // May freeze the mint's token accounts; none when omitted
+    #[serde(rename = "freezeAuthority")]
+    pub freeze_authority: Option<String>,
+    // Makes a Token-2022 mint with these extensions, sized for them
+    pub extensions: Option<MintExtensions>,
+    // Tokens minted to initialHolder's associated token account, which is
+    // created if needed; initialHolder defaults to the mint authority
+    #[serde(rename = "initialSupply")]
+    pub initial_supply: Option<u64>,
+    #[serde(rename = "initialHolder")]
+    pub initial_holder: Option<String>,
+    // Funds the mint account, created here when given, and the holder's
+    // token account. Required with extensions or initialSupply.
+    pub payer: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, ToSchema)]
+pub struct CreateTokenResponse {
+    // Deprecated: the initialize mint instruction alone, kept for clients
+    // from before `instructions`. Use `instructions` instead
+    #[serde(flatten)]
+    pub instruction: InstructionResponse,
+    // The whole setup in the order to send it: create the mint account,
+    // initialize its extensions and the mint, write the token metadata, then
+    // create the holder's token account and mint the initial supply
+    pub instructions: Vec<InstructionResponse>,
+    // Rent paid into the mint account when it is created here
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lamports: Option<u64>,
+    // Receives the initial supply
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub holder_token_account: Option<String>,
+}
+
+// What /token/mint minted before requests named a destination and amount
+const LEGACY_MINT_AMOUNT: u64 = 1_000_000_000;
+
+#[derive(Deserialize, ToSchema)]
 pub struct MintTokenRequest {
     #[serde(rename = "mintAuthority")]
     pub mint_authority: String,
     pub mint: String,
-    pub decimals: u8,
+    // Wallet that receives the tokens, in its associated token account
+    pub destination: Option<String>,
+    // Base units to mint
+    pub amount: Option<u64>,
+    // Required unless configured or preloaded for the mint
+    pub decimals: Option<u8>,
 }
 
-#[derive(Serialize)]
-pub struct AccountInfo {
-    pub pubkey: String,
-    pub is_signer: bool,
-    pub is_writable: bool,
+#[derive(Serialize, ToSchema)]
+pub struct MintTokenResponse {
+    #[serde(flatten)]
+    pub instruction: InstructionResponse,
+    // Set when the request used the old shape without destination and amount
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecation: Option<String>,
 }
 
-#[derive(Serialize)]
-pub struct CreateTokenResponse {
-    pub program_id: String,
-    pub accounts: Vec<AccountInfo>,
-    pub instruction_data: String,
+#[derive(Deserialize, ToSchema)]
+pub struct SendTokenRequest {
+    // The receiving wallet, or a .sol domain resolved to its owner
+    pub destination: String,
+    pub mint: String,
+    pub owner: String,
+    pub amount: u64,
+    // Required unless configured or preloaded for the mint; /send/token
+    // looks it up from the mint account when omitted
+    pub decimals: Option<u8>,
+    #[serde(rename = "priorityFee")]
+    pub priority_fee: Option<PriorityFee>,
+    // Attached as a memo instruction signed by the owner
+    pub memo: Option<String>,
 }
 
-#[derive(Serialize)]
-pub struct MintTokenResponse {
-    pub program_id: String,
-    pub accounts: Vec<AccountInfo>,
-    pub instruction_data: String,
+#[derive(Serialize, ToSchema)]
+pub struct SendTokenResponse {
+    #[serde(flatten)]
+    pub instruction: InstructionResponse,
+    // Set the priority fee; place these ahead of the instruction above
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub compute_budget_instructions: Vec<ComputeBudgetInstructionResponse>,
+    // Place this after the instruction above
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo_instruction: Option<InstructionResponse>,
+    // Set when destination was a .sol domain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_name: Option<ResolvedName>,
 }
 
-#[derive(Deserialize)]
-pub struct SendTokenRequest {
+#[derive(Deserialize, ToSchema)]
+pub struct CloseTokenAccountRequest {
+    // The token account to close; give this or mint to close the owner's
+    // associated token account for that mint
+    pub account: Option<String>,
+    pub mint: Option<String>,
+    pub owner: String,
+    // Receives the account's rent-exempt lamports
     pub destination: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ApproveTokenRequest {
     pub mint: String,
     pub owner: String,
+    pub delegate: String,
+    // Most the delegate may transfer, in base units
     pub amount: u64,
+    // Defaults to the owner's associated token account for the mint
+    pub account: Option<String>,
+    // Needed unless the mint is configured or was loaded at startup
+    pub decimals: Option<u8>,
 }
 
-#[derive(Serialize)]
-pub struct SendTokenResponse {
-    pub program_id: String,
-    pub accounts: Vec<AccountInfo>,
-    pub instruction_data: String,
+#[derive(Deserialize, ToSchema)]
+pub struct RevokeTokenRequest {
+    // The token account to revoke the delegate of; give this or mint to use
+    // the owner's associated token account for that mint
+    pub account: Option<String>,
+    pub mint: Option<String>,
+    pub owner: String,
+}
+
+// An SPL token account as stored on chain, amounts in base units
+#[derive(Serialize, ToSchema)]
+pub struct TokenAccountView {
+    pub mint: String,
+    pub owner: String,
+    pub amount: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delegate: Option<String>,
+    pub delegated_amount: u64,
+    pub frozen: bool,
+    // Wrapped SOL accounts hold their balance as lamports
+    pub is_native: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub close_authority: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MintView {
+    // None once minting has been disabled for good
+    pub mint_authority: Option<String>,
+    pub supply: u64,
+    pub decimals: u8,
+    pub freeze_authority: Option<String>,
+}
+
+impl From<&TokenAccount> for TokenAccountView {
+    fn from(account: &TokenAccount) -> Self {
+        TokenAccountView {
+            mint: account.mint.to_string(),
+            owner: account.owner.to_string(),
+            amount: account.amount,
+            delegate: Option::<Pubkey>::from(account.delegate).map(|delegate| delegate.to_string()),
+            delegated_amount: account.delegated_amount,
+            frozen: account.is_frozen(),
+            is_native: account.is_native(),
+            close_authority: Option::<Pubkey>::from(account.close_authority).map(|authority| authority.to_string()),
+        }
+    }
+}
+
+impl From<&Mint> for MintView {
+    fn from(mint: &Mint) -> Self {
+        MintView {
+            mint_authority: Option::<Pubkey>::from(mint.mint_authority).map(|authority| authority.to_string()),
+            supply: mint.supply,
+            decimals: mint.decimals,
+            freeze_authority: Option::<Pubkey>::from(mint.freeze_authority).map(|authority| authority.to_string()),
+        }
+    }
 }
 
-fn validate_pubkey(key: &str, field_name: &str) -> Result<Pubkey, ErrorResponse> {
-    bs58::decode(key)
-        .into_vec()
-        .map_err(|_| ErrorResponse::new(format!("Invalid base58 encoding for {}", field_name)))
-        .and_then(|bytes| 
-            Pubkey::try_from(bytes.as_slice())
-                .map_err(|_| ErrorResponse::new(format!("Invalid public key format for {}", field_name)))
-        )
+impl From<&spl_token_2022::state::Mint> for MintView {
+    fn from(mint: &spl_token_2022::state::Mint) -> Self {
+        MintView {
+            mint_authority: Option::<Pubkey>::from(mint.mint_authority).map(|authority| authority.to_string()),
+            supply: mint.supply,
+            decimals: mint.decimals,
+            freeze_authority: Option::<Pubkey>::from(mint.freeze_authority).map(|authority| authority.to_string()),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MintInfoResponse {
+    pub mint: String,
+    pub token_program: String,
+    #[serde(flatten)]
+    pub state: MintView,
+    // Display symbol configured for the mint
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    // Token-2022 extensions the mint was created with, e.g. TransferFeeConfig
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<String>,
+}
+
+// Reads the data of an account owned by the SPL Token program as a token
+// account or a mint, telling them apart by size. Multisig accounts and
+// uninitialized data read as neither.
+pub fn parse_token_state(data: &[u8]) -> (Option<TokenAccountView>, Option<MintView>) {
+    match data.len() {
+        TokenAccount::LEN => (TokenAccount::unpack(data).ok().as_ref().map(TokenAccountView::from), None),
+        Mint::LEN => (None, Mint::unpack(data).ok().as_ref().map(MintView::from)),
+        _ => (None, None),
+    }
 }
 
 fn validate_decimals(decimals: u8) -> Result<(), ErrorResponse> {
     if decimals > 9 {
-        return Err(ErrorResponse::new("Decimals must be between 0 and 9"));
+        return Err(ErrorResponse::with_code(ApiErrorCode::InvalidDecimals, "Decimals must be between 0 and 9"));
     }
     Ok(())
 }
 
-fn validate_amount(amount: u64) -> Result<(), ErrorResponse> {
-    if amount == 0 {
-        return Err(ErrorResponse::new("Amount must be greater than 0"));
+// Decimals for a checked instruction: the caller's, which must agree with
+// any configured value, or else the configured or preloaded ones
+fn mint_decimals(mint: &Pubkey, requested: Option<u8>) -> Result<u8, ErrorResponse> {
+    if let Some(decimals) = requested {
+        validate_decimals(decimals)?;
+        mint_config::check_decimals(mint, decimals)?;
+        return Ok(decimals);
     }
-    Ok(())
+    mint_config::forced_decimals(mint)
+        .or_else(|| preload::cached_mint_decimals(mint))
+        .ok_or_else(|| ErrorResponse::with_code(ApiErrorCode::MissingFields, format!("Decimals are required for mint {}", mint)))
 }
 
 pub fn create_token(request: CreateTokenRequest) -> Result<SuccessResponse<CreateTokenResponse>, ErrorResponse> {
     // Validate inputs
-    let mint_authority = validate_pubkey(&request.mint_authority, "mint_authority")?;
-    let mint = validate_pubkey(&request.mint, "mint")?;
-    validate_decimals(request.decimals)?;
+    let mut fields = FieldErrors::default();
+    let mint_authority = fields.check("mintAuthority", validate_pubkey(&request.mint_authority, "mint_authority"));
+    let mint = fields.check("mint", validate_pubkey(&request.mint, "mint"));
+    fields.check("decimals", validate_decimals(request.decimals));
+    let freeze_authority = request
+        .freeze_authority
+        .as_ref()
+        .map(|freeze_authority| fields.check("freezeAuthority", validate_pubkey(freeze_authority, "freezeAuthority")));
+    if let Some(initial_supply) = request.initial_supply {
+        fields.check("initialSupply", validate_amount(initial_supply));
+    }
+    let initial_holder = match &request.initial_holder {
+        Some(holder) => fields.check("initialHolder", validate_pubkey(holder, "initialHolder")),
+        None => mint_authority,
+    };
+    let payer = match &request.payer {
+        Some(payer) => Some(fields.check("payer", validate_pubkey(payer, "payer"))),
+        None if request.extensions.is_some() || request.initial_supply.is_some() => {
+            return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "payer is required with extensions or initialSupply"));
+        }
+        None => None,
+    };
+    fields.finish()?;
+    mint_config::check_decimals(&mint, request.decimals)?;
+    if payer == Some(mint) {
+        return Err(ErrorResponse::new("Payer and new account cannot be the same"));
+    }
 
-    // Get the token program ID
-    let token_program_id = spl_token::id();
+    // Extensions need a Token-2022 mint, laid out for them when the account
+    // is created
+    let (token_program_id, extended) = match &request.extensions {
+        Some(extensions) => (spl_token_2022::id(), extended_mint(&mint, &mint_authority, extensions)?),
+        None => (spl_token::id(), ExtendedMint {
+            space: Mint::LEN,
+            lamports: preload::rent().minimum_balance(Mint::LEN),
+            before_mint: Vec::new(),
+            after_mint: Vec::new(),
+        }),
+    };
 
-    // Required accounts for token initialization
-    let accounts = vec![
-        AccountMeta::new(mint, true),                    // mint account (writable, signer)
-        AccountMeta::new_readonly(mint_authority, true), // mint authority (readonly, signer)
-        AccountMeta::new_readonly(rent::id(), false),    // rent sysvar (readonly)
-        AccountMeta::new_readonly(system_program::id(), false), // system program (readonly)
-    ];
+    let mut instructions = Vec::new();
+    if let Some(payer) = &payer {
+        instructions.push(system_instruction::create_account(payer, &mint, extended.lamports, extended.space as u64, &token_program_id));
+    }
+    instructions.extend(extended.before_mint);
 
     // Create the initialize mint instruction
-    let instruction = token_instruction::initialize_mint(
-        &token_program_id,
-        &mint,
-        &mint_authority,
-        None, // freeze_authority
-        request.decimals,
-    ).map_err(|e| ErrorResponse::new(format!("Failed to create token instruction: {}", e)))?;
+    let initialize_mint = match request.extensions {
+        Some(_) => spl_token_2022::instruction::initialize_mint2(
+            &token_program_id,
+            &mint,
+            &mint_authority,
+            freeze_authority.as_ref(),
+            request.decimals,
+        ),
+        None => token_instruction::initialize_mint(
+            &token_program_id,
+            &mint,
+            &mint_authority,
+            freeze_authority.as_ref(),
+            request.decimals,
+        ),
+    }.map_err(|e| ErrorResponse::with_code(ApiErrorCode::InstructionFailed, format!("Failed to create token instruction: {}", e)))?;
 
-    // Format the response
-    let response = CreateTokenResponse {
-        program_id: token_program_id.to_string(),
-        accounts: accounts.iter().map(|account| AccountInfo {
-            pubkey: bs58::encode(account.pubkey.to_bytes()).into_string(),
-            is_signer: account.is_signer,
-            is_writable: account.is_writable,
-        }).collect(),
-        instruction_data: base64::encode(&instruction.data),
-    };
+    let instruction = InstructionResponse::from(&initialize_mint);
+    instructions.push(initialize_mint);
+    instructions.extend(extended.after_mint);
+    let mut holder_token_account = None;
+    if let (Some(initial_supply), Some(payer)) = (request.initial_supply, &payer) {
+        let token_account = associated_token_address(&initial_holder, &mint, &token_program_id);
+        instructions.push(create_associated_token_account_idempotent(payer, &initial_holder, &mint, &token_program_id));
+        instructions.push(spl_token_2022::instruction::mint_to_checked(
+            &token_program_id,
+            &mint,
+            &token_account,
+            &mint_authority,
+            &[],
+            initial_supply,
+            request.decimals,
+        ).map_err(|e| ErrorResponse::with_code(ApiErrorCode::InstructionFailed, format!("Failed to create token instruction: {}", e)))?);
+        holder_token_account = Some(token_account.to_string());
+    }
 
-    Ok(SuccessResponse::new(response))
+    Ok(SuccessResponse::new(CreateTokenResponse {
+        instruction,
+        instructions: instructions.iter().map(InstructionResponse::from).collect(),
+        lamports: payer.map(|_| extended.lamports),
+        holder_token_account,
+    }))
 }
 
 pub fn mint_token(request: MintTokenRequest) -> Result<SuccessResponse<MintTokenResponse>, ErrorResponse> {
     // Validate inputs
-    let mint_authority = validate_pubkey(&request.mint_authority, "mintAuthority")?;
-    let mint = validate_pubkey(&request.mint, "mint")?;
-    validate_decimals(request.decimals)?;
+    let mut fields = FieldErrors::default();
+    let mint_authority = fields.check("mintAuthority", validate_pubkey(&request.mint_authority, "mintAuthority"));
+    let mint = fields.check("mint", validate_pubkey(&request.mint, "mint"));
+    let (destination, amount, deprecation) = match (&request.destination, request.amount) {
+        (Some(destination), Some(amount)) => {
+            let destination = fields.check("destination", validate_pubkey(destination, "destination address"));
+            fields.check("amount", validate_amount(amount));
+            (destination, amount, None)
+        }
+        // Requests from before destination and amount existed keep working,
+        // minting the old fixed amount to the mint authority's own account
+        (None, None) => (
+            mint_authority,
+            LEGACY_MINT_AMOUNT,
+            Some(format!(
+                "Requests without destination and amount are deprecated; this one mints {} base units to the mint authority",
+                LEGACY_MINT_AMOUNT
+            )),
+        ),
+        _ => return Err(ErrorResponse::new("destination and amount must be given together")),
+    };
+    fields.finish()?;
+    let decimals = mint_decimals(&mint, request.decimals)?;
 
     // Get the token program ID
     let token_program_id = spl_token::id();
 
-    // Required accounts for minting tokens
-    let accounts = vec![
-        AccountMeta::new(mint, true),                    // mint account (writable)
-        AccountMeta::new_readonly(mint_authority, true), // mint authority (signer)
-        AccountMeta::new_readonly(system_program::id(), false), // system program
-    ];
-
     // Create the mint instruction
-    let amount = 1_000_000_000; // Amount to mint (adjust based on decimals)
-    let instruction = token_instruction::mint_to(
+    let instruction = token_instruction::mint_to_checked(
         &token_program_id,
         &mint,
-        &mint,  // token account (same as mint for simplicity)
+        &spl_associated_token_account::get_associated_token_address(&destination, &mint),
         &mint_authority,
         &[],    // signer seeds
         amount,
-    ).map_err(|e| ErrorResponse::new(format!("Failed to create mint instruction: {}", e)))?;
+        decimals,
+    ).map_err(|e| ErrorResponse::with_code(ApiErrorCode::InstructionFailed, format!("Failed to create mint instruction: {}", e)))?;
 
     // Format the response
     let response = MintTokenResponse {
-        program_id: token_program_id.to_string(),
-        accounts: accounts.iter().map(|account| AccountInfo {
-            pubkey: bs58::encode(account.pubkey.to_bytes()).into_string(),
-            is_signer: account.is_signer,
-            is_writable: account.is_writable,
-        }).collect(),
-        instruction_data: base64::encode(&instruction.data),
+        instruction: InstructionResponse::from(&instruction),
+        deprecation,
     };
 
     Ok(SuccessResponse::new(response))
 }
 
-pub fn send_token(request: SendTokenRequest) -> Result<SuccessResponse<SendTokenResponse>, ErrorResponse> {
-    // Validate inputs
+// Destination, mint, owner and compute budget instructions, once every
+// field that can be checked without the mint's state is valid
+fn send_token_fields(request: &SendTokenRequest) -> Result<(Pubkey, Pubkey, Pubkey, Vec<Instruction>), ErrorResponse> {
     if request.destination.is_empty() || request.mint.is_empty() || request.owner.is_empty() {
-        return Err(ErrorResponse::new("Missing required fields"));
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
     }
 
-    let destination = validate_pubkey(&request.destination, "destination address")?;
-    let mint = validate_pubkey(&request.mint, "mint address")?;
-    let owner = validate_pubkey(&request.owner, "owner address")?;
-    validate_amount(request.amount)?;
+    let mut fields = FieldErrors::default();
+    let destination = fields.check("destination", validate_pubkey(&request.destination, "destination address"));
+    let mint = fields.check("mint", validate_pubkey(&request.mint, "mint address"));
+    let owner = fields.check("owner", validate_pubkey(&request.owner, "owner address"));
+    fields.check("amount", validate_amount(request.amount));
+    let compute_budget = fields.check("priorityFee", priority_fee_instructions(request.priority_fee.as_ref()));
+    fields.finish()?;
+    Ok((destination, mint, owner, compute_budget))
+}
+
+pub fn send_token(request: SendTokenRequest) -> Result<SuccessResponse<SendTokenResponse>, ErrorResponse> {
+    // Validate inputs
+    let (destination, mint, owner, compute_budget) = send_token_fields(&request)?;
+    mint_config::check_transfer(&mint, request.amount)?;
+    let decimals = mint_decimals(&mint, request.decimals)?;
+    let memo = request.memo
+        .as_deref()
+        .map(|memo| memo_instruction(memo, &[owner]))
+        .transpose()?;
 
     // Prevent sending to the same address
     if owner == destination {
-        return Err(ErrorResponse::new("Owner and destination addresses cannot be the same"));
+        return Err(ErrorResponse::with_code(ApiErrorCode::SameAddress, "Owner and destination addresses cannot be the same"));
     }
 
     // Get token program ID
     let token_program_id = spl_token::id();
 
     // Derive Associated Token Accounts (ATAs) for both owner and destination
-    let owner_ata = spl_associated_token_account::get_associated_token_address(
-        &owner,
-        &mint
-    );
-    
-    let destination_ata = spl_associated_token_account::get_associated_token_address(
-        &destination,
-        &mint
-    );
-
-    // Create instructions
-    let mut accounts = vec![
-        AccountMeta::new(owner_ata, false),        // Source ATA (writable)
-        AccountMeta::new(destination_ata, false),  // Destination ATA (writable)
-        AccountMeta::new_readonly(owner, true),    // Owner (signer)
-        AccountMeta::new_readonly(mint, false),    // Mint account
-        AccountMeta::new_readonly(token_program_id, false), // Token program
-    ];
+    let owner_ata = associated_token_address(&owner, &mint, &token_program_id);
+    let destination_ata = associated_token_address(&destination, &mint, &token_program_id);
 
     // Create the transfer instruction
     let instruction = token_instruction::transfer_checked(
@@ -216,19 +442,232 @@ pub fn send_token(request: SendTokenRequest) -> Result<SuccessResponse<SendToken
         &owner,
         &[],
         request.amount,
-        9, // Decimals - typically 9 for most tokens
-    ).map_err(|e| ErrorResponse::new(format!("Failed to create transfer instruction: {}", e)))?;
+        decimals,
+    ).map_err(|e| ErrorResponse::with_code(ApiErrorCode::InstructionFailed, format!("Failed to create transfer instruction: {}", e)))?;
 
     // Format the response
     let response = SendTokenResponse {
-        program_id: token_program_id.to_string(),
-        accounts: accounts.iter().map(|account| AccountInfo {
-            pubkey: bs58::encode(account.pubkey.to_bytes()).into_string(),
-            is_signer: account.is_signer,
-            is_writable: account.is_writable,
-        }).collect(),
-        instruction_data: base64::encode(&instruction.data),
+        instruction: InstructionResponse::from(&instruction),
+        compute_budget_instructions: format_compute_budget_instructions(&compute_budget),
+        memo_instruction: memo.as_ref().map(InstructionResponse::from),
+        resolved_name: None,
     };
 
     Ok(SuccessResponse::new(response))
-} 
\ No newline at end of file
+}
+
+// Reads the mint account when its decimals are neither given nor known,
+// caching them for later requests. A freshly read mint also bounds the
+// amount, since no account can hold more than the total supply. A .sol
+// destination is resolved first.
+pub async fn send_token_with_mint_lookup(mut request: SendTokenRequest) -> Result<SuccessResponse<SendTokenResponse>, ErrorResponse> {
+    let resolved_name = resolve_recipient(&mut request.destination).await?;
+    // Bad fields are reported before the mint is looked up
+    let (_, mint, _, _) = send_token_fields(&request)?;
+    if request.decimals.is_none()
+        && mint_config::forced_decimals(&mint).is_none()
+        && !rpc::mint_decimals().contains(&mint)
+    {
+        let state = fetch_mint(mint).await?;
+        if request.amount > state.supply {
+            return Err(ErrorResponse::with_code(ApiErrorCode::AmountTooLarge, format!(
+                "Amount {} exceeds the total supply of mint {} ({})",
+                request.amount, mint, state.supply
+            )));
+        }
+        request.decimals = Some(state.decimals);
+    }
+
+    let mut response = send_token(request)?;
+    response.data.resolved_name = resolved_name;
+    Ok(response)
+}
+
+// Reads an SPL token mint, caching its decimals for later requests
+pub async fn fetch_mint(mint: Pubkey) -> Result<Mint, ErrorResponse> {
+    let fetched = rpc::read("getAccountInfo", move |client| async move {
+        client.get_account(&mint).await
+    })
+        .await
+        .map_err(rpc_error)?;
+    let state = (fetched.owner == spl_token::id())
+        .then(|| Mint::unpack(&fetched.data).ok())
+        .flatten()
+        .ok_or_else(|| ErrorResponse::with_code(ApiErrorCode::InvalidAccount, format!("Account {} is not an SPL token mint", mint)))?;
+    preload::cache_mint_decimals(mint, state.decimals);
+    Ok(state)
+}
+
+// The owner's associated token account for a mint. The token program is
+// part of the derivation, so Token-2022 mints get a different address.
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program_id: &Pubkey) -> Pubkey {
+    spl_associated_token_account::get_associated_token_address_with_program_id(owner, mint, token_program_id)
+}
+
+// A token account given directly, or the owner's associated token account for a mint
+fn token_account_target(account: &Option<String>, mint: &Option<String>, owner: &Pubkey) -> Result<Pubkey, ErrorResponse> {
+    match (account, mint) {
+        (Some(account), None) => validate_pubkey(account, "token account"),
+        (None, Some(mint)) => Ok(spl_associated_token_account::get_associated_token_address(
+            owner,
+            &validate_pubkey(mint, "mint address")?,
+        )),
+        _ => Err(ErrorResponse::new("Exactly one of account or mint is required")),
+    }
+}
+
+// The token program only closes accounts holding no tokens, so callers
+// should empty the account first; /token/account/close checks this on-chain
+pub fn close_token_account(request: CloseTokenAccountRequest) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    let mut fields = FieldErrors::default();
+    let owner = fields.check("owner", validate_pubkey(&request.owner, "owner address"));
+    let destination = fields.check("destination", validate_pubkey(&request.destination, "destination address"));
+    let account = fields.check("account", token_account_target(&request.account, &request.mint, &owner));
+    fields.finish()?;
+
+    if account == destination {
+        return Err(ErrorResponse::with_code(ApiErrorCode::SameAddress, "Token account and destination addresses cannot be the same"));
+    }
+
+    // Get token program ID
+    let token_program_id = spl_token::id();
+
+    let instruction = token_instruction::close_account(
+        &token_program_id,
+        &account,
+        &destination,
+        &owner,
+        &[],
+    ).map_err(|e| ErrorResponse::with_code(ApiErrorCode::InstructionFailed, format!("Failed to create token instruction: {}", e)))?;
+
+    Ok(SuccessResponse::new(InstructionResponse::from(&instruction)))
+}
+
+// Builds the close instruction after checking on-chain that the account is
+// empty and that the owner may close it, which the token program would
+// otherwise only report by failing the transaction
+pub async fn close_token_account_checked(request: CloseTokenAccountRequest) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    let owner = validate_pubkey(&request.owner, "owner address")?;
+    let account = token_account_target(&request.account, &request.mint, &owner)?;
+    let response = close_token_account(request)?;
+
+    let fetched = rpc::read("getAccountInfo", move |client| async move {
+        client.get_account(&account).await
+    })
+        .await
+        .map_err(rpc_error)?;
+    let token_account = (fetched.owner == spl_token::id())
+        .then(|| TokenAccount::unpack(&fetched.data).ok())
+        .flatten()
+        .ok_or_else(|| ErrorResponse::with_code(ApiErrorCode::InvalidAccount, format!("Account {} is not an SPL token account", account)))?;
+
+    if token_account.close_authority.unwrap_or(token_account.owner) != owner {
+        return Err(ErrorResponse::with_code(ApiErrorCode::NotAuthorized, format!("{} is not allowed to close token account {}", owner, account)));
+    }
+    // Wrapped SOL accounts hand their balance to the destination on close
+    if !token_account.is_native() && token_account.amount > 0 {
+        return Err(ErrorResponse::with_code(ApiErrorCode::AccountNotEmpty, format!(
+            "Token account {} still holds {} tokens; its balance must be zero before it can be closed",
+            account, token_account.amount
+        )));
+    }
+
+    Ok(response)
+}
+
+pub fn approve_token(request: ApproveTokenRequest) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    let mut fields = FieldErrors::default();
+    let mint = fields.check("mint", validate_pubkey(&request.mint, "mint address"));
+    let owner = fields.check("owner", validate_pubkey(&request.owner, "owner address"));
+    let delegate = fields.check("delegate", validate_pubkey(&request.delegate, "delegate address"));
+    fields.check("amount", validate_amount(request.amount));
+    let account = match &request.account {
+        Some(account) => fields.check("account", validate_pubkey(account, "token account")),
+        None => spl_associated_token_account::get_associated_token_address(&owner, &mint),
+    };
+    fields.finish()?;
+    let decimals = mint_decimals(&mint, request.decimals)?;
+
+    if owner == delegate {
+        return Err(ErrorResponse::with_code(ApiErrorCode::SameAddress, "Owner and delegate addresses cannot be the same"));
+    }
+
+    // Get token program ID
+    let token_program_id = spl_token::id();
+
+    // Replaces any existing delegate and allowance on the account
+    let instruction = token_instruction::approve_checked(
+        &token_program_id,
+        &account,
+        &mint,
+        &delegate,
+        &owner,
+        &[],
+        request.amount,
+        decimals,
+    ).map_err(|e| ErrorResponse::with_code(ApiErrorCode::InstructionFailed, format!("Failed to create token instruction: {}", e)))?;
+
+    Ok(SuccessResponse::new(InstructionResponse::from(&instruction)))
+}
+
+pub fn revoke_token(request: RevokeTokenRequest) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    let mut fields = FieldErrors::default();
+    let owner = fields.check("owner", validate_pubkey(&request.owner, "owner address"));
+    let account = fields.check("account", token_account_target(&request.account, &request.mint, &owner));
+    fields.finish()?;
+
+    // Get token program ID
+    let token_program_id = spl_token::id();
+
+    let instruction = token_instruction::revoke(
+        &token_program_id,
+        &account,
+        &owner,
+        &[],
+    ).map_err(|e| ErrorResponse::with_code(ApiErrorCode::InstructionFailed, format!("Failed to create token instruction: {}", e)))?;
+
+    Ok(SuccessResponse::new(InstructionResponse::from(&instruction)))
+}
+
+// Works for SPL Token and Token-2022 mints. The decimals are kept for later
+// /send/token and /token/mint requests that leave them out.
+pub async fn get_mint_info(mint: String) -> Result<SuccessResponse<MintInfoResponse>, ErrorResponse> {
+    // Validate inputs
+    let mint = validate_pubkey(&mint, "mint")?;
+
+    let fetched = rpc::read("getAccountInfo", move |client| async move {
+        client.get_account_with_commitment(&mint, client.commitment()).await.map(|response| response.value)
+    })
+        .await
+        .map_err(rpc_error)?;
+    let not_a_mint = || ErrorResponse::with_code(ApiErrorCode::InvalidAccount, format!("Account {} is not an SPL token mint", mint));
+    let fetched = fetched.ok_or_else(not_a_mint)?;
+
+    let (state, extensions) = if fetched.owner == spl_token::id() {
+        let state = Mint::unpack(&fetched.data).map_err(|_| not_a_mint())?;
+        (MintView::from(&state), Vec::new())
+    } else if fetched.owner == spl_token_2022::id() {
+        let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&fetched.data).map_err(|_| not_a_mint())?;
+        let extensions = state
+            .get_extension_types()
+            .unwrap_or_default()
+            .iter()
+            .map(|extension| format!("{:?}", extension))
+            .collect();
+        (MintView::from(&state.base), extensions)
+    } else {
+        return Err(not_a_mint());
+    };
+    preload::cache_mint_decimals(mint, state.decimals);
+
+    Ok(SuccessResponse::new(MintInfoResponse {
+        mint: mint.to_string(),
+        token_program: fetched.owner.to_string(),
+        symbol: mint_config::symbol(&mint).map(str::to_string),
+        state,
+        extensions,
+    }))
+}
@@ -0,0 +1,433 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, system_instruction};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::{
+    extension::{
+        interest_bearing_mint::instruction as interest_bearing_instruction,
+        metadata_pointer::instruction as metadata_pointer_instruction,
+        transfer_fee::{instruction as transfer_fee_instruction, TransferFee, TransferFeeConfig, MAX_FEE_BASIS_POINTS},
+        BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+    },
+    instruction as token_2022_instruction,
+    state::Mint,
+};
+use spl_token_metadata_interface::{instruction as token_metadata_instruction, state::TokenMetadata};
+use super::instruction::{validate_pubkey, InstructionResponse};
+use super::preload;
+use super::response_types::{SuccessResponse, ErrorResponse};
+use super::rpc::{self, rpc_error};
+
+#[derive(Deserialize, ToSchema)]
+pub struct InitializeTransferFeeMintRequest {
+    pub payer: String,
+    pub mint: String,
+    #[serde(rename = "mintAuthority")]
+    pub mint_authority: String,
+    #[serde(rename = "freezeAuthority")]
+    pub freeze_authority: Option<String>,
+    pub decimals: u8,
+    // Fee charged on every transfer, in hundredths of a percent
+    #[serde(rename = "transferFeeBasisPoints")]
+    pub transfer_fee_basis_points: u16,
+    // Cap on the fee for a single transfer, in base units
+    #[serde(rename = "maximumFee")]
+    pub maximum_fee: u64,
+    // May change the fee later; the fee is fixed forever when omitted
+    #[serde(rename = "transferFeeConfigAuthority")]
+    pub transfer_fee_config_authority: Option<String>,
+    // May withdraw withheld fees; they can never be withdrawn when omitted
+    #[serde(rename = "withdrawWithheldAuthority")]
+    pub withdraw_withheld_authority: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct HarvestTransferFeesRequest {
+    pub mint: String,
+    // Token accounts whose withheld fees are moved to the mint
+    pub sources: Vec<String>,
+    // Give both to also withdraw everything withheld in the mint
+    pub destination: Option<String>,
+    #[serde(rename = "withdrawWithheldAuthority")]
+    pub withdraw_withheld_authority: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TransferWithFeeRequest {
+    pub destination: String,
+    pub mint: String,
+    pub owner: String,
+    pub amount: u64,
+    // Mint details; /token-2022/transfer-fee/transfer reads any that are
+    // omitted from the mint account
+    pub decimals: Option<u8>,
+    // The fee the mint charges on this transfer. Calculated from
+    // transferFeeBasisPoints and maximumFee when omitted.
+    pub fee: Option<u64>,
+    #[serde(rename = "transferFeeBasisPoints")]
+    pub transfer_fee_basis_points: Option<u16>,
+    #[serde(rename = "maximumFee")]
+    pub maximum_fee: Option<u64>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct MetadataPointerExtension {
+    // May repoint the metadata later; it is fixed forever when omitted
+    pub authority: Option<String>,
+    // Where the metadata lives; the mint itself when omitted
+    #[serde(rename = "metadataAddress")]
+    pub metadata_address: Option<String>,
+}
+
+// Metadata kept in the mint account itself
+#[derive(Deserialize, ToSchema)]
+pub struct TokenMetadataExtension {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    // The mint authority when omitted
+    #[serde(rename = "updateAuthority")]
+    pub update_authority: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct InterestBearingExtension {
+    // Annual interest, in hundredths of a percent; negative rates are allowed
+    pub rate: i16,
+    // May change the rate later; it is fixed forever when omitted
+    #[serde(rename = "rateAuthority")]
+    pub rate_authority: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct MintExtensions {
+    #[serde(rename = "metadataPointer")]
+    pub metadata_pointer: Option<MetadataPointerExtension>,
+    // Also sets a metadata pointer to the mint when metadataPointer is omitted
+    #[serde(rename = "tokenMetadata")]
+    pub token_metadata: Option<TokenMetadataExtension>,
+    #[serde(rename = "interestBearing")]
+    pub interest_bearing: Option<InterestBearingExtension>,
+}
+
+// A Token-2022 mint account laid out for its extensions, and the
+// instructions that set them up around the mint's initialization
+pub struct ExtendedMint {
+    // Allocated when the account is created. Token metadata is variable
+    // length, so the program grows the account when it is written.
+    pub space: usize,
+    // Rent exemption for the account once the metadata is written too
+    pub lamports: u64,
+    // Extensions are initialized before the mint is
+    pub before_mint: Vec<Instruction>,
+    // Token metadata needs an initialized mint and its authority's signature
+    pub after_mint: Vec<Instruction>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TransferFeeMintResponse {
+    pub mint: String,
+    pub lamports: u64,
+    // In the order they must appear in the transaction
+    pub instructions: Vec<InstructionResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct HarvestTransferFeesResponse {
+    pub instructions: Vec<InstructionResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TransferWithFeeResponse {
+    #[serde(flatten)]
+    pub instruction: InstructionResponse,
+    // Withheld from the amount, so the recipient receives amount - fee
+    pub fee: u64,
+}
+
+fn validate_optional_pubkey(key: &Option<String>, field_name: &str) -> Result<Option<Pubkey>, ErrorResponse> {
+    key.as_deref().map(|key| validate_pubkey(key, field_name)).transpose()
+}
+
+fn validate_decimals(decimals: u8) -> Result<(), ErrorResponse> {
+    if decimals > 9 {
+        return Err(ErrorResponse::new("Decimals must be between 0 and 9"));
+    }
+    Ok(())
+}
+
+fn validate_basis_points(basis_points: u16) -> Result<(), ErrorResponse> {
+    if basis_points > MAX_FEE_BASIS_POINTS {
+        return Err(ErrorResponse::new(format!(
+            "transferFeeBasisPoints must be at most {}",
+            MAX_FEE_BASIS_POINTS
+        )));
+    }
+    Ok(())
+}
+
+fn token_error(err: impl std::fmt::Display) -> ErrorResponse {
+    ErrorResponse::new(format!("Failed to create token instruction: {}", err))
+}
+
+fn fee_for(basis_points: u16, maximum_fee: u64, amount: u64) -> Result<u64, ErrorResponse> {
+    TransferFee {
+        epoch: 0.into(),
+        maximum_fee: maximum_fee.into(),
+        transfer_fee_basis_points: basis_points.into(),
+    }
+        .calculate_fee(amount)
+        .ok_or_else(|| ErrorResponse::new("Transfer fee calculation overflowed"))
+}
+
+pub fn extended_mint(mint: &Pubkey, mint_authority: &Pubkey, extensions: &MintExtensions) -> Result<ExtendedMint, ErrorResponse> {
+    let token_program_id = spl_token_2022::id();
+
+    let mut pointer = match &extensions.metadata_pointer {
+        Some(pointer) => Some((
+            validate_optional_pubkey(&pointer.authority, "metadataPointer.authority")?,
+            validate_optional_pubkey(&pointer.metadata_address, "metadataPointer.metadataAddress")?.unwrap_or(*mint),
+        )),
+        None => None,
+    };
+    let metadata = match &extensions.token_metadata {
+        Some(metadata) => {
+            if metadata.name.is_empty() || metadata.symbol.is_empty() {
+                return Err(ErrorResponse::new("tokenMetadata needs a name and symbol"));
+            }
+            let update_authority = validate_optional_pubkey(&metadata.update_authority, "tokenMetadata.updateAuthority")?
+                .unwrap_or(*mint_authority);
+            match pointer {
+                Some((_, address)) if address != *mint => {
+                    return Err(ErrorResponse::new("tokenMetadata is stored in the mint, so metadataPointer must point at the mint"));
+                }
+                Some(_) => {}
+                None => pointer = Some((Some(update_authority), *mint)),
+            }
+            Some((update_authority, metadata))
+        }
+        None => None,
+    };
+    let rate_authority = match &extensions.interest_bearing {
+        Some(interest) => Some((validate_optional_pubkey(&interest.rate_authority, "interestBearing.rateAuthority")?, interest.rate)),
+        None => None,
+    };
+
+    let mut extension_types = Vec::new();
+    let mut before_mint = Vec::new();
+    if let Some((authority, address)) = pointer {
+        extension_types.push(ExtensionType::MetadataPointer);
+        before_mint.push(
+            metadata_pointer_instruction::initialize(&token_program_id, mint, authority, Some(address)).map_err(token_error)?,
+        );
+    }
+    if let Some((authority, rate)) = rate_authority {
+        extension_types.push(ExtensionType::InterestBearingConfig);
+        before_mint.push(interest_bearing_instruction::initialize(&token_program_id, mint, authority, rate).map_err(token_error)?);
+    }
+    let space = ExtensionType::try_calculate_account_len::<Mint>(&extension_types).map_err(token_error)?;
+
+    let mut metadata_len = 0;
+    let mut after_mint = Vec::new();
+    if let Some((update_authority, metadata)) = metadata {
+        metadata_len = TokenMetadata {
+            update_authority: Some(update_authority).try_into().map_err(token_error)?,
+            mint: *mint,
+            name: metadata.name.clone(),
+            symbol: metadata.symbol.clone(),
+            uri: metadata.uri.clone(),
+            additional_metadata: Vec::new(),
+        }
+            .tlv_size_of()
+            .map_err(token_error)?;
+        after_mint.push(token_metadata_instruction::initialize(
+            &token_program_id,
+            mint,
+            &update_authority,
+            mint,
+            mint_authority,
+            metadata.name.clone(),
+            metadata.symbol.clone(),
+            metadata.uri.clone(),
+        ));
+    }
+
+    Ok(ExtendedMint {
+        space,
+        lamports: preload::rent().minimum_balance(space + metadata_len),
+        before_mint,
+        after_mint,
+    })
+}
+
+pub fn initialize_transfer_fee_mint(request: InitializeTransferFeeMintRequest) -> Result<SuccessResponse<TransferFeeMintResponse>, ErrorResponse> {
+    // Validate inputs
+    let payer = validate_pubkey(&request.payer, "payer")?;
+    let mint = validate_pubkey(&request.mint, "mint address")?;
+    let mint_authority = validate_pubkey(&request.mint_authority, "mintAuthority")?;
+    let freeze_authority = validate_optional_pubkey(&request.freeze_authority, "freezeAuthority")?;
+    let config_authority = validate_optional_pubkey(&request.transfer_fee_config_authority, "transferFeeConfigAuthority")?;
+    let withdraw_authority = validate_optional_pubkey(&request.withdraw_withheld_authority, "withdrawWithheldAuthority")?;
+    validate_decimals(request.decimals)?;
+    validate_basis_points(request.transfer_fee_basis_points)?;
+
+    if payer == mint {
+        return Err(ErrorResponse::new("Payer and new account cannot be the same"));
+    }
+
+    let token_program_id = spl_token_2022::id();
+    let space = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferFeeConfig])
+        .map_err(token_error)?;
+    let lamports = preload::rent().minimum_balance(space);
+
+    // Extensions must be initialized before the mint itself
+    let instructions = [
+        system_instruction::create_account(&payer, &mint, lamports, space as u64, &token_program_id),
+        transfer_fee_instruction::initialize_transfer_fee_config(
+            &token_program_id,
+            &mint,
+            config_authority.as_ref(),
+            withdraw_authority.as_ref(),
+            request.transfer_fee_basis_points,
+            request.maximum_fee,
+        ).map_err(token_error)?,
+        token_2022_instruction::initialize_mint(
+            &token_program_id,
+            &mint,
+            &mint_authority,
+            freeze_authority.as_ref(),
+            request.decimals,
+        ).map_err(token_error)?,
+    ];
+
+    let response = TransferFeeMintResponse {
+        mint: mint.to_string(),
+        lamports,
+        instructions: instructions.iter().map(InstructionResponse::from).collect(),
+    };
+
+    Ok(SuccessResponse::new(response))
+}
+
+pub fn harvest_transfer_fees(request: HarvestTransferFeesRequest) -> Result<SuccessResponse<HarvestTransferFeesResponse>, ErrorResponse> {
+    // Validate inputs
+    let mint = validate_pubkey(&request.mint, "mint address")?;
+    let sources = request.sources
+        .iter()
+        .map(|source| validate_pubkey(source, "source token account"))
+        .collect::<Result<Vec<_>, _>>()?;
+    let withdraw = match (&request.destination, &request.withdraw_withheld_authority) {
+        (Some(destination), Some(authority)) => Some((
+            validate_pubkey(destination, "destination address")?,
+            validate_pubkey(authority, "withdrawWithheldAuthority")?,
+        )),
+        (None, None) => None,
+        _ => return Err(ErrorResponse::new("destination and withdrawWithheldAuthority must be given together")),
+    };
+    if sources.is_empty() && withdraw.is_none() {
+        return Err(ErrorResponse::new("At least one source token account is required"));
+    }
+
+    let token_program_id = spl_token_2022::id();
+
+    // Harvesting is permissionless; withdrawing from the mint is not
+    let mut instructions = Vec::new();
+    if !sources.is_empty() {
+        let sources: Vec<&Pubkey> = sources.iter().collect();
+        instructions.push(
+            transfer_fee_instruction::harvest_withheld_tokens_to_mint(&token_program_id, &mint, &sources)
+                .map_err(token_error)?,
+        );
+    }
+    if let Some((destination, authority)) = withdraw {
+        instructions.push(
+            transfer_fee_instruction::withdraw_withheld_tokens_from_mint(&token_program_id, &mint, &destination, &authority, &[])
+                .map_err(token_error)?,
+        );
+    }
+
+    Ok(SuccessResponse::new(HarvestTransferFeesResponse {
+        instructions: instructions.iter().map(InstructionResponse::from).collect(),
+    }))
+}
+
+// Transfers between the owner's and destination's associated token accounts
+pub fn transfer_with_fee(request: TransferWithFeeRequest) -> Result<SuccessResponse<TransferWithFeeResponse>, ErrorResponse> {
+    // Validate inputs
+    let destination = validate_pubkey(&request.destination, "destination address")?;
+    let mint = validate_pubkey(&request.mint, "mint address")?;
+    let owner = validate_pubkey(&request.owner, "owner address")?;
+    if request.amount == 0 {
+        return Err(ErrorResponse::new("Amount must be greater than 0"));
+    }
+    let decimals = request.decimals
+        .ok_or_else(|| ErrorResponse::new(format!("Decimals are required for mint {}", mint)))?;
+    validate_decimals(decimals)?;
+    let fee = match (request.fee, request.transfer_fee_basis_points, request.maximum_fee) {
+        (Some(fee), _, _) => fee,
+        (None, Some(basis_points), Some(maximum_fee)) => {
+            validate_basis_points(basis_points)?;
+            fee_for(basis_points, maximum_fee, request.amount)?
+        }
+        _ => return Err(ErrorResponse::new("Give fee, or transferFeeBasisPoints and maximumFee to calculate it")),
+    };
+    if fee > request.amount {
+        return Err(ErrorResponse::new("Fee cannot exceed the transfer amount"));
+    }
+
+    if owner == destination {
+        return Err(ErrorResponse::new("Owner and destination addresses cannot be the same"));
+    }
+
+    let token_program_id = spl_token_2022::id();
+    let instruction = transfer_fee_instruction::transfer_checked_with_fee(
+        &token_program_id,
+        &get_associated_token_address_with_program_id(&owner, &mint, &token_program_id),
+        &mint,
+        &get_associated_token_address_with_program_id(&destination, &mint, &token_program_id),
+        &owner,
+        &[],
+        request.amount,
+        decimals,
+        fee,
+    ).map_err(|e| ErrorResponse::new(format!("Failed to create transfer instruction: {}", e)))?;
+
+    Ok(SuccessResponse::new(TransferWithFeeResponse {
+        instruction: InstructionResponse::from(&instruction),
+        fee,
+    }))
+}
+
+// Fills in the decimals and fee from the mint account when the request
+// leaves them out, using the fee in effect for the current epoch
+pub async fn transfer_with_fee_from_mint(mut request: TransferWithFeeRequest) -> Result<SuccessResponse<TransferWithFeeResponse>, ErrorResponse> {
+    let needs_fee = request.fee.is_none() && (request.transfer_fee_basis_points.is_none() || request.maximum_fee.is_none());
+    if request.decimals.is_some() && !needs_fee {
+        return transfer_with_fee(request);
+    }
+
+    let mint = validate_pubkey(&request.mint, "mint address")?;
+    let (account, epoch_info) = tokio::try_join!(
+        rpc::read("getAccountInfo", move |client| async move { client.get_account(&mint).await }),
+        rpc::read("getEpochInfo", |client| async move { client.get_epoch_info().await }),
+    )
+        .map_err(rpc_error)?;
+    let state = (account.owner == spl_token_2022::id())
+        .then(|| StateWithExtensions::<Mint>::unpack(&account.data).ok())
+        .flatten()
+        .ok_or_else(|| ErrorResponse::new(format!("Account {} is not a Token-2022 mint", mint)))?;
+
+    request.decimals = request.decimals.or(Some(state.base.decimals));
+    if needs_fee {
+        let config = state
+            .get_extension::<TransferFeeConfig>()
+            .map_err(|_| ErrorResponse::new(format!("Mint {} has no transfer fee", mint)))?;
+        request.fee = Some(
+            config
+                .calculate_epoch_fee(epoch_info.epoch, request.amount)
+                .ok_or_else(|| ErrorResponse::new("Transfer fee calculation overflowed"))?,
+        );
+    }
+
+    transfer_with_fee(request)
+}
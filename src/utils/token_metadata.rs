@@ -0,0 +1,178 @@
+use borsh::BorshDeserialize;
+use serde::Serialize;
+use utoipa::ToSchema;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use spl_token_2022::extension::{metadata_pointer::MetadataPointer, BaseStateWithExtensions, StateWithExtensions};
+use spl_token_metadata_interface::state::TokenMetadata;
+use super::footprint::{fetch_accounts, metadata_address, TOKEN_METADATA_PROGRAM_ID};
+use super::instruction::validate_pubkey;
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+
+// Metaplex pads names, symbols and URIs with NULs to fixed lengths
+const METADATA_PADDING: char = '\0';
+
+#[derive(Serialize, ToSchema, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum MetadataSource {
+    // A Token Metadata account at the mint's metadata PDA
+    Metaplex,
+    // The Token-2022 metadata extension
+    Token2022,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MetadataCreator {
+    pub address: String,
+    pub verified: bool,
+    // Percentage of royalties
+    pub share: u8,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AdditionalMetadata {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TokenMetadataResponse {
+    pub mint: String,
+    pub source: MetadataSource,
+    // The account the metadata was read from
+    pub metadata_address: String,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_authority: Option<String>,
+    // Metaplex only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seller_fee_basis_points: Option<u16>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub creators: Vec<MetadataCreator>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_mutable: Option<bool>,
+    // Token-2022 only
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub additional_metadata: Vec<AdditionalMetadata>,
+}
+
+#[derive(BorshDeserialize)]
+struct Creator {
+    address: Pubkey,
+    verified: bool,
+    share: u8,
+}
+
+// The leading fields of a Token Metadata account, which is all that is
+// read; later fields have changed between versions of the program
+#[derive(BorshDeserialize)]
+struct Metadata {
+    _key: u8,
+    update_authority: Pubkey,
+    mint: Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+    seller_fee_basis_points: u16,
+    creators: Option<Vec<Creator>>,
+    _primary_sale_happened: bool,
+    is_mutable: bool,
+}
+
+fn unpadded(value: String) -> String {
+    value.trim_end_matches(METADATA_PADDING).to_string()
+}
+
+fn metaplex_metadata(mint: &Pubkey, address: Pubkey, account: &Account) -> Result<TokenMetadataResponse, ErrorResponse> {
+    let unreadable = || ErrorResponse::new(format!("Metadata account {} could not be read", address));
+    if account.owner != TOKEN_METADATA_PROGRAM_ID {
+        return Err(unreadable());
+    }
+    let metadata = Metadata::deserialize(&mut account.data.as_slice()).map_err(|_| unreadable())?;
+    if metadata.mint != *mint {
+        return Err(unreadable());
+    }
+
+    Ok(TokenMetadataResponse {
+        mint: mint.to_string(),
+        source: MetadataSource::Metaplex,
+        metadata_address: address.to_string(),
+        name: unpadded(metadata.name),
+        symbol: unpadded(metadata.symbol),
+        uri: unpadded(metadata.uri),
+        update_authority: Some(metadata.update_authority.to_string()),
+        seller_fee_basis_points: Some(metadata.seller_fee_basis_points),
+        creators: metadata
+            .creators
+            .unwrap_or_default()
+            .into_iter()
+            .map(|creator| MetadataCreator {
+                address: creator.address.to_string(),
+                verified: creator.verified,
+                share: creator.share,
+            })
+            .collect(),
+        is_mutable: Some(metadata.is_mutable),
+        additional_metadata: Vec::new(),
+    })
+}
+
+fn token_2022_metadata(mint: &Pubkey, metadata: TokenMetadata) -> TokenMetadataResponse {
+    TokenMetadataResponse {
+        mint: mint.to_string(),
+        source: MetadataSource::Token2022,
+        metadata_address: mint.to_string(),
+        name: metadata.name,
+        symbol: metadata.symbol,
+        uri: metadata.uri,
+        update_authority: Option::<Pubkey>::from(metadata.update_authority).map(|authority| authority.to_string()),
+        seller_fee_basis_points: None,
+        creators: Vec::new(),
+        is_mutable: None,
+        additional_metadata: metadata
+            .additional_metadata
+            .into_iter()
+            .map(|(key, value)| AdditionalMetadata { key, value })
+            .collect(),
+    }
+}
+
+// Token-2022 mints point at their metadata, which is usually the mint's own
+// metadata extension but may be a Token Metadata account. Other mints only
+// have the Token Metadata account at their PDA.
+pub async fn get_token_metadata(mint: String) -> Result<SuccessResponse<TokenMetadataResponse>, ErrorResponse> {
+    // Validate inputs
+    let mint = validate_pubkey(&mint, "mint")?;
+
+    let metaplex_address = metadata_address(&mint);
+    let mut accounts = fetch_accounts(&[mint, metaplex_address]).await?.into_iter();
+    let (fetched, metaplex) = (accounts.next().flatten(), accounts.next().flatten());
+    let not_a_mint = || ErrorResponse::with_code(ApiErrorCode::InvalidAccount, format!("Account {} is not an SPL token mint", mint));
+    let fetched = fetched.ok_or_else(not_a_mint)?;
+    let no_metadata = || ErrorResponse::new(format!("Mint {} has no metadata", mint));
+
+    if fetched.owner == spl_token_2022::id() {
+        let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&fetched.data).map_err(|_| not_a_mint())?;
+        let pointer = state
+            .get_extension::<MetadataPointer>()
+            .ok()
+            .and_then(|pointer| Option::<Pubkey>::from(pointer.metadata_address));
+        match pointer {
+            Some(address) if address == mint => {
+                let metadata = state.get_variable_len_extension::<TokenMetadata>().map_err(|_| no_metadata())?;
+                return Ok(SuccessResponse::new(token_2022_metadata(&mint, metadata)));
+            }
+            Some(address) if address != metaplex_address => {
+                let account = fetch_accounts(&[address]).await?.pop().flatten().ok_or_else(no_metadata)?;
+                return metaplex_metadata(&mint, address, &account).map(SuccessResponse::new);
+            }
+            _ => {}
+        }
+    } else if fetched.owner != spl_token::id() {
+        return Err(not_a_mint());
+    }
+
+    let metaplex = metaplex.ok_or_else(no_metadata)?;
+    metaplex_metadata(&mint, metaplex_address, &metaplex).map(SuccessResponse::new)
+}
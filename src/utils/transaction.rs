@@ -0,0 +1,280 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_transaction_status::TransactionConfirmationStatus;
+use std::str::FromStr;
+use crate::shutdown;
+use super::rpc::{self, rpc_error};
+use super::signer::{self, SignerBackend, SigningKey};
+use super::audit::{self, AuditOperation};
+use super::timing;
+use super::confirmation::{self, Commitment, Outcome};
+use super::webhook;
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+#[derive(Deserialize, ToSchema)]
+pub struct SignTransactionRequest {
+    pub transaction: String,
+    #[serde(rename = "secretKeys", default)]
+    pub secret_keys: Vec<String>,
+    // Keys held by signerBackend, signing alongside secretKeys
+    #[serde(rename = "keyIds", default)]
+    pub key_ids: Vec<String>,
+    #[serde(rename = "signerBackend", default)]
+    pub signer_backend: SignerBackend,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SignTransactionResponse {
+    pub transaction: String,
+    pub signed_by: Vec<String>,
+    pub missing_signers: Vec<String>,
+    pub complete: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SubmitTransactionRequest {
+    // Base64, with every required signature already present
+    pub transaction: String,
+    #[serde(rename = "skipPreflight", default)]
+    pub skip_preflight: bool,
+    // Where to POST the outcome once the transaction reaches commitment,
+    // fails or expires; deliveries are signed as described in webhook.rs
+    #[serde(rename = "callbackUrl", default, skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+    // How far the transaction is watched, and rebroadcast until confirmed
+    #[serde(default)]
+    pub commitment: Commitment,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SubmitTransactionResponse {
+    pub signature: String,
+}
+
+#[derive(Serialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SignatureConfirmation {
+    // Not seen by the cluster yet, or dropped
+    NotFound,
+    Processed,
+    Confirmed,
+    Finalized,
+    // Submitted here, and its blockhash expired before it landed
+    Expired,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SignatureStatusResponse {
+    pub signature: String,
+    pub status: SignatureConfirmation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u64>,
+    // Set when the transaction landed but failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    // Blocks confirmed on top of the transaction's; null once it is
+    // finalized or before it has landed
+    pub confirmations: Option<usize>,
+    // Program logs of a failed transaction submitted here
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<String>,
+}
+
+pub fn decode_transaction(transaction: &str) -> Result<VersionedTransaction, ErrorResponse> {
+    let bytes = BASE64.decode(transaction)
+        .map_err(|_| ErrorResponse::new("Invalid base64 encoding for transaction"))?;
+    let transaction: VersionedTransaction = bincode::deserialize(&bytes)
+        .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidTransaction, "Invalid transaction format"))?;
+    // Deserializing doesn't check that the header agrees with the keys and
+    // signatures, which everything indexing by it relies on
+    transaction
+        .sanitize()
+        .map_err(|err| ErrorResponse::with_code(ApiErrorCode::InvalidTransaction, format!("Transaction is malformed: {}", err)))?;
+    Ok(transaction)
+}
+
+// Keys whose signatures the message requires, in signature order
+pub fn required_signers(transaction: &VersionedTransaction) -> Result<&[Pubkey], ErrorResponse> {
+    let num_signers = transaction.message.header().num_required_signatures as usize;
+    if transaction.signatures.len() != num_signers {
+        return Err(ErrorResponse::with_code(ApiErrorCode::InvalidTransaction, "Transaction signature count does not match its message header"));
+    }
+    transaction
+        .message
+        .static_account_keys()
+        .get(..num_signers)
+        .ok_or_else(|| ErrorResponse::with_code(ApiErrorCode::InvalidTransaction, "Transaction has fewer account keys than required signers"))
+}
+
+pub fn missing_signers(transaction: &VersionedTransaction) -> Vec<String> {
+    transaction.message.static_account_keys()
+        .iter()
+        .zip(&transaction.signatures)
+        .filter(|(_, signature)| **signature == Signature::default())
+        .map(|(key, _)| key.to_string())
+        .collect()
+}
+
+// Signatures go at the index of the signer's key among the message's
+// required signers, leaving any existing signatures in place. Returns the
+// keys that signed.
+pub async fn sign_with(transaction: &mut VersionedTransaction, keys: &[SigningKey]) -> Result<Vec<String>, ErrorResponse> {
+    let message_bytes = transaction.message.serialize();
+    let mut signed_by = Vec::with_capacity(keys.len());
+    for key in keys {
+        let pubkey = key.pubkey();
+        let index = required_signers(transaction)?
+            .iter()
+            .position(|key| *key == pubkey)
+            .ok_or_else(|| ErrorResponse::new(format!("{} is not a required signer of this transaction", pubkey)))?;
+
+        transaction.signatures[index] = key.sign(&message_bytes).await?;
+        signed_by.push(pubkey.to_string());
+    }
+    Ok(signed_by)
+}
+
+// Every required signature present and valid, as checked before sending
+pub fn verify_signatures(transaction: &VersionedTransaction) -> Result<(), ErrorResponse> {
+    required_signers(transaction)?;
+    let missing_signers = missing_signers(transaction);
+    if !missing_signers.is_empty() {
+        return Err(ErrorResponse::new(format!("Transaction is missing signatures from {}", missing_signers.join(", "))));
+    }
+    if !transaction.verify_with_results().into_iter().all(|valid| valid) {
+        return Err(ErrorResponse::new("Transaction has an invalid signature"));
+    }
+    Ok(())
+}
+
+pub async fn broadcast(transaction: &VersionedTransaction, skip_preflight: bool) -> Result<Signature, ErrorResponse> {
+    let client = rpc::client();
+    let config = RpcSendTransactionConfig {
+        skip_preflight,
+        preflight_commitment: Some(client.commitment().commitment),
+        ..RpcSendTransactionConfig::default()
+    };
+    rpc::write("sendTransaction", client, client.send_transaction_with_config(transaction, config))
+        .await
+        .map_err(rpc_error)
+}
+
+pub async fn sign_transaction(request: SignTransactionRequest) -> Result<SuccessResponse<SignTransactionResponse>, ErrorResponse> {
+    let result = sign(request).await;
+    audit::recorded(AuditOperation::SignTransaction, result, |signed| signed.signed_by.clone())
+}
+
+async fn sign(request: SignTransactionRequest) -> Result<SuccessResponse<SignTransactionResponse>, ErrorResponse> {
+    // Validate inputs
+    if request.transaction.is_empty() || (request.secret_keys.is_empty() && request.key_ids.is_empty()) {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
+    }
+
+    let validation = timing::start("validation");
+    let mut transaction = decode_transaction(&request.transaction)?;
+    let keys = signer::keys(&request.secret_keys, &request.key_ids, request.signer_backend).await?;
+
+    required_signers(&transaction)?;
+    drop(validation);
+
+    let signing = timing::start("signing");
+    let signed_by = sign_with(&mut transaction, &keys).await?;
+    drop(signing);
+
+    let missing_signers = missing_signers(&transaction);
+
+    let serialized = timing::measure("serialization", || bincode::serialize(&transaction))
+        .map_err(|_| ErrorResponse::new("Failed to serialize transaction"))?;
+
+    Ok(SuccessResponse::new(SignTransactionResponse {
+        transaction: BASE64.encode(&serialized),
+        signed_by,
+        complete: missing_signers.is_empty(),
+        missing_signers,
+    }))
+}
+
+// Broadcast a fully signed transaction. Signatures are checked here first so
+// a bad one is reported clearly rather than as a preflight failure.
+pub async fn submit_transaction(request: SubmitTransactionRequest) -> Result<SuccessResponse<SubmitTransactionResponse>, ErrorResponse> {
+    // Validate inputs
+    if request.transaction.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
+    }
+
+    let validation = timing::start("validation");
+    let transaction = decode_transaction(&request.transaction)?;
+    verify_signatures(&transaction)?;
+    let callback_url = request.callback_url.as_deref().map(webhook::validate_callback_url).transpose()?;
+    drop(validation);
+
+    // Once sent, the transaction must be watched even if the client has gone
+    let (skip_preflight, commitment) = (request.skip_preflight, request.commitment);
+    let signature = shutdown::detached(async move {
+        let signature = broadcast(&transaction, skip_preflight).await?;
+        match callback_url {
+            Some(url) => webhook::spawn(transaction, commitment, url),
+            None => confirmation::spawn(transaction, commitment),
+        }
+        Ok::<_, ErrorResponse>(signature)
+    })
+        .await?;
+
+    Ok(SuccessResponse::new(SubmitTransactionResponse {
+        signature: signature.to_string(),
+    }))
+}
+
+// For clients that poll rather than use callbackUrl or the websocket.
+// Searches the ledger history too, so old signatures are still found.
+pub async fn get_signature_status(signature: String) -> Result<SuccessResponse<SignatureStatusResponse>, ErrorResponse> {
+    // Validate inputs
+    let signature = Signature::from_str(&signature)
+        .map_err(|_| ErrorResponse::with_code(ApiErrorCode::InvalidSignature, "Invalid signature format"))?;
+
+    let status = rpc::read("getSignatureStatuses", move |client| async move {
+        client.get_signature_statuses_with_history(&[signature]).await.map(|response| response.value)
+    })
+        .await
+        .map_err(rpc_error)?
+        .pop()
+        .flatten();
+
+    // Transactions submitted here are watched, so what became of one the
+    // cluster doesn't know may be known here
+    let outcome = confirmation::outcome(&signature);
+    let Some(status) = status else {
+        let expired = matches!(outcome, Some(Outcome::Expired));
+        return Ok(SuccessResponse::new(SignatureStatusResponse {
+            signature: signature.to_string(),
+            status: if expired { SignatureConfirmation::Expired } else { SignatureConfirmation::NotFound },
+            slot: None,
+            error: None,
+            confirmations: None,
+            logs: Vec::new(),
+        }));
+    };
+    let confirmation = match status.confirmation_status() {
+        TransactionConfirmationStatus::Processed => SignatureConfirmation::Processed,
+        TransactionConfirmationStatus::Confirmed => SignatureConfirmation::Confirmed,
+        TransactionConfirmationStatus::Finalized => SignatureConfirmation::Finalized,
+    };
+    Ok(SuccessResponse::new(SignatureStatusResponse {
+        signature: signature.to_string(),
+        status: confirmation,
+        slot: Some(status.slot),
+        error: status.err.map(|err| err.to_string()),
+        confirmations: status.confirmations,
+        logs: match outcome {
+            Some(Outcome::Failed { logs, .. }) => logs,
+            _ => Vec::new(),
+        },
+    }))
+}
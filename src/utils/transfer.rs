@@ -1,73 +1,83 @@
 use serde::{Deserialize, Serialize};
-use solana_sdk::{
-    instruction::{AccountMeta, Instruction},
-    pubkey::Pubkey,
-    system_instruction,
-    system_program,
+use utoipa::ToSchema;
+use solana_sdk::{system_instruction, system_program};
+use super::address::derive_with_seed;
+use super::compute_budget::{
+    format_compute_budget_instructions, priority_fee_instructions, ComputeBudgetInstructionResponse, PriorityFee,
 };
-use super::response_types::{SuccessResponse, ErrorResponse};
-use bs58;
-use base64;
+use super::instruction::{validate_lamports, validate_pubkey, InstructionResponse};
+use super::memo::memo_instruction;
+use super::response_types::{ApiErrorCode, FieldErrors, SuccessResponse, ErrorResponse};
+use super::sns::{resolve_recipient, ResolvedName};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct SendSolRequest {
     pub from: String,
+    // An address, or a .sol domain resolved to its owner
     pub to: String,
     pub lamports: u64,
+    #[serde(rename = "priorityFee")]
+    pub priority_fee: Option<PriorityFee>,
+    // Attached as a memo instruction signed by the sender
+    pub memo: Option<String>,
 }
 
-#[derive(Serialize)]
-pub struct AccountInfo {
-    pub pubkey: String,
-    pub is_signer: bool,
-    pub is_writable: bool,
-}
-
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct SendSolResponse {
-    pub program_id: String,
-    pub accounts: Vec<AccountInfo>,
-    pub instruction_data: String,
+    #[serde(flatten)]
+    pub instruction: InstructionResponse,
+    // Set the priority fee; place these ahead of the instruction above
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub compute_budget_instructions: Vec<ComputeBudgetInstructionResponse>,
+    // Place this after the instruction above
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo_instruction: Option<InstructionResponse>,
+    // Set when to was a .sol domain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_name: Option<ResolvedName>,
 }
 
-fn validate_pubkey(key: &str, field_name: &str) -> Result<Pubkey, ErrorResponse> {
-    bs58::decode(key)
-        .into_vec()
-        .map_err(|_| ErrorResponse::new(format!("Invalid base58 encoding for {}", field_name)))
-        .and_then(|bytes| 
-            Pubkey::try_from(bytes.as_slice())
-                .map_err(|_| ErrorResponse::new(format!("Invalid public key format for {}", field_name)))
-        )
+#[derive(Deserialize, ToSchema)]
+pub struct TransferWithSeedRequest {
+    // The account lamports leave is derived from these three; the base key
+    // signs for it
+    pub base: String,
+    pub seed: String,
+    // Program the derived account was created for; the System program by default
+    #[serde(rename = "fromOwner")]
+    pub from_owner: Option<String>,
+    pub to: String,
+    pub lamports: u64,
 }
 
-fn validate_lamports(lamports: u64) -> Result<(), ErrorResponse> {
-    if lamports == 0 {
-        return Err(ErrorResponse::new("Amount must be greater than 0 lamports"));
-    }
-    
-    // Check if amount is reasonable (less than total supply)
-    // Solana total supply is ~500M SOL = 500M * 10^9 lamports
-    const MAX_REASONABLE_LAMPORTS: u64 = 500_000_000 * 1_000_000_000;
-    if lamports > MAX_REASONABLE_LAMPORTS {
-        return Err(ErrorResponse::new("Amount exceeds maximum reasonable transfer"));
-    }
-    
-    Ok(())
+#[derive(Serialize, ToSchema)]
+pub struct TransferWithSeedResponse {
+    #[serde(flatten)]
+    pub instruction: InstructionResponse,
+    // The derived address the lamports leave
+    pub from: String,
 }
 
 pub fn send_sol(request: SendSolRequest) -> Result<SuccessResponse<SendSolResponse>, ErrorResponse> {
     // Validate inputs
     if request.from.is_empty() || request.to.is_empty() {
-        return Err(ErrorResponse::new("Missing required fields"));
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
     }
 
-    let from_pubkey = validate_pubkey(&request.from, "sender address")?;
-    let to_pubkey = validate_pubkey(&request.to, "recipient address")?;
-    validate_lamports(request.lamports)?;
+    let mut fields = FieldErrors::default();
+    let from_pubkey = fields.check("from", validate_pubkey(&request.from, "sender address"));
+    let to_pubkey = fields.check("to", validate_pubkey(&request.to, "recipient address"));
+    fields.check("lamports", validate_lamports(request.lamports));
+    let compute_budget = fields.check("priorityFee", priority_fee_instructions(request.priority_fee.as_ref()));
+    fields.finish()?;
+    let memo = request.memo
+        .as_deref()
+        .map(|memo| memo_instruction(memo, &[from_pubkey]))
+        .transpose()?;
 
     // Prevent sending to the same address
     if from_pubkey == to_pubkey {
-        return Err(ErrorResponse::new("Sender and recipient addresses cannot be the same"));
+        return Err(ErrorResponse::with_code(ApiErrorCode::SameAddress, "Sender and recipient addresses cannot be the same"));
     }
 
     // Create the transfer instruction
@@ -77,22 +87,59 @@ pub fn send_sol(request: SendSolRequest) -> Result<SuccessResponse<SendSolRespon
         request.lamports
     );
 
-    // Required accounts for the transfer
-    let accounts = vec![
-        AccountMeta::new(from_pubkey, true),  // from account (writable and signer)
-        AccountMeta::new(to_pubkey, false),   // to account (writable)
-    ];
-
     // Format the response
     let response = SendSolResponse {
-        program_id: system_program::id().to_string(),
-        accounts: accounts.iter().map(|account| AccountInfo {
-            pubkey: bs58::encode(account.pubkey.to_bytes()).into_string(),
-            is_signer: account.is_signer,
-            is_writable: account.is_writable,
-        }).collect(),
-        instruction_data: base64::encode(&instruction.data),
+        instruction: InstructionResponse::from(&instruction),
+        compute_budget_instructions: format_compute_budget_instructions(&compute_budget),
+        memo_instruction: memo.as_ref().map(InstructionResponse::from),
+        resolved_name: None,
     };
 
     Ok(SuccessResponse::new(response))
-} 
\ No newline at end of file
+}
+
+// Resolves a .sol recipient before building the transfer
+pub async fn send_sol_with_name_lookup(mut request: SendSolRequest) -> Result<SuccessResponse<SendSolResponse>, ErrorResponse> {
+    let resolved_name = resolve_recipient(&mut request.to).await?;
+    let mut response = send_sol(request)?;
+    response.data.resolved_name = resolved_name;
+    Ok(response)
+}
+
+// Moves lamports out of an address derived with Pubkey::create_with_seed,
+// which has no keypair of its own
+pub fn transfer_with_seed(request: TransferWithSeedRequest) -> Result<SuccessResponse<TransferWithSeedResponse>, ErrorResponse> {
+    // Validate inputs
+    if request.base.is_empty() || request.to.is_empty() {
+        return Err(ErrorResponse::with_code(ApiErrorCode::MissingFields, "Missing required fields"));
+    }
+
+    let mut fields = FieldErrors::default();
+    let base = fields.check("base", validate_pubkey(&request.base, "base"));
+    let from_owner = match request.from_owner.as_deref() {
+        Some(owner) => fields.check("fromOwner", validate_pubkey(owner, "from owner")),
+        None => system_program::id(),
+    };
+    let to_pubkey = fields.check("to", validate_pubkey(&request.to, "recipient address"));
+    fields.check("lamports", validate_lamports(request.lamports));
+    fields.finish()?;
+    let from_pubkey = derive_with_seed(&base, &request.seed, &from_owner)?;
+
+    if from_pubkey == to_pubkey {
+        return Err(ErrorResponse::with_code(ApiErrorCode::SameAddress, "Sender and recipient addresses cannot be the same"));
+    }
+
+    let instruction = system_instruction::transfer_with_seed(
+        &from_pubkey,
+        &base,
+        request.seed,
+        &from_owner,
+        &to_pubkey,
+        request.lamports,
+    );
+
+    Ok(SuccessResponse::new(TransferWithSeedResponse {
+        instruction: InstructionResponse::from(&instruction),
+        from: from_pubkey.to_string(),
+    }))
+}
@@ -0,0 +1,265 @@
+use rand::{distributions::Alphanumeric, Rng};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::{signature::Keypair, signer::Signer};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::oneshot;
+use super::audit::{self, AuditOperation};
+use super::response_types::{SuccessResponse, ErrorResponse};
+use bs58;
+
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+// Each extra character multiplies the expected work by 58, so longer
+// patterns would almost never be found within the attempt budget
+const MAX_PATTERN_LEN: usize = 6;
+const DEFAULT_MAX_ATTEMPTS: u64 = 10_000_000;
+const MAX_ATTEMPTS: u64 = 500_000_000;
+
+// Searches that finish within this window are answered inline; longer ones
+// return a job id to poll
+const INLINE_WAIT: Duration = Duration::from_secs(2);
+const MAX_RUNNING_JOBS: usize = 4;
+const JOB_TTL_SECS: u64 = 10 * 60;
+const JOB_ID_LEN: usize = 24;
+
+#[derive(Deserialize, ToSchema)]
+pub struct VanityKeypairRequest {
+    pub prefix: Option<String>,
+    pub suffix: Option<String>,
+    #[serde(rename = "ignoreCase", default)]
+    pub ignore_case: bool,
+    #[serde(rename = "maxAttempts")]
+    pub max_attempts: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VanityJobStatus {
+    Running,
+    Found,
+    Exhausted,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VanityKeypairResponse {
+    pub job_id: String,
+    pub status: VanityJobStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pubkey: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+struct Pattern {
+    prefix: String,
+    suffix: String,
+    ignore_case: bool,
+}
+
+impl Pattern {
+    fn matches(&self, address: &str) -> bool {
+        if self.ignore_case {
+            let address = address.to_ascii_lowercase();
+            address.starts_with(&self.prefix) && address.ends_with(&self.suffix)
+        } else {
+            address.starts_with(&self.prefix) && address.ends_with(&self.suffix)
+        }
+    }
+}
+
+enum JobState {
+    Running,
+    Found(Box<Keypair>),
+    Exhausted,
+}
+
+struct Job {
+    state: JobState,
+    expires_at: u64,
+}
+
+static JOBS: OnceLock<Mutex<HashMap<String, Job>>> = OnceLock::new();
+
+fn jobs() -> &'static Mutex<HashMap<String, Job>> {
+    JOBS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn validate_pattern(part: Option<String>, field_name: &str, ignore_case: bool) -> Result<String, ErrorResponse> {
+    let part = part.unwrap_or_default();
+    let possible = |c: char| {
+        BASE58_ALPHABET.contains(c)
+            || (ignore_case && (BASE58_ALPHABET.contains(c.to_ascii_uppercase()) || BASE58_ALPHABET.contains(c.to_ascii_lowercase())))
+    };
+    if let Some(invalid) = part.chars().find(|c| !possible(*c)) {
+        return Err(ErrorResponse::new(format!(
+            "{} contains '{}', which never appears in base58 addresses",
+            field_name, invalid
+        )));
+    }
+    Ok(if ignore_case { part.to_ascii_lowercase() } else { part })
+}
+
+// Runs on the rayon pool, so the tokio workers stay free while it spins
+fn grind(pattern: &Pattern, max_attempts: u64) -> Option<Keypair> {
+    (0..max_attempts).into_par_iter().find_map_any(|_| {
+        let keypair = Keypair::new();
+        pattern
+            .matches(&keypair.pubkey().to_string())
+            .then_some(keypair)
+    })
+}
+
+fn finished_state(result: Option<Keypair>) -> JobState {
+    match result {
+        Some(keypair) => JobState::Found(Box::new(keypair)),
+        None => JobState::Exhausted,
+    }
+}
+
+// Describes a job, handing over the keypair only once
+fn job_response(job_id: String, state: JobState) -> VanityKeypairResponse {
+    match state {
+        JobState::Running => VanityKeypairResponse { job_id, status: VanityJobStatus::Running, pubkey: None, secret: None },
+        JobState::Exhausted => VanityKeypairResponse { job_id, status: VanityJobStatus::Exhausted, pubkey: None, secret: None },
+        JobState::Found(keypair) => VanityKeypairResponse {
+            job_id,
+            status: VanityJobStatus::Found,
+            pubkey: Some(keypair.pubkey().to_string()),
+            secret: Some(bs58::encode(keypair.to_bytes()).into_string()),
+        },
+    }
+}
+
+// Audited once, when the response hands the found keypair over
+fn handed_over(
+    result: Result<SuccessResponse<VanityKeypairResponse>, ErrorResponse>,
+) -> Result<SuccessResponse<VanityKeypairResponse>, ErrorResponse> {
+    match &result {
+        Ok(response) if response.data.secret.is_some() => {
+            audit::recorded(AuditOperation::GenerateVanityKeypair, result, |found| found.pubkey.iter().cloned().collect())
+        }
+        _ => result,
+    }
+}
+
+pub async fn generate_vanity_keypair(request: VanityKeypairRequest) -> Result<SuccessResponse<VanityKeypairResponse>, ErrorResponse> {
+    // Validate inputs
+    let prefix = validate_pattern(request.prefix, "prefix", request.ignore_case)?;
+    let suffix = validate_pattern(request.suffix, "suffix", request.ignore_case)?;
+    if prefix.is_empty() && suffix.is_empty() {
+        return Err(ErrorResponse::new("A prefix or suffix is required"));
+    }
+    if prefix.len() + suffix.len() > MAX_PATTERN_LEN {
+        return Err(ErrorResponse::new(format!(
+            "prefix and suffix may be at most {} characters combined",
+            MAX_PATTERN_LEN
+        )));
+    }
+    let max_attempts = request.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS);
+    if max_attempts == 0 || max_attempts > MAX_ATTEMPTS {
+        return Err(ErrorResponse::new(format!("maxAttempts must be between 1 and {}", MAX_ATTEMPTS)));
+    }
+
+    let job_id: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(JOB_ID_LEN)
+        .map(char::from)
+        .collect();
+    {
+        let mut jobs = jobs().lock().unwrap();
+        let now = now();
+        jobs.retain(|_, job| matches!(job.state, JobState::Running) || job.expires_at > now);
+        let running = jobs.values().filter(|job| matches!(job.state, JobState::Running)).count();
+        if running >= MAX_RUNNING_JOBS {
+            return Err(ErrorResponse::new("Too many vanity searches are running, try again later"));
+        }
+        jobs.insert(job_id.clone(), Job { state: JobState::Running, expires_at: now + JOB_TTL_SECS });
+    }
+
+    let pattern = Pattern { prefix, suffix, ignore_case: request.ignore_case };
+    let (sender, receiver) = oneshot::channel();
+    rayon::spawn(move || {
+        let _ = sender.send(grind(&pattern, max_attempts));
+    });
+
+    // The job is recorded by its own task rather than by this request, so a
+    // client disconnecting mid-wait can't leave it marked as running forever
+    let (finished, mut done) = oneshot::channel();
+    let supervised_id = job_id.clone();
+    tokio::spawn(async move {
+        let state = finished_state(receiver.await.ok().flatten());
+        if let Some(job) = jobs().lock().unwrap().get_mut(&supervised_id) {
+            job.state = state;
+            job.expires_at = now() + JOB_TTL_SECS;
+        }
+        let _ = finished.send(());
+    });
+
+    if tokio::time::timeout(INLINE_WAIT, &mut done).await.is_ok()
+        && let Some(job) = jobs().lock().unwrap().remove(&job_id)
+    {
+        return handed_over(Ok(SuccessResponse::new(job_response(job_id, job.state))));
+    }
+
+    Ok(SuccessResponse::new(job_response(job_id, JobState::Running)))
+}
+
+pub fn get_vanity_job(job_id: String) -> Result<SuccessResponse<VanityKeypairResponse>, ErrorResponse> {
+    let mut jobs = jobs().lock().unwrap();
+    let running = match jobs.get(&job_id) {
+        None => return Err(ErrorResponse::new("Unknown or expired vanity job")),
+        Some(job) => matches!(job.state, JobState::Running),
+    };
+    if running {
+        return Ok(SuccessResponse::new(job_response(job_id, JobState::Running)));
+    }
+
+    let job = jobs.remove(&job_id).unwrap();
+    drop(jobs);
+    handed_over(Ok(SuccessResponse::new(job_response(job_id, job.state))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn running_jobs() -> usize {
+        jobs().lock().unwrap().values().filter(|job| matches!(job.state, JobState::Running)).count()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn aborted_request_does_not_leave_job_running() {
+        // Long enough to outlast the abort, short enough to finish quickly
+        let request = VanityKeypairRequest {
+            prefix: Some("zzzzzz".to_string()),
+            suffix: None,
+            ignore_case: false,
+            max_attempts: Some(20_000),
+        };
+        let handle = tokio::spawn(generate_vanity_keypair(request));
+        while running_jobs() == 0 {
+            tokio::task::yield_now().await;
+        }
+        handle.abort();
+        let _ = handle.await;
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(30);
+        while running_jobs() > 0 {
+            assert!(tokio::time::Instant::now() < deadline, "vanity job still running after its search finished");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+}
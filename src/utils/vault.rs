@@ -0,0 +1,298 @@
+use aes_gcm_siv::{
+    aead::{Aead, NewAead, Payload},
+    Aes256GcmSiv, Nonce,
+};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::signature::{Keypair, Signer};
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    sync::{Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use crate::config;
+use super::audit::{self, AuditOperation};
+use super::response_types::{ApiErrorCode, SuccessResponse, ErrorResponse};
+use super::instruction::validate_secret_key;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use bs58;
+
+const KEY_ID_LEN: usize = 24;
+
+#[derive(Deserialize, ToSchema)]
+pub struct StoreKeyRequest {
+    // Base58 keypair to import; a new one is generated when absent
+    pub secret: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct StoreKeyResponse {
+    pub key_id: String,
+    pub pubkey: String,
+}
+
+// Paused keys can be resumed; revoked keys never sign again, and only their
+// metadata is kept
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum KeyState {
+    #[default]
+    Active,
+    Paused,
+    Revoked,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateKeyStateRequest {
+    pub state: KeyState,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VaultKeyResponse {
+    pub key_id: String,
+    pub pubkey: String,
+    pub state: KeyState,
+    // Unix seconds
+    pub created_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_changed_at: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteKeyResponse {
+    pub key_id: String,
+    pub deleted: bool,
+    // Always revoked; the key's metadata stays in the vault
+    pub state: KeyState,
+}
+
+// One keypair as written to the vault file. The key id is bound in as
+// associated data, so a record copied under another id fails to decrypt.
+#[derive(Serialize, Deserialize, Clone)]
+struct StoredKey {
+    pubkey: String,
+    // Base64; both are emptied when the key is revoked
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    nonce: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    ciphertext: String,
+    created_at: u64,
+    // Missing in vaults written before keys had states
+    #[serde(default)]
+    state: KeyState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    state_changed_at: Option<u64>,
+}
+
+struct Vault {
+    cipher: Aes256GcmSiv,
+    keys: Mutex<HashMap<String, StoredKey>>,
+}
+
+static VAULT: OnceLock<Option<Vault>> = OnceLock::new();
+
+// The master key is 32 bytes, as 64 hex characters or base64
+fn parse_master_key(value: &str) -> Result<[u8; 32], String> {
+    hex::decode(value)
+        .ok()
+        .or_else(|| BASE64.decode(value).ok())
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| "expected 32 bytes as hex or base64".to_string())
+}
+
+impl Vault {
+    fn encrypt(&self, key_id: &str, keypair: &Keypair) -> StoredKey {
+        let nonce: [u8; 12] = rand::random();
+        let secret = keypair.to_bytes();
+        let ciphertext = self
+            .cipher
+            .encrypt(&Nonce::from(nonce), Payload { msg: &secret, aad: key_id.as_bytes() })
+            .expect("AES-GCM-SIV encrypts any keypair");
+        StoredKey {
+            pubkey: keypair.pubkey().to_string(),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+            created_at: now(),
+            state: KeyState::Active,
+            state_changed_at: None,
+        }
+    }
+
+    fn decrypt(&self, key_id: &str, stored: &StoredKey) -> Option<Keypair> {
+        let nonce = <[u8; 12]>::try_from(BASE64.decode(&stored.nonce).ok()?).ok()?;
+        let ciphertext = BASE64.decode(&stored.ciphertext).ok()?;
+        let secret = self
+            .cipher
+            .decrypt(&Nonce::from(nonce), Payload { msg: &ciphertext, aad: key_id.as_bytes() })
+            .ok()?;
+        Keypair::from_bytes(&secret).ok()
+    }
+
+    // Written to a temporary file and renamed into place, so a crash never
+    // leaves a half-written vault
+    fn save(&self, keys: &HashMap<String, StoredKey>) -> Result<(), ErrorResponse> {
+        let path = &config::get().vault_path;
+        let temporary = format!("{}.tmp", path);
+        let contents = serde_json::to_vec_pretty(keys).unwrap();
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(&temporary)
+            .and_then(|mut file| file.write_all(&contents).and_then(|_| file.sync_all()))
+            .and_then(|_| fs::rename(&temporary, path))
+            .map_err(|err| ErrorResponse::new(format!("Failed to write the key vault: {}", err)))
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0)
+}
+
+fn load(master_key: &str) -> Vault {
+    let master_key = parse_master_key(master_key).unwrap_or_else(|err| panic!("VAULT_MASTER_KEY is invalid: {}", err));
+    let path = &config::get().vault_path;
+    let keys: HashMap<String, StoredKey> = match fs::read(path) {
+        Ok(contents) => serde_json::from_slice(&contents).unwrap_or_else(|err| panic!("Key vault {} is corrupt: {}", path, err)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+        Err(err) => panic!("Failed to read key vault {}: {}", path, err),
+    };
+    let vault = Vault {
+        cipher: Aes256GcmSiv::new_from_slice(&master_key).expect("master key is 32 bytes"),
+        keys: Mutex::new(HashMap::new()),
+    };
+    // A different master key would only show up as failed signing later
+    if let Some((key_id, stored)) = keys.iter().find(|(_, stored)| stored.state != KeyState::Revoked)
+        && vault.decrypt(key_id, stored).is_none()
+    {
+        panic!("VAULT_MASTER_KEY does not decrypt the keys in {}", path);
+    }
+    *vault.keys.lock().unwrap() = keys;
+    vault
+}
+
+// Opens the vault when a master key is configured. Called at startup so a
+// bad key or vault file stops the server instead of failing requests.
+pub fn store() {
+    vault();
+}
+
+fn vault() -> Option<&'static Vault> {
+    VAULT
+        .get_or_init(|| config::get().vault_master_key.as_deref().map(load))
+        .as_ref()
+}
+
+fn enabled_vault() -> Result<&'static Vault, ErrorResponse> {
+    vault().ok_or_else(|| ErrorResponse::new("The key vault is not enabled on this server"))
+}
+
+fn unknown_key(key_id: &str) -> ErrorResponse {
+    ErrorResponse::with_code(ApiErrorCode::InvalidSecret, format!("Unknown vault key {}", key_id))
+}
+
+fn key_response(key_id: String, stored: &StoredKey) -> VaultKeyResponse {
+    VaultKeyResponse {
+        key_id,
+        pubkey: stored.pubkey.clone(),
+        state: stored.state,
+        created_at: stored.created_at,
+        state_changed_at: stored.state_changed_at,
+    }
+}
+
+// The keypair stored under an id, for endpoints that take keyId in place of
+// a secret. Checked on every use, so pausing or revoking a key takes effect
+// on the next signature.
+pub fn keypair(key_id: &str) -> Result<Keypair, ErrorResponse> {
+    let vault = enabled_vault()?;
+    let stored = vault.keys.lock().unwrap().get(key_id).cloned().ok_or_else(|| unknown_key(key_id))?;
+    match stored.state {
+        KeyState::Active => vault.decrypt(key_id, &stored).ok_or_else(|| unknown_key(key_id)),
+        KeyState::Paused => Err(ErrorResponse::with_code(ApiErrorCode::NotAuthorized, format!("Vault key {} is paused", key_id))),
+        KeyState::Revoked => Err(ErrorResponse::with_code(ApiErrorCode::NotAuthorized, format!("Vault key {} is revoked", key_id))),
+    }
+}
+
+fn store_new_key(request: StoreKeyRequest) -> Result<SuccessResponse<StoreKeyResponse>, ErrorResponse> {
+    let vault = enabled_vault()?;
+    let keypair = match request.secret.as_deref() {
+        Some(secret) => validate_secret_key(secret)?,
+        None => Keypair::new(),
+    };
+
+    let key_id: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(KEY_ID_LEN)
+        .map(char::from)
+        .collect();
+    let stored = vault.encrypt(&key_id, &keypair);
+    let mut keys = vault.keys.lock().unwrap();
+    keys.insert(key_id.clone(), stored);
+    if let Err(err) = vault.save(&keys) {
+        keys.remove(&key_id);
+        return Err(err);
+    }
+
+    Ok(SuccessResponse::new(StoreKeyResponse {
+        key_id,
+        pubkey: bs58::encode(keypair.pubkey().to_bytes()).into_string(),
+    }))
+}
+
+pub fn store_key(request: StoreKeyRequest) -> Result<SuccessResponse<StoreKeyResponse>, ErrorResponse> {
+    audit::recorded(AuditOperation::StoreVaultKey, store_new_key(request), |stored| vec![stored.pubkey.clone()])
+}
+
+pub fn get_key(key_id: String) -> Result<SuccessResponse<VaultKeyResponse>, ErrorResponse> {
+    let vault = enabled_vault()?;
+    let stored = vault.keys.lock().unwrap().get(&key_id).cloned().ok_or_else(|| unknown_key(&key_id))?;
+    Ok(SuccessResponse::new(key_response(key_id, &stored)))
+}
+
+// Moves a key between active and paused, or revokes it. Revoking destroys
+// the encrypted secret but keeps the key's id, public key and timestamps, so
+// audit entries naming it can still be traced.
+fn transition(key_id: String, state: KeyState) -> Result<SuccessResponse<VaultKeyResponse>, ErrorResponse> {
+    let vault = enabled_vault()?;
+    let mut keys = vault.keys.lock().unwrap();
+    let Some(previous) = keys.get(&key_id).cloned() else {
+        return Err(unknown_key(&key_id));
+    };
+    if previous.state == state {
+        return Ok(SuccessResponse::new(key_response(key_id, &previous)));
+    }
+    if previous.state == KeyState::Revoked {
+        return Err(ErrorResponse::new(format!("Vault key {} is revoked and cannot change state", key_id)));
+    }
+
+    let mut stored = previous.clone();
+    stored.state = state;
+    stored.state_changed_at = Some(now());
+    if state == KeyState::Revoked {
+        stored.nonce.clear();
+        stored.ciphertext.clear();
+    }
+    keys.insert(key_id.clone(), stored.clone());
+    if let Err(err) = vault.save(&keys) {
+        keys.insert(key_id, previous);
+        return Err(err);
+    }
+    Ok(SuccessResponse::new(key_response(key_id, &stored)))
+}
+
+pub fn update_key_state(key_id: String, request: UpdateKeyStateRequest) -> Result<SuccessResponse<VaultKeyResponse>, ErrorResponse> {
+    let result = transition(key_id, request.state);
+    audit::recorded(AuditOperation::UpdateVaultKeyState, result, |key| vec![key.pubkey.clone()])
+}
+
+// Deleting revokes the key rather than dropping its record
+pub fn delete_key(key_id: String) -> Result<SuccessResponse<DeleteKeyResponse>, ErrorResponse> {
+    let result = transition(key_id, KeyState::Revoked);
+    let result = audit::recorded(AuditOperation::UpdateVaultKeyState, result, |key| vec![key.pubkey.clone()])?;
+    Ok(SuccessResponse::new(DeleteKeyResponse { key_id: result.data.key_id, deleted: true, state: result.data.state }))
+}
@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::vote::{
+    instruction::{self as vote_instruction, CreateVoteAccountConfig},
+    state::{VoteAuthorize, VoteInit},
+};
+use super::instruction::{validate_pubkey, InstructionResponse};
+use super::response_types::{SuccessResponse, ErrorResponse};
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateVoteAccountRequest {
+    pub from: String,
+    #[serde(rename = "voteAccount")]
+    pub vote_account: String,
+    pub identity: String,
+    #[serde(rename = "authorizedVoter")]
+    pub authorized_voter: String,
+    #[serde(rename = "authorizedWithdrawer")]
+    pub authorized_withdrawer: String,
+    pub commission: u8,
+    pub lamports: u64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AuthorizeVoteRequest {
+    #[serde(rename = "voteAccount")]
+    pub vote_account: String,
+    pub authority: String,
+    #[serde(rename = "newAuthority")]
+    pub new_authority: String,
+    #[serde(rename = "authorizeType")]
+    pub authorize_type: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateCommissionRequest {
+    #[serde(rename = "voteAccount")]
+    pub vote_account: String,
+    #[serde(rename = "authorizedWithdrawer")]
+    pub authorized_withdrawer: String,
+    pub commission: u8,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct WithdrawVoteRequest {
+    #[serde(rename = "voteAccount")]
+    pub vote_account: String,
+    #[serde(rename = "authorizedWithdrawer")]
+    pub authorized_withdrawer: String,
+    pub destination: String,
+    pub lamports: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreateVoteAccountResponse {
+    pub vote_account: String,
+    pub instructions: Vec<InstructionResponse>,
+}
+
+fn validate_commission(commission: u8) -> Result<(), ErrorResponse> {
+    if commission > 100 {
+        return Err(ErrorResponse::new("Commission must be between 0 and 100"));
+    }
+    Ok(())
+}
+
+fn validate_lamports(lamports: u64) -> Result<(), ErrorResponse> {
+    if lamports == 0 {
+        return Err(ErrorResponse::new("Amount must be greater than 0 lamports"));
+    }
+    Ok(())
+}
+
+fn parse_authorize_type(authorize_type: &str) -> Result<VoteAuthorize, ErrorResponse> {
+    match authorize_type {
+        "voter" => Ok(VoteAuthorize::Voter),
+        "withdrawer" => Ok(VoteAuthorize::Withdrawer),
+        _ => Err(ErrorResponse::new("authorizeType must be either 'voter' or 'withdrawer'")),
+    }
+}
+
+pub fn create_vote_account(request: CreateVoteAccountRequest) -> Result<SuccessResponse<CreateVoteAccountResponse>, ErrorResponse> {
+    // Validate inputs
+    let from = validate_pubkey(&request.from, "funding address")?;
+    let vote_account = validate_pubkey(&request.vote_account, "vote account")?;
+    let identity = validate_pubkey(&request.identity, "validator identity")?;
+    let authorized_voter = validate_pubkey(&request.authorized_voter, "authorized voter")?;
+    let authorized_withdrawer = validate_pubkey(&request.authorized_withdrawer, "authorized withdrawer")?;
+    validate_commission(request.commission)?;
+    validate_lamports(request.lamports)?;
+
+    if from == vote_account {
+        return Err(ErrorResponse::new("Funding address and vote account cannot be the same"));
+    }
+
+    let vote_init = VoteInit {
+        node_pubkey: identity,
+        authorized_voter,
+        authorized_withdrawer,
+        commission: request.commission,
+    };
+
+    // System create_account followed by the vote program's InitializeAccount
+    let instructions = vote_instruction::create_account_with_config(
+        &from,
+        &vote_account,
+        &vote_init,
+        request.lamports,
+        CreateVoteAccountConfig::default(),
+    );
+
+    let response = CreateVoteAccountResponse {
+        vote_account: vote_account.to_string(),
+        instructions: instructions.iter().map(InstructionResponse::from).collect(),
+    };
+
+    Ok(SuccessResponse::new(response))
+}
+
+pub fn authorize_vote(request: AuthorizeVoteRequest) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    let vote_account = validate_pubkey(&request.vote_account, "vote account")?;
+    let authority = validate_pubkey(&request.authority, "current authority")?;
+    let new_authority = validate_pubkey(&request.new_authority, "new authority")?;
+    let vote_authorize = parse_authorize_type(&request.authorize_type)?;
+
+    let instruction = vote_instruction::authorize(
+        &vote_account,
+        &authority,
+        &new_authority,
+        vote_authorize,
+    );
+
+    Ok(SuccessResponse::new(InstructionResponse::from(&instruction)))
+}
+
+pub fn update_commission(request: UpdateCommissionRequest) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    let vote_account = validate_pubkey(&request.vote_account, "vote account")?;
+    let authorized_withdrawer = validate_pubkey(&request.authorized_withdrawer, "authorized withdrawer")?;
+    validate_commission(request.commission)?;
+
+    let instruction = vote_instruction::update_commission(
+        &vote_account,
+        &authorized_withdrawer,
+        request.commission,
+    );
+
+    Ok(SuccessResponse::new(InstructionResponse::from(&instruction)))
+}
+
+pub fn withdraw_from_vote_account(request: WithdrawVoteRequest) -> Result<SuccessResponse<InstructionResponse>, ErrorResponse> {
+    // Validate inputs
+    let vote_account = validate_pubkey(&request.vote_account, "vote account")?;
+    let authorized_withdrawer = validate_pubkey(&request.authorized_withdrawer, "authorized withdrawer")?;
+    let destination = validate_pubkey(&request.destination, "destination address")?;
+    validate_lamports(request.lamports)?;
+
+    if vote_account == destination {
+        return Err(ErrorResponse::new("Vote account and destination addresses cannot be the same"));
+    }
+
+    let instruction = vote_instruction::withdraw(
+        &vote_account,
+        &authorized_withdrawer,
+        request.lamports,
+        &destination,
+    );
+
+    Ok(SuccessResponse::new(InstructionResponse::from(&instruction)))
+}
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use super::batch::{BatchInstruction, IntoInstructions};
+use super::encoding::InstructionEncoding;
+use super::response_types::{SuccessResponse, ErrorResponse};
+
+// Builder endpoints accept `?format=walletAdapter` to return their
+// instructions in the JSON shape of @solana/web3.js's TransactionInstruction,
+// so JS clients can pass them straight to `new TransactionInstruction(...)`
+
+#[derive(Deserialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFormat {
+    #[serde(rename = "walletAdapter")]
+    WalletAdapter,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FormatQuery {
+    // Omit for the default response shape
+    pub format: Option<ResponseFormat>,
+    // Encoding of the instruction data, base64 unless base58 is asked for
+    #[serde(default)]
+    pub encoding: InstructionEncoding,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WalletAdapterAccountMeta {
+    pub pubkey: String,
+    #[serde(rename = "isSigner")]
+    pub is_signer: bool,
+    #[serde(rename = "isWritable")]
+    pub is_writable: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WalletAdapterInstruction {
+    pub keys: Vec<WalletAdapterAccountMeta>,
+    #[serde(rename = "programId")]
+    pub program_id: String,
+    pub data: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WalletAdapterResponse {
+    pub instructions: Vec<WalletAdapterInstruction>,
+}
+
+impl From<BatchInstruction> for WalletAdapterInstruction {
+    fn from(instruction: BatchInstruction) -> Self {
+        WalletAdapterInstruction {
+            keys: instruction
+                .accounts
+                .into_iter()
+                .map(|account| WalletAdapterAccountMeta {
+                    pubkey: account.pubkey,
+                    is_signer: account.is_signer,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+            program_id: instruction.program_id,
+            data: instruction.instruction_data,
+        }
+    }
+}
+
+pub fn to_wallet_adapter<T: IntoInstructions>(
+    result: Result<SuccessResponse<T>, ErrorResponse>,
+) -> Result<SuccessResponse<WalletAdapterResponse>, ErrorResponse> {
+    result.map(|response| {
+        SuccessResponse::new(WalletAdapterResponse {
+            instructions: response
+                .data
+                .into_instructions()
+                .into_iter()
+                .map(WalletAdapterInstruction::from)
+                .collect(),
+        })
+    })
+}
@@ -0,0 +1,214 @@
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use reqwest::{header::CONTENT_TYPE, redirect, Client, Url};
+use serde::Serialize;
+use sha2::Sha256;
+use solana_sdk::{signature::Signature, transaction::VersionedTransaction};
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::config;
+use crate::shutdown;
+use super::confirmation::{self, Commitment, Outcome};
+use super::response_types::ErrorResponse;
+
+type HmacSha256 = Hmac<Sha256>;
+
+// Receivers verify a delivery by computing the HMAC-SHA256 of
+// "{timestamp}.{body}" with the shared secret, and comparing it to the hex
+// digest after "sha256=" in the signature header
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+pub const TIMESTAMP_HEADER: &str = "x-webhook-timestamp";
+
+const DELIVERY_ATTEMPTS: u32 = 6;
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    // The commitment the transaction reached, which is the one asked for
+    Processed,
+    Confirmed,
+    Finalized,
+    Expired,
+    Failed,
+}
+
+#[derive(Serialize)]
+struct Payload {
+    signature: String,
+    status: Status,
+    // Slot the transaction landed in, when it landed
+    slot: Option<u64>,
+    error: Option<String>,
+    // Program logs of a failed transaction, when they could be fetched
+    logs: Vec<String>,
+    timestamp: u64,
+}
+
+// Callbacks only go to the public internet, never to loopback, private or
+// link-local addresses such as the cloud metadata service or fly.io's
+// private network
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || first == 0
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (first == 100 && (64..128).contains(&second)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                // Unique local fc00::/7 and link-local fe80::/10
+                !(ip.is_loopback() || ip.is_unspecified() || first & 0xfe00 == 0xfc00 || first & 0xffc0 == 0xfe80)
+            }
+        },
+    }
+}
+
+// The URL's host as an address when it is one; IPv6 hosts come bracketed
+fn host_ip(url: &Url) -> Option<IpAddr> {
+    url.host_str()?.trim_start_matches('[').trim_end_matches(']').parse().ok()
+}
+
+// Checked before the transaction is sent, so a bad URL fails the submission
+// instead of losing the callback. Names are checked again when delivering,
+// against what they resolve to then.
+pub fn validate_callback_url(url: &str) -> Result<Url, ErrorResponse> {
+    if config::get().webhook_secret.is_none() {
+        return Err(ErrorResponse::new("Webhooks are not enabled on this server"));
+    }
+    let url = Url::parse(url)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some())
+        .ok_or_else(|| ErrorResponse::new("callbackUrl must be an http or https URL"))?;
+    let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+    let internal = match host_ip(&url) {
+        Some(ip) => !is_public(ip),
+        None => host == "localhost" || host.ends_with(".localhost"),
+    };
+    if internal {
+        return Err(ErrorResponse::new("callbackUrl must be a public address"));
+    }
+    Ok(url)
+}
+
+// A client that connects only to a public address of the URL's host. The
+// address is resolved and checked here and the client pinned to it, so a
+// name can't resolve differently by the time the request connects.
+async fn http_client(url: &Url) -> Result<Client, String> {
+    let builder = Client::builder()
+        .timeout(DELIVERY_TIMEOUT)
+        // A redirect would send the payload somewhere the caller didn't name
+        .redirect(redirect::Policy::none());
+    let builder = match (host_ip(url), url.host_str()) {
+        (Some(ip), _) if is_public(ip) => builder,
+        (Some(_), _) | (None, None) => return Err("the callback host is not a public address".to_string()),
+        (None, Some(domain)) => {
+            let port = url.port_or_known_default().unwrap_or(443);
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((domain, port))
+                .await
+                .map_err(|err| format!("cannot resolve the callback host: {}", err))?
+                .collect();
+            if addresses.is_empty() || addresses.iter().any(|address| !is_public(address.ip())) {
+                return Err("the callback host resolves to a non-public address".to_string());
+            }
+            builder.resolve(domain, addresses[0])
+        }
+    };
+    builder.build().map_err(|err| err.to_string())
+}
+
+// Watch a submitted transaction in the background and POST its outcome to
+// the callback URL. The watch runs on a detached task, so shutdown waits
+// for it, up to the shutdown timeout.
+pub fn spawn(transaction: VersionedTransaction, commitment: Commitment, url: Url) {
+    shutdown::spawn_detached(async move {
+        let signature = transaction.signatures[0];
+        let outcome = confirmation::watch(transaction, commitment).await;
+        deliver(&url, signature, &outcome).await;
+    });
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or_default()
+}
+
+fn sign(secret: &str, timestamp: u64, body: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn retry_delay(attempt: u32) -> Duration {
+    let delay = RETRY_BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt)).min(RETRY_MAX_DELAY);
+    delay + RETRY_BASE_DELAY.mul_f64(rand::thread_rng().gen_range(0.0..0.5))
+}
+
+// Any response other than 2xx is retried with backoff. Retries carry the
+// same body and timestamp, so receivers can drop duplicates by signature.
+pub async fn deliver(url: &Url, signature: Signature, outcome: &Outcome) {
+    let Some(secret) = config::get().webhook_secret.as_deref() else {
+        return;
+    };
+    let (status, slot, error, logs) = match outcome {
+        Outcome::Landed { slot, commitment } => {
+            let status = match commitment {
+                Commitment::Processed => Status::Processed,
+                Commitment::Confirmed => Status::Confirmed,
+                Commitment::Finalized => Status::Finalized,
+            };
+            (status, Some(*slot), None, Vec::new())
+        }
+        Outcome::Expired => (Status::Expired, None, Some("Transaction expired before it was confirmed".to_string()), Vec::new()),
+        Outcome::Failed { slot, error, logs } => (Status::Failed, *slot, Some(error.clone()), logs.clone()),
+    };
+    let payload = Payload { signature: signature.to_string(), status, slot, error, logs, timestamp: now() };
+    let body = serde_json::to_string(&payload).unwrap();
+    let digest = format!("sha256={}", sign(secret, payload.timestamp, &body));
+    // The URL may carry a token, so only its host is logged
+    let host = url.host_str().unwrap_or_default();
+    let client = match http_client(url).await {
+        Ok(client) => client,
+        Err(error) => {
+            tracing::error!(signature = %payload.signature, host, error, "Refusing webhook delivery");
+            return;
+        }
+    };
+
+    for attempt in 0..DELIVERY_ATTEMPTS {
+        if attempt > 0 {
+            tokio::time::sleep(retry_delay(attempt - 1)).await;
+        }
+        let result = client
+            .post(url.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .header(TIMESTAMP_HEADER, payload.timestamp)
+            .header(SIGNATURE_HEADER, &digest)
+            .body(body.clone())
+            .send()
+            .await;
+        match result {
+            Ok(response) if response.status().is_success() => {
+                tracing::info!(signature = %payload.signature, host, attempt, "Webhook delivered");
+                return;
+            }
+            Ok(response) => {
+                tracing::warn!(signature = %payload.signature, host, attempt, status = %response.status(), "Webhook rejected");
+            }
+            Err(err) => {
+                tracing::warn!(signature = %payload.signature, host, attempt, error = %err.without_url(), "Webhook delivery failed");
+            }
+        }
+    }
+    tracing::error!(signature = %payload.signature, host, "Giving up on webhook delivery");
+}
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use solana_sdk::system_instruction;
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+use spl_token::{instruction as token_instruction, native_mint};
+use super::instruction::{validate_pubkey, InstructionResponse};
+use super::response_types::{SuccessResponse, ErrorResponse};
+
+#[derive(Deserialize, ToSchema)]
+pub struct WrapSolRequest {
+    pub owner: String,
+    pub lamports: u64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UnwrapSolRequest {
+    pub owner: String,
+    // Receives the unwrapped SOL; defaults to the owner
+    pub destination: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WrappedSolResponse {
+    // The owner's wrapped SOL associated token account
+    pub token_account: String,
+    // In the order they must appear in the transaction
+    pub instructions: Vec<InstructionResponse>,
+}
+
+pub fn wrap_sol(request: WrapSolRequest) -> Result<SuccessResponse<WrappedSolResponse>, ErrorResponse> {
+    // Validate inputs
+    let owner = validate_pubkey(&request.owner, "owner address")?;
+    if request.lamports == 0 {
+        return Err(ErrorResponse::new("Amount must be greater than 0 lamports"));
+    }
+
+    let token_account = get_associated_token_address(&owner, &native_mint::id());
+
+    // Create the account if needed, fund it, then have the token program
+    // count the new lamports as its token balance
+    let sync_native = token_instruction::sync_native(&spl_token::id(), &token_account)
+        .map_err(|e| ErrorResponse::new(format!("Failed to create token instruction: {}", e)))?;
+    let instructions = [
+        create_associated_token_account_idempotent(&owner, &owner, &native_mint::id(), &spl_token::id()),
+        system_instruction::transfer(&owner, &token_account, request.lamports),
+        sync_native,
+    ];
+
+    let response = WrappedSolResponse {
+        token_account: token_account.to_string(),
+        instructions: instructions.iter().map(InstructionResponse::from).collect(),
+    };
+
+    Ok(SuccessResponse::new(response))
+}
+
+// Closing the wrapped SOL account returns its whole balance, rent included,
+// as SOL; there is no partial unwrap
+pub fn unwrap_sol(request: UnwrapSolRequest) -> Result<SuccessResponse<WrappedSolResponse>, ErrorResponse> {
+    // Validate inputs
+    let owner = validate_pubkey(&request.owner, "owner address")?;
+    let destination = request.destination
+        .as_deref()
+        .map(|destination| validate_pubkey(destination, "destination address"))
+        .transpose()?
+        .unwrap_or(owner);
+
+    let token_account = get_associated_token_address(&owner, &native_mint::id());
+
+    let instruction = token_instruction::close_account(
+        &spl_token::id(),
+        &token_account,
+        &destination,
+        &owner,
+        &[],
+    ).map_err(|e| ErrorResponse::new(format!("Failed to create token instruction: {}", e)))?;
+
+    let response = WrappedSolResponse {
+        token_account: token_account.to_string(),
+        instructions: vec![InstructionResponse::from(&instruction)],
+    };
+
+    Ok(SuccessResponse::new(response))
+}
@@ -0,0 +1,27 @@
+use axum::{
+    body::Body,
+    http::{HeaderName, HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+
+// The API is served under this prefix. The same routes are still served
+// unprefixed for existing clients, marked deprecated, and breaking changes
+// land under the next version instead.
+pub const PREFIX: &str = "/v1";
+
+pub const DEPRECATION_HEADER: HeaderName = HeaderName::from_static("deprecation");
+const LINK_HEADER: HeaderName = HeaderName::from_static("link");
+
+// Unprefixed responses point at their versioned route, so clients can see
+// from any response what to move to
+pub async fn deprecate_unversioned(request: Request<Body>, next: Next) -> Response {
+    let successor = format!("<{}{}>; rel=\"successor-version\"", PREFIX, request.uri().path());
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert(DEPRECATION_HEADER, HeaderValue::from_static("true"));
+    if let Ok(successor) = HeaderValue::from_str(&successor) {
+        headers.insert(LINK_HEADER, successor);
+    }
+    response
+}
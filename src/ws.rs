@@ -0,0 +1,185 @@
+use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    response::Response,
+};
+use futures::{future::join_all, SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::RpcSignatureSubscribeConfig,
+    rpc_response::RpcSignatureResult,
+};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+use tokio::{sync::mpsc, task::JoinHandle};
+use crate::cluster;
+use crate::utils::rpc;
+
+const MAX_SUBSCRIPTIONS_PER_CONNECTION: usize = 100;
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+enum ClientMessage {
+    Subscribe { signature: String },
+    Unsubscribe { signature: String },
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ServerMessage {
+    Subscribed { signature: String },
+    Unsubscribed { signature: String },
+    Status {
+        signature: String,
+        commitment: String,
+        slot: u64,
+        err: Option<String>,
+    },
+    Done { signature: String },
+    Error { message: String },
+}
+
+type Outbox = mpsc::UnboundedSender<ServerMessage>;
+
+#[utoipa::path(
+    get,
+    path = "/ws",
+    operation_id = "signatureSubscriptions",
+    tag = "transaction",
+    responses((
+        status = 101,
+        description = "WebSocket upgrade. Send {\"action\":\"subscribe\",\"signature\":\"...\"} to receive \
+            status messages as the transaction reaches processed, confirmed, and finalized commitment",
+    ))
+)]
+pub async fn handler(ws: WebSocketUpgrade) -> Response {
+    // The socket is served on its own task, which needs to be told the cluster
+    let target = cluster::current();
+    ws.on_upgrade(move |socket| cluster::scope(target, handle_socket(socket)))
+}
+
+async fn handle_socket(socket: WebSocket) {
+    let (mut sender, mut receiver) = socket.split();
+    let (outbox, mut inbox) = mpsc::unbounded_channel::<ServerMessage>();
+
+    // All subscription tasks write through one channel so only this task
+    // touches the socket sink
+    let writer = tokio::spawn(async move {
+        while let Some(message) = inbox.recv().await {
+            let text = serde_json::to_string(&message).unwrap();
+            if sender.send(Message::Text(text)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let pubsub = match PubsubClient::new(&rpc::ws_url()).await {
+        Ok(pubsub) => Arc::new(pubsub),
+        Err(err) => {
+            let _ = outbox.send(ServerMessage::Error {
+                message: format!("Failed to connect to Solana pubsub: {}", err),
+            });
+            drop(outbox);
+            let _ = writer.await;
+            return;
+        }
+    };
+
+    let mut subscriptions: HashMap<Signature, JoinHandle<()>> = HashMap::new();
+
+    while let Some(Ok(message)) = receiver.next().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+
+        subscriptions.retain(|_, task| !task.is_finished());
+
+        match serde_json::from_str::<ClientMessage>(&text) {
+            Ok(ClientMessage::Subscribe { signature }) => {
+                let Ok(parsed) = Signature::from_str(&signature) else {
+                    let _ = outbox.send(ServerMessage::Error { message: "Invalid signature format".to_string() });
+                    continue;
+                };
+                if subscriptions.contains_key(&parsed) {
+                    continue;
+                }
+                if subscriptions.len() >= MAX_SUBSCRIPTIONS_PER_CONNECTION {
+                    let _ = outbox.send(ServerMessage::Error {
+                        message: format!("At most {} concurrent subscriptions are allowed", MAX_SUBSCRIPTIONS_PER_CONNECTION),
+                    });
+                    continue;
+                }
+
+                let _ = outbox.send(ServerMessage::Subscribed { signature });
+                let task = tokio::spawn(track_signature(pubsub.clone(), parsed, outbox.clone()));
+                subscriptions.insert(parsed, task);
+            }
+            Ok(ClientMessage::Unsubscribe { signature }) => {
+                if let Some(task) = Signature::from_str(&signature)
+                    .ok()
+                    .and_then(|parsed| subscriptions.remove(&parsed))
+                {
+                    task.abort();
+                    let _ = outbox.send(ServerMessage::Unsubscribed { signature });
+                }
+            }
+            Err(_) => {
+                let _ = outbox.send(ServerMessage::Error {
+                    message: "Expected {\"action\": \"subscribe\" | \"unsubscribe\", \"signature\": \"...\"}".to_string(),
+                });
+            }
+        }
+    }
+
+    for task in subscriptions.into_values() {
+        task.abort();
+    }
+    writer.abort();
+}
+
+// signatureSubscribe notifies once at the requested commitment, so each
+// level gets its own subscription
+async fn track_signature(pubsub: Arc<PubsubClient>, signature: Signature, outbox: Outbox) {
+    let levels = [
+        CommitmentConfig::processed(),
+        CommitmentConfig::confirmed(),
+        CommitmentConfig::finalized(),
+    ];
+
+    join_all(levels.into_iter().map(|commitment| {
+        let pubsub = pubsub.clone();
+        let outbox = outbox.clone();
+        async move {
+            let config = RpcSignatureSubscribeConfig {
+                commitment: Some(commitment),
+                enable_received_notification: Some(false),
+            };
+            let (mut stream, unsubscribe) = match pubsub.signature_subscribe(&signature, Some(config)).await {
+                Ok(subscription) => subscription,
+                Err(err) => {
+                    let _ = outbox.send(ServerMessage::Error {
+                        message: format!("Failed to subscribe to {}: {}", signature, err),
+                    });
+                    return;
+                }
+            };
+
+            if let Some(response) = stream.next().await
+                && let RpcSignatureResult::ProcessedSignature(result) = response.value
+            {
+                let _ = outbox.send(ServerMessage::Status {
+                    signature: signature.to_string(),
+                    commitment: commitment.commitment.to_string(),
+                    slot: response.context.slot,
+                    err: result.err.map(|err| err.to_string()),
+                });
+            }
+            drop(stream);
+            unsubscribe().await;
+        }
+    })).await;
+
+    let _ = outbox.send(ServerMessage::Done { signature: signature.to_string() });
+}
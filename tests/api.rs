@@ -0,0 +1,283 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use rust_server_superdev::{auth, build_router, config::AppConfig};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+fn app() -> Router {
+    build_router(AppConfig::from_env())
+}
+
+async fn post(uri: &str, body: Value) -> (StatusCode, Value) {
+    let request = Request::post(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn health_responds_with_request_id() {
+    let response = app()
+        .oneshot(Request::get("/health").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().contains_key("x-request-id"));
+}
+
+#[tokio::test]
+async fn send_sol_returns_the_transfer_instruction() {
+    let (status, body) = post("/send/sol", json!({
+        "from": "11111111111111111111111111111112",
+        "to": "So11111111111111111111111111111111111111112",
+        "lamports": 5,
+    })).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["success"], true);
+    assert_eq!(body["data"]["program_id"], "11111111111111111111111111111111");
+    let accounts = body["data"]["accounts"].as_array().unwrap();
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[0]["is_signer"], true);
+    assert_eq!(accounts[1]["is_signer"], false);
+}
+
+#[tokio::test]
+async fn send_token_reports_every_invalid_field() {
+    let (_, body) = post("/send/token", json!({
+        "destination": "11111111111111111111111111111112",
+        "mint": "So11111111111111111111111111111111111111112",
+        "owner": "not a pubkey",
+        "amount": 0,
+    })).await;
+
+    assert_eq!(body["success"], false);
+    assert_eq!(body["code"], "INVALID_PUBKEY");
+    let fields: Vec<_> = body["errors"].as_array().unwrap().iter().map(|error| error["field"].clone()).collect();
+    assert_eq!(fields, [json!("owner"), json!("amount")]);
+}
+
+#[tokio::test]
+async fn builders_encode_instruction_data_as_asked() {
+    let body = json!({
+        "from": "11111111111111111111111111111112",
+        "to": "So11111111111111111111111111111111111111112",
+        "lamports": 5,
+    });
+    let (_, base64) = post("/send/sol", body.clone()).await;
+    let (_, base58) = post("/send/sol?encoding=base58", body).await;
+
+    assert_eq!(base64["data"]["encoding"], "base64");
+    assert_eq!(base64["data"]["instruction_data"], "AgAAAAUAAAAAAAAA");
+    assert_eq!(base58["data"]["encoding"], "base58");
+    assert_eq!(base58["data"]["instruction_data"], "3Bxs41hAcYbghSB9");
+}
+
+#[tokio::test]
+async fn unprefixed_routes_are_deprecated_aliases_of_v1() {
+    let body = json!({
+        "from": "11111111111111111111111111111112",
+        "to": "So11111111111111111111111111111111111111112",
+        "lamports": 5,
+    });
+    let send = |uri: &str| {
+        Request::post(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let versioned = app().oneshot(send("/v1/send/sol")).await.unwrap();
+    let unversioned = app().oneshot(send("/send/sol")).await.unwrap();
+
+    assert_eq!(versioned.status(), StatusCode::OK);
+    assert!(!versioned.headers().contains_key("deprecation"));
+    assert_eq!(unversioned.status(), StatusCode::OK);
+    assert_eq!(unversioned.headers()["deprecation"], "true");
+    assert_eq!(unversioned.headers()["link"], "</v1/send/sol>; rel=\"successor-version\"");
+}
+
+#[tokio::test]
+async fn api_keys_are_limited_to_their_scopes() {
+    let mut config = AppConfig::from_env();
+    config.api_keys = auth::ApiKeys::load(auth::parse_api_keys("frontend:read+build").unwrap(), None);
+    let app = build_router(config);
+    let request = |uri: &str, key: Option<&str>, body: Value| {
+        let mut request = Request::post(uri).header("content-type", "application/json");
+        if let Some(key) = key {
+            request = request.header("x-api-key", key);
+        }
+        request.body(Body::from(body.to_string())).unwrap()
+    };
+    let transfer = json!({
+        "from": "11111111111111111111111111111112",
+        "to": "So11111111111111111111111111111111111111112",
+        "lamports": 5,
+    });
+
+    let anonymous = app.clone().oneshot(request("/v1/send/sol", None, transfer.clone())).await.unwrap();
+    let build = app.clone().oneshot(request("/v1/send/sol", Some("frontend"), transfer)).await.unwrap();
+    let sign = app
+        .oneshot(request("/v1/message/sign", Some("frontend"), json!({ "message": "hi", "secret": "" })))
+        .await
+        .unwrap();
+
+    assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(build.status(), StatusCode::OK);
+    assert_eq!(sign.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn rotated_api_keys_overlap_until_revoked() {
+    let mut config = AppConfig::from_env();
+    config.api_keys = auth::ApiKeys::load(auth::parse_api_keys("operator:read+admin").unwrap(), None);
+    let app = build_router(config);
+    let call = |method: &str, uri: &str, key: &str| {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("x-api-key", key)
+            .header("content-type", "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            (status, serde_json::from_slice::<Value>(&body).unwrap())
+        }
+    };
+
+    let (_, keys) = call("GET", "/api-keys", "operator").await;
+    let old_id = keys["data"]["keys"][0]["key_id"].as_str().unwrap().to_string();
+    let (_, rotated) = call("POST", &format!("/api-keys/{}/rotate", old_id), "operator").await;
+    let new_key = rotated["data"]["key"].as_str().unwrap().to_string();
+    assert_eq!(rotated["data"]["scopes"], json!(["read", "admin"]));
+
+    // Both keys work during the overlap, and each one's use is counted
+    assert_eq!(call("GET", "/api-keys", &new_key).await.0, StatusCode::OK);
+    let (status, keys) = call("GET", "/api-keys", "operator").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(keys["data"]["keys"][1]["requests"], 1);
+
+    let (_, revoked) = call("DELETE", &format!("/api-keys/{}", old_id), &new_key).await;
+    assert_eq!(revoked["data"]["valid"], false);
+    assert_eq!(call("GET", "/api-keys", "operator").await.0, StatusCode::UNAUTHORIZED);
+    assert_eq!(call("GET", "/api-keys", &new_key).await.0, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn create_token_with_initial_supply_returns_the_full_setup() {
+    let (_, body) = post("/token/create", json!({
+        "mintAuthority": "11111111111111111111111111111112",
+        "mint": "So11111111111111111111111111111111111111112",
+        "decimals": 6,
+        "freezeAuthority": "11111111111111111111111111111112",
+        "initialSupply": 1_000_000,
+        "payer": "SysvarRent111111111111111111111111111111111",
+    })).await;
+
+    assert_eq!(body["success"], true);
+    let data = &body["data"];
+    let programs = |instructions: &Value| -> Vec<String> {
+        instructions.as_array().unwrap().iter().map(|instruction| instruction["program_id"].as_str().unwrap().to_string()).collect()
+    };
+    assert_eq!(
+        programs(&data["instructions"]),
+        [
+            "11111111111111111111111111111111",
+            "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+            "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL",
+            "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+        ],
+    );
+    // The deprecated alias is the initialize mint instruction
+    assert_eq!(data["instruction_data"], data["instructions"][1]["instruction_data"]);
+    assert!(data["holder_token_account"].is_string());
+}
+
+#[tokio::test]
+async fn transactions_with_more_signers_than_keys_are_rejected() {
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use solana_sdk::{
+        message::{Message, MessageHeader, VersionedMessage},
+        pubkey::Pubkey,
+        signature::{Keypair, Signature},
+        transaction::VersionedTransaction,
+    };
+
+    // The header claims two signers and the signatures agree, but there is
+    // only one account key
+    let message = Message {
+        header: MessageHeader { num_required_signatures: 2, num_readonly_signed_accounts: 0, num_readonly_unsigned_accounts: 0 },
+        account_keys: vec![Pubkey::new_unique()],
+        ..Message::default()
+    };
+    let transaction = VersionedTransaction {
+        signatures: vec![Signature::default(); 2],
+        message: VersionedMessage::Legacy(message),
+    };
+    let encoded = BASE64.encode(bincode::serialize(&transaction).unwrap());
+
+    for (uri, body) in [
+        ("/transaction/sign", json!({ "transaction": encoded, "secretKeys": [Keypair::new().to_base58_string()] })),
+        ("/transaction/pending", json!({ "transaction": encoded })),
+    ] {
+        let (status, body) = post(uri, body).await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+        assert_eq!(body["code"], "INVALID_TRANSACTION", "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn remote_signers_only_use_configured_keys() {
+    for (backend, key_id, code) in [
+        ("vault-transit", "payments-hot", "NOT_AUTHORIZED"),
+        ("aws-kms", "1234abcd-12ab-34cd-56ef-1234567890ab", "NOT_AUTHORIZED"),
+        ("aws-kms", "arn:aws:kms:us-east-2:111122223333:alias/payments", "NOT_AUTHORIZED"),
+        ("aws-kms", "arn:aws:s3:::bucket/key", "INVALID_REQUEST"),
+    ] {
+        let (_, body) = post("/message/sign", json!({
+            "message": "hello",
+            "keyId": key_id,
+            "signerBackend": backend,
+        })).await;
+        assert_eq!(body["code"], code, "{} {}", backend, key_id);
+    }
+}
+
+#[tokio::test]
+async fn borsh_decoding_of_empty_structs_is_bounded() {
+    // Empty structs take no bytes, so 2,000 bytes would otherwise decode to
+    // 2,000 by 2,000 values
+    let (_, body) = post("/decode/borsh", json!({
+        "schema": { "array": [{ "array": [{ "defined": "Empty" }, 2000] }, 2000] },
+        "types": [{ "name": "Empty", "type": { "kind": "struct", "fields": [] } }],
+        "data": "00".repeat(2000),
+        "dataEncoding": "hex",
+    })).await;
+
+    assert_eq!(body["success"], false);
+    assert_eq!(body["error"], "Data decodes to more than 2097152 values");
+}
+
+#[tokio::test]
+async fn borsh_enums_fit_their_variant_index_in_a_byte() {
+    let variants: Vec<Value> = (0..257).map(|index| json!({ "name": format!("V{}", index) })).collect();
+    let (_, body) = post("/encode/borsh", json!({
+        "schema": { "defined": "Wide" },
+        "types": [{ "name": "Wide", "type": { "kind": "enum", "variants": variants } }],
+        "value": "V256",
+    })).await;
+
+    assert_eq!(body["success"], false);
+    assert_eq!(body["error"], "Invalid schema: enums can have at most 256 variants");
+}
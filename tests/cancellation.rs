@@ -0,0 +1,125 @@
+// Requests aborted mid-flight, as when a client disconnects. These run
+// against the mock RPC, so they live apart from the tests in api.rs.
+use axum::{
+    body::{to_bytes, Body},
+    http::Request,
+    Router,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use rust_server_superdev::{build_router, config::AppConfig};
+use serde_json::{json, Value};
+use solana_sdk::{
+    hash::hashv,
+    message::Message,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::Transaction,
+};
+use std::{
+    sync::{Once, OnceLock},
+    task::Poll,
+    time::Duration,
+};
+use tower::ServiceExt;
+
+fn sponsor() -> &'static Keypair {
+    static SPONSOR: OnceLock<Keypair> = OnceLock::new();
+    SPONSOR.get_or_init(Keypair::new)
+}
+
+fn app() -> Router {
+    static ENV: Once = Once::new();
+    ENV.call_once(|| {
+        // SAFETY: set before the configuration is first read, while no
+        // other test thread reads the environment
+        unsafe {
+            std::env::set_var("MOCK_RPC", "true");
+            std::env::set_var("SPONSOR_SECRET_KEY", sponsor().to_base58_string());
+        }
+    });
+    build_router(AppConfig::from_env())
+}
+
+fn post(uri: &str, body: Value) -> Request<Body> {
+    Request::post(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+// A memo paid for by the payer, on the mock's blockhash
+fn memo(payer: &Keypair) -> Transaction {
+    let instruction = spl_memo::build_memo(b"aborted", &[]);
+    let message = Message::new_with_blockhash(&[instruction], Some(&payer.pubkey()), &hashv(&[b"mock blockhash"]));
+    Transaction::new_unsigned(message)
+}
+
+fn encode(transaction: &Transaction) -> String {
+    BASE64.encode(bincode::serialize(transaction).unwrap())
+}
+
+// Polls the request once and drops it, returning whether it was still in
+// flight at that point
+async fn abort(request: Request<Body>) -> bool {
+    let mut response = Box::pin(app().oneshot(request));
+    let in_flight = matches!(futures::poll!(&mut response), Poll::Pending);
+    drop(response);
+    in_flight
+}
+
+async fn wait_until_sent(signature: Signature) {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+    loop {
+        let request = Request::get(format!("/transaction/{}/status", signature)).body(Body::empty()).unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        let body: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        if body["data"]["status"] == "finalized" {
+            return;
+        }
+        assert!(tokio::time::Instant::now() < deadline, "transaction was never sent after the request was aborted");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+#[tokio::test]
+async fn aborted_request_still_submits_the_transaction() {
+    let payer = Keypair::new();
+    let mut transaction = memo(&payer);
+    transaction.sign(&[&payer], transaction.message.recent_blockhash);
+
+    let in_flight = abort(post("/transaction/submit", json!({ "transaction": encode(&transaction) }))).await;
+
+    assert!(in_flight);
+    wait_until_sent(transaction.signatures[0]).await;
+}
+
+#[tokio::test]
+async fn aborted_request_still_submits_the_sponsored_transaction() {
+    let mut transaction = memo(sponsor());
+    transaction.sign(&[sponsor()], transaction.message.recent_blockhash);
+    let signature = transaction.signatures[0];
+    transaction.signatures[0] = Signature::default();
+
+    let in_flight = abort(post("/transaction/sponsor", json!({
+        "transaction": encode(&transaction),
+        "submit": true,
+    }))).await;
+
+    assert!(in_flight);
+    wait_until_sent(signature).await;
+}
+
+#[tokio::test]
+async fn aborted_request_still_runs_the_job() {
+    let payer = Keypair::new();
+    let mut signed = memo(&payer);
+    signed.sign(&[&payer], signed.message.recent_blockhash);
+
+    // Queued before the request yields, so the job has nothing to abort
+    abort(post("/jobs/submit", json!({
+        "transaction": encode(&memo(&payer)),
+        "secretKeys": [payer.to_base58_string()],
+    }))).await;
+
+    wait_until_sent(signed.signatures[0]).await;
+}